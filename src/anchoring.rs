@@ -0,0 +1,138 @@
+//! Per-screen anchoring to the room or the head
+//!
+//! Every virtual screen has a layout position in front of the viewer, which
+//! is carried around the viewer by an anchor rotation: none for world-locked
+//! screens, the head orientation for head-locked ones and its yaw alone for
+//! horizon-locked ones. Lazy-follow screens keep their anchor until the head
+//! turns past a threshold, then glide back in front of the viewer.
+
+use bevy::prelude::*;
+
+use crate::neck;
+use crate::prediction::PredictedOrientation;
+use crate::render::VirtualScreen;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::window::{AnchorMode, LazyFollowSettings};
+
+/// Head orientation without roll
+fn level(head: Quat) -> Quat {
+    let (yaw, pitch, _) = head.to_euler(EulerRot::YXZ);
+    Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0)
+}
+
+/// Head orientation reduced to its yaw
+fn yaw_only(head: Quat) -> Quat {
+    Quat::from_rotation_y(head.to_euler(EulerRot::YXZ).0)
+}
+
+/// Anchor of a lazy-follow screen
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LazyFollow {
+    /// Current anchor, set from the head on the first update
+    anchor: Option<Quat>,
+    /// Start of the running glide and the time since
+    glide: Option<(Quat, f32)>,
+}
+
+impl LazyFollow {
+    /// Anchor rotation for the head orientation `dt` seconds after the previous update
+    pub fn update(&mut self, head: Quat, dt: f32, settings: &LazyFollowSettings) -> Quat {
+        let target = level(head);
+        let anchor = *self.anchor.get_or_insert(target);
+
+        if !self.is_following()
+            && anchor.angle_between(target) > settings.threshold_deg.to_radians()
+        {
+            self.glide = Some((anchor, 0.0));
+        }
+        let Some((from, elapsed)) = &mut self.glide else {
+            return anchor;
+        };
+
+        // The glide keeps tracking the head so it ends where the head points
+        *elapsed += dt;
+        let progress = *elapsed / settings.duration_s;
+        let anchor = if progress >= 1.0 {
+            self.glide = None;
+            target
+        } else {
+            from.slerp(target, settings.easing.apply(progress))
+        };
+        self.anchor = Some(anchor);
+        anchor
+    }
+
+    /// True while gliding back into view
+    #[inline]
+    pub fn is_following(&self) -> bool {
+        self.glide.is_some()
+    }
+
+    /// Start over from the next head orientation
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Placement of a virtual screen before anchoring
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct ScreenAnchor {
+    /// Position relative to the viewer, from the layout and screen distance
+    pub local_position: Vec3,
+    /// Mode the anchor was last updated in
+    mode: AnchorMode,
+    follow: LazyFollow,
+}
+
+impl ScreenAnchor {
+    pub fn new(local_position: Vec3) -> Self {
+        Self {
+            local_position,
+            ..Default::default()
+        }
+    }
+
+    /// Anchor rotation for the head orientation `dt` seconds after the previous update
+    pub fn rotation(
+        &mut self,
+        mode: AnchorMode,
+        head: Quat,
+        dt: f32,
+        settings: &LazyFollowSettings,
+    ) -> Quat {
+        if mode != self.mode {
+            self.mode = mode;
+            self.follow.reset();
+        }
+        match mode {
+            AnchorMode::WorldLocked => Quat::IDENTITY,
+            AnchorMode::HeadLocked => head,
+            AnchorMode::LazyFollow => self.follow.update(head, dt, settings),
+            AnchorMode::HorizonLocked => yaw_only(head),
+        }
+    }
+}
+
+/// Place every virtual screen according to its anchor mode
+pub fn apply_screen_anchors(
+    orientation: Res<PredictedOrientation>,
+    persistent_state: Res<PersistentAppState>,
+    time: Res<Time>,
+    mut query: Query<(&VirtualScreen, &mut ScreenAnchor, &mut Transform)>,
+) {
+    let anchoring = &persistent_state.window_layout.anchoring;
+    let dt = time.delta_secs();
+    // Head-locked screens travel with the eyes when the neck model moves them
+    let eyes = neck::eye_position(orientation.quat, &persistent_state.tracking.neck_model);
+    for (screen, mut anchor, mut transform) in &mut query {
+        let mode = anchoring.mode(screen.0);
+        let rotation = anchor.rotation(mode, orientation.quat, dt, &anchoring.lazy_follow);
+        let origin = if mode == AnchorMode::HeadLocked {
+            eyes
+        } else {
+            Vec3::ZERO
+        };
+        transform.translation = origin + rotation * anchor.local_position;
+        transform.rotation = rotation;
+    }
+}
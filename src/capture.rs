@@ -10,6 +10,8 @@ use scap::{
     get_all_targets, has_permission, is_supported, request_permission,
 };
 
+use crate::driver::{DeviceInfo, DeviceProfile};
+
 #[derive(Component)]
 #[allow(dead_code)]
 pub struct CaptureTask(pub Task<CommandQueue>);
@@ -19,6 +21,11 @@ pub struct ScreenCaptures {
     pub num_streams: usize,
     pub capture_requested: bool,
     capturer: Option<Capturer>,
+    // Display being captured and the capture rate, kept to rebuild the capturer
+    target: Option<scap::Target>,
+    fps: u32,
+    // Frame capture is skipped while paused, e.g. with the glasses taken off
+    paused: bool,
     // Pre-allocated buffer pool for zero hot-path allocations
     #[allow(dead_code)]
     rgba_buffer: Vec<u8>,
@@ -55,26 +62,10 @@ impl ScreenCaptures {
             .collect();
         let num_displays = display_targets.len().max(1);
 
-        // Use async framerate detection for optimal performance
-        let target_fps = Self::detect_optimal_framerate_async().await;
-
-        let options = Options {
-            fps: target_fps, // Adaptive: 120Hz for Pro, 72Hz for Air, 60Hz fallback
-            target: display_targets.first().cloned(), // Use first display
-            show_cursor: true,
-            show_highlight: false,
-            excluded_targets: None,
-            output_type: FrameType::BGRAFrame, // Most efficient on macOS
-            output_resolution: Resolution::Captured, // Native resolution for best performance
-            crop_area: None,
-        };
-
-        // Build capturer with proper error handling
-        let mut capturer = Capturer::build(options)
-            .map_err(|e| anyhow::anyhow!("Failed to build capturer: {}", e))?;
-
-        // Start capture immediately for minimal latency
-        capturer.start_capture();
+        // Until glasses connect, capture at the rate every model supports
+        let target = display_targets.first().cloned();
+        let fps = DeviceProfile::GENERIC.base_refresh_rate();
+        let capturer = Self::build_capturer(target.clone(), fps)?;
 
         // Pre-allocate buffer for 4K RGBA (worst case) to avoid hot-path allocations
         const MAX_BUFFER_SIZE: usize = 3840 * 2160 * 4; // 4K RGBA
@@ -83,9 +74,12 @@ impl ScreenCaptures {
         Ok(Self {
             num_streams: num_displays,
             capturer: Some(capturer),
+            target,
+            fps,
             rgba_buffer,
             buffer_capacity: MAX_BUFFER_SIZE,
             capture_requested: false,
+            paused: false,
         })
     }
 
@@ -112,12 +106,32 @@ impl ScreenCaptures {
             .collect();
         let num_displays = display_targets.len().max(1);
 
-        // Adaptive frame rate for commercial compatibility (all XREAL models)
-        let target_fps = Self::detect_optimal_framerate();
+        // Until glasses connect, capture at the rate every model supports
+        let target = display_targets.first().cloned();
+        let fps = DeviceProfile::GENERIC.base_refresh_rate();
+        let capturer = Self::build_capturer(target.clone(), fps)?;
 
+        // Pre-allocate buffer for 4K RGBA (worst case) to avoid hot-path allocations
+        const MAX_BUFFER_SIZE: usize = 3840 * 2160 * 4; // 4K RGBA
+        let rgba_buffer = Vec::with_capacity(MAX_BUFFER_SIZE);
+
+        Ok(Self {
+            num_streams: num_displays,
+            capturer: Some(capturer),
+            target,
+            fps,
+            rgba_buffer,
+            buffer_capacity: MAX_BUFFER_SIZE,
+            capture_requested: false,
+            paused: false,
+        })
+    }
+
+    /// Build and start a capturer for the display at the given rate
+    fn build_capturer(target: Option<scap::Target>, fps: u32) -> Result<Capturer> {
         let options = Options {
-            fps: target_fps, // Adaptive: 120Hz for Pro, 72Hz for Air, 60Hz fallback
-            target: display_targets.first().cloned(), // Use first display
+            fps,
+            target, // Use first display
             show_cursor: true,
             show_highlight: false,
             excluded_targets: None,
@@ -132,66 +146,42 @@ impl ScreenCaptures {
 
         // Start capture immediately for minimal latency
         capturer.start_capture();
-
-        // Pre-allocate buffer for 4K RGBA (worst case) to avoid hot-path allocations
-        const MAX_BUFFER_SIZE: usize = 3840 * 2160 * 4; // 4K RGBA
-        let rgba_buffer = Vec::with_capacity(MAX_BUFFER_SIZE);
-
-        Ok(Self {
-            num_streams: num_displays,
-            capturer: Some(capturer),
-            rgba_buffer,
-            buffer_capacity: MAX_BUFFER_SIZE,
-            capture_requested: false,
-        })
+        Ok(capturer)
     }
 
-    /// Detect optimal frame rate for XREAL 2 series and other models
-    /// Returns a future that resolves to the optimal framerate
-    async fn detect_optimal_framerate_async() -> u32 {
-        use bevy::tasks::AsyncComputeTaskPool;
-
-        let task_pool = AsyncComputeTaskPool::get();
-        let task = task_pool.spawn(async {
-            // Try to detect display refresh rate for optimal performance
-            // Priority: 120Hz (XREAL 2 Pro), 90Hz (XREAL 2), 72Hz (Air), 60Hz (fallback)
-            if let Ok(output) = async_process::Command::new("system_profiler")
-                .args(&["SPDisplaysDataType"])
-                .output()
-                .await
-            {
-                let display_info = String::from_utf8_lossy(&output.stdout);
-
-                // Check for high refresh rate capabilities
-                if display_info.contains("120") || display_info.contains(" 120 ") {
-                    return 120; // XREAL 2 Pro
-                } else if display_info.contains("90") || display_info.contains(" 90 ") {
-                    return 90; // XREAL 2
-                } else if display_info.contains("72") || display_info.contains(" 72 ") {
-                    return 72; // XREAL Air series
-                }
-            }
+    /// Current capture rate in frames per second
+    #[inline]
+    pub fn frame_rate(&self) -> u32 {
+        self.fps
+    }
 
-            // Safe fallback for all models
-            60
-        });
+    /// Restart capture at a new rate, e.g. to match the glasses refresh rate
+    pub fn set_frame_rate(&mut self, fps: u32) -> Result<()> {
+        if fps == self.fps {
+            return Ok(());
+        }
+        if let Some(mut capturer) = self.capturer.take() {
+            capturer.stop_capture();
+            self.capturer = Some(Self::build_capturer(self.target.clone(), fps)?);
+        }
+        self.fps = fps;
+        Ok(())
+    }
 
-        task.await
+    /// Pause or resume frame capture
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
     }
 
-    /// Synchronous wrapper that provides fallback when async detection isn't available
-    #[inline]
-    #[allow(dead_code)]
-    fn detect_optimal_framerate() -> u32 {
-        // Safe fallback for synchronous initialization
-        // The async version should be preferred when possible
-        72 // Conservative default for XREAL Air series compatibility
+    /// True while frame capture is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
     }
 
     /// Spawn async capture task for non-blocking screen capture
     #[allow(dead_code)]
     pub fn spawn_capture_task(&self, entity: Entity) -> Option<CaptureTask> {
-        if self.capturer.is_none() {
+        if self.capturer.is_none() || self.paused {
             return None;
         }
 
@@ -480,12 +470,30 @@ impl ScreenCaptures {
     }
 }
 
+/// Capture at the refresh rate of the connected glasses
+pub fn match_capture_frame_rate(
+    device_info: Res<DeviceInfo>,
+    mut screen_captures: ResMut<ScreenCaptures>,
+) {
+    let fps = device_info.refresh_rate();
+    if fps == screen_captures.frame_rate() {
+        return;
+    }
+    match screen_captures.set_frame_rate(fps) {
+        Ok(()) => info!("🎞️ Screen capture running at {} fps", fps),
+        Err(e) => error!("❌ Failed to change capture rate to {} fps: {}", fps, e),
+    }
+}
+
 impl Default for ScreenCaptures {
     fn default() -> Self {
         Self {
             num_streams: 0,
             capture_requested: false,
+            paused: false,
             capturer: None,
+            target: None,
+            fps: DeviceProfile::GENERIC.base_refresh_rate(),
             rgba_buffer: Vec::new(),
             buffer_capacity: 0,
         }
@@ -1,4 +1,6 @@
+use crate::neck;
 use crate::render::VirtualScreen;
+use crate::state::schema::core::PersistentAppState;
 use crate::tracking::Orientation;
 use bevy::prelude::*;
 
@@ -77,6 +79,7 @@ pub fn update_head_cursor(
     mut cursor_query: Query<(&mut Transform, &mut HeadCursor)>,
     mut cursor_state: ResMut<CursorState>,
     orientation: Res<Orientation>,
+    persistent_state: Res<PersistentAppState>,
     virtual_screens: Query<(&Transform, &VirtualScreen)>,
     time: Res<Time>,
 ) {
@@ -90,7 +93,7 @@ pub fn update_head_cursor(
 
     // Use real head tracking data for cursor positioning
     let head_rotation = orientation.quat;
-    let head_position = Vec3::ZERO;
+    let head_position = neck::eye_position(head_rotation, &persistent_state.tracking.neck_model);
 
     // Cast ray from head position in head direction
     let ray_origin = head_position;
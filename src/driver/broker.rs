@@ -0,0 +1,467 @@
+//! Device broker thread
+//!
+//! A single thread owns the `Box<dyn ARGlasses>` handle, reads events in a
+//! blocking loop and fans them out over typed channels:
+//! - IMU samples for the tracking thread
+//! - Key presses and proximity changes for input handling
+//! - Connection and display status for the Bevy app
+//!
+//! Display and recording commands are sent back to the broker over a channel,
+//! so nothing else ever touches the glasses handle.
+//!
+//! The broker also drives the connection state machine
+//! (Disconnected → Probing → Connected → Lost → Probing ...), re-probing
+//! with exponential backoff until the glasses come back.
+
+use super::optics::DisplayOptics;
+use super::profile::{DeviceProfile, ImuAxes};
+use super::recording::{RecordedCalibration, RecordingHeader, SessionRecorder};
+use super::{open_glasses, GlassesSource};
+use anyhow::Result;
+use ar_drivers::{ARGlasses, DisplayMode, GlassesEvent};
+use bevy::math::Vec3;
+use crossbeam_channel::{
+    bounded, unbounded, Receiver, RecvTimeoutError, Sender, TryRecvError, TrySendError,
+};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Capacity of the IMU channel (about one second at 1 kHz)
+pub const IMU_CHANNEL_CAPACITY: usize = 1024;
+
+/// Capacity of the low-rate key and proximity channels
+/// Status is never dropped, its channel is unbounded
+pub const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// IMU sample forwarded to the tracking thread
+///
+/// Units follow ar-drivers: m/s², rad/s, µT and device timestamps in µs.
+/// `received` is when the broker read the sample, on the host clock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImuEvent {
+    AccGyro {
+        accelerometer: Vec3,
+        gyroscope: Vec3,
+        timestamp: u64,
+        received: Instant,
+    },
+    Magnetometer {
+        magnetometer: Vec3,
+        timestamp: u64,
+        received: Instant,
+    },
+}
+
+/// Key press on the glasses, stamped when the broker received it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: u8,
+    pub at: Instant,
+}
+
+/// Proximity sensor change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProximityEvent {
+    Near,
+    Far,
+}
+
+/// Static information about the connected glasses
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceIdentity {
+    pub name: &'static str,
+    pub serial: String,
+    /// Half horizontal display FOV in radians
+    pub display_fov: f32,
+    /// Display delay in microseconds
+    pub display_delay_us: u64,
+    pub display_mode: Option<DisplayMode>,
+    /// Per-eye projection and pose
+    pub optics: DisplayOptics,
+}
+
+/// Connection and display status reported by the broker
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceStatus {
+    /// Trying to open the glasses (1-based attempt since the last connection)
+    Probing {
+        attempt: u32,
+    },
+    Connected(DeviceIdentity),
+    /// Opening failed; the next probe happens after `retry_in`, if at all
+    Disconnected {
+        reason: String,
+        retry_in: Option<Duration>,
+    },
+    /// A connected device stopped responding
+    Lost {
+        reason: String,
+    },
+    DisplayModeChanged(DisplayMode),
+    DisplayModeFailed(String),
+}
+
+/// Exponential backoff between probe attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f32,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(8),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    /// Delay after the given failed attempt (1-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let scaled = self.initial.as_secs_f32() * self.multiplier.max(1.0).powi(exponent);
+        Duration::from_secs_f32(scaled.min(self.max.as_secs_f32()))
+    }
+}
+
+/// Broker behaviour when the glasses are missing or go away
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrokerConfig {
+    /// Keep probing after a failed open or a lost connection
+    pub reconnect: bool,
+    pub backoff: ReconnectBackoff,
+}
+
+impl Default for BrokerConfig {
+    fn default() -> Self {
+        Self {
+            reconnect: true,
+            backoff: ReconnectBackoff::default(),
+        }
+    }
+}
+
+/// Requests handled by the broker thread
+#[derive(Debug, Clone)]
+pub enum DeviceCommand {
+    SetDisplayMode(DisplayMode),
+    /// Record the raw event stream with the calibration in use
+    StartRecording {
+        path: PathBuf,
+        calibration: RecordedCalibration,
+    },
+    StopRecording,
+    Shutdown,
+}
+
+/// Channel endpoints of a running broker thread
+pub struct DeviceBroker {
+    pub imu: Receiver<ImuEvent>,
+    pub keys: Receiver<KeyEvent>,
+    pub proximity: Receiver<ProximityEvent>,
+    pub status: Receiver<DeviceStatus>,
+    pub commands: Sender<DeviceCommand>,
+    pub thread: JoinHandle<()>,
+}
+
+impl DeviceBroker {
+    /// Spawn the broker for the given glasses source
+    /// Replays end with their recording, so they are never re-probed
+    pub fn spawn(source: GlassesSource) -> Result<Self> {
+        let config = BrokerConfig {
+            reconnect: !matches!(source, GlassesSource::Replay { .. }),
+            ..Default::default()
+        };
+        // Only the first failed probe explains itself, retries stay quiet
+        let mut diagnosed = false;
+        Self::spawn_with(
+            move || {
+                let glasses = open_glasses(&source, !diagnosed);
+                diagnosed |= glasses.is_err();
+                glasses
+            },
+            config,
+        )
+    }
+
+    /// Spawn the broker with a custom way of (re)opening the glasses
+    pub fn spawn_with<F>(open: F, config: BrokerConfig) -> Result<Self>
+    where
+        F: FnMut() -> Result<Box<dyn ARGlasses>> + Send + 'static,
+    {
+        let (imu_tx, imu) = bounded(IMU_CHANNEL_CAPACITY);
+        let (keys_tx, keys) = bounded(EVENT_CHANNEL_CAPACITY);
+        let (proximity_tx, proximity) = bounded(EVENT_CHANNEL_CAPACITY);
+        let (status_tx, status) = unbounded();
+        let (commands, commands_rx) = bounded(EVENT_CHANNEL_CAPACITY);
+
+        let outputs = BrokerOutputs {
+            imu: imu_tx,
+            stale_imu: imu.clone(),
+            keys: keys_tx,
+            proximity: proximity_tx,
+            status: status_tx,
+        };
+
+        let thread = std::thread::Builder::new()
+            .name("glasses-broker".into())
+            .spawn(move || run_broker(open, config, &outputs, &commands_rx))?;
+
+        Ok(Self {
+            imu,
+            keys,
+            proximity,
+            status,
+            commands,
+            thread,
+        })
+    }
+}
+
+/// Sending halves owned by the broker thread
+struct BrokerOutputs {
+    imu: Sender<ImuEvent>,
+    /// Receiving end of the IMU channel, to drop the oldest sample when it is full
+    stale_imu: Receiver<ImuEvent>,
+    keys: Sender<KeyEvent>,
+    proximity: Sender<ProximityEvent>,
+    status: Sender<DeviceStatus>,
+}
+
+impl BrokerOutputs {
+    #[inline]
+    fn send_status(&self, status: DeviceStatus) {
+        // Consumers poll once per frame; every connection transition counts
+        let _ = self.status.send(status);
+    }
+
+    /// Route one glasses event to its typed channel
+    /// IMU samples are turned from the model's sensor axes into Bevy's frame
+    fn dispatch(&self, event: GlassesEvent, axes: &ImuAxes) {
+        let received = Instant::now();
+        match event {
+            GlassesEvent::AccGyro {
+                accelerometer,
+                gyroscope,
+                timestamp,
+            } => self.send_imu(ImuEvent::AccGyro {
+                accelerometer: axes.to_bevy(Vec3::new(
+                    accelerometer.x,
+                    accelerometer.y,
+                    accelerometer.z,
+                )),
+                gyroscope: axes.to_bevy(Vec3::new(gyroscope.x, gyroscope.y, gyroscope.z)),
+                timestamp,
+                received,
+            }),
+            GlassesEvent::Magnetometer {
+                magnetometer,
+                timestamp,
+            } => self.send_imu(ImuEvent::Magnetometer {
+                magnetometer: axes.to_bevy(Vec3::new(
+                    magnetometer.x,
+                    magnetometer.y,
+                    magnetometer.z,
+                )),
+                timestamp,
+                received,
+            }),
+            GlassesEvent::KeyPress(key) => {
+                let _ = self.keys.try_send(KeyEvent { key, at: received });
+            }
+            GlassesEvent::ProximityNear => {
+                let _ = self.proximity.try_send(ProximityEvent::Near);
+            }
+            GlassesEvent::ProximityFar => {
+                let _ = self.proximity.try_send(ProximityEvent::Far);
+            }
+            GlassesEvent::AmbientLight(_) | GlassesEvent::VSync => {}
+        }
+    }
+
+    #[inline]
+    fn send_imu(&self, event: ImuEvent) {
+        let mut event = event;
+        loop {
+            match self.imu.try_send(event) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                Err(TrySendError::Full(rejected)) => {
+                    // Tracking fell behind; newer samples matter more than old ones
+                    bevy::log::warn_once!("⚠️  IMU channel full, dropping the oldest samples");
+                    let _ = self.stale_imu.try_recv();
+                    event = rejected;
+                }
+            }
+        }
+    }
+}
+
+/// How a connected session ended
+enum SessionEnd {
+    Shutdown,
+    Lost(String),
+}
+
+/// Connection state machine: probe, read until lost, back off, probe again
+fn run_broker<F>(
+    mut open: F,
+    config: BrokerConfig,
+    outputs: &BrokerOutputs,
+    commands: &Receiver<DeviceCommand>,
+) where
+    F: FnMut() -> Result<Box<dyn ARGlasses>>,
+{
+    let mut attempt = 0u32;
+    loop {
+        attempt = attempt.saturating_add(1);
+        outputs.send_status(DeviceStatus::Probing { attempt });
+
+        match open() {
+            Ok(glasses) => {
+                attempt = 0;
+                match run_connected(glasses, outputs, commands) {
+                    SessionEnd::Shutdown => return,
+                    SessionEnd::Lost(reason) => {
+                        outputs.send_status(DeviceStatus::Lost { reason });
+                        if !config.reconnect {
+                            return;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                let retry_in = config.reconnect.then(|| config.backoff.delay(attempt));
+                outputs.send_status(DeviceStatus::Disconnected {
+                    reason: e.to_string(),
+                    retry_in,
+                });
+                match retry_in {
+                    Some(delay) if wait_for_retry(commands, outputs, delay) => {}
+                    _ => return,
+                }
+            }
+        }
+    }
+}
+
+/// Wait out a backoff delay while still answering commands
+/// Returns false if the broker should shut down instead of retrying
+fn wait_for_retry(
+    commands: &Receiver<DeviceCommand>,
+    outputs: &BrokerOutputs,
+    delay: Duration,
+) -> bool {
+    let deadline = Instant::now() + delay;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match commands.recv_timeout(remaining) {
+            Ok(DeviceCommand::Shutdown) | Err(RecvTimeoutError::Disconnected) => return false,
+            Ok(DeviceCommand::SetDisplayMode(_)) => {
+                outputs.send_status(DeviceStatus::DisplayModeFailed(
+                    "Glasses not connected".to_string(),
+                ));
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => return true,
+        }
+    }
+}
+
+/// Blocking read loop owning the glasses until they are lost or shut down
+fn run_connected(
+    mut glasses: Box<dyn ARGlasses>,
+    outputs: &BrokerOutputs,
+    commands: &Receiver<DeviceCommand>,
+) -> SessionEnd {
+    let identity = DeviceIdentity {
+        name: glasses.name(),
+        serial: glasses.serial().unwrap_or_default(),
+        display_fov: glasses.display_fov(),
+        display_delay_us: glasses.display_delay(),
+        display_mode: glasses.get_display_mode().ok(),
+        optics: DisplayOptics::from_glasses(glasses.as_ref()),
+    };
+    bevy::log::info!(
+        "🥽 Device broker connected to {} ({})",
+        identity.name,
+        identity.serial
+    );
+    let axes = DeviceProfile::for_model(identity.name).imu_axes;
+    outputs.send_status(DeviceStatus::Connected(identity));
+
+    let mut recorder: Option<SessionRecorder<BufWriter<File>>> = None;
+
+    loop {
+        loop {
+            match commands.try_recv() {
+                Ok(DeviceCommand::Shutdown) | Err(TryRecvError::Disconnected) => {
+                    finish_recording(recorder.take());
+                    return SessionEnd::Shutdown;
+                }
+                Ok(command) => handle_command(command, glasses.as_mut(), outputs, &mut recorder),
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        match glasses.read_event() {
+            Ok(event) => {
+                if let Some(rec) = recorder.as_mut() {
+                    if let Err(e) = rec.record(&event) {
+                        bevy::log::error!("❌ IMU recording stopped: {}", e);
+                        recorder = None;
+                    }
+                }
+                outputs.dispatch(event, &axes);
+            }
+            Err(e) => {
+                bevy::log::warn!("⚠️  Glasses read failed: {}", e);
+                finish_recording(recorder.take());
+                return SessionEnd::Lost(e.to_string());
+            }
+        }
+    }
+}
+
+fn handle_command(
+    command: DeviceCommand,
+    glasses: &mut dyn ARGlasses,
+    outputs: &BrokerOutputs,
+    recorder: &mut Option<SessionRecorder<BufWriter<File>>>,
+) {
+    match command {
+        DeviceCommand::SetDisplayMode(mode) => match glasses.set_display_mode(mode) {
+            Ok(()) => outputs.send_status(DeviceStatus::DisplayModeChanged(mode)),
+            Err(e) => outputs.send_status(DeviceStatus::DisplayModeFailed(e.to_string())),
+        },
+        DeviceCommand::StartRecording { path, calibration } => {
+            finish_recording(recorder.take());
+            let header = RecordingHeader::for_glasses(glasses).with_calibration(calibration);
+            match SessionRecorder::create(&path, &header) {
+                Ok(rec) => {
+                    bevy::log::info!("📼 Recording IMU session to {}", path.display());
+                    *recorder = Some(rec);
+                }
+                Err(e) => bevy::log::error!("❌ Failed to start IMU recording: {}", e),
+            }
+        }
+        DeviceCommand::StopRecording => finish_recording(recorder.take()),
+        DeviceCommand::Shutdown => {}
+    }
+}
+
+fn finish_recording(recorder: Option<SessionRecorder<BufWriter<File>>>) {
+    if let Some(rec) = recorder {
+        let count = rec.event_count();
+        match rec.finish() {
+            Ok(_) => bevy::log::info!("📼 IMU recording saved ({} events)", count),
+            Err(e) => bevy::log::error!("❌ Failed to finish IMU recording: {}", e),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Glasses connection state for the Bevy app
+//!
+//! The device broker drives the actual state machine and re-probing; this
+//! module mirrors its status into [`XRealDevice`], emits a
+//! [`GlassesConnectionEvent`] on every transition and restores display mode
+//! and calibration once the glasses come back.
+
+use super::profile::sync_device_info;
+use super::{DeviceStatus, XRealDevice, XRealDisplayMode};
+use crate::tracking::{send_queued, Command};
+use crate::{CommandChannel, DisplayModeState};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Connection state of the glasses as seen by the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    Probing,
+    Connected,
+    Lost,
+}
+
+impl ConnectionState {
+    /// Short label for status displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectionState::Disconnected => "Disconnected",
+            ConnectionState::Probing => "Searching...",
+            ConnectionState::Connected => "Connected",
+            ConnectionState::Lost => "Connection lost",
+        }
+    }
+}
+
+/// Emitted on every glasses connection state transition
+#[derive(Event, Debug, Clone)]
+pub enum GlassesConnectionEvent {
+    Probing {
+        attempt: u32,
+    },
+    Connected {
+        name: &'static str,
+        serial: String,
+        /// True if the glasses were connected before in this session
+        reconnected: bool,
+    },
+    Disconnected {
+        reason: String,
+        retry_in: Option<Duration>,
+    },
+    Lost {
+        reason: String,
+    },
+}
+
+impl GlassesConnectionEvent {
+    /// Connection state entered by this transition
+    pub fn state(&self) -> ConnectionState {
+        match self {
+            GlassesConnectionEvent::Probing { .. } => ConnectionState::Probing,
+            GlassesConnectionEvent::Connected { .. } => ConnectionState::Connected,
+            GlassesConnectionEvent::Disconnected { .. } => ConnectionState::Disconnected,
+            GlassesConnectionEvent::Lost { .. } => ConnectionState::Lost,
+        }
+    }
+
+    /// Build the transition event for a broker status message, if it is one
+    pub fn from_status(status: &DeviceStatus, was_ever_connected: bool) -> Option<Self> {
+        match status {
+            DeviceStatus::Probing { attempt } => {
+                Some(GlassesConnectionEvent::Probing { attempt: *attempt })
+            }
+            DeviceStatus::Connected(identity) => Some(GlassesConnectionEvent::Connected {
+                name: identity.name,
+                serial: identity.serial.clone(),
+                reconnected: was_ever_connected,
+            }),
+            DeviceStatus::Disconnected { reason, retry_in } => {
+                Some(GlassesConnectionEvent::Disconnected {
+                    reason: reason.clone(),
+                    retry_in: *retry_in,
+                })
+            }
+            DeviceStatus::Lost { reason } => Some(GlassesConnectionEvent::Lost {
+                reason: reason.clone(),
+            }),
+            DeviceStatus::DisplayModeChanged(_) | DeviceStatus::DisplayModeFailed(_) => None,
+        }
+    }
+}
+
+/// Registers connection monitoring and reconnect handling
+pub struct GlassesConnectionPlugin;
+
+impl Plugin for GlassesConnectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GlassesConnectionEvent>().add_systems(
+            Update,
+            (
+                monitor_glasses_connection,
+                restore_state_on_reconnect.after(monitor_glasses_connection),
+                sync_device_info.after(monitor_glasses_connection),
+            ),
+        );
+    }
+}
+
+/// Apply broker status updates and emit transition events
+pub fn monitor_glasses_connection(
+    xreal_device: Option<ResMut<XRealDevice>>,
+    mut connection_events: EventWriter<GlassesConnectionEvent>,
+) {
+    let Some(mut device) = xreal_device else {
+        return;
+    };
+
+    for event in device.sync_status() {
+        match &event {
+            GlassesConnectionEvent::Probing { attempt } => {
+                info!("🔍 Probing for glasses (attempt {})", attempt)
+            }
+            GlassesConnectionEvent::Connected { name, serial, .. } => {
+                info!("✅ Glasses connected: {} ({})", name, serial)
+            }
+            GlassesConnectionEvent::Disconnected { reason, retry_in } => match retry_in {
+                Some(delay) => info!(
+                    "🔌 Glasses not available: {} (retrying in {:.1}s)",
+                    reason,
+                    delay.as_secs_f32()
+                ),
+                None => info!("🔌 Glasses not available: {}", reason),
+            },
+            GlassesConnectionEvent::Lost { reason } => {
+                warn!("⚠️  Glasses connection lost: {}", reason)
+            }
+        }
+        connection_events.write(event);
+    }
+}
+
+/// Restore the display mode and restart tracking after the glasses reconnect
+pub fn restore_state_on_reconnect(
+    mut connection_events: EventReader<GlassesConnectionEvent>,
+    xreal_device: Option<ResMut<XRealDevice>>,
+    display_mode: Res<DisplayModeState>,
+    command_sender: Res<CommandChannel>,
+    mut queued: Local<Vec<Command>>,
+) {
+    // A full command channel is retried on the next frame
+    send_queued(&command_sender.0, &mut queued);
+
+    let mut reconnected = false;
+    for event in connection_events.read() {
+        if let GlassesConnectionEvent::Connected {
+            reconnected: true, ..
+        } = event
+        {
+            reconnected = true;
+        }
+    }
+    if !reconnected {
+        return;
+    }
+
+    if let Some(mut device) = xreal_device {
+        let mode = if display_mode.is_3d_enabled {
+            XRealDisplayMode::Stereo
+        } else {
+            XRealDisplayMode::Mirror
+        };
+        if let Err(e) = device.set_display_mode(mode) {
+            error!("❌ Failed to restore display mode: {}", e);
+        }
+    }
+
+    // The gyro bias refined this session stays in use, another pair's
+    // record is applied by the calibration systems
+    queued.push(Command::Restart);
+    send_queued(&command_sender.0, &mut queued);
+}
@@ -0,0 +1,458 @@
+use anyhow::Result;
+use ar_drivers::{any_glasses, ARGlasses, DisplayMode};
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
+use std::path::PathBuf;
+
+pub mod broker;
+pub mod connection;
+pub mod optics;
+pub mod profile;
+pub mod proximity;
+pub mod recording;
+pub mod simulated;
+
+pub use broker::{
+    BrokerConfig, DeviceBroker, DeviceCommand, DeviceIdentity, DeviceStatus, ImuEvent, KeyEvent,
+    ProximityEvent, ReconnectBackoff,
+};
+pub use connection::{ConnectionState, GlassesConnectionEvent, GlassesConnectionPlugin};
+pub use optics::{DisplayOptics, DisplayProfile, EyeOptics, OpticsSource};
+pub use profile::{DeviceInfo, DeviceProfile, ImuProfile};
+pub use proximity::{
+    AutoPauseEvent, GlassesPresence, GlassesPresenceEvent, GlassesProximityPlugin,
+};
+pub use recording::{ReplayGlasses, ReplaySpeed, ReplayStepper, SessionRecorder};
+pub use simulated::{SimulatedGlasses, SimulationConfig};
+
+/// Where glasses events come from, selected once at startup
+#[derive(Resource, Debug, Clone, Default)]
+pub enum GlassesSource {
+    /// Physical glasses detected through ar-drivers
+    #[default]
+    Hardware,
+    /// Synthetic glasses driven by a motion script
+    Simulated(SimulationConfig),
+    /// Previously recorded IMU session
+    /// `stepper` advances the replay when `speed` is [`ReplaySpeed::Stepped`]
+    Replay {
+        path: PathBuf,
+        speed: ReplaySpeed,
+        stepper: ReplayStepper,
+    },
+}
+
+impl GlassesSource {
+    /// Select the source from the command line and environment
+    /// `--replay <file>` or `XREAL_REPLAY=<file>` replays a recording at
+    /// `XREAL_REPLAY_SPEED` (`realtime`, `step`, `max` or a multiplier).
+    /// `--simulate` or `XREAL_GLASSES=sim` selects the simulator,
+    /// configured through `XREAL_SIM_*` variables
+    pub fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let replay_path = args
+            .iter()
+            .position(|arg| arg == "--replay")
+            .and_then(|i| args.get(i + 1).cloned())
+            .or_else(|| std::env::var("XREAL_REPLAY").ok());
+        if let Some(path) = replay_path {
+            let speed = std::env::var("XREAL_REPLAY_SPEED")
+                .ok()
+                .and_then(|v| ReplaySpeed::parse(&v))
+                .unwrap_or_default();
+            return GlassesSource::Replay {
+                path: PathBuf::from(path),
+                speed,
+                stepper: ReplayStepper::default(),
+            };
+        }
+
+        let simulate_arg = args.iter().any(|arg| arg == "--simulate");
+        let simulate_env = std::env::var("XREAL_GLASSES")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "sim" | "simulated"))
+            .unwrap_or(false);
+
+        if simulate_arg || simulate_env {
+            GlassesSource::Simulated(SimulationConfig::from_env())
+        } else {
+            GlassesSource::Hardware
+        }
+    }
+
+    /// Check if this source uses synthetic or recorded data
+    #[inline]
+    pub fn is_simulated(&self) -> bool {
+        !matches!(self, GlassesSource::Hardware)
+    }
+
+    /// Handle for advancing the replay, if this is a stepped replay
+    pub fn replay_stepper(&self) -> Option<ReplayStepper> {
+        match self {
+            GlassesSource::Replay {
+                speed: ReplaySpeed::Stepped,
+                stepper,
+                ..
+            } => Some(stepper.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Run condition: the glasses are physical ones
+/// Calibration records belong to real glasses, so simulated and replayed
+/// sessions neither switch nor write them. Without the resource the glasses
+/// are taken to be physical
+pub fn hardware_source(source: Option<Res<GlassesSource>>) -> bool {
+    source.is_none_or(|source| !source.is_simulated())
+}
+
+/// Run condition: calibrations persisted in the app state apply to the glasses
+/// Replays run with the calibration recorded in their header instead
+pub fn persisted_calibration_applies(source: Option<Res<GlassesSource>>) -> bool {
+    !matches!(source.as_deref(), Some(GlassesSource::Replay { .. }))
+}
+
+/// Steps a replay started with `XREAL_REPLAY_SPEED=step` from the app
+#[derive(Resource, Debug, Clone)]
+pub struct ReplayStepControl(pub ReplayStepper);
+
+/// Open glasses from the given source
+/// `verbose` logs troubleshooting steps if physical glasses are not found
+pub fn open_glasses(source: &GlassesSource, verbose: bool) -> Result<Box<dyn ARGlasses>> {
+    match source {
+        GlassesSource::Hardware => detect_hardware_glasses(verbose),
+        GlassesSource::Simulated(config) => {
+            println!("   🧪 Using simulated glasses (no hardware required)");
+            Ok(Box::new(SimulatedGlasses::new(config.clone())))
+        }
+        GlassesSource::Replay {
+            path,
+            speed,
+            stepper,
+        } => {
+            println!("   📼 Replaying IMU session from {}", path.display());
+            Ok(Box::new(
+                ReplayGlasses::open(path, *speed)?.with_stepper(stepper.clone()),
+            ))
+        }
+    }
+}
+
+/// Zero-allocation XREAL device handle for the Bevy app
+/// The glasses themselves are owned by the device broker thread;
+/// this resource tracks the reported connection state and forwards display commands
+#[derive(Resource)]
+pub struct XRealDevice {
+    commands: Sender<DeviceCommand>,
+    status: Receiver<DeviceStatus>,
+    identity: Option<DeviceIdentity>,
+    connection: ConnectionState,
+    last_error: Option<String>,
+    was_ever_connected: bool,
+    stereo_enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub enum XRealDisplayMode {
+    Mirror, // 2D mirrored display
+    Stereo, // 3D stereo AR display
+    Off,    // Display disabled
+}
+
+impl Default for XRealDisplayMode {
+    #[inline]
+    fn default() -> Self {
+        XRealDisplayMode::Stereo
+    }
+}
+
+impl XRealDevice {
+    /// Create a device handle from the broker's command and status channels
+    #[inline]
+    pub fn new(commands: Sender<DeviceCommand>, status: Receiver<DeviceStatus>) -> Self {
+        Self {
+            commands,
+            status,
+            identity: None,
+            connection: ConnectionState::Disconnected,
+            last_error: None,
+            was_ever_connected: false,
+            stereo_enabled: false,
+        }
+    }
+
+    /// Request a display mode change from the broker
+    /// Supports stereo AR mode for 3D desktop experience
+    #[inline]
+    pub fn set_display_mode(&mut self, mode: XRealDisplayMode) -> Result<()> {
+        let ar_mode = match mode {
+            XRealDisplayMode::Stereo => DisplayMode::Stereo,
+            XRealDisplayMode::Mirror => DisplayMode::SameOnBoth,
+            XRealDisplayMode::Off => DisplayMode::SameOnBoth, // Fallback
+        };
+        if let Some(identity) = &self.identity {
+            if !DeviceProfile::for_model(identity.name).supports_mode(ar_mode) {
+                return Err(anyhow::anyhow!(
+                    "{} does not support {:?} display mode",
+                    identity.name,
+                    ar_mode
+                ));
+            }
+        }
+        self.send_command(DeviceCommand::SetDisplayMode(ar_mode))
+    }
+
+    /// Send a command to the broker thread
+    #[inline]
+    pub fn send_command(&self, command: DeviceCommand) -> Result<()> {
+        self.commands
+            .try_send(command)
+            .map_err(|e| anyhow::anyhow!("Device broker unavailable: {}", e))
+    }
+
+    /// Optics of the connected glasses, or the generic profile before any connect
+    #[inline]
+    pub fn optics(&self) -> DisplayOptics {
+        self.identity
+            .as_ref()
+            .map(|identity| identity.optics)
+            .unwrap_or_default()
+    }
+
+    /// Check if stereo mode is enabled
+    #[inline]
+    pub fn is_stereo_enabled(&self) -> bool {
+        self.stereo_enabled
+    }
+
+    /// Check if glasses are connected
+    #[inline]
+    pub fn is_connected(&self) -> bool {
+        self.connection == ConnectionState::Connected
+    }
+
+    /// Current connection state reported by the broker
+    #[inline]
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection
+    }
+
+    /// Reason for the most recent failed probe or lost connection
+    #[inline]
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Identity of the connected glasses, if any
+    #[inline]
+    pub fn identity(&self) -> Option<&DeviceIdentity> {
+        self.identity.as_ref()
+    }
+
+    /// Apply pending status updates from the broker without blocking
+    /// Returns the connection transitions in the order they happened
+    pub fn sync_status(&mut self) -> Vec<GlassesConnectionEvent> {
+        let mut transitions = Vec::new();
+        while let Ok(status) = self.status.try_recv() {
+            if let Some(event) =
+                GlassesConnectionEvent::from_status(&status, self.was_ever_connected)
+            {
+                self.connection = event.state();
+                transitions.push(event);
+            }
+
+            match status {
+                DeviceStatus::Probing { .. } => {}
+                DeviceStatus::Connected(identity) => {
+                    self.stereo_enabled = identity.display_mode.is_some_and(is_stereo_mode);
+                    self.identity = Some(identity);
+                    self.last_error = None;
+                    self.was_ever_connected = true;
+                }
+                DeviceStatus::Disconnected { reason, .. } | DeviceStatus::Lost { reason } => {
+                    self.stereo_enabled = false;
+                    self.last_error = Some(reason);
+                }
+                DeviceStatus::DisplayModeChanged(mode) => {
+                    self.stereo_enabled = is_stereo_mode(mode);
+                    if let Some(identity) = self.identity.as_mut() {
+                        identity.display_mode = Some(mode);
+                    }
+                }
+                DeviceStatus::DisplayModeFailed(reason) => {
+                    error!("❌ Display mode change failed: {}", reason);
+                }
+            }
+        }
+        transitions
+    }
+}
+
+/// Key press and proximity events fanned out by the device broker
+#[derive(Resource)]
+pub struct GlassesInputChannels {
+    pub keys: Receiver<KeyEvent>,
+    pub proximity: Receiver<ProximityEvent>,
+}
+
+#[inline]
+fn is_stereo_mode(mode: DisplayMode) -> bool {
+    !matches!(mode, DisplayMode::SameOnBoth | DisplayMode::HighRefreshRate)
+}
+
+/// Debug function to provide detailed information about glasses detection
+/// This function helps diagnose why `any_glasses()` might be failing
+#[allow(dead_code)]
+fn debug_glasses_detection_process() {
+    println!("🔍 Debugging glasses detection process...");
+
+    // ar-drivers handles feature detection internally
+    println!("   📦 ar-drivers features enabled in Cargo.toml: nreal, rokid, mad_gaze, grawoow");
+
+    // Try to enumerate USB devices using hidapi directly
+    match hidapi::HidApi::new() {
+        Ok(api) => {
+            println!("   ✅ hidapi initialized successfully");
+
+            // Look for devices that might be AR glasses
+            let mut found_potential_devices = false;
+            for device_info in api.device_list() {
+                let vendor_id = device_info.vendor_id();
+                let product_id = device_info.product_id();
+
+                // Known AR glasses vendor/product IDs
+                let is_potential_ar_device =
+                    crate::setup::hidraw::device_name(vendor_id, product_id).is_some();
+
+                if is_potential_ar_device {
+                    found_potential_devices = true;
+                    println!(
+                        "   🎯 Found potential AR device: VID={:04x}, PID={:04x}",
+                        vendor_id, product_id
+                    );
+                    if let Some(manufacturer) = device_info.manufacturer_string() {
+                        println!("      Manufacturer: {}", manufacturer);
+                    }
+                    if let Some(product) = device_info.product_string() {
+                        println!("      Product: {}", product);
+                    }
+                }
+            }
+
+            if !found_potential_devices {
+                println!("   ⚠️  No potential AR devices found in HID enumeration");
+                println!("      This could mean:");
+                println!("      - Glasses are not connected");
+                println!("      - USB permissions issue");
+                println!("      - Device not recognized by HID subsystem");
+            }
+        }
+        Err(e) => {
+            println!("   ❌ Failed to initialize hidapi: {}", e);
+            println!("      This suggests a system-level HID issue");
+        }
+    }
+
+    // Provide macOS-specific guidance without system calls
+    #[cfg(target_os = "macos")]
+    {
+        println!("   🍎 Running on macOS - checking for common USB permission issues");
+        println!("      🔒 IMPORTANT: XREAL glasses may require accessibility permissions");
+        println!("      📝 Go to System Settings → Privacy & Security → Accessibility");
+        println!("      📝 Add this application to the allowed list and restart");
+    }
+
+    // ar-drivers features are handled internally by the library
+    println!("   📋 ar-drivers features configured: nreal, rokid, mad_gaze, grawoow");
+
+    println!("   💡 Now attempting ar-drivers detection...");
+}
+
+/// Detect physical glasses
+/// With `verbose` set a failed probe logs troubleshooting steps; reconnect
+/// probes leave that out so an unplugged pair does not flood the log
+fn detect_hardware_glasses(verbose: bool) -> Result<Box<dyn ARGlasses>> {
+    if !verbose {
+        return any_glasses().map_err(|e| anyhow::anyhow!("Failed to detect AR glasses: {}", e));
+    }
+
+    info!("🔌 Calling ar-drivers any_glasses() function...");
+    match any_glasses() {
+        Ok(glasses) => {
+            info!("✅ ar-drivers successfully detected glasses!");
+            Ok(glasses)
+        }
+        Err(e) => {
+            warn!("❌ ar-drivers any_glasses() failed: {:?}", e);
+
+            // Try individual glasses detection as fallback
+            info!("🔄 Attempting fallback individual detection...");
+            match try_individual_glasses_detection() {
+                Ok(glasses) => {
+                    info!("✅ Individual detection succeeded!");
+                    Ok(glasses)
+                }
+                Err(individual_error) => {
+                    warn!("❌ Individual detection also failed: {}", individual_error);
+                    log_detection_advice(&e.to_string());
+
+                    Err(anyhow::anyhow!(
+                        "Failed to detect AR glasses: {} (fallback also failed: {})",
+                        e,
+                        individual_error
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Troubleshooting steps matching a failed detection
+fn log_detection_advice(error_msg: &str) {
+    if error_msg.contains("NotFound") || error_msg.contains("not found") {
+        warn!("💡 No supported AR glasses were detected");
+        info!("📝 Ensure the glasses are connected via USB-C and powered on, then reconnect the cable");
+    } else if error_msg.contains("Permission") || error_msg.contains("permission") {
+        warn!("💡 This suggests a USB permissions issue");
+    } else {
+        warn!("💡 Unknown error type - may need deeper investigation");
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        info!("🔒 Check System Settings → Privacy & Security → Accessibility,");
+        info!("   add this application to the allowed list and restart it");
+        info!("📦 Verify libusb is installed: brew install libusb");
+    }
+    #[cfg(target_os = "linux")]
+    info!("🔒 Check that the glasses' hidraw devices are accessible, see the startup checks");
+}
+
+/// Attempt to detect glasses with additional debugging information
+fn try_individual_glasses_detection() -> Result<Box<dyn ARGlasses>> {
+    info!("🔍 Attempting detection with additional debugging...");
+
+    // The ar-drivers library doesn't expose individual device constructors
+    // Instead, it uses any_glasses() to automatically detect and connect
+    // to any supported device (XREAL Air, Rokid Air/Max, Mad Gaze, Grawoow)
+
+    info!("   Using ar-drivers any_glasses() with enhanced error reporting...");
+    match any_glasses() {
+        Ok(mut glasses) => {
+            info!("   ✅ Successfully detected AR glasses!");
+            info!("      Device name: {}", glasses.name());
+
+            // Try to get additional device info
+            if let Ok(serial) = glasses.serial() {
+                info!("      Serial: {}", serial);
+            }
+
+            Ok(glasses)
+        }
+        Err(e) => {
+            warn!("   ❌ Enhanced detection also failed: {}", e);
+            Err(anyhow::anyhow!("AR glasses detection failed: {}", e))
+        }
+    }
+}
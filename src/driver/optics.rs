@@ -0,0 +1,222 @@
+//! Display optics of the connected glasses
+//!
+//! Per-eye projections and eye poses come from the display calibration
+//! (`display_matrices`) when the driver provides it. Otherwise they are built
+//! from `display_fov` and `imu_to_display_matrix`, with the display resolution
+//! and any invalid FOV taken from a per-model [`DisplayProfile`].
+//!
+//! Eye poses are expressed in the IMU frame, which uses the same
+//! right/up/back axes as Bevy, so the head orientation can be applied to them
+//! directly.
+
+use ar_drivers::{ARGlasses, DisplayMatrices, Side};
+use bevy::prelude::*;
+use nalgebra::Isometry3;
+use std::f32::consts::FRAC_PI_2;
+
+use super::profile::DeviceProfile;
+
+/// Interpupillary distance used when none is configured, in meters
+pub const DEFAULT_IPD: f32 = 0.064;
+
+/// Display characteristics of a glasses model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayProfile {
+    /// Device name as reported by ar-drivers
+    pub name: &'static str,
+    /// Half horizontal FOV in degrees
+    pub half_fov_deg: f32,
+    /// Native resolution of one display
+    pub resolution: (u32, u32),
+}
+
+impl DisplayProfile {
+    /// Used for unknown models and before any glasses are connected
+    pub const GENERIC: DisplayProfile = DisplayProfile {
+        name: "Generic",
+        half_fov_deg: 23.0,
+        resolution: (1920, 1080),
+    };
+
+    /// Profile for the named model, or the generic one
+    pub fn for_model(name: &str) -> &'static DisplayProfile {
+        &DeviceProfile::for_model(name).display
+    }
+
+    /// Half horizontal FOV in radians
+    #[inline]
+    pub fn half_fov(&self) -> f32 {
+        self.half_fov_deg.to_radians()
+    }
+}
+
+/// Where the optics values came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpticsSource {
+    /// Factory display calibration read from the glasses
+    Calibration,
+    /// Driver FOV and IMU extrinsics with the model's display profile
+    Profile,
+}
+
+/// Projection and pose of one display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EyeOptics {
+    /// Display pose in the IMU frame
+    pub pose: Transform,
+    /// Full horizontal FOV in radians
+    pub fov_x: f32,
+    /// Full vertical FOV in radians
+    pub fov_y: f32,
+    /// Native display resolution
+    pub resolution: (u32, u32),
+}
+
+impl EyeOptics {
+    fn from_calibration(matrices: &DisplayMatrices) -> Option<Self> {
+        let (width, height) = matrices.resolution;
+        let fx = matrices.intrinsic_matrix[(0, 0)];
+        let fy = matrices.intrinsic_matrix[(1, 1)];
+        if width == 0 || height == 0 || !(fx > 0.0 && fy > 0.0) {
+            return None;
+        }
+
+        Some(Self {
+            pose: to_transform(&matrices.isometry),
+            fov_x: 2.0 * (width as f64 / (2.0 * fx)).atan() as f32,
+            fov_y: 2.0 * (height as f64 / (2.0 * fy)).atan() as f32,
+            resolution: matrices.resolution,
+        })
+    }
+
+    fn from_half_fov(pose: Transform, half_fov: f32, resolution: (u32, u32)) -> Self {
+        let (width, height) = resolution;
+        let half_fov_y = (half_fov.tan() * height as f32 / width.max(1) as f32).atan();
+        Self {
+            pose,
+            fov_x: 2.0 * half_fov,
+            fov_y: 2.0 * half_fov_y,
+            resolution,
+        }
+    }
+}
+
+/// Per-eye optics of the connected glasses
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct DisplayOptics {
+    pub left: EyeOptics,
+    pub right: EyeOptics,
+    /// Distance between the eye poses, in meters
+    pub ipd: f32,
+    pub source: OpticsSource,
+}
+
+impl Default for DisplayOptics {
+    fn default() -> Self {
+        Self::from_profile(&DisplayProfile::GENERIC)
+    }
+}
+
+impl DisplayOptics {
+    /// Read the optics from the glasses, falling back to the model profile
+    /// for anything the driver does not provide
+    pub fn from_glasses(glasses: &dyn ARGlasses) -> Self {
+        match glasses.display_matrices() {
+            Ok((left, right)) => {
+                if let Some(optics) = Self::from_calibration(&left, &right) {
+                    return optics;
+                }
+                warn!(
+                    "⚠️  Ignoring invalid display calibration from {}",
+                    glasses.name()
+                );
+            }
+            Err(e) => debug!("No display calibration from {}: {}", glasses.name(), e),
+        }
+
+        let profile = DisplayProfile::for_model(glasses.name());
+        let mut half_fov = glasses.display_fov();
+        if !(half_fov > 0.0 && half_fov < FRAC_PI_2) {
+            half_fov = profile.half_fov();
+        }
+
+        let eye = |side| {
+            let pose = to_transform(&glasses.imu_to_display_matrix(side, DEFAULT_IPD));
+            EyeOptics::from_half_fov(pose, half_fov, profile.resolution)
+        };
+        let left = eye(Side::Left);
+        let right = eye(Side::Right);
+        Self {
+            left,
+            right,
+            ipd: left.pose.translation.distance(right.pose.translation),
+            source: OpticsSource::Profile,
+        }
+    }
+
+    /// Optics from a display profile alone, with parallel eyes
+    pub fn from_profile(profile: &DisplayProfile) -> Self {
+        let eye = |x| {
+            let pose = Transform::from_xyz(x, 0.0, 0.0);
+            EyeOptics::from_half_fov(pose, profile.half_fov(), profile.resolution)
+        };
+        Self {
+            left: eye(-DEFAULT_IPD * 0.5),
+            right: eye(DEFAULT_IPD * 0.5),
+            ipd: DEFAULT_IPD,
+            source: OpticsSource::Profile,
+        }
+    }
+
+    /// Optics from factory calibration, if it is usable
+    pub fn from_calibration(left: &DisplayMatrices, right: &DisplayMatrices) -> Option<Self> {
+        let left = EyeOptics::from_calibration(left)?;
+        let right = EyeOptics::from_calibration(right)?;
+        Some(Self {
+            left,
+            right,
+            ipd: left.pose.translation.distance(right.pose.translation),
+            source: OpticsSource::Calibration,
+        })
+    }
+
+    /// Optics of one display
+    #[inline]
+    pub fn eye(&self, side: Side) -> &EyeOptics {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    /// Eye pose in the IMU frame, with the eyes moved apart to match `ipd`
+    pub fn eye_pose(&self, side: Side, ipd: f32) -> Transform {
+        let direction = match side {
+            Side::Left => -0.5,
+            Side::Right => 0.5,
+        };
+        let mut pose = self.eye(side).pose;
+        pose.translation.x += direction * (ipd - self.ipd);
+        pose
+    }
+}
+
+fn to_transform(isometry: &Isometry3<f64>) -> Transform {
+    let translation = isometry.translation.vector;
+    let rotation = isometry.rotation.coords;
+    Transform {
+        translation: Vec3::new(
+            translation.x as f32,
+            translation.y as f32,
+            translation.z as f32,
+        ),
+        rotation: Quat::from_xyzw(
+            rotation.x as f32,
+            rotation.y as f32,
+            rotation.z as f32,
+            rotation.w as f32,
+        )
+        .normalize(),
+        scale: Vec3::ONE,
+    }
+}
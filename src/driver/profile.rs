@@ -0,0 +1,417 @@
+//! Per-model device profiles
+//!
+//! ar-drivers only reports a model name and a few calibration values, so
+//! everything else the app needs to know about a glasses model (display
+//! resolution and refresh rates, supported display modes, hardware buttons
+//! and IMU axes) is kept in a static [`DeviceProfile`] registry keyed by
+//! `ARGlasses::name()`. The connected glasses are described by the
+//! [`DeviceInfo`] resource.
+
+use ar_drivers::DisplayMode;
+use bevy::prelude::*;
+
+use super::optics::{DisplayProfile, OpticsSource};
+use super::{DeviceIdentity, XRealDevice};
+
+/// Direction of a sensor axis in the head frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Right,
+    Left,
+    Up,
+    Down,
+    Back,
+    Forward,
+}
+
+impl Axis {
+    /// Unit vector of the axis in Bevy's right/up/back frame
+    #[inline]
+    pub fn to_vec3(self) -> Vec3 {
+        match self {
+            Axis::Right => Vec3::X,
+            Axis::Left => Vec3::NEG_X,
+            Axis::Up => Vec3::Y,
+            Axis::Down => Vec3::NEG_Y,
+            Axis::Back => Vec3::Z,
+            Axis::Forward => Vec3::NEG_Z,
+        }
+    }
+}
+
+/// Where the IMU x, y and z axes point on the head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImuAxes {
+    pub x: Axis,
+    pub y: Axis,
+    pub z: Axis,
+}
+
+impl ImuAxes {
+    /// Right/up/back, the frame ar-drivers reports every model in
+    pub const RUB: ImuAxes = ImuAxes {
+        x: Axis::Right,
+        y: Axis::Up,
+        z: Axis::Back,
+    };
+
+    /// Convert a sensor vector into Bevy's frame
+    #[inline]
+    pub fn to_bevy(&self, v: Vec3) -> Vec3 {
+        self.x.to_vec3() * v.x + self.y.to_vec3() * v.y + self.z.to_vec3() * v.z
+    }
+
+    /// Label such as `RUB`
+    pub fn label(&self) -> String {
+        [self.x, self.y, self.z]
+            .iter()
+            .map(|axis| match axis {
+                Axis::Right => 'R',
+                Axis::Left => 'L',
+                Axis::Up => 'U',
+                Axis::Down => 'D',
+                Axis::Back => 'B',
+                Axis::Forward => 'F',
+            })
+            .collect()
+    }
+}
+
+/// Sample rate and ranges of the IMU, as ar-drivers configures it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuProfile {
+    /// Rate the gyro and accelerometer samples stream at, in Hz
+    pub sample_rate_hz: f32,
+    /// Gyro full scale range, in °/s
+    pub gyro_range_dps: f32,
+    /// Accelerometer full scale range, in g
+    pub accel_range_g: f32,
+}
+
+impl ImuProfile {
+    /// 1 kHz at ±2000°/s and ±16 g, as the XREAL glasses stream
+    pub const GENERIC: ImuProfile = ImuProfile {
+        sample_rate_hz: 1000.0,
+        gyro_range_dps: 2000.0,
+        accel_range_g: 16.0,
+    };
+}
+
+impl Default for ImuProfile {
+    fn default() -> Self {
+        Self::GENERIC
+    }
+}
+
+/// A hardware button as reported in key press events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonInfo {
+    /// Key id from `GlassesEvent::KeyPress`
+    pub id: u8,
+    pub label: &'static str,
+}
+
+/// Everything known about a glasses model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceProfile {
+    /// Device name as reported by ar-drivers
+    pub name: &'static str,
+    pub vendor: &'static str,
+    /// Per-eye resolution and FOV
+    pub display: DisplayProfile,
+    /// Supported refresh rates in Hz, lowest first
+    pub refresh_rates: &'static [u32],
+    /// Display modes the glasses accept
+    pub display_modes: &'static [DisplayMode],
+    pub buttons: &'static [ButtonInfo],
+    /// Frame the IMU samples are reported in
+    pub imu_axes: ImuAxes,
+    pub imu: ImuProfile,
+}
+
+const ALL_MODES: &[DisplayMode] = &[
+    DisplayMode::SameOnBoth,
+    DisplayMode::Stereo,
+    DisplayMode::HalfSBS,
+    DisplayMode::HighRefreshRate,
+    DisplayMode::HighRefreshRateSBS,
+];
+
+const BASIC_MODES: &[DisplayMode] = &[DisplayMode::SameOnBoth, DisplayMode::Stereo];
+
+const XREAL_BUTTONS: &[ButtonInfo] = &[
+    ButtonInfo {
+        id: 0,
+        label: "Power",
+    },
+    ButtonInfo {
+        id: 1,
+        label: "Brightness up",
+    },
+    ButtonInfo {
+        id: 2,
+        label: "Brightness down",
+    },
+];
+
+const SINGLE_BUTTON: &[ButtonInfo] = &[ButtonInfo {
+    id: 0,
+    label: "Button",
+}];
+
+impl DeviceProfile {
+    /// Used for unknown models and before any glasses are connected
+    pub const GENERIC: DeviceProfile = DeviceProfile {
+        name: "Generic",
+        vendor: "Unknown",
+        display: DisplayProfile::GENERIC,
+        refresh_rates: &[60],
+        display_modes: BASIC_MODES,
+        buttons: &[
+            ButtonInfo {
+                id: 0,
+                label: "Button 0",
+            },
+            ButtonInfo {
+                id: 1,
+                label: "Button 1",
+            },
+        ],
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    };
+
+    /// Profile for the named model, or the generic one
+    pub fn for_model(name: &str) -> &'static DeviceProfile {
+        DEVICE_PROFILES
+            .iter()
+            .find(|profile| profile.name == name)
+            .unwrap_or(&Self::GENERIC)
+    }
+
+    /// True if this is the fallback for an unknown model
+    #[inline]
+    pub fn is_generic(&self) -> bool {
+        self.name == Self::GENERIC.name
+    }
+
+    /// Check if the glasses accept a display mode
+    #[inline]
+    pub fn supports_mode(&self, mode: DisplayMode) -> bool {
+        self.display_modes.contains(&mode)
+    }
+
+    /// Highest supported refresh rate in Hz
+    #[inline]
+    pub fn max_refresh_rate(&self) -> u32 {
+        self.refresh_rates.last().copied().unwrap_or(60)
+    }
+
+    /// Refresh rate outside the high refresh rate modes, in Hz
+    #[inline]
+    pub fn base_refresh_rate(&self) -> u32 {
+        self.refresh_rates.first().copied().unwrap_or(60)
+    }
+
+    /// Refresh rate the displays run at in a display mode
+    pub fn refresh_rate(&self, mode: DisplayMode) -> u32 {
+        match mode {
+            DisplayMode::HighRefreshRate | DisplayMode::HighRefreshRateSBS => {
+                self.max_refresh_rate()
+            }
+            _ => self.base_refresh_rate(),
+        }
+    }
+
+    /// Label of a button, if the model has it
+    pub fn button_label(&self, id: u8) -> Option<&'static str> {
+        self.buttons
+            .iter()
+            .find(|button| button.id == id)
+            .map(|button| button.label)
+    }
+}
+
+/// Known models, FOVs as measured by ar-drivers
+const DEVICE_PROFILES: &[DeviceProfile] = &[
+    DeviceProfile {
+        name: "XREAL Air",
+        vendor: "XREAL",
+        display: DisplayProfile {
+            name: "XREAL Air",
+            half_fov_deg: 24.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60, 72],
+        display_modes: ALL_MODES,
+        buttons: XREAL_BUTTONS,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    },
+    DeviceProfile {
+        name: "XREAL Air 2",
+        vendor: "XREAL",
+        display: DisplayProfile {
+            name: "XREAL Air 2",
+            half_fov_deg: 24.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60, 72, 90, 120],
+        display_modes: ALL_MODES,
+        buttons: XREAL_BUTTONS,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    },
+    DeviceProfile {
+        name: "XREAL Air 2 Pro",
+        vendor: "XREAL",
+        display: DisplayProfile {
+            name: "XREAL Air 2 Pro",
+            half_fov_deg: 24.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60, 72, 90, 120],
+        display_modes: ALL_MODES,
+        buttons: XREAL_BUTTONS,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    },
+    DeviceProfile {
+        name: "Nreal Light",
+        vendor: "Nreal",
+        display: DisplayProfile {
+            name: "Nreal Light",
+            half_fov_deg: 26.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60],
+        display_modes: BASIC_MODES,
+        buttons: SINGLE_BUTTON,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    },
+    DeviceProfile {
+        name: "Rokid Air",
+        vendor: "Rokid",
+        display: DisplayProfile {
+            name: "Rokid Air",
+            half_fov_deg: 20.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60],
+        display_modes: BASIC_MODES,
+        buttons: SINGLE_BUTTON,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    },
+    DeviceProfile {
+        name: "Rokid Max",
+        vendor: "Rokid",
+        display: DisplayProfile {
+            name: "Rokid Max",
+            half_fov_deg: 23.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60, 90, 120],
+        display_modes: &[
+            DisplayMode::SameOnBoth,
+            DisplayMode::Stereo,
+            DisplayMode::HighRefreshRate,
+        ],
+        buttons: SINGLE_BUTTON,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile::GENERIC,
+    },
+    DeviceProfile {
+        name: "Mad Gaze Glow",
+        vendor: "Mad Gaze",
+        display: DisplayProfile {
+            name: "Mad Gaze Glow",
+            half_fov_deg: 23.5,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60],
+        display_modes: &[DisplayMode::SameOnBoth],
+        buttons: SINGLE_BUTTON,
+        imu_axes: ImuAxes::RUB,
+        // BMI160 at ±2 g, ar-drivers steps its timestamps by 10 ms
+        imu: ImuProfile {
+            sample_rate_hz: 100.0,
+            gyro_range_dps: 2000.0,
+            accel_range_g: 2.0,
+        },
+    },
+    DeviceProfile {
+        name: "Grawoow G530",
+        vendor: "Grawoow",
+        display: DisplayProfile {
+            name: "Grawoow G530",
+            half_fov_deg: 22.0,
+            resolution: (1920, 1080),
+        },
+        refresh_rates: &[60],
+        display_modes: BASIC_MODES,
+        buttons: SINGLE_BUTTON,
+        imu_axes: ImuAxes::RUB,
+        imu: ImuProfile {
+            accel_range_g: 2.0,
+            ..ImuProfile::GENERIC
+        },
+    },
+];
+
+/// Description of the connected (or last connected) glasses
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    pub profile: &'static DeviceProfile,
+    pub serial: String,
+    pub display_mode: Option<DisplayMode>,
+    pub optics_source: OpticsSource,
+}
+
+impl DeviceInfo {
+    /// Build from the identity reported by the device broker
+    pub fn from_identity(identity: &DeviceIdentity) -> Self {
+        Self {
+            profile: DeviceProfile::for_model(identity.name),
+            serial: identity.serial.clone(),
+            display_mode: identity.display_mode,
+            optics_source: identity.optics.source,
+        }
+    }
+
+    /// Refresh rate of the current display mode in Hz
+    pub fn refresh_rate(&self) -> u32 {
+        self.display_mode
+            .map_or(self.profile.base_refresh_rate(), |mode| {
+                self.profile.refresh_rate(mode)
+            })
+    }
+}
+
+/// Keep [`DeviceInfo`] in sync with the glasses identity
+/// The last connected glasses stay described after a disconnect
+pub fn sync_device_info(
+    mut commands: Commands,
+    device: Option<Res<XRealDevice>>,
+    device_info: Option<Res<DeviceInfo>>,
+) {
+    let Some(identity) = device.as_deref().and_then(XRealDevice::identity) else {
+        return;
+    };
+    let current = DeviceInfo::from_identity(identity);
+    let previous = device_info.as_deref();
+    if previous == Some(&current) {
+        return;
+    }
+    if previous.is_none_or(|previous| previous.serial != current.serial) {
+        info!(
+            "🕶️ {} {} (serial {}), {} Hz",
+            current.profile.vendor,
+            identity.name,
+            current.serial,
+            current.refresh_rate()
+        );
+    }
+    commands.insert_resource(current);
+}
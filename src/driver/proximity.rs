@@ -0,0 +1,325 @@
+//! Proximity sensor driven auto-pause
+//!
+//! The broker forwards the glasses proximity sensor as near/far events. This
+//! module turns them into [`GlassesPresenceEvent`]s and, once the glasses have
+//! been off for the configured delay, pauses screen capture, the stereo
+//! cameras, input injection and throttles the frame rate until they are worn
+//! again.
+
+use super::{GlassesInputChannels, ProximityEvent};
+use crate::capture::ScreenCaptures;
+use crate::input::buttons::InputActionEvent;
+use crate::input::InputSystem;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::input::InputAction;
+use crate::state::schema::preferences::AutoPauseSettings;
+use crate::xreal_stereo::StereoEye;
+use bevy::prelude::*;
+use bevy::winit::{UpdateMode, WinitSettings};
+use std::time::{Duration, Instant};
+
+/// Emitted when the proximity sensor reports the glasses taken off or put on
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlassesPresenceEvent {
+    Removed,
+    Worn,
+}
+
+/// Emitted when the app pauses or resumes because of glasses presence
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoPauseEvent {
+    Paused,
+    Resumed,
+}
+
+/// Pause decision driven by presence changes and the pause delay
+#[derive(Debug, Default)]
+pub struct AutoPauseController {
+    removed_at: Option<Instant>,
+    paused: bool,
+}
+
+impl AutoPauseController {
+    /// Record that the glasses were taken off
+    pub fn glasses_removed(&mut self, at: Instant) {
+        if !self.paused && self.removed_at.is_none() {
+            self.removed_at = Some(at);
+        }
+    }
+
+    /// Record that the glasses were put on, resuming if paused
+    pub fn glasses_worn(&mut self) -> Option<AutoPauseEvent> {
+        self.removed_at = None;
+        self.resume()
+    }
+
+    /// Pause once the glasses have been off for `delay`
+    /// Resumes immediately if auto-pause gets disabled while paused
+    pub fn poll(&mut self, now: Instant, delay: Duration, enabled: bool) -> Option<AutoPauseEvent> {
+        if !enabled {
+            self.removed_at = None;
+            return self.resume();
+        }
+
+        let removed_at = self.removed_at?;
+        if now.saturating_duration_since(removed_at) < delay {
+            return None;
+        }
+        self.removed_at = None;
+        self.paused = true;
+        Some(AutoPauseEvent::Paused)
+    }
+
+    /// True while paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn resume(&mut self) -> Option<AutoPauseEvent> {
+        if !self.paused {
+            return None;
+        }
+        self.paused = false;
+        Some(AutoPauseEvent::Resumed)
+    }
+}
+
+/// What was changed when pausing, so resuming only undoes that
+#[derive(Debug, Default)]
+struct AppliedPause {
+    capture: bool,
+    stereo: bool,
+    input: bool,
+    winit: Option<WinitSettings>,
+}
+
+/// Current glasses presence and pause state
+#[derive(Resource, Debug)]
+pub struct GlassesPresence {
+    worn: bool,
+    controller: AutoPauseController,
+    applied: Option<AppliedPause>,
+}
+
+impl Default for GlassesPresence {
+    fn default() -> Self {
+        // Assume the glasses are worn until the sensor says otherwise
+        Self {
+            worn: true,
+            controller: AutoPauseController::default(),
+            applied: None,
+        }
+    }
+}
+
+impl GlassesPresence {
+    /// True unless the proximity sensor reports the glasses taken off
+    pub fn is_worn(&self) -> bool {
+        self.worn
+    }
+
+    /// True while the app is paused because the glasses are off
+    pub fn is_paused(&self) -> bool {
+        self.controller.is_paused()
+    }
+}
+
+/// Registers proximity handling and auto-pause
+pub struct GlassesProximityPlugin;
+
+impl Plugin for GlassesProximityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GlassesPresenceEvent>()
+            .add_event::<AutoPauseEvent>()
+            .add_event::<InputActionEvent>()
+            .init_resource::<GlassesPresence>()
+            .add_systems(
+                Update,
+                (read_glasses_presence, update_auto_pause, apply_auto_pause).chain(),
+            );
+    }
+}
+
+/// Turn proximity sensor reports into presence events
+pub fn read_glasses_presence(
+    channels: Option<Res<GlassesInputChannels>>,
+    mut presence: ResMut<GlassesPresence>,
+    mut presence_events: EventWriter<GlassesPresenceEvent>,
+) {
+    let Some(channels) = channels else {
+        return;
+    };
+
+    for event in channels.proximity.try_iter() {
+        let worn = event == ProximityEvent::Near;
+        if worn == presence.worn {
+            continue;
+        }
+        presence.worn = worn;
+        if worn {
+            info!("👓 Glasses put on");
+            presence_events.write(GlassesPresenceEvent::Worn);
+        } else {
+            info!("👓 Glasses taken off");
+            presence_events.write(GlassesPresenceEvent::Removed);
+        }
+    }
+}
+
+/// Decide when to pause and resume
+pub fn update_auto_pause(
+    mut presence_events: EventReader<GlassesPresenceEvent>,
+    persistent_state: Res<PersistentAppState>,
+    mut presence: ResMut<GlassesPresence>,
+    mut pause_events: EventWriter<AutoPauseEvent>,
+) {
+    let settings = &persistent_state.user_preferences.auto_pause;
+    let now = Instant::now();
+
+    for event in presence_events.read() {
+        let transition = match event {
+            GlassesPresenceEvent::Removed => {
+                presence.controller.glasses_removed(now);
+                None
+            }
+            GlassesPresenceEvent::Worn => presence.controller.glasses_worn(),
+        };
+        if let Some(transition) = transition {
+            pause_events.write(transition);
+        }
+    }
+
+    let delay = Duration::from_millis(settings.pause_delay_ms as u64);
+    if let Some(transition) = presence.controller.poll(now, delay, settings.enabled) {
+        pause_events.write(transition);
+    }
+}
+
+/// Pause or resume capture, stereo rendering, input injection and frame rate
+#[allow(clippy::too_many_arguments)]
+pub fn apply_auto_pause(
+    mut pause_events: EventReader<AutoPauseEvent>,
+    persistent_state: Res<PersistentAppState>,
+    mut presence: ResMut<GlassesPresence>,
+    mut captures: Option<ResMut<ScreenCaptures>>,
+    mut stereo_cameras: Query<&mut Camera, With<StereoEye>>,
+    mut winit_settings: Option<ResMut<WinitSettings>>,
+    mut input_system: Option<NonSendMut<InputSystem>>,
+    mut action_events: EventWriter<InputActionEvent>,
+) {
+    let settings = &persistent_state.user_preferences.auto_pause;
+
+    for event in pause_events.read() {
+        match event {
+            AutoPauseEvent::Paused => {
+                info!("⏸️  Glasses off, pausing");
+                presence.applied = Some(pause(
+                    settings,
+                    captures.as_deref_mut(),
+                    winit_settings.as_deref_mut(),
+                    input_system.as_deref_mut(),
+                ));
+            }
+            AutoPauseEvent::Resumed => {
+                info!("▶️  Glasses on, resuming");
+                if let Some(applied) = presence.applied.take() {
+                    resume(
+                        applied,
+                        captures.as_deref_mut(),
+                        &mut stereo_cameras,
+                        winit_settings.as_deref_mut(),
+                        input_system.as_deref_mut(),
+                    );
+                }
+                if settings.recenter_on_wake {
+                    action_events.write(InputActionEvent(InputAction::Recenter));
+                }
+            }
+        }
+    }
+
+    // Stereo cameras may be rebuilt by a reconnect while paused
+    if presence
+        .applied
+        .as_ref()
+        .is_some_and(|applied| applied.stereo)
+    {
+        for mut camera in &mut stereo_cameras {
+            if camera.is_active {
+                camera.is_active = false;
+            }
+        }
+    }
+}
+
+fn pause(
+    settings: &AutoPauseSettings,
+    captures: Option<&mut ScreenCaptures>,
+    winit_settings: Option<&mut WinitSettings>,
+    input_system: Option<&mut InputSystem>,
+) -> AppliedPause {
+    let mut applied = AppliedPause {
+        stereo: settings.disable_stereo,
+        ..default()
+    };
+
+    if settings.pause_capture {
+        if let Some(captures) = captures.filter(|c| !c.is_paused()) {
+            captures.set_paused(true);
+            applied.capture = true;
+        }
+    }
+
+    if settings.suspend_input {
+        if let Some(input_system) = input_system.filter(|i| i.is_enabled()) {
+            if let Err(e) = input_system.update_config(|config| config.enabled = false) {
+                warn!("Failed to suspend input: {}", e);
+            } else {
+                applied.input = true;
+            }
+        }
+    }
+
+    if settings.paused_fps > 0 {
+        if let Some(winit_settings) = winit_settings {
+            let wait = Duration::from_secs_f32(1.0 / settings.paused_fps as f32);
+            applied.winit = Some(winit_settings.clone());
+            winit_settings.focused_mode = UpdateMode::reactive_low_power(wait);
+            winit_settings.unfocused_mode = UpdateMode::reactive_low_power(wait);
+        }
+    }
+
+    applied
+}
+
+fn resume(
+    applied: AppliedPause,
+    captures: Option<&mut ScreenCaptures>,
+    stereo_cameras: &mut Query<&mut Camera, With<StereoEye>>,
+    winit_settings: Option<&mut WinitSettings>,
+    input_system: Option<&mut InputSystem>,
+) {
+    if applied.capture {
+        if let Some(captures) = captures {
+            captures.set_paused(false);
+        }
+    }
+
+    if applied.stereo {
+        for mut camera in stereo_cameras.iter_mut() {
+            camera.is_active = true;
+        }
+    }
+
+    if applied.input {
+        if let Some(input_system) = input_system {
+            if let Err(e) = input_system.update_config(|config| config.enabled = true) {
+                warn!("Failed to resume input: {}", e);
+            }
+        }
+    }
+
+    if let (Some(previous), Some(winit_settings)) = (applied.winit, winit_settings) {
+        *winit_settings = previous;
+    }
+}
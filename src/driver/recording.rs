@@ -0,0 +1,616 @@
+//! IMU session recording and replay
+//!
+//! Sessions are stored in a compact little-endian binary file:
+//! - 8-byte magic `XRIMUREC` followed by a `u16` format version
+//! - A header with device name, serial, display parameters and the
+//!   calibration in use when recording started. Version 1 files carry the
+//!   biases only, version 2 adds the accelerometer correction, the
+//!   magnetometer soft-iron matrix and the gyro bias temperature points
+//! - A stream of tagged records, each carrying a device timestamp in µs
+//!
+//! [`ReplayGlasses`] implements [`ARGlasses`] on top of a recording so it can
+//! be fed through the regular tracking pipeline via [`super::GlassesSource`].
+
+use crate::state::schema::calibration::GyroBiasPoint;
+use anyhow::{bail, Context, Result};
+use ar_drivers::{ARGlasses, DisplayMode, GlassesEvent, Side};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// File magic identifying IMU session recordings
+pub const RECORDING_MAGIC: [u8; 8] = *b"XRIMUREC";
+
+/// Current recording format version
+pub const RECORDING_VERSION: u16 = 2;
+
+/// File extension used for recordings
+pub const RECORDING_EXTENSION: &str = "xrimu";
+
+const TAG_ACC_GYRO: u8 = 1;
+const TAG_MAGNETOMETER: u8 = 2;
+const TAG_KEY_PRESS: u8 = 3;
+const TAG_PROXIMITY_NEAR: u8 = 4;
+const TAG_PROXIMITY_FAR: u8 = 5;
+const TAG_AMBIENT_LIGHT: u8 = 6;
+const TAG_VSYNC: u8 = 7;
+
+/// Columns of the identity matrix, the correction of an uncalibrated sensor
+const IDENTITY_COLS: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Calibration in use when a recording started
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCalibration {
+    /// Gyroscope bias (rad/s)
+    pub gyro_bias: [f32; 3],
+    /// Accelerometer bias (m/s²)
+    pub accel_bias: [f32; 3],
+    /// Accelerometer scale and cross-axis correction, by columns
+    pub accel_correction: [[f32; 3]; 3],
+    /// Magnetometer hard-iron bias (µT)
+    pub mag_bias: [f32; 3],
+    /// Magnetometer soft-iron correction, by columns
+    pub mag_soft_iron: [[f32; 3]; 3],
+    /// Gyro bias measured at each IMU temperature
+    pub gyro_bias_points: Vec<GyroBiasPoint>,
+}
+
+impl Default for RecordedCalibration {
+    fn default() -> Self {
+        Self {
+            gyro_bias: [0.0; 3],
+            accel_bias: [0.0; 3],
+            accel_correction: IDENTITY_COLS,
+            mag_bias: [0.0; 3],
+            mag_soft_iron: IDENTITY_COLS,
+            gyro_bias_points: Vec::new(),
+        }
+    }
+}
+
+/// Session metadata stored at the start of every recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingHeader {
+    /// Device name as reported by `ARGlasses::name`
+    pub device_name: String,
+    /// Device serial number
+    pub serial: String,
+    /// Recording start time in milliseconds since the Unix epoch
+    pub recorded_at_ms: u64,
+    /// Half horizontal display FOV in radians
+    pub display_fov: f32,
+    /// Display delay in microseconds
+    pub display_delay_us: u64,
+    /// Calibration in use while recording
+    pub calibration: RecordedCalibration,
+}
+
+impl RecordingHeader {
+    /// Build a header describing the given glasses
+    pub fn for_glasses(glasses: &mut dyn ARGlasses) -> Self {
+        Self {
+            device_name: glasses.name().to_string(),
+            serial: glasses.serial().unwrap_or_default(),
+            recorded_at_ms: unix_time_ms(),
+            display_fov: glasses.display_fov(),
+            display_delay_us: glasses.display_delay(),
+            calibration: RecordedCalibration::default(),
+        }
+    }
+
+    /// Set the calibration in use
+    #[inline]
+    pub fn with_calibration(mut self, calibration: RecordedCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+}
+
+/// Single recorded event with its device timestamp
+///
+/// Events without their own timestamp (keys, proximity, ...) carry the
+/// timestamp of the most recent IMU sample.
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    pub timestamp_us: u64,
+    pub event: GlassesEvent,
+}
+
+/// Writes a `GlassesEvent` stream to a recording
+pub struct SessionRecorder<W: Write> {
+    writer: W,
+    last_timestamp: u64,
+    event_count: u64,
+}
+
+impl SessionRecorder<BufWriter<File>> {
+    /// Create a recording file, creating parent directories as needed
+    pub fn create(path: &Path, header: &RecordingHeader) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create recording {}", path.display()))?;
+        Self::new(BufWriter::new(file), header)
+    }
+}
+
+impl<W: Write> SessionRecorder<W> {
+    /// Start a recording on an arbitrary writer
+    pub fn new(mut writer: W, header: &RecordingHeader) -> Result<Self> {
+        writer.write_all(&RECORDING_MAGIC)?;
+        writer.write_all(&RECORDING_VERSION.to_le_bytes())?;
+        write_string(&mut writer, &header.device_name)?;
+        write_string(&mut writer, &header.serial)?;
+        writer.write_all(&header.recorded_at_ms.to_le_bytes())?;
+        writer.write_all(&header.display_fov.to_le_bytes())?;
+        writer.write_all(&header.display_delay_us.to_le_bytes())?;
+        let calibration = &header.calibration;
+        write_f32s(&mut writer, &calibration.gyro_bias)?;
+        write_f32s(&mut writer, &calibration.accel_bias)?;
+        write_f32s(&mut writer, &calibration.mag_bias)?;
+        for column in calibration
+            .accel_correction
+            .iter()
+            .chain(&calibration.mag_soft_iron)
+        {
+            write_f32s(&mut writer, column)?;
+        }
+        let points = u16::try_from(calibration.gyro_bias_points.len())
+            .context("Too many gyro bias points for recording header")?;
+        writer.write_all(&points.to_le_bytes())?;
+        for point in &calibration.gyro_bias_points {
+            writer.write_all(&point.temperature_celsius.to_le_bytes())?;
+            write_f32s(&mut writer, &point.bias)?;
+            writer.write_all(&point.samples.to_le_bytes())?;
+        }
+
+        Ok(Self {
+            writer,
+            last_timestamp: 0,
+            event_count: 0,
+        })
+    }
+
+    /// Append one event to the recording
+    pub fn record(&mut self, event: &GlassesEvent) -> Result<()> {
+        match event {
+            GlassesEvent::AccGyro {
+                accelerometer,
+                gyroscope,
+                timestamp,
+            } => {
+                self.last_timestamp = *timestamp;
+                self.write_record_header(TAG_ACC_GYRO)?;
+                write_vector(&mut self.writer, accelerometer)?;
+                write_vector(&mut self.writer, gyroscope)?;
+            }
+            GlassesEvent::Magnetometer {
+                magnetometer,
+                timestamp,
+            } => {
+                self.last_timestamp = *timestamp;
+                self.write_record_header(TAG_MAGNETOMETER)?;
+                write_vector(&mut self.writer, magnetometer)?;
+            }
+            GlassesEvent::KeyPress(key) => {
+                self.write_record_header(TAG_KEY_PRESS)?;
+                self.writer.write_all(&[*key])?;
+            }
+            GlassesEvent::ProximityNear => self.write_record_header(TAG_PROXIMITY_NEAR)?,
+            GlassesEvent::ProximityFar => self.write_record_header(TAG_PROXIMITY_FAR)?,
+            GlassesEvent::AmbientLight(level) => {
+                self.write_record_header(TAG_AMBIENT_LIGHT)?;
+                self.writer.write_all(&level.to_le_bytes())?;
+            }
+            GlassesEvent::VSync => self.write_record_header(TAG_VSYNC)?,
+        }
+        self.event_count += 1;
+        Ok(())
+    }
+
+    /// Number of events recorded so far
+    #[inline]
+    pub fn event_count(&self) -> u64 {
+        self.event_count
+    }
+
+    /// Flush buffered data and return the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    #[inline]
+    fn write_record_header(&mut self, tag: u8) -> Result<()> {
+        self.writer.write_all(&[tag])?;
+        self.writer.write_all(&self.last_timestamp.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+/// Reads events back from a recording
+pub struct SessionReader<R: Read> {
+    reader: R,
+    header: RecordingHeader,
+}
+
+impl SessionReader<BufReader<File>> {
+    /// Open a recording file
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> SessionReader<R> {
+    /// Read and validate the recording header
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .context("Recording is too short")?;
+        if magic != RECORDING_MAGIC {
+            bail!("Not an IMU session recording");
+        }
+
+        let version = read_u16(&mut reader)?;
+        if version == 0 || version > RECORDING_VERSION {
+            bail!(
+                "Unsupported recording version {} (supported: {})",
+                version,
+                RECORDING_VERSION
+            );
+        }
+
+        let mut header = RecordingHeader {
+            device_name: read_string(&mut reader)?,
+            serial: read_string(&mut reader)?,
+            recorded_at_ms: read_u64(&mut reader)?,
+            display_fov: read_f32(&mut reader)?,
+            display_delay_us: read_u64(&mut reader)?,
+            calibration: RecordedCalibration {
+                gyro_bias: read_f32s(&mut reader)?,
+                accel_bias: read_f32s(&mut reader)?,
+                mag_bias: read_f32s(&mut reader)?,
+                ..RecordedCalibration::default()
+            },
+        };
+        // Version 1 recordings ran with identity corrections and no bias points
+        if version >= 2 {
+            let calibration = &mut header.calibration;
+            for column in calibration
+                .accel_correction
+                .iter_mut()
+                .chain(&mut calibration.mag_soft_iron)
+            {
+                *column = read_f32s(&mut reader)?;
+            }
+            let points = read_u16(&mut reader)?;
+            for _ in 0..points {
+                calibration.gyro_bias_points.push(GyroBiasPoint {
+                    temperature_celsius: read_f32(&mut reader)?,
+                    bias: read_f32s(&mut reader)?,
+                    samples: read_u32(&mut reader)?,
+                });
+            }
+        }
+
+        Ok(Self { reader, header })
+    }
+
+    /// Session metadata
+    #[inline]
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+
+    /// Read the next event, or `None` at the end of the recording
+    pub fn next_event(&mut self) -> Result<Option<RecordedEvent>> {
+        let mut tag = [0u8; 1];
+        match self.reader.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let timestamp_us = read_u64(&mut self.reader)?;
+
+        let event = match tag[0] {
+            TAG_ACC_GYRO => GlassesEvent::AccGyro {
+                accelerometer: read_vector(&mut self.reader)?,
+                gyroscope: read_vector(&mut self.reader)?,
+                timestamp: timestamp_us,
+            },
+            TAG_MAGNETOMETER => GlassesEvent::Magnetometer {
+                magnetometer: read_vector(&mut self.reader)?,
+                timestamp: timestamp_us,
+            },
+            TAG_KEY_PRESS => {
+                let mut key = [0u8; 1];
+                self.reader.read_exact(&mut key)?;
+                GlassesEvent::KeyPress(key[0])
+            }
+            TAG_PROXIMITY_NEAR => GlassesEvent::ProximityNear,
+            TAG_PROXIMITY_FAR => GlassesEvent::ProximityFar,
+            TAG_AMBIENT_LIGHT => GlassesEvent::AmbientLight(read_u16(&mut self.reader)?),
+            TAG_VSYNC => GlassesEvent::VSync,
+            other => bail!("Corrupt recording: unknown record tag {}", other),
+        };
+
+        Ok(Some(RecordedEvent {
+            timestamp_us,
+            event,
+        }))
+    }
+}
+
+/// Read only the header of a recording file
+pub fn read_header(path: &Path) -> Result<RecordingHeader> {
+    Ok(SessionReader::open(path)?.header().clone())
+}
+
+/// Default location for new recordings: `~/.xreal/recordings/session-<time>.xrimu`
+pub fn default_recording_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".xreal")
+        .join("recordings")
+        .join(format!(
+            "session-{}.{}",
+            unix_time_ms() / 1000,
+            RECORDING_EXTENSION
+        ))
+}
+
+/// Playback speed for [`ReplayGlasses`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// Pace events by their recorded timestamps
+    #[default]
+    RealTime,
+    /// Play faster (or slower) than recorded; infinity disables pacing
+    Accelerated(f32),
+    /// Release one event per [`ReplayStepper::step`]
+    Stepped,
+}
+
+impl ReplaySpeed {
+    /// Parse `realtime`, `step`, `max` or a speed multiplier such as `4`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "realtime" | "real-time" | "1" => Some(ReplaySpeed::RealTime),
+            "step" | "stepped" => Some(ReplaySpeed::Stepped),
+            "max" | "fast" => Some(ReplaySpeed::Accelerated(f32::INFINITY)),
+            other => other
+                .trim_end_matches('x')
+                .parse::<f32>()
+                .ok()
+                .filter(|s| *s > 0.0)
+                .map(ReplaySpeed::Accelerated),
+        }
+    }
+}
+
+/// Handle for advancing a [`ReplaySpeed::Stepped`] replay
+#[derive(Debug, Clone, Default)]
+pub struct ReplayStepper {
+    budget: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl ReplayStepper {
+    /// Allow `count` more events to be read
+    pub fn step(&self, count: u64) {
+        let (lock, condvar) = &*self.budget;
+        if let Ok(mut budget) = lock.lock() {
+            *budget = budget.saturating_add(count);
+            condvar.notify_all();
+        }
+    }
+
+    fn wait_for_step(&self) {
+        let (lock, condvar) = &*self.budget;
+        if let Ok(mut budget) = lock.lock() {
+            while *budget == 0 {
+                budget = match condvar.wait(budget) {
+                    Ok(guard) => guard,
+                    Err(_) => return,
+                };
+            }
+            *budget -= 1;
+        }
+    }
+}
+
+/// Glasses that replay a recorded session
+pub struct ReplayGlasses {
+    reader: SessionReader<Box<dyn Read + Send>>,
+    name: &'static str,
+    speed: ReplaySpeed,
+    stepper: ReplayStepper,
+    display_mode: DisplayMode,
+    started_at: Option<(Instant, u64)>,
+}
+
+impl ReplayGlasses {
+    /// Open a recording file for replay
+    pub fn open(path: &Path, speed: ReplaySpeed) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open recording {}", path.display()))?;
+        Self::from_reader(Box::new(BufReader::new(file)), speed)
+    }
+
+    /// Replay a recording from an arbitrary reader
+    pub fn from_reader(reader: Box<dyn Read + Send>, speed: ReplaySpeed) -> Result<Self> {
+        let reader = SessionReader::new(reader)?;
+        // `ARGlasses::name` hands out a static string; replays are opened
+        // once per session so leaking the recorded name is acceptable
+        let name: &'static str = Box::leak(reader.header().device_name.clone().into_boxed_str());
+
+        Ok(Self {
+            reader,
+            name,
+            speed,
+            stepper: ReplayStepper::default(),
+            display_mode: DisplayMode::SameOnBoth,
+            started_at: None,
+        })
+    }
+
+    /// Advance a stepped replay through a handle created beforehand,
+    /// e.g. one the app holds while the broker thread opens the replay
+    pub fn with_stepper(mut self, stepper: ReplayStepper) -> Self {
+        self.stepper = stepper;
+        self
+    }
+
+    fn pace(&mut self, timestamp_us: u64) {
+        let scale = match self.speed {
+            ReplaySpeed::Stepped => {
+                self.stepper.wait_for_step();
+                return;
+            }
+            ReplaySpeed::RealTime => 1.0,
+            ReplaySpeed::Accelerated(speed) if speed.is_finite() && speed > 0.0 => speed as f64,
+            ReplaySpeed::Accelerated(_) => return,
+        };
+
+        let (start, first_timestamp) = *self
+            .started_at
+            .get_or_insert_with(|| (Instant::now(), timestamp_us));
+        let offset_secs = timestamp_us.saturating_sub(first_timestamp) as f64 / 1_000_000.0;
+        let target = start + Duration::from_secs_f64(offset_secs / scale);
+        let now = Instant::now();
+        if target > now {
+            std::thread::sleep(target - now);
+        }
+    }
+}
+
+impl ARGlasses for ReplayGlasses {
+    fn serial(&mut self) -> Result<String, ar_drivers::Error> {
+        Ok(self.reader.header().serial.clone())
+    }
+
+    fn read_event(&mut self) -> Result<GlassesEvent, ar_drivers::Error> {
+        match self.reader.next_event() {
+            Ok(Some(recorded)) => {
+                self.pace(recorded.timestamp_us);
+                Ok(recorded.event)
+            }
+            Ok(None) => Err(ar_drivers::Error::Other("End of recording")),
+            Err(_) => Err(ar_drivers::Error::Other("Corrupt recording")),
+        }
+    }
+
+    fn get_display_mode(&mut self) -> Result<DisplayMode, ar_drivers::Error> {
+        Ok(self.display_mode)
+    }
+
+    fn set_display_mode(&mut self, display_mode: DisplayMode) -> Result<(), ar_drivers::Error> {
+        self.display_mode = display_mode;
+        Ok(())
+    }
+
+    fn display_fov(&self) -> f32 {
+        self.reader.header().display_fov
+    }
+
+    fn imu_to_display_matrix(&self, side: Side, ipd: f32) -> Isometry3<f64> {
+        let side_multiplier = match side {
+            Side::Left => -0.5,
+            Side::Right => 0.5,
+        };
+        Isometry3::from_parts(
+            Translation3::new(ipd as f64 * side_multiplier, 0.0, 0.0),
+            UnitQuaternion::identity(),
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn display_delay(&self) -> u64 {
+        self.reader.header().display_delay_us
+    }
+}
+
+#[inline]
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    let bytes = value.as_bytes();
+    let len = u16::try_from(bytes.len()).context("String too long for recording header")?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+#[inline]
+fn write_f32s<W: Write>(writer: &mut W, values: &[f32; 3]) -> Result<()> {
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[inline]
+fn write_vector<W: Write>(writer: &mut W, vector: &Vector3<f32>) -> Result<()> {
+    write_f32s(writer, &[vector.x, vector.y, vector.z])
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let len = read_u16(reader)? as usize;
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).context("Recording header contains invalid UTF-8")
+}
+
+#[inline]
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+#[inline]
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[inline]
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[inline]
+fn read_f32<R: Read>(reader: &mut R) -> Result<f32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[inline]
+fn read_f32s<R: Read>(reader: &mut R) -> Result<[f32; 3]> {
+    Ok([read_f32(reader)?, read_f32(reader)?, read_f32(reader)?])
+}
+
+#[inline]
+fn read_vector<R: Read>(reader: &mut R) -> Result<Vector3<f32>> {
+    let [x, y, z] = read_f32s(reader)?;
+    Ok(Vector3::new(x, y, z))
+}
@@ -0,0 +1,601 @@
+//! Simulated AR glasses for development without hardware
+//!
+//! Provides a [`SimulatedGlasses`] implementation of [`ARGlasses`] that
+//! synthesizes the same `GlassesEvent` stream real glasses produce:
+//! - Accelerometer/gyroscope samples from a scripted head motion
+//! - Magnetometer samples from a configurable earth field
+//! - Scripted key presses and proximity changes
+//! - Configurable noise, sensor bias, gyro bias drift and sample rates
+//!
+//! All sensor values follow the ar-drivers conventions: RUB device frame,
+//! gyroscope in rad/s, accelerometer in m/s² and magnetometer in µT.
+
+use ar_drivers::{ARGlasses, DisplayMode, GlassesEvent, Side};
+use bevy::math::{Quat, Vec3};
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use std::time::{Duration, Instant};
+
+/// Standard gravity in m/s²
+pub const GRAVITY: f32 = 9.81;
+
+/// Name reported by [`SimulatedGlasses::name`]
+pub const SIMULATED_DEVICE_NAME: &str = "Simulated Glasses";
+
+/// Motion performed during a single script segment
+///
+/// Rotation axes are expressed in the device frame at the start of the segment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentMotion {
+    /// Head held still
+    Hold,
+    /// Constant angular velocity in rad/s
+    Rotate { axis: Vec3, rate: f32 },
+    /// Sinusoidal rotation with amplitude in radians
+    Oscillate {
+        axis: Vec3,
+        amplitude: f32,
+        frequency_hz: f32,
+    },
+}
+
+impl SegmentMotion {
+    /// Rotation angle and angular rate around the segment axis after `t` seconds
+    #[inline]
+    fn angle_and_rate(&self, t: f32) -> (Vec3, f32, f32) {
+        match *self {
+            SegmentMotion::Hold => (Vec3::Y, 0.0, 0.0),
+            SegmentMotion::Rotate { axis, rate } => (axis.normalize_or_zero(), rate * t, rate),
+            SegmentMotion::Oscillate {
+                axis,
+                amplitude,
+                frequency_hz,
+            } => {
+                let omega = std::f32::consts::TAU * frequency_hz;
+                (
+                    axis.normalize_or_zero(),
+                    amplitude * (omega * t).sin(),
+                    amplitude * omega * (omega * t).cos(),
+                )
+            }
+        }
+    }
+}
+
+/// Timed segment of a [`MotionScript`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionSegment {
+    pub motion: SegmentMotion,
+    pub duration_secs: f32,
+}
+
+/// Scripted head motion played back by the simulator
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotionScript {
+    pub segments: Vec<MotionSegment>,
+    /// Restart from the first segment when the script ends
+    pub looping: bool,
+}
+
+impl MotionScript {
+    /// Glasses resting upright without moving
+    pub fn stationary() -> Self {
+        Self {
+            segments: vec![MotionSegment {
+                motion: SegmentMotion::Hold,
+                duration_secs: f32::INFINITY,
+            }],
+            looping: false,
+        }
+    }
+
+    /// Continuous rotation around a single axis
+    pub fn constant_rotation(axis: Vec3, rate: f32) -> Self {
+        Self {
+            segments: vec![MotionSegment {
+                motion: SegmentMotion::Rotate { axis, rate },
+                duration_secs: f32::INFINITY,
+            }],
+            looping: false,
+        }
+    }
+
+    /// Endless sinusoidal rotation around a single axis
+    pub fn oscillation(axis: Vec3, amplitude: f32, frequency_hz: f32) -> Self {
+        Self {
+            segments: vec![MotionSegment {
+                motion: SegmentMotion::Oscillate {
+                    axis,
+                    amplitude,
+                    frequency_hz,
+                },
+                duration_secs: f32::INFINITY,
+            }],
+            looping: false,
+        }
+    }
+
+    /// Looping quick turns by `angle` radians and back, each followed by a hold
+    pub fn step_turns(axis: Vec3, angle: f32, turn_secs: f32, hold_secs: f32) -> Self {
+        let turn = |rate| MotionSegment {
+            motion: SegmentMotion::Rotate { axis, rate },
+            duration_secs: turn_secs,
+        };
+        let hold = MotionSegment {
+            motion: SegmentMotion::Hold,
+            duration_secs: hold_secs,
+        };
+        let rate = angle / turn_secs;
+        Self {
+            segments: vec![hold, turn(rate), hold, turn(-rate)],
+            looping: true,
+        }
+    }
+
+    /// Looping "look left, look right, nod" routine for interactive testing
+    pub fn look_around() -> Self {
+        let turn = 45f32.to_radians();
+        let segment = |motion, duration_secs| MotionSegment {
+            motion,
+            duration_secs,
+        };
+        Self {
+            segments: vec![
+                segment(SegmentMotion::Hold, 2.0),
+                segment(
+                    SegmentMotion::Rotate {
+                        axis: Vec3::Y,
+                        rate: turn,
+                    },
+                    1.0,
+                ),
+                segment(SegmentMotion::Hold, 2.0),
+                segment(
+                    SegmentMotion::Rotate {
+                        axis: Vec3::Y,
+                        rate: -turn,
+                    },
+                    2.0,
+                ),
+                segment(SegmentMotion::Hold, 2.0),
+                segment(
+                    SegmentMotion::Rotate {
+                        axis: Vec3::Y,
+                        rate: turn,
+                    },
+                    1.0,
+                ),
+                segment(
+                    SegmentMotion::Oscillate {
+                        axis: Vec3::X,
+                        amplitude: 15f32.to_radians(),
+                        frequency_hz: 1.0,
+                    },
+                    2.0,
+                ),
+            ],
+            looping: true,
+        }
+    }
+}
+
+impl Default for MotionScript {
+    fn default() -> Self {
+        Self::stationary()
+    }
+}
+
+/// Non-IMU event emitted by the simulator at a scripted time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulatedEvent {
+    KeyPress(u8),
+    ProximityNear,
+    ProximityFar,
+}
+
+/// Scripted event with its trigger time in simulated seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScriptedEvent {
+    pub at_secs: f32,
+    pub event: SimulatedEvent,
+}
+
+/// Configuration for [`SimulatedGlasses`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationConfig {
+    /// Scripted head motion
+    pub motion: MotionScript,
+    /// Accelerometer/gyroscope sample rate in Hz
+    pub sample_rate_hz: u32,
+    /// Magnetometer sample rate in Hz (0 disables the magnetometer)
+    pub mag_rate_hz: u32,
+    /// Gyroscope noise standard deviation in rad/s
+    pub gyro_noise_std: f32,
+    /// Accelerometer noise standard deviation in m/s²
+    pub accel_noise_std: f32,
+    /// Magnetometer noise standard deviation in µT
+    pub mag_noise_std: f32,
+    /// Constant gyroscope bias in rad/s
+    pub gyro_bias: Vec3,
+    /// Change of the gyroscope bias over time in rad/s per second, e.g. while warming up
+    pub gyro_bias_drift: Vec3,
+    /// Constant accelerometer bias in m/s²
+    pub accel_bias: Vec3,
+    /// Constant magnetometer (hard-iron) bias in µT
+    pub mag_bias: Vec3,
+    /// Earth magnetic field in the world frame, in µT
+    pub earth_field: Vec3,
+    /// Key presses and proximity changes
+    pub events: Vec<ScriptedEvent>,
+    /// Seed for the noise generator
+    pub seed: u64,
+    /// Pace `read_event` to wall-clock time like real hardware
+    pub realtime: bool,
+    /// Half of the horizontal display FOV in radians, as reported by ar-drivers
+    pub display_fov: f32,
+    /// Display delay in microseconds
+    pub display_delay_us: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            motion: MotionScript::default(),
+            sample_rate_hz: 1000,
+            mag_rate_hz: 100,
+            gyro_noise_std: 0.002,
+            accel_noise_std: 0.02,
+            mag_noise_std: 0.3,
+            gyro_bias: Vec3::ZERO,
+            gyro_bias_drift: Vec3::ZERO,
+            accel_bias: Vec3::ZERO,
+            mag_bias: Vec3::ZERO,
+            // Mid-latitude field: north is forward (-Z), dipping downwards
+            earth_field: Vec3::new(0.0, -44.0, -20.0),
+            events: Vec::new(),
+            seed: 0x5EED_1234_ABCD_0001,
+            realtime: true,
+            display_fov: 24f32.to_radians(),
+            display_delay_us: 7000,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// Build a configuration from `XREAL_SIM_*` environment variables
+    ///
+    /// - `XREAL_SIM_MOTION`: `stationary`, `rotate`, `oscillate`, `step-turns` or `look-around`
+    /// - `XREAL_SIM_RATE`: accelerometer/gyroscope rate in Hz
+    /// - `XREAL_SIM_NOISE`: multiplier applied to the default noise levels
+    /// - `XREAL_SIM_GYRO_BIAS`: constant yaw-axis gyro bias in rad/s
+    /// - `XREAL_SIM_SEED`: noise generator seed
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+
+        if let Ok(motion) = std::env::var("XREAL_SIM_MOTION") {
+            config.motion = match motion.as_str() {
+                "rotate" => MotionScript::constant_rotation(Vec3::Y, 20f32.to_radians()),
+                "oscillate" => MotionScript::oscillation(Vec3::Y, 30f32.to_radians(), 0.25),
+                "step-turns" => MotionScript::step_turns(Vec3::Y, 90f32.to_radians(), 0.3, 2.0),
+                "look-around" => MotionScript::look_around(),
+                _ => MotionScript::stationary(),
+            };
+        }
+        if let Some(rate) = env_parse::<u32>("XREAL_SIM_RATE") {
+            config.sample_rate_hz = rate.clamp(10, 4000);
+        }
+        if let Some(scale) = env_parse::<f32>("XREAL_SIM_NOISE") {
+            config.gyro_noise_std *= scale;
+            config.accel_noise_std *= scale;
+            config.mag_noise_std *= scale;
+        }
+        if let Some(bias) = env_parse::<f32>("XREAL_SIM_GYRO_BIAS") {
+            config.gyro_bias = Vec3::new(0.0, bias, 0.0);
+        }
+        if let Some(seed) = env_parse::<u64>("XREAL_SIM_SEED") {
+            config.seed = seed;
+        }
+
+        config
+    }
+}
+
+#[inline]
+fn env_parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|v| v.parse().ok())
+}
+
+/// Small deterministic Gaussian noise source (xorshift64* + Box-Muller)
+#[derive(Debug, Clone)]
+pub struct NoiseGenerator {
+    state: u64,
+    spare: Option<f32>,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed.max(1),
+            spare: None,
+        }
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform sample in (0, 1]
+    #[inline]
+    fn next_unit(&mut self) -> f32 {
+        ((self.next_u64() >> 40) as f32 + 1.0) / (1u64 << 24) as f32
+    }
+
+    /// Sample from N(0, 1)
+    pub fn gaussian(&mut self) -> f32 {
+        if let Some(spare) = self.spare.take() {
+            return spare;
+        }
+        let u1 = self.next_unit();
+        let u2 = self.next_unit();
+        let radius = (-2.0 * u1.ln()).sqrt();
+        let theta = std::f32::consts::TAU * u2;
+        self.spare = Some(radius * theta.sin());
+        radius * theta.cos()
+    }
+
+    /// Vector with independent N(0, std²) components
+    #[inline]
+    pub fn vec3(&mut self, std: f32) -> Vec3 {
+        if std <= 0.0 {
+            return Vec3::ZERO;
+        }
+        Vec3::new(self.gaussian(), self.gaussian(), self.gaussian()) * std
+    }
+}
+
+/// Tracks progress through a [`MotionScript`] as simulated time advances
+#[derive(Debug, Clone)]
+pub struct MotionPlayer {
+    script: MotionScript,
+    segment_index: usize,
+    segment_start: f64,
+    segment_origin: Quat,
+}
+
+impl MotionPlayer {
+    pub fn new(script: MotionScript) -> Self {
+        Self {
+            script,
+            segment_index: 0,
+            segment_start: 0.0,
+            segment_origin: Quat::IDENTITY,
+        }
+    }
+
+    /// Orientation (device to world) and body-frame angular velocity at `t`
+    ///
+    /// `t` must not decrease between calls.
+    pub fn sample(&mut self, t: f64) -> (Quat, Vec3) {
+        if self.script.segments.is_empty() {
+            return (self.segment_origin, Vec3::ZERO);
+        }
+
+        // Segments without a positive duration take no time, a pass through
+        // the script that doesn't move time on holds the pose
+        let mut passed = 0;
+        let mut pass_start = self.segment_start;
+        loop {
+            let segment = self.script.segments[self.segment_index];
+            let duration = if segment.duration_secs > 0.0 {
+                segment.duration_secs as f64
+            } else {
+                0.0
+            };
+            let local_t = t - self.segment_start;
+
+            let last_segment = self.segment_index + 1 == self.script.segments.len();
+            if local_t < duration || (last_segment && !self.script.looping) {
+                let (axis, angle, rate) = segment.motion.angle_and_rate(local_t as f32);
+                let orientation = self.segment_origin * Quat::from_axis_angle(axis, angle);
+                return (orientation.normalize(), axis * rate);
+            }
+
+            // Carry the end pose of this segment over to the next one
+            if duration > 0.0 {
+                let (axis, angle, _) = segment.motion.angle_and_rate(duration as f32);
+                self.segment_origin =
+                    (self.segment_origin * Quat::from_axis_angle(axis, angle)).normalize();
+                self.segment_start += duration;
+            }
+            self.segment_index = (self.segment_index + 1) % self.script.segments.len();
+
+            passed += 1;
+            if passed == self.script.segments.len() {
+                if self.segment_start <= pass_start {
+                    return (self.segment_origin, Vec3::ZERO);
+                }
+                passed = 0;
+                pass_start = self.segment_start;
+            }
+        }
+    }
+}
+
+/// Simulated glasses producing a synthetic `GlassesEvent` stream
+pub struct SimulatedGlasses {
+    config: SimulationConfig,
+    player: MotionPlayer,
+    noise: NoiseGenerator,
+    display_mode: DisplayMode,
+    started_at: Instant,
+    imu_tick: u64,
+    mag_tick: u64,
+    next_event: usize,
+    true_orientation: Quat,
+}
+
+impl SimulatedGlasses {
+    pub fn new(config: SimulationConfig) -> Self {
+        let mut events = config.events.clone();
+        events.sort_by(|a, b| a.at_secs.total_cmp(&b.at_secs));
+
+        Self {
+            player: MotionPlayer::new(config.motion.clone()),
+            noise: NoiseGenerator::new(config.seed),
+            config: SimulationConfig { events, ..config },
+            display_mode: DisplayMode::SameOnBoth,
+            started_at: Instant::now(),
+            imu_tick: 0,
+            mag_tick: 0,
+            next_event: 0,
+            true_orientation: Quat::IDENTITY,
+        }
+    }
+
+    #[inline]
+    fn imu_time(&self) -> f64 {
+        self.imu_tick as f64 / self.config.sample_rate_hz.max(1) as f64
+    }
+
+    #[inline]
+    fn mag_time(&self) -> f64 {
+        if self.config.mag_rate_hz == 0 {
+            f64::INFINITY
+        } else {
+            self.mag_tick as f64 / self.config.mag_rate_hz as f64
+        }
+    }
+
+    #[inline]
+    fn scripted_time(&self) -> f64 {
+        self.config
+            .events
+            .get(self.next_event)
+            .map(|e| e.at_secs as f64)
+            .unwrap_or(f64::INFINITY)
+    }
+
+    /// Block until wall-clock time catches up with simulated time `t`
+    fn pace(&self, t: f64) {
+        if !self.config.realtime {
+            return;
+        }
+        let target = self.started_at + Duration::from_secs_f64(t);
+        let now = Instant::now();
+        if target > now {
+            std::thread::sleep(target - now);
+        }
+    }
+
+    #[inline]
+    fn timestamp_us(t: f64) -> u64 {
+        (t * 1_000_000.0).round() as u64
+    }
+
+    fn next_imu_event(&mut self) -> GlassesEvent {
+        let t = self.imu_time();
+        self.imu_tick += 1;
+
+        let (orientation, angular_velocity) = self.player.sample(t);
+        self.true_orientation = orientation;
+
+        let gravity = orientation.inverse() * Vec3::new(0.0, GRAVITY, 0.0);
+        let gyro_bias = self.config.gyro_bias + self.config.gyro_bias_drift * t as f32;
+        let gyro = angular_velocity + gyro_bias + self.noise.vec3(self.config.gyro_noise_std);
+        let accel = gravity + self.config.accel_bias + self.noise.vec3(self.config.accel_noise_std);
+
+        GlassesEvent::AccGyro {
+            accelerometer: to_vector3(accel),
+            gyroscope: to_vector3(gyro),
+            timestamp: Self::timestamp_us(t),
+        }
+    }
+
+    fn next_mag_event(&mut self) -> GlassesEvent {
+        let t = self.mag_time();
+        self.mag_tick += 1;
+
+        let field = self.true_orientation.inverse() * self.config.earth_field
+            + self.config.mag_bias
+            + self.noise.vec3(self.config.mag_noise_std);
+
+        GlassesEvent::Magnetometer {
+            magnetometer: to_vector3(field),
+            timestamp: Self::timestamp_us(t),
+        }
+    }
+
+    fn next_scripted_event(&mut self) -> GlassesEvent {
+        let event = self.config.events[self.next_event].event;
+        self.next_event += 1;
+
+        match event {
+            SimulatedEvent::KeyPress(key) => GlassesEvent::KeyPress(key),
+            SimulatedEvent::ProximityNear => GlassesEvent::ProximityNear,
+            SimulatedEvent::ProximityFar => GlassesEvent::ProximityFar,
+        }
+    }
+}
+
+impl ARGlasses for SimulatedGlasses {
+    fn serial(&mut self) -> Result<String, ar_drivers::Error> {
+        Ok(format!("SIM-{:08X}", self.config.seed as u32))
+    }
+
+    fn read_event(&mut self) -> Result<GlassesEvent, ar_drivers::Error> {
+        let imu_t = self.imu_time();
+        let mag_t = self.mag_time();
+        let scripted_t = self.scripted_time();
+
+        if scripted_t <= imu_t && scripted_t <= mag_t {
+            self.pace(scripted_t);
+            Ok(self.next_scripted_event())
+        } else if mag_t < imu_t {
+            self.pace(mag_t);
+            Ok(self.next_mag_event())
+        } else {
+            self.pace(imu_t);
+            Ok(self.next_imu_event())
+        }
+    }
+
+    fn get_display_mode(&mut self) -> Result<DisplayMode, ar_drivers::Error> {
+        Ok(self.display_mode)
+    }
+
+    fn set_display_mode(&mut self, display_mode: DisplayMode) -> Result<(), ar_drivers::Error> {
+        self.display_mode = display_mode;
+        Ok(())
+    }
+
+    fn display_fov(&self) -> f32 {
+        self.config.display_fov
+    }
+
+    fn imu_to_display_matrix(&self, side: Side, ipd: f32) -> Isometry3<f64> {
+        let side_multiplier = match side {
+            Side::Left => -0.5,
+            Side::Right => 0.5,
+        };
+        Isometry3::from_parts(
+            Translation3::new(ipd as f64 * side_multiplier, 0.0, 0.0),
+            UnitQuaternion::identity(),
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        SIMULATED_DEVICE_NAME
+    }
+
+    fn display_delay(&self) -> u64 {
+        self.config.display_delay_us
+    }
+}
+
+#[inline]
+fn to_vector3(v: Vec3) -> Vector3<f32> {
+    Vector3::new(v.x, v.y, v.z)
+}
@@ -0,0 +1,266 @@
+//! Glasses hardware buttons
+//!
+//! The glasses only report individual key presses, so press variants are
+//! recognised from timing: a lone press is a short press, two presses within
+//! the double press window are a double press, and a burst of repeated
+//! presses lasting at least the long press duration is a long press.
+//! Recognised presses are looked up in the persisted bindings and turned
+//! into [`InputActionEvent`]s.
+
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use crate::driver::GlassesInputChannels;
+use crate::input::CursorState;
+use crate::render::ScreenLayout;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::input::{ButtonPress, GlassesButtonSettings, InputAction};
+use crate::tracking::Command;
+use crate::ui::state::SettingsPanelState;
+use crate::{BrightnessState, CommandChannel, DisplayModeState};
+
+/// Highest brightness level supported by the glasses
+const MAX_BRIGHTNESS: u8 = 7;
+
+/// A recognised glasses button press
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlassesButtonEvent {
+    pub button: u8,
+    pub press: ButtonPress,
+}
+
+/// Request to perform a bound input action
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputActionEvent(pub InputAction);
+
+/// Presses of one button that may still grow into a double or long press
+#[derive(Debug, Clone, Copy)]
+struct PressSequence {
+    button: u8,
+    first: Instant,
+    last: Instant,
+    count: u32,
+    long_reported: bool,
+}
+
+/// Turns raw key presses into short, long and double presses
+#[derive(Debug, Default)]
+pub struct ButtonPressDetector {
+    sequences: Vec<PressSequence>,
+}
+
+impl ButtonPressDetector {
+    /// Feed a raw key press, returning any presses it completes
+    pub fn press(
+        &mut self,
+        button: u8,
+        at: Instant,
+        settings: &GlassesButtonSettings,
+    ) -> Vec<GlassesButtonEvent> {
+        let window = Duration::from_millis(settings.double_press_ms as u64);
+        let long_press = Duration::from_millis(settings.long_press_ms as u64);
+        let mut completed = Vec::new();
+
+        if let Some(index) = self.sequences.iter().position(|s| s.button == button) {
+            let sequence = &mut self.sequences[index];
+            if at.saturating_duration_since(sequence.last) <= window {
+                sequence.count += 1;
+                sequence.last = at;
+                if !sequence.long_reported
+                    && sequence.count >= 3
+                    && at.saturating_duration_since(sequence.first) >= long_press
+                {
+                    sequence.long_reported = true;
+                    completed.push(GlassesButtonEvent {
+                        button,
+                        press: ButtonPress::Long,
+                    });
+                }
+                return completed;
+            }
+            completed.extend(Self::finish(self.sequences.swap_remove(index)));
+        }
+
+        self.sequences.push(PressSequence {
+            button,
+            first: at,
+            last: at,
+            count: 1,
+            long_reported: false,
+        });
+        completed
+    }
+
+    /// Complete sequences whose double press window has passed
+    pub fn poll(
+        &mut self,
+        now: Instant,
+        settings: &GlassesButtonSettings,
+    ) -> Vec<GlassesButtonEvent> {
+        let window = Duration::from_millis(settings.double_press_ms as u64);
+        let mut completed = Vec::new();
+        self.sequences.retain(|sequence| {
+            if now.saturating_duration_since(sequence.last) <= window {
+                return true;
+            }
+            completed.extend(Self::finish(*sequence));
+            false
+        });
+        completed
+    }
+
+    /// True while a press is waiting for its double press window to pass
+    pub fn is_pending(&self) -> bool {
+        !self.sequences.is_empty()
+    }
+
+    fn finish(sequence: PressSequence) -> Option<GlassesButtonEvent> {
+        if sequence.long_reported {
+            return None;
+        }
+        let press = if sequence.count == 1 {
+            ButtonPress::Short
+        } else {
+            ButtonPress::Double
+        };
+        Some(GlassesButtonEvent {
+            button: sequence.button,
+            press,
+        })
+    }
+}
+
+/// Button detection state shared with the settings UI
+#[derive(Resource, Debug, Default)]
+pub struct GlassesButtonState {
+    detector: ButtonPressDetector,
+    /// Most recent recognised press
+    pub last_press: Option<GlassesButtonEvent>,
+    /// Buttons pressed since startup
+    pub seen_buttons: BTreeSet<u8>,
+}
+
+/// Registers glasses button handling and bound action dispatch
+pub struct GlassesButtonPlugin;
+
+impl Plugin for GlassesButtonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GlassesButtonEvent>()
+            .add_event::<InputActionEvent>()
+            .init_resource::<GlassesButtonState>()
+            .add_systems(Update, (read_glasses_buttons, apply_input_actions).chain());
+    }
+}
+
+/// Recognise button presses from the broker key stream and emit bound actions
+pub fn read_glasses_buttons(
+    channels: Option<Res<GlassesInputChannels>>,
+    persistent_state: Res<PersistentAppState>,
+    mut state: ResMut<GlassesButtonState>,
+    mut button_events: EventWriter<GlassesButtonEvent>,
+    mut action_events: EventWriter<InputActionEvent>,
+) {
+    let Some(channels) = channels else {
+        return;
+    };
+    let settings = &persistent_state.input_config.glasses_buttons;
+    if !settings.enabled {
+        channels.keys.try_iter().for_each(drop);
+        return;
+    }
+
+    let mut presses = Vec::new();
+    for key in channels.keys.try_iter() {
+        state.seen_buttons.insert(key.key);
+        presses.extend(state.detector.press(key.key, key.at, settings));
+    }
+    if state.detector.is_pending() {
+        presses.extend(state.detector.poll(Instant::now(), settings));
+    }
+
+    for press in presses {
+        state.last_press = Some(press);
+        button_events.write(press);
+        match settings.action_for(press.button, press.press) {
+            Some(action) => {
+                info!(
+                    "🔘 Button {} {} press: {}",
+                    press.button,
+                    press.press.label().to_lowercase(),
+                    action.label()
+                );
+                action_events.write(InputActionEvent(action));
+            }
+            None => debug!(
+                "Unbound button {} {} press",
+                press.button,
+                press.press.label().to_lowercase()
+            ),
+        }
+    }
+}
+
+/// Perform input actions requested by buttons or other input sources
+pub fn apply_input_actions(
+    mut action_events: EventReader<InputActionEvent>,
+    command_sender: Res<CommandChannel>,
+    mut display_mode: ResMut<DisplayModeState>,
+    mut brightness: ResMut<BrightnessState>,
+    mut screen_layout: ResMut<ScreenLayout>,
+    mut persistent_state: ResMut<PersistentAppState>,
+    mut settings_panel: Option<ResMut<SettingsPanelState>>,
+    mut cursor_state: Option<ResMut<CursorState>>,
+) {
+    for InputActionEvent(action) in action_events.read() {
+        match action {
+            InputAction::Recenter => {
+                if let Err(e) = command_sender.0.try_send(Command::Recenter) {
+                    error!("Failed to send recenter command: {}", e);
+                }
+            }
+            InputAction::ToggleDisplayMode => {
+                let enabled = display_mode
+                    .pending_change
+                    .unwrap_or(display_mode.is_3d_enabled);
+                display_mode.pending_change = Some(!enabled);
+                if let Some(panel) = settings_panel.as_mut() {
+                    panel.sbs_enabled = !enabled;
+                }
+            }
+            InputAction::CycleBrightness => {
+                let level = (brightness.current_level + 1) % (MAX_BRIGHTNESS + 1);
+                brightness.current_level = level;
+                brightness.pending_change = Some(level);
+                if let Some(panel) = settings_panel.as_mut() {
+                    panel.brightness = level;
+                }
+                if let Err(e) = command_sender.0.try_send(Command::SetBrightness(level)) {
+                    error!("Failed to send brightness command: {}", e);
+                }
+            }
+            InputAction::ToggleSettingsPanel => {
+                if let Some(panel) = settings_panel.as_mut() {
+                    panel.is_open = !panel.is_open;
+                }
+            }
+            InputAction::SwitchLayout => {
+                let arrangement = screen_layout.next_arrangement();
+                screen_layout.arrangement = arrangement;
+                persistent_state.window_layout.multi_monitor.arrangement = arrangement;
+                info!("🖼️  Screen layout: {:?}", arrangement);
+            }
+            InputAction::ConfirmDwell => {
+                if let Some(cursor) = cursor_state.as_mut() {
+                    cursor.confirm_dwell();
+                }
+            }
+            InputAction::DismissDialog => {
+                if let Some(panel) = settings_panel.as_mut() {
+                    panel.is_open = false;
+                }
+                persistent_state.tracking.prediction.show_overlay = false;
+            }
+        }
+    }
+}
@@ -51,6 +51,12 @@ impl CursorState {
         self.is_dwelling = true;
     }
 
+    /// Complete the dwell at once, e.g. when confirmed by a head gesture
+    pub fn confirm_dwell(&mut self) {
+        self.is_dwelling = true;
+        self.dwell_time = self.dwell_threshold;
+    }
+
     /// Stop the dwell timer
     pub fn stop_dwell(&mut self) {
         self.is_dwelling = false;
@@ -15,6 +15,7 @@ use bevy::ecs::schedule::SystemSet;
 use bevy::log::warn;
 
 // Re-export commonly used types
+pub use buttons::{GlassesButtonEvent, GlassesButtonPlugin, InputActionEvent};
 pub use cursor::CursorState;
 // pub use error::InputError; // Currently unused
 pub use plugins::input::InputSystem;
@@ -22,6 +23,7 @@ pub use render::VirtualScreen;
 pub use tracking::Orientation;
 
 // Internal modules
+pub mod buttons;
 mod cursor;
 mod error;
 pub mod plugins;
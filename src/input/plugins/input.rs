@@ -160,6 +160,11 @@ impl InputSystem {
             .map_err(|e| InputError::TextInput(e.to_string()))
     }
 
+    /// True unless input injection is disabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
     /// Update configuration
     pub fn update_config<F>(&mut self, f: F) -> Result<(), InputError>
     where
@@ -7,11 +7,14 @@ use bevy::prelude::*;
 use crossbeam_channel::{Receiver, Sender};
 
 // Include all modules that need to be available for both binary and library
+pub mod anchoring;
 pub mod capture;
 pub mod cursor;
 pub mod driver;
 pub mod input;
+pub mod neck;
 pub mod plugins;
+pub mod prediction;
 pub mod render;
 pub mod setup;
 pub mod state;
@@ -10,11 +10,14 @@ use crossbeam_channel::{bounded, Receiver, Sender};
 use xreal_browser_plugin::BrowserPlugin;
 use xreal_terminal_plugin::TerminalPlugin;
 
+mod anchoring;
 mod capture;
 mod cursor;
 mod driver;
 mod input;
+mod neck;
 mod plugins;
+mod prediction;
 mod render;
 mod setup;
 mod tracking;
@@ -32,10 +35,10 @@ use render::{
 
 use tracking::{CalibrationState, Command, Data, Orientation};
 use ui::{reset_ui_guard, settings_ui, state::*};
-use xreal_stereo::{StereoRenderTargets, StereoSettings, XRealStereoRenderingPlugin};
+use xreal_stereo::{StereoRenderTargets, XRealStereoRenderingPlugin};
 
 // Re-export state types from lib.rs for internal module access
-pub use xreal_virtual_desktop::{BrightnessState, DisplayModeState, RollLockState};
+pub use xreal_virtual_desktop::{state, BrightnessState, DisplayModeState, RollLockState};
 
 // Import plugin system
 use plugins::{add_plugin_system, PluginSystemConfig};
@@ -68,12 +71,28 @@ pub struct FrameCounter {
 async fn main() -> Result<()> {
     let (command_tx, command_rx) = bounded(10);
     let (data_tx, data_rx) = bounded(10);
-
-    tokio::spawn(async move {
-        if let Err(e) = tracking::poll_imu_bevy(command_rx, data_tx).await {
-            error!("IMU polling task failed: {}", e);
-        }
-    });
+    let poses = tracking::PoseQueue::default();
+
+    // The device broker is the only owner of the glasses handle
+    let source = driver::GlassesSource::from_env();
+    let replay_stepper = source.replay_stepper();
+    let driver::DeviceBroker {
+        imu,
+        keys,
+        proximity,
+        status,
+        commands: device_tx,
+        ..
+    } = driver::DeviceBroker::spawn(source.clone())?;
+
+    tracking::spawn_imu_tracking(
+        source.clone(),
+        imu,
+        command_rx,
+        data_tx,
+        poses.clone(),
+        device_tx.clone(),
+    )?;
 
     let mut app = App::new();
 
@@ -84,8 +103,17 @@ async fn main() -> Result<()> {
         }),
         ..default()
     }))
+    .init_state::<AppState>()
     .add_plugins((EguiPlugin::default(), FrameTimeDiagnosticsPlugin::default()))
+    .add_plugins(setup::StartupChecksPlugin)
     .add_plugins(XRealStereoRenderingPlugin)
+    .add_plugins(driver::GlassesConnectionPlugin)
+    .add_plugins(state::PersistentStatePlugin::default())
+    .init_resource::<render::ScreenLayout>()
+    .add_plugins(input::GlassesButtonPlugin)
+    .add_plugins(driver::GlassesProximityPlugin)
+    .add_plugins(prediction::PosePredictionPlugin)
+    .add_plugins(tracking::TrackingSettingsPlugin)
     // Add the new Bevy plugin system
     .add_plugins((
         BrowserPlugin::new()
@@ -102,8 +130,17 @@ async fn main() -> Result<()> {
         error!("Failed to initialize plugin system: {}", e);
     }
 
-    app.insert_resource(DataChannel(data_rx))
+    // Stepped replays only advance when stepped from the settings panel
+    if let Some(stepper) = replay_stepper {
+        app.insert_resource(driver::ReplayStepControl(stepper));
+    }
+
+    app.insert_resource(source)
+        .insert_resource(DataChannel(data_rx))
+        .insert_resource(poses)
         .insert_resource(CommandChannel(command_tx))
+        .insert_resource(driver::XRealDevice::new(device_tx, status))
+        .insert_resource(driver::GlassesInputChannels { keys, proximity })
         .insert_resource(Orientation::default())
         .insert_resource(CalibrationState::default())
         .insert_resource(ScreenDistance(2.0))
@@ -125,24 +162,47 @@ async fn main() -> Result<()> {
         })
         .add_systems(
             OnEnter(AppState::Running),
-            (initialize_xreal_device, setup_3d_scene, spawn_head_cursor).chain(),
+            (setup_3d_scene, spawn_head_cursor).chain(),
+        )
+        .add_systems(
+            OnEnter(AppState::ChecksFailed),
+            ui::checks::spawn_checks_failed_camera,
+        )
+        .add_systems(
+            OnExit(AppState::ChecksFailed),
+            ui::checks::despawn_checks_failed_camera,
         )
         .add_systems(
             Update,
             (
-                update_from_data_channel.run_if(in_state(AppState::Running)),
+                // Drained in every state, the tracking thread waits while the channel is full
+                update_from_data_channel.before(prediction::predict_display_pose),
                 settings_ui.run_if(in_state(AppState::Running)),
                 handle_input.run_if(in_state(AppState::Running)),
-                update_head_cursor.run_if(in_state(AppState::Running)),
+                update_head_cursor
+                    .run_if(in_state(AppState::Running))
+                    .after(anchoring::apply_screen_anchors),
                 update_cursor_material.run_if(in_state(AppState::Running)),
                 log_fps.run_if(in_state(AppState::Running)),
                 reset_ui_guard.run_if(in_state(AppState::Running)),
                 // Render system functions
-                update_camera_from_orientation.run_if(in_state(AppState::Running)),
+                update_camera_from_orientation
+                    .run_if(in_state(AppState::Running))
+                    .after(prediction::predict_display_pose),
                 spawn_capture_tasks.run_if(in_state(AppState::Running)),
                 handle_capture_tasks.run_if(in_state(AppState::Running)),
                 update_screen_positions.run_if(in_state(AppState::Running)),
+                render::apply_screen_layout,
+                anchoring::apply_screen_anchors
+                    .run_if(in_state(AppState::Running))
+                    .after(prediction::predict_display_pose)
+                    .after(update_screen_positions)
+                    .after(render::apply_screen_layout),
                 exercise_stereo_fields.run_if(in_state(AppState::Running)),
+                ui::prediction::prediction_overlay_ui.run_if(in_state(AppState::Running)),
+                ui::checks::checks_failed_ui.run_if(in_state(AppState::ChecksFailed)),
+                capture::match_capture_frame_rate
+                    .run_if(resource_exists_and_changed::<driver::DeviceInfo>),
             ),
         );
 
@@ -151,31 +211,44 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn initialize_xreal_device(mut commands: Commands) {
-    info!("Initializing XREAL device...");
-    match driver::XRealDevice::new() {
-        Ok(device) => {
-            info!("✅ XREAL device initialized successfully.");
-            commands.insert_resource(device);
-        }
-        Err(e) => {
-            error!(
-                "❌ Failed to initialize XREAL device: {}. Running in desktop mode.",
-                e
-            );
-        }
-    }
-}
-
 fn update_from_data_channel(
     rx: Res<DataChannel>,
+    poses: Res<tracking::PoseQueue>,
     mut orientation: ResMut<Orientation>,
+    mut pose_history: ResMut<prediction::PoseHistory>,
     mut cal_state: ResMut<CalibrationState>,
+    mut drift_estimate: ResMut<tracking::drift::DriftEstimate>,
+    mut mag_calibration: ResMut<tracking::magnetometer::MagCalibrationStatus>,
+    mut accel_calibration: ResMut<tracking::accelerometer::AccelCalibrationStatus>,
+    mut gyro_calibration: ResMut<tracking::calibration::LatestGyroCalibration>,
+    mut gesture_events: EventWriter<tracking::gestures::HeadGestureEvent>,
+    mut telemetry: ResMut<tracking::health::ImuTelemetry>,
+    mut quality_events: EventWriter<tracking::health::TrackingQualityEvent>,
 ) {
+    for pose in poses.drain() {
+        orientation.quat = pose.quat;
+        pose_history.push(pose);
+    }
     while let Ok(data) = rx.0.try_recv() {
         match data {
-            Data::Orientation(q) => orientation.quat = q,
             Data::CalState(s) => *cal_state = s,
+            Data::GyroCalibration(result) => gyro_calibration.0 = Some(result),
+            Data::Drift(estimate) => {
+                drift_estimate.set_if_neq(estimate);
+            }
+            Data::MagCalibration(status) => *mag_calibration = status,
+            Data::AccelCalibration(status) => accel_calibration.set_if_neq(status),
+            Data::Gesture(gesture) => {
+                gesture_events.write(tracking::gestures::HeadGestureEvent(gesture));
+            }
+            Data::Telemetry(report) => *telemetry = report,
+            // Sent right after the telemetry that changed it
+            Data::TrackingQuality(quality) => {
+                quality_events.write(tracking::health::TrackingQualityEvent {
+                    quality,
+                    telemetry: *telemetry,
+                });
+            }
         }
     }
 }
@@ -191,7 +264,6 @@ fn log_fps(diagnostics: Res<DiagnosticsStore>) {
 #[inline]
 fn exercise_stereo_fields(
     stereo_targets: Option<Res<StereoRenderTargets>>,
-    stereo_settings: Option<Res<StereoSettings>>,
     mut commands: Commands,
     _asset_server: Res<AssetServer>,
     mut images: ResMut<Assets<Image>>,
@@ -215,18 +287,6 @@ fn exercise_stereo_fields(
                 is_active: true,
             });
         }
-
-        if let Some(settings) = stereo_settings {
-            let _convergence = settings.convergence_distance;
-            let _scale = settings.render_scale;
-        } else {
-            // Create stereo settings to exercise the fields
-            commands.insert_resource(StereoSettings {
-                eye_separation: 0.065,
-                convergence_distance: 2.0,
-                render_scale: 1.0,
-            });
-        }
     }
 }
 
@@ -0,0 +1,28 @@
+//! Neck model for pseudo-6DoF head tracking
+//!
+//! The glasses only report rotation, which turns the cameras about the eye
+//! point. Real heads turn about the neck, so the eyes travel on a small sphere
+//! around it. Deriving that translation from the orientation gives near
+//! content the parallax it would have in the room instead of feeling glued to
+//! the face.
+
+use bevy::prelude::*;
+
+use crate::state::schema::tracking::NeckModelSettings;
+
+/// Eyes relative to the neck pivot, in the head frame
+#[inline]
+fn pivot_to_eyes(settings: &NeckModelSettings) -> Vec3 {
+    Vec3::new(0.0, settings.neck_length_m, -settings.eye_offset_m)
+}
+
+/// Position of the eye center for the head orientation, relative to where it
+/// is when looking straight ahead
+/// Zero while the neck model is disabled
+pub fn eye_position(head: Quat, settings: &NeckModelSettings) -> Vec3 {
+    if !settings.enabled {
+        return Vec3::ZERO;
+    }
+    let eyes = pivot_to_eyes(settings);
+    head * eyes - eyes
+}
@@ -0,0 +1,267 @@
+//! Motion-to-photon latency compensation
+//!
+//! The tracking thread publishes timestamped poses with angular velocity,
+//! which are kept in a short [`PoseHistory`]. Every frame the latest pose is
+//! extrapolated to the expected display time (the glasses display delay plus
+//! the render pipeline latency) and stored in [`PredictedOrientation`], which
+//! the camera systems follow. Each prediction is later compared with the pose
+//! measured at its target time to report the prediction error.
+
+use bevy::prelude::*;
+use instant::Instant;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::driver::XRealDevice;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::tracking::PredictionSettings;
+use crate::tracking::{Orientation, PoseSample};
+
+/// Number of poses kept, about a quarter second at 1 kHz
+const HISTORY_LEN: usize = 256;
+
+/// Number of predictions waiting for their target time to be measured
+const PENDING_LEN: usize = 128;
+
+/// Weight of a new error measurement in the running averages
+const ERROR_SMOOTHING: f32 = 0.05;
+
+/// Recent head poses from the tracking thread, oldest first
+#[derive(Resource, Debug, Default)]
+pub struct PoseHistory {
+    samples: VecDeque<PoseSample>,
+}
+
+impl PoseHistory {
+    /// Add a pose, dropping the oldest once full
+    /// Poses older than the latest one are ignored
+    pub fn push(&mut self, sample: PoseSample) {
+        if self
+            .latest()
+            .is_some_and(|latest| sample.timestamp < latest.timestamp)
+        {
+            return;
+        }
+        if self.samples.len() == HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Most recent pose
+    #[inline]
+    pub fn latest(&self) -> Option<&PoseSample> {
+        self.samples.back()
+    }
+
+    /// Oldest pose still kept
+    #[inline]
+    pub fn oldest(&self) -> Option<&PoseSample> {
+        self.samples.front()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Forget all poses, e.g. after a recenter
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Measured orientation at `at`, interpolated between the surrounding poses
+    /// Returns `None` outside the recorded time span
+    pub fn orientation_at(&self, at: Instant) -> Option<Quat> {
+        let after = self.samples.partition_point(|s| s.timestamp < at);
+        let next = self.samples.get(after)?;
+        if next.timestamp == at {
+            return Some(next.quat);
+        }
+        let previous = self.samples.get(after.checked_sub(1)?)?;
+
+        let span = next
+            .timestamp
+            .duration_since(previous.timestamp)
+            .as_secs_f32();
+        let t = at.duration_since(previous.timestamp).as_secs_f32() / span;
+        Some(previous.quat.slerp(next.quat, t))
+    }
+}
+
+/// Extrapolate a pose `dt` seconds ahead
+/// The angular velocity decays at `damping` per second, so fast turns that
+/// are about to stop are not overshot as much
+pub fn predict_orientation(sample: &PoseSample, dt: f32, damping: f32) -> Quat {
+    let dt = dt.max(0.0);
+    let effective_dt = if damping > 0.0 {
+        (1.0 - (-damping * dt).exp()) / damping
+    } else {
+        dt
+    };
+    // Angular velocity is measured in the head frame
+    (sample.quat * Quat::from_scaled_axis(sample.angular_velocity * effective_dt)).normalize()
+}
+
+/// How far ahead of the current frame the pose should be predicted
+pub fn prediction_horizon(settings: &PredictionSettings, display_delay_us: u64) -> Duration {
+    let horizon_ms = (display_delay_us as f32 / 1000.0 + settings.render_latency_ms)
+        .clamp(0.0, settings.max_horizon_ms.max(0.0));
+    Duration::from_secs_f32(horizon_ms / 1000.0)
+}
+
+/// Head orientation predicted for the expected display time
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PredictedOrientation {
+    pub quat: Quat,
+    /// Time between the frame and the display time the pose is predicted for
+    pub horizon: Duration,
+}
+
+impl Default for PredictedOrientation {
+    fn default() -> Self {
+        Self {
+            quat: Quat::IDENTITY,
+            horizon: Duration::ZERO,
+        }
+    }
+}
+
+/// A prediction waiting for the pose at its target time
+#[derive(Debug, Clone, Copy)]
+struct PendingPrediction {
+    target: Instant,
+    predicted: Quat,
+    unpredicted: Quat,
+}
+
+/// Prediction error measured against the poses that actually arrived
+#[derive(Resource, Debug, Default)]
+pub struct PredictionStats {
+    pending: VecDeque<PendingPrediction>,
+    /// Running average error of the predicted pose, in degrees
+    pub mean_error_deg: f32,
+    /// Running average error without prediction, in degrees
+    pub mean_unpredicted_error_deg: f32,
+    /// Largest predicted pose error since the last reset, in degrees
+    pub max_error_deg: f32,
+    /// Number of predictions measured since the last reset
+    pub measured: u64,
+    /// Head angular speed of the latest pose, in degrees per second
+    pub angular_speed_dps: f32,
+}
+
+impl PredictionStats {
+    /// Remember a prediction for `target` along with the pose it started from
+    pub fn record(&mut self, target: Instant, predicted: Quat, unpredicted: Quat) {
+        if self.pending.len() == PENDING_LEN {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(PendingPrediction {
+            target,
+            predicted,
+            unpredicted,
+        });
+    }
+
+    /// Measure predictions whose target time is covered by the history
+    pub fn resolve(&mut self, history: &PoseHistory) {
+        let (Some(oldest), Some(latest)) = (history.oldest(), history.latest()) else {
+            return;
+        };
+        let (oldest, latest) = (oldest.timestamp, latest.timestamp);
+
+        while let Some(pending) = self.pending.front().copied() {
+            if pending.target > latest {
+                break;
+            }
+            self.pending.pop_front();
+            if pending.target < oldest {
+                continue;
+            }
+            if let Some(actual) = history.orientation_at(pending.target) {
+                let error = pending.predicted.angle_between(actual).to_degrees();
+                let unpredicted = pending.unpredicted.angle_between(actual).to_degrees();
+                self.add_measurement(error, unpredicted);
+            }
+        }
+    }
+
+    /// Clear the error statistics
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn add_measurement(&mut self, error: f32, unpredicted: f32) {
+        if self.measured == 0 {
+            self.mean_error_deg = error;
+            self.mean_unpredicted_error_deg = unpredicted;
+        } else {
+            self.mean_error_deg += (error - self.mean_error_deg) * ERROR_SMOOTHING;
+            self.mean_unpredicted_error_deg +=
+                (unpredicted - self.mean_unpredicted_error_deg) * ERROR_SMOOTHING;
+        }
+        self.max_error_deg = self.max_error_deg.max(error);
+        self.measured += 1;
+    }
+}
+
+/// Registers pose history and display time prediction
+pub struct PosePredictionPlugin;
+
+impl Plugin for PosePredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PoseHistory>()
+            .init_resource::<PredictedOrientation>()
+            .init_resource::<PredictionStats>()
+            .add_systems(Update, predict_display_pose);
+    }
+}
+
+/// Predict the head orientation for when the current frame reaches the displays
+pub fn predict_display_pose(
+    persistent_state: Res<PersistentAppState>,
+    xreal_device: Option<Res<XRealDevice>>,
+    orientation: Res<Orientation>,
+    history: Res<PoseHistory>,
+    mut predicted: ResMut<PredictedOrientation>,
+    mut stats: ResMut<PredictionStats>,
+) {
+    let settings = &persistent_state.tracking.prediction;
+    let latest = history.latest().copied();
+
+    let Some(latest) = latest.filter(|_| settings.enabled) else {
+        if predicted.quat != orientation.quat || predicted.horizon != Duration::ZERO {
+            *predicted = PredictedOrientation {
+                quat: orientation.quat,
+                horizon: Duration::ZERO,
+            };
+        }
+        return;
+    };
+
+    let display_delay_us = xreal_device
+        .as_deref()
+        .and_then(XRealDevice::identity)
+        .map_or(0, |identity| identity.display_delay_us);
+    let horizon = prediction_horizon(settings, display_delay_us);
+    let target = Instant::now() + horizon;
+
+    // Samples that stopped arriving are not extrapolated indefinitely
+    let max_ahead = Duration::from_secs_f32(settings.max_horizon_ms.max(0.0) / 1000.0);
+    let ahead = target
+        .saturating_duration_since(latest.timestamp)
+        .min(max_ahead);
+    let quat = predict_orientation(&latest, ahead.as_secs_f32(), settings.damping);
+
+    *predicted = PredictedOrientation { quat, horizon };
+
+    stats.angular_speed_dps = latest.angular_velocity.length().to_degrees();
+    stats.resolve(&history);
+    stats.record(target, quat, latest.quat);
+}
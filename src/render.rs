@@ -1,5 +1,9 @@
+use crate::anchoring::ScreenAnchor;
 use crate::capture::CaptureTask;
-use crate::{Orientation, ScreenCaptures, ScreenDistance};
+use crate::neck;
+use crate::prediction::PredictedOrientation;
+use crate::state::schema::{core::PersistentAppState, MonitorArrangement};
+use crate::{ScreenCaptures, ScreenDistance};
 use bevy::prelude::*;
 // render_asset and render_resource imports are used in the Image creation functions
 
@@ -9,6 +13,56 @@ pub struct VirtualScreen(pub usize);
 #[derive(Component)]
 pub struct ScreenMaterial(pub Handle<StandardMaterial>);
 
+/// Spacing between neighbouring virtual screens
+const SCREEN_SPACING: f32 = 3.0;
+
+/// Arrangement of the virtual screens, persisted with the window layout
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ScreenLayout {
+    pub arrangement: MonitorArrangement,
+}
+
+impl FromWorld for ScreenLayout {
+    fn from_world(world: &mut World) -> Self {
+        let arrangement = world
+            .get_resource::<PersistentAppState>()
+            .map(|state| state.window_layout.multi_monitor.arrangement)
+            .unwrap_or_default();
+        Self { arrangement }
+    }
+}
+
+impl ScreenLayout {
+    /// Next arrangement in the layout cycle
+    pub fn next_arrangement(&self) -> MonitorArrangement {
+        match self.arrangement {
+            MonitorArrangement::Horizontal => MonitorArrangement::Vertical,
+            MonitorArrangement::Vertical => MonitorArrangement::Grid,
+            MonitorArrangement::Grid | MonitorArrangement::Custom => MonitorArrangement::Horizontal,
+        }
+    }
+
+    /// Offset of a screen from the layout center
+    pub fn screen_offset(&self, index: usize, count: usize) -> Vec2 {
+        let centered =
+            |i: usize, n: usize| (i as f32 - (n.max(1) - 1) as f32 * 0.5) * SCREEN_SPACING;
+        match self.arrangement {
+            MonitorArrangement::Horizontal | MonitorArrangement::Custom => {
+                Vec2::new(centered(index, count), 0.0)
+            }
+            MonitorArrangement::Vertical => Vec2::new(0.0, -centered(index, count)),
+            MonitorArrangement::Grid => {
+                let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+                let rows = count.div_ceil(columns);
+                Vec2::new(
+                    centered(index % columns, columns),
+                    -centered(index / columns, rows),
+                )
+            }
+        }
+    }
+}
+
 #[inline]
 pub fn setup_3d_scene(
     mut commands: Commands,
@@ -16,12 +70,13 @@ pub fn setup_3d_scene(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
     captures: Option<Res<ScreenCaptures>>,
+    layout: Res<ScreenLayout>,
 ) {
     let num_screens = captures.as_ref().map(|c| c.num_streams).unwrap_or(1);
 
     // Create virtual screens with optimized spacing
     for i in 0..num_screens {
-        let x = (i as f32 - (num_screens - 1) as f32 * 0.5) * 3.0;
+        let offset = layout.screen_offset(i, num_screens);
         // Create production screen capture texture with double buffering
         let capture_texture = create_screen_capture_texture(i as u32);
 
@@ -37,9 +92,10 @@ pub fn setup_3d_scene(
             .spawn_empty()
             .insert(Mesh3d(mesh_handle))
             .insert(MeshMaterial3d(material_handle.clone()))
-            .insert(Transform::from_xyz(x, 0.0, -5.0))
+            .insert(Transform::from_xyz(offset.x, offset.y, -5.0))
             .insert(Visibility::default())
             .insert(VirtualScreen(i))
+            .insert(ScreenAnchor::new(Vec3::new(offset.x, offset.y, -5.0)))
             .insert(ScreenMaterial(material_handle));
     }
 
@@ -68,13 +124,18 @@ pub fn setup_3d_scene(
     });
 }
 
+/// Rotate the main camera to the head orientation predicted for display time
+/// and move it with the eyes when the neck model is enabled
 #[inline]
 pub fn update_camera_from_orientation(
     mut query: Query<&mut Transform, With<Camera>>,
-    orientation: Res<Orientation>,
+    orientation: Res<PredictedOrientation>,
+    persistent_state: Res<PersistentAppState>,
 ) {
     if let Ok(mut transform) = query.single_mut() {
         transform.rotation = orientation.quat;
+        transform.translation =
+            neck::eye_position(orientation.quat, &persistent_state.tracking.neck_model);
     }
 }
 
@@ -138,12 +199,29 @@ pub fn handle_capture_tasks(
 
 #[inline]
 pub fn update_screen_positions(
-    mut query: Query<&mut Transform, With<VirtualScreen>>,
+    mut query: Query<&mut ScreenAnchor, With<VirtualScreen>>,
     distance: Res<ScreenDistance>,
 ) {
     let dist = distance.0;
-    for mut transform in &mut query {
-        transform.translation.z = dist;
+    for mut anchor in &mut query {
+        anchor.local_position.z = dist;
+    }
+}
+
+/// Re-arrange the virtual screens when the layout changes
+pub fn apply_screen_layout(
+    layout: Res<ScreenLayout>,
+    mut query: Query<(&VirtualScreen, &mut ScreenAnchor)>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+
+    let count = query.iter().count();
+    for (screen, mut anchor) in &mut query {
+        let offset = layout.screen_offset(screen.0, count);
+        anchor.local_position.x = offset.x;
+        anchor.local_position.y = offset.y;
     }
 }
 
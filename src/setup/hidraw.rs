@@ -0,0 +1,307 @@
+//! Linux device permission checks
+//!
+//! On Linux the glasses are read through `/dev/hidraw*` nodes, which only root
+//! can open until a udev rule grants access. This scans sysfs for USB and
+//! hidraw devices of known glasses models, checks read/write access to
+//! their device nodes and generates the udev rules that fix missing access.
+//! The sysfs and `/dev` roots are injectable so the scan can run against a
+//! fake tree.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the generated udev rules file
+/// Ordered before 73-seat-late.rules so the `uaccess` tag takes effect
+pub const UDEV_RULES_FILE: &str = "70-xreal-glasses.rules";
+
+/// USB vendor and product IDs of supported glasses, from the ar-drivers device constants
+/// Vendors such as OmniVision also make unrelated devices, so only both IDs together
+/// identify the glasses. Mad Gaze glasses talk over a USB serial port instead of hidraw
+/// and are not listed.
+pub const KNOWN_DEVICES: &[(u16, u16, &str)] = &[
+    (0x3318, 0x0424, "XREAL Air"),
+    (0x3318, 0x0428, "XREAL Air 2"),
+    (0x3318, 0x0432, "XREAL Air 2 Pro"),
+    (0x0486, 0x573c, "Nreal Light"),
+    (0x05a9, 0x0680, "Nreal Light camera"),
+    (0x04d2, 0x162f, "Rokid Air/Max"),
+    (0x1ff7, 0x0ff4, "Grawoow G530"),
+    (0x05a9, 0x0f87, "Grawoow G530 camera"),
+];
+
+/// Model name for a known glasses USB device
+pub fn device_name(vendor_id: u16, product_id: u16) -> Option<&'static str> {
+    KNOWN_DEVICES
+        .iter()
+        .find(|(vendor, product, _)| *vendor == vendor_id && *product == product_id)
+        .map(|(_, _, name)| *name)
+}
+
+/// Filesystem roots scanned for devices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceRoots {
+    pub sys: PathBuf,
+    pub dev: PathBuf,
+}
+
+impl Default for DeviceRoots {
+    fn default() -> Self {
+        Self {
+            sys: PathBuf::from("/sys"),
+            dev: PathBuf::from("/dev"),
+        }
+    }
+}
+
+/// Access the current user has to a device node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeAccess {
+    ReadWrite,
+    ReadOnly,
+    Denied,
+    /// Listed in sysfs but no node under `/dev`
+    Missing,
+}
+
+impl NodeAccess {
+    /// True if the glasses can be driven through the node
+    #[inline]
+    pub fn is_usable(&self) -> bool {
+        *self == NodeAccess::ReadWrite
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NodeAccess::ReadWrite => "read/write",
+            NodeAccess::ReadOnly => "read only",
+            NodeAccess::Denied => "no access",
+            NodeAccess::Missing => "missing",
+        }
+    }
+}
+
+/// Glasses seen on the USB bus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbGlasses {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub product: Option<String>,
+}
+
+/// A hidraw interface of the glasses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HidrawNode {
+    /// Device node, e.g. `/dev/hidraw3`
+    pub path: PathBuf,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub name: Option<String>,
+    pub access: NodeAccess,
+}
+
+/// Result of scanning for glasses and their device permissions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceAccessReport {
+    pub usb_devices: Vec<UsbGlasses>,
+    pub hidraw_nodes: Vec<HidrawNode>,
+    /// Generated udev rules file, written when access is missing
+    pub rules_path: Option<PathBuf>,
+}
+
+impl DeviceAccessReport {
+    /// Scan sysfs and `/dev` under the given roots
+    /// Missing directories are treated as no devices
+    pub fn scan(roots: &DeviceRoots) -> Self {
+        let mut report = Self {
+            usb_devices: scan_usb_devices(&roots.sys.join("bus/usb/devices")),
+            hidraw_nodes: scan_hidraw_nodes(&roots.sys.join("class/hidraw"), &roots.dev),
+            rules_path: None,
+        };
+        report.hidraw_nodes.sort_by(|a, b| a.path.cmp(&b.path));
+        report
+    }
+
+    /// True if any glasses were found on USB or hidraw
+    pub fn glasses_detected(&self) -> bool {
+        !self.usb_devices.is_empty() || !self.hidraw_nodes.is_empty()
+    }
+
+    /// Hidraw nodes of the glasses the current user cannot use
+    pub fn inaccessible(&self) -> impl Iterator<Item = &HidrawNode> {
+        self.hidraw_nodes
+            .iter()
+            .filter(|node| !node.access.is_usable())
+    }
+
+    /// True unless detected glasses are missing device access
+    pub fn is_ok(&self) -> bool {
+        self.inaccessible().next().is_none()
+    }
+
+    /// udev rules granting the logged-in user access to the glasses
+    /// Covers the detected models, or every known model if none were found
+    pub fn udev_rules(&self) -> String {
+        let mut devices: Vec<(u16, u16)> = self
+            .usb_devices
+            .iter()
+            .map(|d| (d.vendor_id, d.product_id))
+            .chain(
+                self.hidraw_nodes
+                    .iter()
+                    .map(|n| (n.vendor_id, n.product_id)),
+            )
+            .collect();
+        if devices.is_empty() {
+            devices = KNOWN_DEVICES
+                .iter()
+                .map(|(vendor_id, product_id, _)| (*vendor_id, *product_id))
+                .collect();
+        }
+        devices.sort_unstable();
+        devices.dedup();
+
+        let mut rules = String::from("# udev rules for AR glasses, generated by XREAL Bevy\n");
+        for (vendor_id, product_id) in devices {
+            let name = device_name(vendor_id, product_id).unwrap_or("Unknown");
+            let _ = writeln!(rules, "\n# {} ({:04x}:{:04x})", name, vendor_id, product_id);
+            for subsystem in ["hidraw", "usb"] {
+                let _ = writeln!(
+                    rules,
+                    "SUBSYSTEM==\"{}\", ATTRS{{idVendor}}==\"{:04x}\", ATTRS{{idProduct}}==\"{:04x}\", MODE=\"0660\", TAG+=\"uaccess\"",
+                    subsystem, vendor_id, product_id
+                );
+            }
+        }
+        rules
+    }
+
+    /// Write the udev rules into `dir`, remembering where they went
+    pub fn write_udev_rules(&mut self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(UDEV_RULES_FILE);
+        fs::write(&path, self.udev_rules())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        self.rules_path = Some(path.clone());
+        Ok(path)
+    }
+
+    /// Shell command installing the generated rules, if they were written
+    pub fn install_command(&self) -> Option<String> {
+        let path = self.rules_path.as_ref()?;
+        Some(format!(
+            "sudo install -m 644 {} /etc/udev/rules.d/{} && sudo udevadm control --reload-rules && sudo udevadm trigger",
+            path.display(),
+            UDEV_RULES_FILE
+        ))
+    }
+}
+
+fn scan_usb_devices(usb_root: &Path) -> Vec<UsbGlasses> {
+    let Ok(entries) = fs::read_dir(usb_root) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<UsbGlasses> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let vendor_id = read_hex_attribute(&dir.join("idVendor"))?;
+            let product_id = read_hex_attribute(&dir.join("idProduct"))?;
+            device_name(vendor_id, product_id)?;
+            Some(UsbGlasses {
+                vendor_id,
+                product_id,
+                product: read_attribute(&dir.join("product")),
+            })
+        })
+        .collect();
+    devices.sort_by_key(|d| (d.vendor_id, d.product_id));
+    devices.dedup();
+    devices
+}
+
+fn scan_hidraw_nodes(hidraw_root: &Path, dev_root: &Path) -> Vec<HidrawNode> {
+    let Ok(entries) = fs::read_dir(hidraw_root) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let uevent = fs::read_to_string(entry.path().join("device/uevent")).ok()?;
+            let (vendor_id, product_id, name) = parse_hid_uevent(&uevent)?;
+            device_name(vendor_id, product_id)?;
+            let path = dev_root.join(entry.file_name());
+            Some(HidrawNode {
+                access: node_access(&path),
+                path,
+                vendor_id,
+                product_id,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// Vendor, product and name from a HID device uevent
+/// `HID_ID` has the form `BUS:VENDOR:PRODUCT` with 8 hex digits per ID
+pub fn parse_hid_uevent(uevent: &str) -> Option<(u16, u16, Option<String>)> {
+    let mut ids = None;
+    let mut name = None;
+    for line in uevent.lines() {
+        if let Some(value) = line.strip_prefix("HID_ID=") {
+            let mut parts = value.trim().split(':').skip(1);
+            let vendor = u32::from_str_radix(parts.next()?, 16).ok()?;
+            let product = u32::from_str_radix(parts.next()?, 16).ok()?;
+            ids = Some((vendor as u16, product as u16));
+        } else if let Some(value) = line.strip_prefix("HID_NAME=") {
+            name = Some(value.trim().to_string()).filter(|n| !n.is_empty());
+        }
+    }
+    let (vendor_id, product_id) = ids?;
+    Some((vendor_id, product_id, name))
+}
+
+fn read_attribute(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn read_hex_attribute(path: &Path) -> Option<u16> {
+    u16::from_str_radix(&read_attribute(path)?, 16).ok()
+}
+
+#[cfg(unix)]
+fn node_access(path: &Path) -> NodeAccess {
+    use std::os::unix::ffi::OsStrExt;
+
+    if !path.exists() {
+        return NodeAccess::Missing;
+    }
+    let Ok(path_cstr) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return NodeAccess::Denied;
+    };
+
+    // access() checks against the real user without opening the device
+    let check = |mode| unsafe { libc::access(path_cstr.as_ptr(), mode) } == 0;
+    if check(libc::R_OK | libc::W_OK) {
+        NodeAccess::ReadWrite
+    } else if check(libc::R_OK) {
+        NodeAccess::ReadOnly
+    } else {
+        NodeAccess::Denied
+    }
+}
+
+#[cfg(not(unix))]
+fn node_access(path: &Path) -> NodeAccess {
+    if path.exists() {
+        NodeAccess::ReadWrite
+    } else {
+        NodeAccess::Missing
+    }
+}
@@ -1,10 +1,19 @@
+//! Startup dependency and device access checks
+//!
+//! Runs while in [`crate::AppState::Startup`] and moves to `Running` once all
+//! checks pass, or to `ChecksFailed` with a report of what needs fixing.
+
+pub mod hidraw;
+
 use anyhow::{Context, Result};
 use bevy::prelude::*;
 use bevy::tasks::{AsyncComputeTaskPool, Task};
+use std::fs;
 use std::path::PathBuf;
+use std::process::Command;
 use std::time::SystemTime;
-use tokio::fs;
-use tokio::process::Command;
+
+use self::hidraw::{DeviceAccessReport, DeviceRoots};
 
 pub const XREAL_VENDOR_ID: u16 = 0x3318;
 pub const XREAL_PRODUCT_ID: u16 = 0x0424;
@@ -16,8 +25,9 @@ pub struct LibusbCheckState(pub Option<bool>);
 #[derive(Resource, Default, Debug)]
 pub struct LibusbInstallStatus(pub Option<bool>);
 
+/// Result of the glasses device permission scan
 #[derive(Resource, Default, Debug)]
-pub struct GlassesConnectionState(pub Option<bool>);
+pub struct DeviceAccessState(pub Option<DeviceAccessReport>);
 
 #[derive(Resource, Default, Debug)]
 pub struct CacheValidityState(pub Option<bool>);
@@ -29,22 +39,62 @@ pub struct DependencyCheckState(pub Option<bool>);
 pub struct LibusbCheckTask(pub Task<bool>);
 
 #[derive(Component)]
-#[allow(dead_code)]
 pub struct LibusbInstallTask(pub Task<bool>);
 
 #[derive(Component)]
-#[allow(dead_code)]
-pub struct GlassesCheckTask(pub Task<bool>);
+pub struct DeviceAccessCheckTask(pub Task<DeviceAccessReport>);
 
 #[derive(Component)]
-#[allow(dead_code)]
 pub struct CacheCheckTask(pub Task<bool>);
 
 #[derive(Component)]
-#[allow(dead_code)]
 pub struct CacheUpdateTask(pub Task<bool>);
 
-#[allow(dead_code)]
+/// Runs the startup checks and reports failures through `AppState::ChecksFailed`
+pub struct StartupChecksPlugin;
+
+impl Plugin for StartupChecksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LibusbCheckState>()
+            .init_resource::<LibusbInstallStatus>()
+            .init_resource::<DeviceAccessState>()
+            .init_resource::<CacheValidityState>()
+            .add_systems(
+                OnEnter(crate::AppState::Startup),
+                (reset_startup_checks, spawn_startup_tasks).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_libusb_check_task,
+                    handle_libusb_install_task,
+                    handle_device_access_task,
+                    handle_cache_check_task,
+                    handle_cache_update_task,
+                    check_startup_completion,
+                )
+                    .chain()
+                    .run_if(in_state(crate::AppState::Startup)),
+            )
+            .add_systems(OnEnter(crate::AppState::ChecksFailed), show_failure_message);
+    }
+}
+
+/// Clear previous results so the checks can be retried
+pub fn reset_startup_checks(
+    mut libusb_check: ResMut<LibusbCheckState>,
+    mut libusb_install: ResMut<LibusbInstallStatus>,
+    mut device_access: ResMut<DeviceAccessState>,
+    mut cache_check: ResMut<CacheValidityState>,
+) {
+    libusb_check.0 = None;
+    libusb_install.0 = None;
+    device_access.0 = None;
+    cache_check.0 = None;
+}
+
+/// Spawn the checks on the compute pool
+/// Its threads run no Tokio reactor, so the checks use blocking `std` process and file calls
 pub fn spawn_startup_tasks(mut commands: Commands) {
     info!("🚀 Spawning startup tasks...");
     let thread_pool = AsyncComputeTaskPool::get();
@@ -52,14 +102,13 @@ pub fn spawn_startup_tasks(mut commands: Commands) {
     let task = thread_pool.spawn(async_check_libusb_task());
     commands.spawn(LibusbCheckTask(task));
 
-    let task = thread_pool.spawn(async_check_glasses_task());
-    commands.spawn(GlassesCheckTask(task));
+    let task = thread_pool.spawn(async_check_device_access_task());
+    commands.spawn(DeviceAccessCheckTask(task));
 
     let task = thread_pool.spawn(async_check_cache_task());
     commands.spawn(CacheCheckTask(task));
 }
 
-#[allow(dead_code)]
 pub fn handle_libusb_check_task(
     mut commands: Commands,
     mut task_query: Query<(Entity, &mut LibusbCheckTask)>,
@@ -72,7 +121,7 @@ pub fn handle_libusb_check_task(
         if task.0.is_finished() {
             // Create a no-op waker for non-blocking poll
             let waker = Waker::noop();
-            let mut context = Context::from_waker(&waker);
+            let mut context = Context::from_waker(waker);
 
             // Poll the task directly without blocking
             match task.0.poll(&mut context) {
@@ -96,7 +145,6 @@ pub fn handle_libusb_check_task(
     }
 }
 
-#[allow(dead_code)]
 pub fn handle_libusb_install_task(
     mut commands: Commands,
     mut task_query: Query<(Entity, &mut LibusbInstallTask)>,
@@ -108,7 +156,7 @@ pub fn handle_libusb_install_task(
     for (entity, mut task) in task_query.iter_mut() {
         if task.0.is_finished() {
             let waker = Waker::noop();
-            let mut context = Context::from_waker(&waker);
+            let mut context = Context::from_waker(waker);
 
             match task.0.poll(&mut context) {
                 Poll::Ready(result) => {
@@ -124,11 +172,10 @@ pub fn handle_libusb_install_task(
     }
 }
 
-#[allow(dead_code)]
-pub fn handle_glasses_check_task(
+pub fn handle_device_access_task(
     mut commands: Commands,
-    mut task_query: Query<(Entity, &mut GlassesCheckTask)>,
-    mut state: ResMut<GlassesConnectionState>,
+    mut task_query: Query<(Entity, &mut DeviceAccessCheckTask)>,
+    mut state: ResMut<DeviceAccessState>,
 ) {
     use futures_lite::future::FutureExt;
     use std::task::{Context, Poll, Waker};
@@ -136,11 +183,11 @@ pub fn handle_glasses_check_task(
     for (entity, mut task) in task_query.iter_mut() {
         if task.0.is_finished() {
             let waker = Waker::noop();
-            let mut context = Context::from_waker(&waker);
+            let mut context = Context::from_waker(waker);
 
             match task.0.poll(&mut context) {
-                Poll::Ready(result) => {
-                    state.0 = Some(result);
+                Poll::Ready(report) => {
+                    state.0 = Some(report);
                     commands.entity(entity).despawn();
                 }
                 Poll::Pending => {
@@ -152,7 +199,6 @@ pub fn handle_glasses_check_task(
     }
 }
 
-#[allow(dead_code)]
 pub fn handle_cache_check_task(
     mut commands: Commands,
     mut task_query: Query<(Entity, &mut CacheCheckTask)>,
@@ -164,7 +210,7 @@ pub fn handle_cache_check_task(
     for (entity, mut task) in task_query.iter_mut() {
         if task.0.is_finished() {
             let waker = Waker::noop();
-            let mut context = Context::from_waker(&waker);
+            let mut context = Context::from_waker(waker);
 
             match task.0.poll(&mut context) {
                 Poll::Ready(result) => {
@@ -186,7 +232,6 @@ pub fn handle_cache_check_task(
     }
 }
 
-#[allow(dead_code)]
 pub fn handle_cache_update_task(
     mut commands: Commands,
     mut task_query: Query<(Entity, &mut CacheUpdateTask)>,
@@ -198,7 +243,7 @@ pub fn handle_cache_update_task(
     for (entity, mut task) in task_query.iter_mut() {
         if task.0.is_finished() {
             let waker = Waker::noop();
-            let mut context = Context::from_waker(&waker);
+            let mut context = Context::from_waker(waker);
 
             match task.0.poll(&mut context) {
                 Poll::Ready(result) => {
@@ -216,12 +261,11 @@ pub fn handle_cache_update_task(
     }
 }
 
-#[allow(dead_code)]
 pub fn check_startup_completion(
     mut next_state: ResMut<NextState<crate::AppState>>,
     libusb_check: Res<LibusbCheckState>,
     libusb_install: Res<LibusbInstallStatus>,
-    glasses_check: Res<GlassesConnectionState>,
+    device_access: Res<DeviceAccessState>,
     cache_check: Res<CacheValidityState>,
     q_libusb_install: Query<&LibusbInstallTask>,
     q_cache_update: Query<&CacheUpdateTask>,
@@ -236,44 +280,80 @@ pub fn check_startup_completion(
         None => return,
     };
 
-    let glasses_ok = glasses_check.0.unwrap_or(false);
+    // Glasses that are not plugged in yet are picked up by the device broker,
+    // so only missing access to detected glasses fails the check
+    let access_ok = device_access
+        .0
+        .as_ref()
+        .is_some_and(|report| report.is_ok());
     let cache_ok = cache_check.0.unwrap_or(false);
 
-    if libusb_ok && glasses_ok && cache_ok {
+    if libusb_ok && access_ok && cache_ok {
         info!("✅ All startup checks passed. Transitioning to Running state.");
         next_state.set(crate::AppState::Running);
-    } else if libusb_check.0.is_some() && glasses_check.0.is_some() && cache_check.0.is_some() {
+    } else if libusb_check.0.is_some() && device_access.0.is_some() && cache_check.0.is_some() {
         error!("❌ A startup check failed. Transitioning to ChecksFailed state.");
         next_state.set(crate::AppState::ChecksFailed);
     }
 }
 
-#[allow(dead_code)]
-pub fn show_failure_message() {
+pub fn show_failure_message(
+    libusb_check: Res<LibusbCheckState>,
+    libusb_install: Res<LibusbInstallStatus>,
+    device_access: Res<DeviceAccessState>,
+    cache_check: Res<CacheValidityState>,
+) {
     error!("FATAL: Startup checks failed. Please check the logs for more details. The application cannot continue.");
+
+    if libusb_check.0 == Some(false) && libusb_install.0 != Some(true) {
+        error!("   ❌ libusb is not installed");
+        if cfg!(target_os = "linux") {
+            error!("      Install libusb-1.0 with your package manager (e.g. `sudo apt install libusb-1.0-0`)");
+        }
+    }
+    if cache_check.0 == Some(false) {
+        error!("   ❌ Dependency cache could not be updated");
+    }
+    if let Some(report) = device_access.0.as_ref().filter(|report| !report.is_ok()) {
+        for node in report.inaccessible() {
+            error!(
+                "   ❌ {} ({:04x}:{:04x}): {}",
+                node.path.display(),
+                node.vendor_id,
+                node.product_id,
+                node.access.label()
+            );
+        }
+        match report.install_command() {
+            Some(command) => error!(
+                "      Install the generated udev rules with:\n      {}",
+                command
+            ),
+            None => error!("      Add a udev rule granting access to the glasses hidraw devices"),
+        }
+    }
 }
 
-#[allow(dead_code)]
 async fn async_check_libusb_task() -> bool {
     info!("Checking for libusb...");
     match Command::new("pkg-config")
         .arg("--exists")
         .arg("libusb-1.0")
         .status()
-        .await
     {
         Ok(status) if status.success() => {
             info!("libusb found via pkg-config.");
             true
         }
+        _ if cfg!(target_os = "linux") => {
+            // ar-drivers talks to the glasses through hidraw, so a missing
+            // libusb development package is not fatal on Linux
+            warn!("libusb-1.0 not found via pkg-config, continuing with hidraw access");
+            true
+        }
         _ => {
             info!("pkg-config check failed, trying 'brew list libusb'");
-            match Command::new("brew")
-                .arg("list")
-                .arg("libusb")
-                .status()
-                .await
-            {
+            match Command::new("brew").arg("list").arg("libusb").status() {
                 Ok(status) => status.success(),
                 Err(_) => false,
             }
@@ -281,10 +361,9 @@ async fn async_check_libusb_task() -> bool {
     }
 }
 
-#[allow(dead_code)]
 async fn async_install_libusb_task() -> bool {
     info!("Attempting to install libusb via Homebrew...");
-    let brew_installed = match Command::new("which").arg("brew").status().await {
+    let brew_installed = match Command::new("which").arg("brew").status() {
         Ok(status) => status.success(),
         Err(_) => false,
     };
@@ -294,12 +373,7 @@ async fn async_install_libusb_task() -> bool {
         return false;
     }
 
-    match Command::new("brew")
-        .arg("install")
-        .arg("libusb")
-        .status()
-        .await
-    {
+    match Command::new("brew").arg("install").arg("libusb").status() {
         Ok(status) if status.success() => {
             info!("libusb installed successfully.");
             true
@@ -315,36 +389,61 @@ async fn async_install_libusb_task() -> bool {
     }
 }
 
-#[allow(dead_code)]
-async fn async_check_glasses_task() -> bool {
-    info!("Checking for XREAL glasses connection...");
-    match ar_drivers::any_glasses() {
-        Ok(_glasses) => {
-            info!("✅ XREAL glasses detected.");
-            true
-        }
-        Err(e) => {
-            error!("❌ XREAL glasses not detected: {}", e);
-            false
-        }
+/// Scan for glasses the current user cannot access and write udev rules for them
+async fn async_check_device_access_task() -> DeviceAccessReport {
+    if !cfg!(target_os = "linux") {
+        return DeviceAccessReport::default();
+    }
+    if crate::driver::GlassesSource::from_env().is_simulated() {
+        info!("🧪 Simulated glasses selected, skipping device access check.");
+        return DeviceAccessReport::default();
     }
+
+    info!("Checking glasses device access...");
+    let mut report = DeviceAccessReport::scan(&DeviceRoots::default());
+    if !report.glasses_detected() {
+        info!("No glasses detected yet, the device broker will keep probing.");
+        return report;
+    }
+    if report.is_ok() {
+        info!("✅ Glasses device access granted.");
+        return report;
+    }
+
+    for node in report.inaccessible() {
+        warn!(
+            "🔒 No access to {} ({:04x}:{:04x})",
+            node.path.display(),
+            node.vendor_id,
+            node.product_id
+        );
+    }
+    match get_cache_dir() {
+        Ok(dir) => match report.write_udev_rules(&dir) {
+            Ok(path) => info!("📝 udev rules written to {}", path.display()),
+            Err(e) => error!("Failed to write udev rules: {}", e),
+        },
+        Err(e) => error!("Failed to write udev rules: {}", e),
+    }
+    report
 }
 
-#[allow(dead_code)]
-fn get_cache_file_path() -> Result<PathBuf> {
+fn get_cache_dir() -> Result<PathBuf> {
     let cache_dir = dirs::cache_dir().context("Failed to find cache directory")?;
-    let app_cache_dir = cache_dir.join("xreal_bevy");
-    Ok(app_cache_dir.join("dependency_check.timestamp"))
+    Ok(cache_dir.join("xreal_bevy"))
+}
+
+fn get_cache_file_path() -> Result<PathBuf> {
+    Ok(get_cache_dir()?.join("dependency_check.timestamp"))
 }
 
-#[allow(dead_code)]
 async fn async_check_cache_task() -> bool {
     info!("Checking cache validity...");
     let Ok(cache_file) = get_cache_file_path() else {
         return false;
     };
 
-    let Ok(metadata) = fs::metadata(&cache_file).await else {
+    let Ok(metadata) = fs::metadata(&cache_file) else {
         return false;
     };
 
@@ -365,7 +464,6 @@ async fn async_check_cache_task() -> bool {
     }
 }
 
-#[allow(dead_code)]
 async fn async_update_cache_task() -> bool {
     info!("Updating cache...");
     let Ok(cache_file) = get_cache_file_path() else {
@@ -373,7 +471,7 @@ async fn async_update_cache_task() -> bool {
     };
 
     if let Some(parent) = cache_file.parent() {
-        if let Err(e) = fs::create_dir_all(parent).await {
+        if let Err(e) = fs::create_dir_all(parent) {
             error!("Failed to create cache directory: {}", e);
             return false;
         }
@@ -384,7 +482,7 @@ async fn async_update_cache_task() -> bool {
         Err(_) => return false, // Should not happen
     };
 
-    match fs::write(&cache_file, now_str.as_bytes()).await {
+    match fs::write(&cache_file, now_str.as_bytes()) {
         Ok(_) => {
             info!("✅ Cache updated successfully.");
             true
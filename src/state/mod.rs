@@ -13,6 +13,8 @@
 use crate::AppState;
 use anyhow::Result;
 use bevy::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
 
 pub mod recovery;
 pub mod schema;
@@ -353,3 +355,103 @@ pub async fn add_state_persistence(app: &mut App) -> Result<()> {
     info!("✅ State persistence system initialized");
     Ok(())
 }
+
+/// Keeps user settings loaded as a [`schema::core::PersistentAppState`] resource
+/// and writes them back to disk shortly after they change
+#[derive(Default)]
+pub struct PersistentStatePlugin {
+    pub storage: StorageConfig,
+    pub auto_save: AutoSaveConfig,
+}
+
+impl Plugin for PersistentStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_persistent_state(&self.storage))
+            .insert_resource(PersistentStateWriter::new(
+                self.storage.primary_state_file(),
+                &self.auto_save,
+            ))
+            .add_systems(Update, systems::save_persistent_state_on_change)
+            .add_systems(Last, systems::flush_persistent_state_on_exit);
+    }
+}
+
+/// Load persisted settings, falling back to defaults if missing or invalid
+pub fn load_persistent_state(storage: &StorageConfig) -> schema::core::PersistentAppState {
+    let path = storage.primary_state_file();
+    if !path.exists() {
+        info!("📄 No saved settings at {:?}, using defaults", path);
+        return schema::core::PersistentAppState::default();
+    }
+
+    let loaded = StateSerializer::new()
+        .deserialize_from_file(&path)
+        .and_then(|state| state.validate().map(|()| state));
+    match loaded {
+        Ok(state) => state,
+        Err(e) => {
+            warn!("⚠️  Ignoring saved settings: {}", e);
+            schema::core::PersistentAppState::default()
+        }
+    }
+}
+
+/// Debounced writer for the persisted settings file
+#[derive(Resource, Debug)]
+pub struct PersistentStateWriter {
+    path: PathBuf,
+    enabled: bool,
+    debounce: Duration,
+    pending_since: Option<Duration>,
+}
+
+impl PersistentStateWriter {
+    pub fn new(path: PathBuf, config: &AutoSaveConfig) -> Self {
+        Self {
+            path,
+            enabled: config.enabled,
+            debounce: Duration::from_secs_f32(config.debounce_delay.max(0.0)),
+            pending_since: None,
+        }
+    }
+
+    /// Path of the settings file
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Record a change at the given app time, restarting the debounce window
+    pub fn mark_changed(&mut self, now: Duration) {
+        if self.enabled {
+            self.pending_since = Some(now);
+        }
+    }
+
+    /// True if unsaved changes exist
+    pub fn has_pending(&self) -> bool {
+        self.pending_since.is_some()
+    }
+
+    /// True once changes have settled for the debounce delay
+    pub fn is_due(&self, now: Duration) -> bool {
+        self.pending_since
+            .is_some_and(|since| now.saturating_sub(since) >= self.debounce)
+    }
+
+    /// Take a validated, timestamped snapshot for saving and clear the pending flag
+    pub fn take_snapshot(
+        &mut self,
+        state: &schema::core::PersistentAppState,
+    ) -> Option<schema::core::PersistentAppState> {
+        self.pending_since = None;
+        let mut snapshot = state.clone();
+        snapshot.touch();
+        match snapshot.validate() {
+            Ok(()) => Some(snapshot),
+            Err(e) => {
+                warn!("⚠️  Not saving invalid settings: {}", e);
+                None
+            }
+        }
+    }
+}
@@ -7,17 +7,38 @@ use super::core::StateValidation;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+/// Age after which a calibration should be redone, 30 days in seconds
+pub const CALIBRATION_MAX_AGE_S: u64 = 30 * 24 * 60 * 60;
+
 /// IMU calibration data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CalibrationData {
+    /// Serial number of the glasses this calibration belongs to, empty if unknown
+    #[serde(default)]
+    pub device_serial: String,
     /// Calibration state
     pub state: CalibrationState,
-    /// Accelerometer bias values
+    /// Accelerometer offset, in m/s²
     pub accel_bias: [f32; 3],
+    /// Accelerometer scale and cross-axis correction, as matrix columns
+    #[serde(default = "identity_matrix")]
+    pub accel_correction: [[f32; 3]; 3],
+    /// Six-position accelerometer fit quality (0.0-1.0)
+    #[serde(default)]
+    pub accel_fit_quality: f32,
     /// Gyroscope bias values
     pub gyro_bias: [f32; 3],
-    /// Magnetometer bias values
+    /// Magnetometer hard-iron offset, in µT
     pub mag_bias: [f32; 3],
+    /// Magnetometer soft-iron correction, as matrix columns
+    #[serde(default = "identity_matrix")]
+    pub mag_soft_iron: [[f32; 3]; 3],
+    /// Local field strength the soft-iron correction scales to, in µT
+    #[serde(default)]
+    pub mag_field_strength: f32,
+    /// Magnetometer ellipsoid fit quality (0.0-1.0)
+    #[serde(default)]
+    pub mag_fit_quality: f32,
     /// Calibration timestamp
     pub calibrated_at: u64,
     /// Number of calibration samples
@@ -26,23 +47,83 @@ pub struct CalibrationData {
     pub quality_score: f32,
     /// Temperature at calibration
     pub temperature_celsius: f32,
+    /// Residual yaw drift learned while the head was still, in rad/s
+    #[serde(default)]
+    pub yaw_drift_rate: f32,
+    /// Gyroscope bias recorded at several IMU temperatures, ordered by temperature
+    #[serde(default)]
+    pub gyro_bias_points: Vec<GyroBiasPoint>,
+}
+
+/// Gyroscope bias measured at one IMU temperature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GyroBiasPoint {
+    /// IMU temperature, measured or estimated from the uptime, in °C
+    pub temperature_celsius: f32,
+    /// Gyroscope bias, in rad/s
+    pub bias: [f32; 3],
+    /// Still samples the bias was averaged from
+    pub samples: u32,
+}
+
+fn identity_matrix() -> [[f32; 3]; 3] {
+    [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+}
+
+fn determinant(m: &[[f32; 3]; 3]) -> f32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2])
+        - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+        + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
 }
 
 impl Default for CalibrationData {
     fn default() -> Self {
         Self {
+            device_serial: String::new(),
             state: CalibrationState::Idle,
             accel_bias: [0.0; 3],
+            accel_correction: identity_matrix(),
+            accel_fit_quality: 0.0,
             gyro_bias: [0.0; 3],
             mag_bias: [0.0; 3],
+            mag_soft_iron: identity_matrix(),
+            mag_field_strength: 0.0,
+            mag_fit_quality: 0.0,
             calibrated_at: 0,
             sample_count: 0,
             quality_score: 0.0,
             temperature_celsius: 20.0,
+            yaw_drift_rate: 0.0,
+            gyro_bias_points: Vec::new(),
         }
     }
 }
 
+impl CalibrationData {
+    /// Empty record for the glasses with `serial`
+    pub fn for_device(serial: &str) -> Self {
+        Self {
+            device_serial: serial.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// True once a calibration has been completed
+    pub fn is_calibrated(&self) -> bool {
+        self.state == CalibrationState::Calibrated
+    }
+
+    /// Seconds since the calibration, given the current Unix time
+    pub fn age_seconds(&self, now: u64) -> u64 {
+        now.saturating_sub(self.calibrated_at)
+    }
+
+    /// True if the glasses were never calibrated or the calibration is too old
+    pub fn needs_recalibration(&self, now: u64) -> bool {
+        !self.is_calibrated() || self.age_seconds(now) > CALIBRATION_MAX_AGE_S
+    }
+}
+
 impl StateValidation for CalibrationData {
     fn validate(&self) -> Result<()> {
         // Validate quality score
@@ -74,20 +155,81 @@ impl StateValidation for CalibrationData {
             }
         }
 
+        let correction = &self.accel_correction;
+        if correction.iter().flatten().any(|v| !v.is_finite())
+            || (0..3).any(|axis| !(0.5..=2.0).contains(&correction[axis][axis]))
+            || determinant(correction) <= 0.0
+        {
+            anyhow::bail!("Accelerometer correction out of range: {:?}", correction);
+        }
+
+        if !(0.0..=1.0).contains(&self.accel_fit_quality) {
+            anyhow::bail!(
+                "Accelerometer fit quality out of range: {}",
+                self.accel_fit_quality
+            );
+        }
+
+        let soft_iron = &self.mag_soft_iron;
+        if soft_iron
+            .iter()
+            .flatten()
+            .any(|v| !v.is_finite() || v.abs() > 100.0)
+        {
+            anyhow::bail!("Magnetometer soft-iron matrix out of range");
+        }
+        let determinant = determinant(soft_iron);
+        if determinant <= 0.0 {
+            anyhow::bail!(
+                "Magnetometer soft-iron matrix is not invertible: {}",
+                determinant
+            );
+        }
+
+        if !(0.0..=1.0).contains(&self.mag_fit_quality) {
+            anyhow::bail!(
+                "Magnetometer fit quality out of range: {}",
+                self.mag_fit_quality
+            );
+        }
+
+        if !self.yaw_drift_rate.is_finite() || self.yaw_drift_rate.abs() > 1.0 {
+            anyhow::bail!("Yaw drift rate out of range: {}", self.yaw_drift_rate);
+        }
+
+        for point in &self.gyro_bias_points {
+            if !(-40.0..=85.0).contains(&point.temperature_celsius)
+                || point
+                    .bias
+                    .iter()
+                    .any(|bias| !bias.is_finite() || bias.abs() > 1000.0)
+            {
+                anyhow::bail!("Gyroscope bias point out of range: {:?}", point);
+            }
+        }
+
         Ok(())
     }
 
     fn merge(&mut self, other: &Self) -> Result<()> {
         // Only merge if other calibration is newer and better quality
         if other.calibrated_at > self.calibrated_at && other.quality_score >= self.quality_score {
+            self.device_serial = other.device_serial.clone();
             self.state = other.state;
             self.accel_bias = other.accel_bias;
+            self.accel_correction = other.accel_correction;
+            self.accel_fit_quality = other.accel_fit_quality;
             self.gyro_bias = other.gyro_bias;
             self.mag_bias = other.mag_bias;
+            self.mag_soft_iron = other.mag_soft_iron;
+            self.mag_field_strength = other.mag_field_strength;
+            self.mag_fit_quality = other.mag_fit_quality;
             self.calibrated_at = other.calibrated_at;
             self.sample_count = other.sample_count;
             self.quality_score = other.quality_score;
             self.temperature_celsius = other.temperature_celsius;
+            self.yaw_drift_rate = other.yaw_drift_rate;
+            self.gyro_bias_points = other.gyro_bias_points.clone();
         }
         Ok(())
     }
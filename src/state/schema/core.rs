@@ -6,7 +6,7 @@
 use anyhow::Result;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-// use std::collections::HashMap; // Unused import removed
+use std::collections::HashMap;
 
 /// Schema version for state migration support
 pub const STATE_SCHEMA_VERSION: &str = "1.0.0";
@@ -22,8 +22,11 @@ pub struct PersistentAppState {
     pub user_preferences: super::preferences::UserPreferences,
     /// UI state and layout
     pub ui_state: super::ui::UiState,
-    /// IMU calibration data
+    /// IMU calibration data of the glasses in use
     pub calibration_data: super::calibration::CalibrationData,
+    /// Calibration data of other glasses, keyed by serial number
+    #[serde(default)]
+    pub calibration_records: HashMap<String, super::calibration::CalibrationData>,
     /// Plugin system state
     pub plugin_state: super::plugins::PluginSystemState,
     /// Performance settings and thresholds
@@ -38,6 +41,9 @@ pub struct PersistentAppState {
     pub network_config: super::network::NetworkConfig,
     /// Security settings
     pub security_settings: super::security::SecuritySettings,
+    /// Head tracking settings
+    #[serde(default)]
+    pub tracking: super::tracking::TrackingSettings,
 }
 
 impl Default for PersistentAppState {
@@ -51,6 +57,7 @@ impl Default for PersistentAppState {
             user_preferences: Default::default(),
             ui_state: Default::default(),
             calibration_data: Default::default(),
+            calibration_records: Default::default(),
             plugin_state: Default::default(),
             performance_settings: Default::default(),
             window_layout: Default::default(),
@@ -58,6 +65,7 @@ impl Default for PersistentAppState {
             audio_settings: Default::default(),
             network_config: Default::default(),
             security_settings: Default::default(),
+            tracking: Default::default(),
         }
     }
 }
@@ -90,6 +98,29 @@ impl PersistentAppState {
         now.saturating_sub(self.last_updated)
     }
 
+    /// Switch the active calibration to the record of the glasses with `serial`
+    /// The active record is kept for its own glasses; one without a serial is
+    /// adopted by these glasses. Returns true if another record became active
+    pub fn activate_calibration(&mut self, serial: &str) -> bool {
+        let active_serial = &self.calibration_data.device_serial;
+        if serial.is_empty() || active_serial == serial {
+            return false;
+        }
+        if active_serial.is_empty() {
+            self.calibration_data.device_serial = serial.to_string();
+            return false;
+        }
+
+        let record = self
+            .calibration_records
+            .remove(serial)
+            .unwrap_or_else(|| super::calibration::CalibrationData::for_device(serial));
+        let previous = std::mem::replace(&mut self.calibration_data, record);
+        self.calibration_records
+            .insert(previous.device_serial.clone(), previous);
+        true
+    }
+
     /// Validate the entire state for consistency
     pub fn validate(&self) -> Result<()> {
         // Validate schema version
@@ -105,6 +136,9 @@ impl PersistentAppState {
 
         // Validate calibration data
         self.calibration_data.validate()?;
+        for record in self.calibration_records.values() {
+            record.validate()?;
+        }
 
         // Validate plugin state
         self.plugin_state.validate()?;
@@ -127,6 +161,9 @@ impl PersistentAppState {
         // Validate security settings
         self.security_settings.validate()?;
 
+        // Validate tracking settings
+        self.tracking.validate()?;
+
         Ok(())
     }
 
@@ -151,6 +188,7 @@ impl PersistentAppState {
         // Merge calibration data (only if newer)
         if other.last_updated > self.last_updated {
             self.calibration_data = other.calibration_data.clone();
+            self.calibration_records = other.calibration_records.clone();
         }
 
         // Merge plugin state
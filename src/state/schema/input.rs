@@ -21,6 +21,9 @@ pub struct InputConfig {
     pub keyboard_shortcuts: HashMap<String, String>,
     /// Input sensitivity settings
     pub sensitivity: SensitivitySettings,
+    /// Glasses hardware button bindings
+    #[serde(default)]
+    pub glasses_buttons: GlassesButtonSettings,
 }
 
 impl Default for InputConfig {
@@ -31,6 +34,7 @@ impl Default for InputConfig {
             voice_input: VoiceInputSettings::default(),
             keyboard_shortcuts: HashMap::new(),
             sensitivity: SensitivitySettings::default(),
+            glasses_buttons: GlassesButtonSettings::default(),
         }
     }
 }
@@ -41,6 +45,7 @@ impl StateValidation for InputConfig {
         self.gesture_input.validate()?;
         self.voice_input.validate()?;
         self.sensitivity.validate()?;
+        self.glasses_buttons.validate()?;
         Ok(())
     }
 
@@ -55,6 +60,7 @@ impl StateValidation for InputConfig {
         }
 
         self.sensitivity.merge(&other.sensitivity)?;
+        self.glasses_buttons.merge(&other.glasses_buttons)?;
         Ok(())
     }
 }
@@ -104,8 +110,74 @@ impl StateValidation for GazeInputSettings {
     }
 }
 
+/// Head gestures recognised from the IMU stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HeadGesture {
+    /// Pitch down and back up
+    Nod,
+    /// Yaw from side to side
+    Shake,
+    /// Roll towards the left shoulder and back
+    TiltLeft,
+    /// Roll towards the right shoulder and back
+    TiltRight,
+    /// Two taps on the frame
+    DoubleTap,
+}
+
+impl HeadGesture {
+    /// All gestures, in display order
+    pub const ALL: [HeadGesture; 5] = [
+        HeadGesture::Nod,
+        HeadGesture::Shake,
+        HeadGesture::TiltLeft,
+        HeadGesture::TiltRight,
+        HeadGesture::DoubleTap,
+    ];
+
+    /// Human readable name for settings displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            HeadGesture::Nod => "Nod",
+            HeadGesture::Shake => "Shake",
+            HeadGesture::TiltLeft => "Tilt left",
+            HeadGesture::TiltRight => "Tilt right",
+            HeadGesture::DoubleTap => "Double tap",
+        }
+    }
+}
+
+/// A head gesture bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GestureBinding {
+    pub gesture: HeadGesture,
+    /// Action to trigger
+    pub action: InputAction,
+}
+
+fn default_gesture_cooldown_ms() -> u32 {
+    800
+}
+
+fn default_gesture_bindings() -> Vec<GestureBinding> {
+    vec![
+        GestureBinding {
+            gesture: HeadGesture::Nod,
+            action: InputAction::ConfirmDwell,
+        },
+        GestureBinding {
+            gesture: HeadGesture::Shake,
+            action: InputAction::DismissDialog,
+        },
+        GestureBinding {
+            gesture: HeadGesture::DoubleTap,
+            action: InputAction::Recenter,
+        },
+    ]
+}
+
 /// Gesture input settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GestureInputSettings {
     /// Gesture input enabled
     pub enabled: bool,
@@ -115,6 +187,12 @@ pub struct GestureInputSettings {
     pub min_duration_ms: u32,
     /// Maximum gesture duration in ms
     pub max_duration_ms: u32,
+    /// Time after a recognised gesture during which no other is recognised, in ms
+    #[serde(default = "default_gesture_cooldown_ms")]
+    pub cooldown_ms: u32,
+    /// Bound gestures
+    #[serde(default = "default_gesture_bindings")]
+    pub bindings: Vec<GestureBinding>,
 }
 
 impl Default for GestureInputSettings {
@@ -124,6 +202,27 @@ impl Default for GestureInputSettings {
             sensitivity: 0.7,
             min_duration_ms: 200,
             max_duration_ms: 3000,
+            cooldown_ms: default_gesture_cooldown_ms(),
+            bindings: default_gesture_bindings(),
+        }
+    }
+}
+
+impl GestureInputSettings {
+    /// Action bound to the gesture
+    pub fn action_for(&self, gesture: HeadGesture) -> Option<InputAction> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.gesture == gesture)
+            .map(|binding| binding.action)
+    }
+
+    /// Bind, rebind or (with `None`) unbind a gesture
+    pub fn set_action(&mut self, gesture: HeadGesture, action: Option<InputAction>) {
+        self.bindings.retain(|binding| binding.gesture != gesture);
+        if let Some(action) = action {
+            self.bindings.push(GestureBinding { gesture, action });
+            self.bindings.sort_by_key(|binding| binding.gesture as u8);
         }
     }
 }
@@ -138,6 +237,19 @@ impl StateValidation for GestureInputSettings {
             anyhow::bail!("Invalid gesture duration range");
         }
 
+        if self.cooldown_ms < 100 || self.cooldown_ms > 5000 {
+            anyhow::bail!("Gesture cooldown out of range: {}", self.cooldown_ms);
+        }
+
+        for (i, binding) in self.bindings.iter().enumerate() {
+            if self.bindings[..i]
+                .iter()
+                .any(|other| other.gesture == binding.gesture)
+            {
+                anyhow::bail!("Duplicate binding for gesture {:?}", binding.gesture);
+            }
+        }
+
         Ok(())
     }
 
@@ -146,6 +258,8 @@ impl StateValidation for GestureInputSettings {
         self.sensitivity = other.sensitivity;
         self.min_duration_ms = other.min_duration_ms;
         self.max_duration_ms = other.max_duration_ms;
+        self.cooldown_ms = other.cooldown_ms;
+        self.bindings = other.bindings.clone();
         Ok(())
     }
 }
@@ -250,3 +364,199 @@ impl StateValidation for SensitivitySettings {
         Ok(())
     }
 }
+
+/// Actions that can be bound to glasses buttons and head gestures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    /// Reset the head orientation to look straight ahead
+    Recenter,
+    /// Switch between 2D mirror and 3D stereo display modes
+    ToggleDisplayMode,
+    /// Step through the glasses brightness levels
+    CycleBrightness,
+    /// Open or close the settings panel
+    ToggleSettingsPanel,
+    /// Cycle the virtual screen arrangement
+    SwitchLayout,
+    /// Click at the head cursor without waiting for the dwell time
+    ConfirmDwell,
+    /// Close the open settings panel or overlay
+    DismissDialog,
+}
+
+impl InputAction {
+    /// All bindable actions, in display order
+    pub const ALL: [InputAction; 7] = [
+        InputAction::Recenter,
+        InputAction::ToggleDisplayMode,
+        InputAction::CycleBrightness,
+        InputAction::ToggleSettingsPanel,
+        InputAction::SwitchLayout,
+        InputAction::ConfirmDwell,
+        InputAction::DismissDialog,
+    ];
+
+    /// Human readable name for settings displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::Recenter => "Recenter",
+            InputAction::ToggleDisplayMode => "Toggle 3D",
+            InputAction::CycleBrightness => "Cycle brightness",
+            InputAction::ToggleSettingsPanel => "Toggle settings",
+            InputAction::SwitchLayout => "Switch layout",
+            InputAction::ConfirmDwell => "Confirm dwell click",
+            InputAction::DismissDialog => "Dismiss dialog",
+        }
+    }
+}
+
+/// How a glasses button was pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ButtonPress {
+    Short,
+    Long,
+    Double,
+}
+
+impl ButtonPress {
+    /// All press variants, in display order
+    pub const ALL: [ButtonPress; 3] = [ButtonPress::Short, ButtonPress::Long, ButtonPress::Double];
+
+    /// Human readable name for settings displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            ButtonPress::Short => "Short",
+            ButtonPress::Long => "Long",
+            ButtonPress::Double => "Double",
+        }
+    }
+}
+
+/// A single button/press combination bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonBinding {
+    /// Key id as reported by the glasses, starting from 0
+    pub button: u8,
+    /// Press variant
+    pub press: ButtonPress,
+    /// Action to trigger
+    pub action: InputAction,
+}
+
+/// Glasses hardware button settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlassesButtonSettings {
+    /// Button handling enabled
+    pub enabled: bool,
+    /// Repeated presses of a held button must span this long to count as a long press
+    pub long_press_ms: u32,
+    /// Maximum gap between the presses of a double press
+    pub double_press_ms: u32,
+    /// Bound button/press combinations
+    pub bindings: Vec<ButtonBinding>,
+}
+
+impl Default for GlassesButtonSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            long_press_ms: 600,
+            double_press_ms: 300,
+            bindings: vec![
+                ButtonBinding {
+                    button: 0,
+                    press: ButtonPress::Short,
+                    action: InputAction::Recenter,
+                },
+                ButtonBinding {
+                    button: 0,
+                    press: ButtonPress::Long,
+                    action: InputAction::ToggleDisplayMode,
+                },
+                ButtonBinding {
+                    button: 0,
+                    press: ButtonPress::Double,
+                    action: InputAction::ToggleSettingsPanel,
+                },
+                ButtonBinding {
+                    button: 1,
+                    press: ButtonPress::Short,
+                    action: InputAction::CycleBrightness,
+                },
+                ButtonBinding {
+                    button: 1,
+                    press: ButtonPress::Double,
+                    action: InputAction::SwitchLayout,
+                },
+            ],
+        }
+    }
+}
+
+impl GlassesButtonSettings {
+    /// Action bound to the given button and press variant
+    pub fn action_for(&self, button: u8, press: ButtonPress) -> Option<InputAction> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.button == button && binding.press == press)
+            .map(|binding| binding.action)
+    }
+
+    /// Bind, rebind or (with `None`) unbind a button/press combination
+    pub fn set_action(&mut self, button: u8, press: ButtonPress, action: Option<InputAction>) {
+        self.bindings
+            .retain(|binding| !(binding.button == button && binding.press == press));
+        if let Some(action) = action {
+            self.bindings.push(ButtonBinding {
+                button,
+                press,
+                action,
+            });
+            self.bindings
+                .sort_by_key(|binding| (binding.button, binding.press as u8));
+        }
+    }
+
+    /// Buttons that currently have at least one binding
+    pub fn bound_buttons(&self) -> Vec<u8> {
+        let mut buttons: Vec<u8> = self.bindings.iter().map(|binding| binding.button).collect();
+        buttons.sort_unstable();
+        buttons.dedup();
+        buttons
+    }
+}
+
+impl StateValidation for GlassesButtonSettings {
+    fn validate(&self) -> Result<()> {
+        if self.long_press_ms < 200 || self.long_press_ms > 3000 {
+            anyhow::bail!("Long press duration out of range: {}", self.long_press_ms);
+        }
+
+        if self.double_press_ms < 100 || self.double_press_ms > 1000 {
+            anyhow::bail!("Double press window out of range: {}", self.double_press_ms);
+        }
+
+        for (i, binding) in self.bindings.iter().enumerate() {
+            if self.bindings[..i]
+                .iter()
+                .any(|other| other.button == binding.button && other.press == binding.press)
+            {
+                anyhow::bail!(
+                    "Duplicate binding for button {} ({:?})",
+                    binding.button,
+                    binding.press
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.enabled = other.enabled;
+        self.long_press_ms = other.long_press_ms;
+        self.double_press_ms = other.double_press_ms;
+        self.bindings = other.bindings.clone();
+        Ok(())
+    }
+}
@@ -19,6 +19,7 @@
 //! - [`audio`]: Audio settings and spatial audio configuration
 //! - [`network`]: Network configuration and proxy settings
 //! - [`security`]: Security settings and access control
+//! - [`tracking`]: Head tracking, orientation filter, smoothing and pose prediction settings
 //!
 //! # Usage Examples
 //!
@@ -48,6 +49,7 @@ pub mod performance;
 pub mod plugins;
 pub mod preferences;
 pub mod security;
+pub mod tracking;
 pub mod ui;
 pub mod window;
 
@@ -55,8 +57,8 @@ pub mod window;
 pub use core::{StateMigration, StateValidation, STATE_SCHEMA_VERSION};
 
 pub use preferences::{
-    AccessibilitySettings, AppearanceSettings, ColorBlindType, ComfortSettings, PrivacySettings,
-    UserPreferences,
+    AccessibilitySettings, AppearanceSettings, AutoPauseSettings, ColorBlindType, ComfortSettings,
+    PrivacySettings, UserPreferences,
 };
 
 pub use ui::{
@@ -64,7 +66,7 @@ pub use ui::{
     ToolbarPosition, ToolbarSize, ToolbarState, UiState, WindowPositions, WindowRect,
 };
 
-pub use calibration::{CalibrationData, CalibrationState};
+pub use calibration::{CalibrationData, CalibrationState, GyroBiasPoint};
 
 pub use plugins::{PluginConfig, PluginPermissions, PluginSystemState, ResourceLimits};
 
@@ -74,12 +76,14 @@ pub use performance::{
 };
 
 pub use window::{
-    DisplayConfig, MonitorArrangement, MultiMonitorConfig, VirtualScreenConfig, WindowLayout,
-    WindowManagementSettings,
+    AnchorMode, DisplayConfig, Easing, LazyFollowSettings, MonitorArrangement, MultiMonitorConfig,
+    ScreenAnchoring, VirtualScreenConfig, WindowLayout, WindowManagementSettings,
 };
 
 pub use input::{
-    GazeInputSettings, GestureInputSettings, InputConfig, SensitivitySettings, VoiceInputSettings,
+    ButtonBinding, ButtonPress, GazeInputSettings, GestureBinding, GestureInputSettings,
+    GlassesButtonSettings, HeadGesture, InputAction, InputConfig, SensitivitySettings,
+    VoiceInputSettings,
 };
 
 pub use audio::{AudioDeviceConfig, AudioEffectsSettings, AudioSettings, SpatialAudioSettings};
@@ -90,3 +94,9 @@ pub use security::{
     AccessControlSettings, AuthenticationMethod, AuthenticationSettings, SecurityLevel,
     SecuritySettings,
 };
+
+pub use tracking::{
+    ComplementaryFilterSettings, DriftCompensationSettings, FilterKind, FusionFilterSettings,
+    MadgwickFilterSettings, MahonyFilterSettings, NeckModelSettings, OrientationFilterSettings,
+    PredictionSettings, RecenterSettings, SmoothingPreset, SmoothingSettings, TrackingSettings,
+};
@@ -28,6 +28,9 @@ pub struct UserPreferences {
     pub privacy_settings: PrivacySettings,
     /// Theme and appearance settings
     pub appearance_settings: AppearanceSettings,
+    /// Behaviour when the glasses are taken off
+    #[serde(default)]
+    pub auto_pause: AutoPauseSettings,
 }
 
 impl Default for UserPreferences {
@@ -42,6 +45,7 @@ impl Default for UserPreferences {
             accessibility_settings: AccessibilitySettings::default(),
             privacy_settings: PrivacySettings::default(),
             appearance_settings: AppearanceSettings::default(),
+            auto_pause: AutoPauseSettings::default(),
         }
     }
 }
@@ -63,6 +67,7 @@ impl StateValidation for UserPreferences {
         self.accessibility_settings.validate()?;
         self.privacy_settings.validate()?;
         self.appearance_settings.validate()?;
+        self.auto_pause.validate()?;
 
         Ok(())
     }
@@ -81,11 +86,68 @@ impl StateValidation for UserPreferences {
             .merge(&other.accessibility_settings)?;
         self.privacy_settings.merge(&other.privacy_settings)?;
         self.appearance_settings.merge(&other.appearance_settings)?;
+        self.auto_pause.merge(&other.auto_pause)?;
 
         Ok(())
     }
 }
 
+/// Proximity sensor driven pause settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoPauseSettings {
+    /// Pause when the glasses are taken off
+    pub enabled: bool,
+    /// Time the glasses must stay off before pausing, in ms
+    pub pause_delay_ms: u32,
+    /// Stop screen capture while paused
+    pub pause_capture: bool,
+    /// Disable the stereo cameras while paused
+    pub disable_stereo: bool,
+    /// Suspend mouse and keyboard injection while paused
+    pub suspend_input: bool,
+    /// Frame rate while paused (0 = unthrottled)
+    pub paused_fps: u32,
+    /// Recenter the view when the glasses are put back on
+    pub recenter_on_wake: bool,
+}
+
+impl Default for AutoPauseSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pause_delay_ms: 1000,
+            pause_capture: true,
+            disable_stereo: true,
+            suspend_input: true,
+            paused_fps: 5,
+            recenter_on_wake: true,
+        }
+    }
+}
+
+impl StateValidation for AutoPauseSettings {
+    fn validate(&self) -> Result<()> {
+        if self.pause_delay_ms > 60_000 {
+            anyhow::bail!("Pause delay out of range: {}", self.pause_delay_ms);
+        }
+        if self.paused_fps > 60 {
+            anyhow::bail!("Paused frame rate out of range: {}", self.paused_fps);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.enabled = other.enabled;
+        self.pause_delay_ms = other.pause_delay_ms;
+        self.pause_capture = other.pause_capture;
+        self.disable_stereo = other.disable_stereo;
+        self.suspend_input = other.suspend_input;
+        self.paused_fps = other.paused_fps;
+        self.recenter_on_wake = other.recenter_on_wake;
+        Ok(())
+    }
+}
+
 /// Comfort settings for user experience
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComfortSettings {
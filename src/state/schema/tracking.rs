@@ -0,0 +1,605 @@
+//! Head tracking schema definitions
+//!
+//! This module provides head tracking, orientation filter, smoothing, drift
+//! compensation, recenter and neck model settings with validation and
+//! serialization support for the XREAL application state system.
+
+use super::core::StateValidation;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Head tracking settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrackingSettings {
+    /// Motion-to-photon latency compensation
+    #[serde(default)]
+    pub prediction: PredictionSettings,
+    /// Sensor fusion algorithm turning IMU samples into an orientation
+    #[serde(default)]
+    pub filter: OrientationFilterSettings,
+    /// Anti-jitter smoothing of the filtered orientation
+    #[serde(default)]
+    pub smoothing: SmoothingSettings,
+    /// Online gyro bias refinement and yaw drift correction
+    #[serde(default)]
+    pub drift: DriftCompensationSettings,
+    /// How the view is brought back in front of the viewer
+    #[serde(default)]
+    pub recenter: RecenterSettings,
+    /// Eye translation derived from the head orientation
+    #[serde(default)]
+    pub neck_model: NeckModelSettings,
+}
+
+impl StateValidation for TrackingSettings {
+    fn validate(&self) -> Result<()> {
+        self.prediction.validate()?;
+        self.filter.validate()?;
+        self.smoothing.validate()?;
+        self.drift.validate()?;
+        self.recenter.validate()?;
+        self.neck_model.validate()
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.prediction.merge(&other.prediction)?;
+        self.filter.merge(&other.filter)?;
+        self.smoothing.merge(&other.smoothing)?;
+        self.drift.merge(&other.drift)?;
+        self.recenter.merge(&other.recenter)?;
+        self.neck_model.merge(&other.neck_model)
+    }
+}
+
+/// Smoothing parameter sets, matching the display presets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SmoothingPreset {
+    /// Balanced for desktop work
+    #[default]
+    Standard,
+    /// Steady image for watching video, more lag on fast turns
+    Cinema,
+    /// Least lag, more jitter while still
+    Gaming,
+    /// Hand-tuned parameters
+    Custom,
+}
+
+impl SmoothingPreset {
+    /// All presets, in display order
+    pub const ALL: [SmoothingPreset; 4] = [
+        SmoothingPreset::Standard,
+        SmoothingPreset::Cinema,
+        SmoothingPreset::Gaming,
+        SmoothingPreset::Custom,
+    ];
+
+    /// Human readable name for settings displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            SmoothingPreset::Standard => "Standard",
+            SmoothingPreset::Cinema => "Cinema",
+            SmoothingPreset::Gaming => "Gaming",
+            SmoothingPreset::Custom => "Custom",
+        }
+    }
+}
+
+/// Velocity-adaptive (One-Euro) smoothing settings
+/// The cutoff rises from `min_cutoff_hz` by `beta` Hz per rad/s of head motion,
+/// so slow motion is smoothed heavily while fast turns follow with little lag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmoothingSettings {
+    pub enabled: bool,
+    /// Preset the parameters came from
+    pub preset: SmoothingPreset,
+    /// Cutoff frequency while the head is still, in Hz
+    pub min_cutoff_hz: f32,
+    /// Cutoff increase per rad/s of angular speed, in Hz
+    pub beta: f32,
+    /// Cutoff frequency of the angular speed estimate, in Hz
+    pub d_cutoff_hz: f32,
+}
+
+impl SmoothingSettings {
+    /// Parameters of a preset; `Custom` uses the standard ones as a starting point
+    pub fn from_preset(preset: SmoothingPreset) -> Self {
+        let (min_cutoff_hz, beta, d_cutoff_hz) = match preset {
+            SmoothingPreset::Standard | SmoothingPreset::Custom => (1.0, 5.0, 1.0),
+            SmoothingPreset::Cinema => (0.5, 2.0, 1.0),
+            SmoothingPreset::Gaming => (3.0, 20.0, 2.0),
+        };
+        Self {
+            enabled: true,
+            preset,
+            min_cutoff_hz,
+            beta,
+            d_cutoff_hz,
+        }
+    }
+}
+
+impl Default for SmoothingSettings {
+    fn default() -> Self {
+        Self::from_preset(SmoothingPreset::Standard)
+    }
+}
+
+impl StateValidation for SmoothingSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.01..=30.0).contains(&self.min_cutoff_hz) {
+            anyhow::bail!("Smoothing min cutoff out of range: {}", self.min_cutoff_hz);
+        }
+        if !(0.0..=100.0).contains(&self.beta) {
+            anyhow::bail!("Smoothing beta out of range: {}", self.beta);
+        }
+        if !(0.01..=30.0).contains(&self.d_cutoff_hz) {
+            anyhow::bail!(
+                "Smoothing derivative cutoff out of range: {}",
+                self.d_cutoff_hz
+            );
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.enabled = other.enabled;
+        self.preset = other.preset;
+        self.min_cutoff_hz = other.min_cutoff_hz;
+        self.beta = other.beta;
+        self.d_cutoff_hz = other.d_cutoff_hz;
+        Ok(())
+    }
+}
+
+/// Drift compensation settings
+/// While the head is held still the gyro bias is refined and the yaw is held,
+/// the yaw drift rate learned there is removed while the head moves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DriftCompensationSettings {
+    pub enabled: bool,
+    /// Refine the gyro bias while still
+    pub learn_gyro_bias: bool,
+    /// Hold yaw while still and remove the learned drift rate while moving,
+    /// skipped while the orientation filter uses the magnetometer
+    pub yaw_correction: bool,
+    /// How fast estimates follow the measurements while still, in 1/s
+    pub correction_rate: f32,
+    /// Gyro fluctuation below which the head counts as still, in °/s
+    pub stationary_threshold_dps: f32,
+    /// How long the head has to be still before learning starts, in seconds
+    pub stationary_time_s: f32,
+    /// Largest rotation rate taken as bias rather than slow motion, in °/s
+    pub drift_threshold_dps: f32,
+    /// Follow the gyro bias recorded at several IMU temperatures while warming up
+    #[serde(default = "default_temperature_model")]
+    pub temperature_model: bool,
+}
+
+fn default_temperature_model() -> bool {
+    true
+}
+
+impl Default for DriftCompensationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            learn_gyro_bias: true,
+            yaw_correction: true,
+            correction_rate: 0.2,
+            stationary_threshold_dps: 1.0,
+            stationary_time_s: 1.0,
+            drift_threshold_dps: 3.0,
+            temperature_model: true,
+        }
+    }
+}
+
+impl StateValidation for DriftCompensationSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=10.0).contains(&self.correction_rate) {
+            anyhow::bail!(
+                "Drift correction rate out of range: {}",
+                self.correction_rate
+            );
+        }
+        if !(0.01..=20.0).contains(&self.stationary_threshold_dps) {
+            anyhow::bail!(
+                "Stationary threshold out of range: {}",
+                self.stationary_threshold_dps
+            );
+        }
+        if !(0.0..=30.0).contains(&self.stationary_time_s) {
+            anyhow::bail!("Stationary time out of range: {}", self.stationary_time_s);
+        }
+        if !(0.0..=20.0).contains(&self.drift_threshold_dps) {
+            anyhow::bail!("Drift threshold out of range: {}", self.drift_threshold_dps);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.enabled = other.enabled;
+        self.learn_gyro_bias = other.learn_gyro_bias;
+        self.yaw_correction = other.yaw_correction;
+        self.correction_rate = other.correction_rate;
+        self.stationary_threshold_dps = other.stationary_threshold_dps;
+        self.stationary_time_s = other.stationary_time_s;
+        self.drift_threshold_dps = other.drift_threshold_dps;
+        self.temperature_model = other.temperature_model;
+        Ok(())
+    }
+}
+
+/// Recenter settings
+/// Recentering turns the view by a reference offset on top of the filtered
+/// orientation, so the tilt measured from gravity is kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecenterSettings {
+    /// Zero pitch as well as yaw
+    pub include_pitch: bool,
+    /// Time the view takes to turn to the new center, in seconds (0 = instant)
+    pub transition_s: f32,
+    /// Recenter after looking away for a while
+    pub auto_recenter: bool,
+    /// Angle from the center beyond which the head counts as looking away, in degrees
+    pub auto_angle_deg: f32,
+    /// How long the head has to look away before recentering, in seconds
+    pub auto_delay_s: f32,
+}
+
+impl Default for RecenterSettings {
+    fn default() -> Self {
+        Self {
+            include_pitch: false,
+            transition_s: 0.4,
+            auto_recenter: false,
+            auto_angle_deg: 45.0,
+            auto_delay_s: 10.0,
+        }
+    }
+}
+
+impl StateValidation for RecenterSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=5.0).contains(&self.transition_s) {
+            anyhow::bail!("Recenter transition out of range: {}", self.transition_s);
+        }
+        if !(5.0..=180.0).contains(&self.auto_angle_deg) {
+            anyhow::bail!("Auto-recenter angle out of range: {}", self.auto_angle_deg);
+        }
+        if !(1.0..=600.0).contains(&self.auto_delay_s) {
+            anyhow::bail!("Auto-recenter delay out of range: {}", self.auto_delay_s);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.include_pitch = other.include_pitch;
+        self.transition_s = other.transition_s;
+        self.auto_recenter = other.auto_recenter;
+        self.auto_angle_deg = other.auto_angle_deg;
+        self.auto_delay_s = other.auto_delay_s;
+        Ok(())
+    }
+}
+
+/// Neck model settings
+/// The head turns about a pivot in the neck rather than about the eyes, so the
+/// eyes move a little as the head rotates and near content shows parallax
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NeckModelSettings {
+    pub enabled: bool,
+    /// Height of the eyes above the neck pivot, in meters
+    pub neck_length_m: f32,
+    /// Distance of the eyes in front of the neck pivot, in meters
+    pub eye_offset_m: f32,
+}
+
+impl Default for NeckModelSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            neck_length_m: 0.075,
+            eye_offset_m: 0.08,
+        }
+    }
+}
+
+impl StateValidation for NeckModelSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=0.3).contains(&self.neck_length_m) {
+            anyhow::bail!("Neck length out of range: {}", self.neck_length_m);
+        }
+        if !(0.0..=0.2).contains(&self.eye_offset_m) {
+            anyhow::bail!("Eye offset out of range: {}", self.eye_offset_m);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.enabled = other.enabled;
+        self.neck_length_m = other.neck_length_m;
+        self.eye_offset_m = other.eye_offset_m;
+        Ok(())
+    }
+}
+
+/// Pose prediction settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredictionSettings {
+    /// Predict the head pose for the expected display time
+    pub enabled: bool,
+    /// Render pipeline latency added to the glasses display delay, in ms
+    pub render_latency_ms: f32,
+    /// Longest prediction horizon, in ms
+    pub max_horizon_ms: f32,
+    /// Angular velocity decay rate over the horizon, in 1/s (0 = constant velocity)
+    pub damping: f32,
+    /// Show the prediction error overlay
+    pub show_overlay: bool,
+}
+
+impl Default for PredictionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            render_latency_ms: 16.0,
+            max_horizon_ms: 50.0,
+            damping: 5.0,
+            show_overlay: false,
+        }
+    }
+}
+
+impl StateValidation for PredictionSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.render_latency_ms) {
+            anyhow::bail!("Render latency out of range: {}", self.render_latency_ms);
+        }
+        if !(0.0..=200.0).contains(&self.max_horizon_ms) {
+            anyhow::bail!("Prediction horizon out of range: {}", self.max_horizon_ms);
+        }
+        if !(0.0..=50.0).contains(&self.damping) {
+            anyhow::bail!("Prediction damping out of range: {}", self.damping);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.enabled = other.enabled;
+        self.render_latency_ms = other.render_latency_ms;
+        self.max_horizon_ms = other.max_horizon_ms;
+        self.damping = other.damping;
+        self.show_overlay = other.show_overlay;
+        Ok(())
+    }
+}
+
+/// Orientation filter algorithms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FilterKind {
+    /// Fusion AHRS with acceleration and magnetic rejection
+    #[default]
+    Fusion,
+    /// Gyro integration with a low-pass gravity correction
+    Complementary,
+    /// Madgwick gradient descent
+    Madgwick,
+    /// Mahony PI controller with gyro bias estimation
+    Mahony,
+}
+
+impl FilterKind {
+    /// All filters, in display order
+    pub const ALL: [FilterKind; 4] = [
+        FilterKind::Fusion,
+        FilterKind::Complementary,
+        FilterKind::Madgwick,
+        FilterKind::Mahony,
+    ];
+
+    /// Human readable name for settings displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            FilterKind::Fusion => "Fusion AHRS",
+            FilterKind::Complementary => "Complementary",
+            FilterKind::Madgwick => "Madgwick",
+            FilterKind::Mahony => "Mahony",
+        }
+    }
+}
+
+/// Selected orientation filter and the parameters of every filter
+/// Parameters of the inactive filters are kept so switching back restores them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrientationFilterSettings {
+    pub kind: FilterKind,
+    #[serde(default)]
+    pub fusion: FusionFilterSettings,
+    #[serde(default)]
+    pub complementary: ComplementaryFilterSettings,
+    #[serde(default)]
+    pub madgwick: MadgwickFilterSettings,
+    #[serde(default)]
+    pub mahony: MahonyFilterSettings,
+}
+
+impl StateValidation for OrientationFilterSettings {
+    fn validate(&self) -> Result<()> {
+        self.fusion.validate()?;
+        self.complementary.validate()?;
+        self.madgwick.validate()?;
+        self.mahony.validate()
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.kind = other.kind;
+        self.fusion.merge(&other.fusion)?;
+        self.complementary.merge(&other.complementary)?;
+        self.madgwick.merge(&other.madgwick)?;
+        self.mahony.merge(&other.mahony)
+    }
+}
+
+/// Fusion AHRS parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FusionFilterSettings {
+    /// Weight of accelerometer and magnetometer corrections (0 = gyro only)
+    pub gain: f32,
+    /// Accelerometer readings further than this from gravity are ignored, in degrees
+    pub acc_rejection: f32,
+    /// Magnetometer readings further than this from the field are ignored, in degrees
+    pub mag_rejection: f32,
+    /// Time after which rejected readings are trusted again, in seconds
+    pub recovery_trigger_s: f32,
+    /// Use the magnetometer for absolute heading
+    pub use_magnetometer: bool,
+}
+
+impl Default for FusionFilterSettings {
+    fn default() -> Self {
+        Self {
+            gain: 0.5,
+            acc_rejection: 10.0,
+            mag_rejection: 10.0,
+            recovery_trigger_s: 5.0,
+            use_magnetometer: true,
+        }
+    }
+}
+
+impl StateValidation for FusionFilterSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=10.0).contains(&self.gain) {
+            anyhow::bail!("Fusion gain out of range: {}", self.gain);
+        }
+        if !(0.0..=180.0).contains(&self.acc_rejection) {
+            anyhow::bail!(
+                "Acceleration rejection out of range: {}",
+                self.acc_rejection
+            );
+        }
+        if !(0.0..=180.0).contains(&self.mag_rejection) {
+            anyhow::bail!("Magnetic rejection out of range: {}", self.mag_rejection);
+        }
+        if !(0.0..=60.0).contains(&self.recovery_trigger_s) {
+            anyhow::bail!(
+                "Recovery trigger period out of range: {}",
+                self.recovery_trigger_s
+            );
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.gain = other.gain;
+        self.acc_rejection = other.acc_rejection;
+        self.mag_rejection = other.mag_rejection;
+        self.recovery_trigger_s = other.recovery_trigger_s;
+        self.use_magnetometer = other.use_magnetometer;
+        Ok(())
+    }
+}
+
+/// Complementary filter parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplementaryFilterSettings {
+    /// Time constant of the gravity correction, in seconds (longer = trust the gyro more)
+    pub time_constant_s: f32,
+    /// Skip the correction when acceleration deviates from 1 g by more than this fraction
+    pub accel_tolerance: f32,
+}
+
+impl Default for ComplementaryFilterSettings {
+    fn default() -> Self {
+        Self {
+            time_constant_s: 1.0,
+            accel_tolerance: 0.1,
+        }
+    }
+}
+
+impl StateValidation for ComplementaryFilterSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.01..=60.0).contains(&self.time_constant_s) {
+            anyhow::bail!(
+                "Complementary time constant out of range: {}",
+                self.time_constant_s
+            );
+        }
+        if !(0.0..=1.0).contains(&self.accel_tolerance) {
+            anyhow::bail!(
+                "Acceleration tolerance out of range: {}",
+                self.accel_tolerance
+            );
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.time_constant_s = other.time_constant_s;
+        self.accel_tolerance = other.accel_tolerance;
+        Ok(())
+    }
+}
+
+/// Madgwick filter parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MadgwickFilterSettings {
+    /// Gradient descent step size, in rad/s
+    pub beta: f32,
+}
+
+impl Default for MadgwickFilterSettings {
+    fn default() -> Self {
+        Self { beta: 0.1 }
+    }
+}
+
+impl StateValidation for MadgwickFilterSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=2.0).contains(&self.beta) {
+            anyhow::bail!("Madgwick beta out of range: {}", self.beta);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.beta = other.beta;
+        Ok(())
+    }
+}
+
+/// Mahony filter parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MahonyFilterSettings {
+    /// Proportional gain of the gravity correction
+    pub kp: f32,
+    /// Integral gain, estimates the gyro bias (0 = disabled)
+    pub ki: f32,
+}
+
+impl Default for MahonyFilterSettings {
+    fn default() -> Self {
+        Self { kp: 1.0, ki: 0.01 }
+    }
+}
+
+impl StateValidation for MahonyFilterSettings {
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=20.0).contains(&self.kp) {
+            anyhow::bail!("Mahony kp out of range: {}", self.kp);
+        }
+        if !(0.0..=5.0).contains(&self.ki) {
+            anyhow::bail!("Mahony ki out of range: {}", self.ki);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.kp = other.kp;
+        self.ki = other.ki;
+        Ok(())
+    }
+}
@@ -18,6 +18,9 @@ pub struct WindowLayout {
     pub multi_monitor: MultiMonitorConfig,
     /// Window management settings
     pub window_management: WindowManagementSettings,
+    /// How each virtual screen follows the head
+    #[serde(default)]
+    pub anchoring: ScreenAnchoring,
 }
 
 impl Default for WindowLayout {
@@ -27,6 +30,7 @@ impl Default for WindowLayout {
             virtual_screen: VirtualScreenConfig::default(),
             multi_monitor: MultiMonitorConfig::default(),
             window_management: WindowManagementSettings::default(),
+            anchoring: ScreenAnchoring::default(),
         }
     }
 }
@@ -37,6 +41,7 @@ impl StateValidation for WindowLayout {
         self.virtual_screen.validate()?;
         self.multi_monitor.validate()?;
         self.window_management.validate()?;
+        self.anchoring.validate()?;
         Ok(())
     }
 
@@ -45,6 +50,7 @@ impl StateValidation for WindowLayout {
         self.virtual_screen.merge(&other.virtual_screen)?;
         self.multi_monitor.merge(&other.multi_monitor)?;
         self.window_management.merge(&other.window_management)?;
+        self.anchoring.merge(&other.anchoring)?;
         Ok(())
     }
 }
@@ -266,7 +272,7 @@ impl StateValidation for MultiMonitorConfig {
 }
 
 /// Monitor arrangement options
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MonitorArrangement {
     Horizontal,
     Vertical,
@@ -342,3 +348,142 @@ impl StateValidation for WindowManagementSettings {
         Ok(())
     }
 }
+
+/// How a virtual screen moves with the head
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnchorMode {
+    /// Fixed in the room
+    #[default]
+    WorldLocked,
+    /// Moves rigidly with the head
+    HeadLocked,
+    /// Stays put until the head turns away, then glides back into view
+    LazyFollow,
+    /// Follows the head's yaw, staying level with the horizon
+    HorizonLocked,
+}
+
+impl AnchorMode {
+    /// All modes, in display order
+    pub const ALL: [AnchorMode; 4] = [
+        AnchorMode::WorldLocked,
+        AnchorMode::HeadLocked,
+        AnchorMode::LazyFollow,
+        AnchorMode::HorizonLocked,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnchorMode::WorldLocked => "World-locked",
+            AnchorMode::HeadLocked => "Head-locked",
+            AnchorMode::LazyFollow => "Lazy follow",
+            AnchorMode::HorizonLocked => "Horizon-locked",
+        }
+    }
+}
+
+/// Easing curve of a lazy-follow transition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    /// Starts fast and settles gently
+    #[default]
+    EaseOut,
+    /// Starts and settles gently
+    EaseInOut,
+}
+
+impl Easing {
+    /// All curves, in display order
+    pub const ALL: [Easing; 3] = [Easing::Linear, Easing::EaseOut, Easing::EaseInOut];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseOut => "Ease out",
+            Easing::EaseInOut => "Ease in-out",
+        }
+    }
+
+    /// Eased progress for a linear progress `t` from 0 to 1
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Lazy-follow tuning
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LazyFollowSettings {
+    /// Head turn away from the screen that starts a follow, in degrees
+    pub threshold_deg: f32,
+    /// Duration of the glide back into view, in seconds
+    pub duration_s: f32,
+    /// Easing curve of the glide
+    pub easing: Easing,
+}
+
+impl Default for LazyFollowSettings {
+    fn default() -> Self {
+        Self {
+            threshold_deg: 30.0,
+            duration_s: 0.6,
+            easing: Easing::EaseOut,
+        }
+    }
+}
+
+impl StateValidation for LazyFollowSettings {
+    fn validate(&self) -> Result<()> {
+        if !(5.0..=90.0).contains(&self.threshold_deg) {
+            anyhow::bail!("Lazy-follow threshold out of range: {}", self.threshold_deg);
+        }
+        if !(0.05..=5.0).contains(&self.duration_s) {
+            anyhow::bail!("Lazy-follow duration out of range: {}", self.duration_s);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        *self = *other;
+        Ok(())
+    }
+}
+
+/// Anchoring of the virtual screens
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScreenAnchoring {
+    /// Mode of each screen by index; screens without an entry are world-locked
+    pub modes: Vec<AnchorMode>,
+    /// Tuning of lazy-follow screens
+    pub lazy_follow: LazyFollowSettings,
+}
+
+impl ScreenAnchoring {
+    /// Anchor mode of the screen at `index`
+    pub fn mode(&self, index: usize) -> AnchorMode {
+        self.modes.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn set_mode(&mut self, index: usize, mode: AnchorMode) {
+        if self.modes.len() <= index {
+            self.modes.resize(index + 1, AnchorMode::default());
+        }
+        self.modes[index] = mode;
+    }
+}
+
+impl StateValidation for ScreenAnchoring {
+    fn validate(&self) -> Result<()> {
+        self.lazy_follow.validate()
+    }
+
+    fn merge(&mut self, other: &Self) -> Result<()> {
+        self.modes = other.modes.clone();
+        self.lazy_follow.merge(&other.lazy_follow)
+    }
+}
@@ -43,6 +43,13 @@ impl Default for StorageConfig {
     }
 }
 
+impl StorageConfig {
+    /// Path of the primary state file
+    pub fn primary_state_file(&self) -> PathBuf {
+        self.base_directory.join("app_state.json")
+    }
+}
+
 /// Backup configuration
 #[derive(Debug, Clone)]
 pub struct BackupConfig {
@@ -370,7 +377,7 @@ impl StateStorage {
 
     /// Get primary state file path
     fn get_primary_state_file_path(&self) -> PathBuf {
-        self.config.base_directory.join("app_state.json")
+        self.config.primary_state_file()
     }
 
     /// Create backup of current state file
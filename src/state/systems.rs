@@ -4,8 +4,12 @@
 //! Uses AsyncComputeTaskPool for non-blocking operations.
 
 // use anyhow::Result; // Unused import removed
-use crate::state::StatePersistenceManager;
+use crate::state::{
+    schema::core::PersistentAppState, PersistentStateWriter, StatePersistenceManager,
+    StateSerializer,
+};
 use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
 
 /// System to monitor state changes and trigger auto-save
 pub fn state_auto_save_system(mut state_manager: ResMut<StatePersistenceManager>, world: &World) {
@@ -50,3 +54,47 @@ pub fn state_monitoring_system(state_manager: Res<StatePersistenceManager>, time
         debug!("State has changes pending save");
     }
 }
+
+/// Save persisted settings once they stop changing
+pub fn save_persistent_state_on_change(
+    state: Res<PersistentAppState>,
+    mut writer: ResMut<PersistentStateWriter>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed();
+    if state.is_changed() && !state.is_added() {
+        writer.mark_changed(now);
+    }
+    if !writer.is_due(now) {
+        return;
+    }
+
+    let Some(snapshot) = writer.take_snapshot(&state) else {
+        return;
+    };
+    let path = writer.path().to_path_buf();
+    IoTaskPool::get()
+        .spawn(async move {
+            if let Err(e) = StateSerializer::new().serialize_to_file(&snapshot, &path) {
+                error!("❌ Failed to save settings: {}", e);
+            }
+        })
+        .detach();
+}
+
+/// Write any unsaved settings before the app exits
+pub fn flush_persistent_state_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    state: Res<PersistentAppState>,
+    mut writer: ResMut<PersistentStateWriter>,
+) {
+    if exit_events.read().next().is_none() || !writer.has_pending() {
+        return;
+    }
+
+    if let Some(snapshot) = writer.take_snapshot(&state) {
+        if let Err(e) = StateSerializer::new().serialize_to_file(&snapshot, writer.path()) {
+            error!("❌ Failed to save settings on exit: {}", e);
+        }
+    }
+}
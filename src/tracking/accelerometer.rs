@@ -0,0 +1,312 @@
+//! Six-position accelerometer calibration
+//!
+//! At rest the accelerometer only measures gravity, so resting the glasses on
+//! each of their six sides gives readings of +g and -g along every axis. Half
+//! the difference of opposite readings is the axis scale (and, for the other
+//! components, the cross-axis coupling), their mean is the offset. Poses are
+//! captured automatically whenever the glasses rest still in one that is
+//! still missing, in any order.
+
+use anyhow::Result;
+use bevy::prelude::*;
+
+use super::filter::STANDARD_GRAVITY;
+use crate::state::schema::calibration::CalibrationData;
+use crate::state::schema::core::{PersistentAppState, StateValidation};
+
+/// Still samples averaged per pose
+pub const SAMPLES_PER_POSE: u32 = 500;
+
+/// Gyro rate above which the glasses count as moving, in rad/s
+const MOTION_THRESHOLD: f32 = 0.05;
+
+/// Smallest share of the reading on the pose axis, about 35° of tilt
+const POSE_ALIGNMENT: f32 = 0.8;
+
+/// Residual relative to g at which the quality drops to zero
+const MAX_RESIDUAL: f32 = 0.05;
+
+/// Resting orientation, named after the side facing up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccelPose {
+    TopUp,
+    BottomUp,
+    RightUp,
+    LeftUp,
+    BackUp,
+    FrontUp,
+}
+
+impl AccelPose {
+    /// All poses, in the suggested order
+    pub const ALL: [AccelPose; 6] = [
+        AccelPose::TopUp,
+        AccelPose::BottomUp,
+        AccelPose::RightUp,
+        AccelPose::LeftUp,
+        AccelPose::BackUp,
+        AccelPose::FrontUp,
+    ];
+
+    /// Reading direction of gravity in this pose, in the IMU frame
+    pub fn gravity_axis(&self) -> Vec3 {
+        match self {
+            AccelPose::TopUp => Vec3::Y,
+            AccelPose::BottomUp => Vec3::NEG_Y,
+            AccelPose::RightUp => Vec3::X,
+            AccelPose::LeftUp => Vec3::NEG_X,
+            AccelPose::BackUp => Vec3::Z,
+            AccelPose::FrontUp => Vec3::NEG_Z,
+        }
+    }
+
+    /// Pose whose axis the reading is aligned with
+    pub fn detect(accel: Vec3) -> Option<AccelPose> {
+        let length = accel.length();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        AccelPose::ALL
+            .into_iter()
+            .find(|pose| accel.dot(pose.gravity_axis()) >= POSE_ALIGNMENT * length)
+    }
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// How to place the glasses
+    pub fn instruction(&self) -> &'static str {
+        match self {
+            AccelPose::TopUp => "Stand the glasses on the table as if worn, temples unfolded",
+            AccelPose::BottomUp => "Turn them upside down, resting on the top of the frame",
+            AccelPose::RightUp => "Rest them on their left side, right side pointing up",
+            AccelPose::LeftUp => "Rest them on their right side, left side pointing up",
+            AccelPose::BackUp => "Rest them on the front of the frame, temples pointing up",
+            AccelPose::FrontUp => "Lay them flat on the folded temples, lenses facing up",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AccelPose::TopUp => "Upright",
+            AccelPose::BottomUp => "Upside down",
+            AccelPose::RightUp => "Right side up",
+            AccelPose::LeftUp => "Left side up",
+            AccelPose::BackUp => "Lenses down",
+            AccelPose::FrontUp => "Lenses up",
+        }
+    }
+}
+
+/// Offset, scale and cross-axis correction of accelerometer samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccelCalibration {
+    /// Reading with no acceleration, in m/s²
+    pub offset: Vec3,
+    /// Inverse of the sensor's scale and cross-axis response
+    pub correction: Mat3,
+    /// RMS error of the corrected pose readings, relative to g
+    pub residual: f32,
+}
+
+impl Default for AccelCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl AccelCalibration {
+    /// Leaves samples unchanged
+    pub const IDENTITY: AccelCalibration = AccelCalibration {
+        offset: Vec3::ZERO,
+        correction: Mat3::IDENTITY,
+        residual: 0.0,
+    };
+
+    /// Correct a raw sample
+    #[inline]
+    pub fn apply(&self, raw: Vec3) -> Vec3 {
+        self.correction * (raw - self.offset)
+    }
+
+    /// Per-axis scale factors of the sensor
+    pub fn scale(&self) -> Vec3 {
+        let inverse = self.correction.inverse();
+        Vec3::new(inverse.x_axis.x, inverse.y_axis.y, inverse.z_axis.z)
+    }
+
+    /// Fit quality from 0 to 1
+    pub fn quality(&self) -> f32 {
+        (1.0 - self.residual / MAX_RESIDUAL).clamp(0.0, 1.0)
+    }
+
+    /// Solve from the mean reading in each pose, indexed like [`AccelPose::ALL`]
+    /// Without `cross_axis` only the per-axis offset and scale are solved
+    pub fn solve(readings: &[Vec3; 6], cross_axis: bool) -> Result<Self> {
+        let reading = |pose: AccelPose| readings[pose.index()];
+        let pairs = [
+            (AccelPose::RightUp, AccelPose::LeftUp),
+            (AccelPose::TopUp, AccelPose::BottomUp),
+            (AccelPose::BackUp, AccelPose::FrontUp),
+        ];
+
+        // Raw response to +g along each axis, the columns of the sensitivity matrix
+        let mut columns = [Vec3::ZERO; 3];
+        let mut offset = Vec3::ZERO;
+        for (axis, (positive, negative)) in pairs.into_iter().enumerate() {
+            let (up, down) = (reading(positive), reading(negative));
+            columns[axis] = (up - down) / (2.0 * STANDARD_GRAVITY);
+            offset += (up + down) / 6.0;
+        }
+        let mut sensitivity = Mat3::from_cols(columns[0], columns[1], columns[2]);
+        if !cross_axis {
+            sensitivity = Mat3::from_diagonal(Vec3::new(
+                sensitivity.x_axis.x,
+                sensitivity.y_axis.y,
+                sensitivity.z_axis.z,
+            ));
+        }
+        if sensitivity.determinant() <= f32::EPSILON {
+            anyhow::bail!("Accelerometer readings do not span all axes");
+        }
+
+        let mut calibration = Self {
+            offset,
+            correction: sensitivity.inverse(),
+            residual: 0.0,
+        };
+        let squared_error = AccelPose::ALL
+            .into_iter()
+            .map(|pose| {
+                let expected = pose.gravity_axis() * STANDARD_GRAVITY;
+                calibration.apply(reading(pose)).distance_squared(expected)
+            })
+            .sum::<f32>();
+        calibration.residual = (squared_error / 6.0).sqrt() / STANDARD_GRAVITY;
+        Ok(calibration)
+    }
+
+    /// Calibration stored in the persistent state
+    pub fn from_calibration_data(data: &CalibrationData) -> Self {
+        Self {
+            offset: Vec3::from_array(data.accel_bias),
+            correction: Mat3::from_cols_array_2d(&data.accel_correction),
+            residual: 0.0,
+        }
+    }
+
+    /// Store into the persistent state
+    pub fn store(&self, data: &mut CalibrationData) {
+        data.accel_bias = self.offset.to_array();
+        data.accel_correction = self.correction.to_cols_array_2d();
+        data.accel_fit_quality = self.quality();
+    }
+}
+
+/// Progress of an accelerometer calibration, reported by the tracking thread
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub enum AccelCalibrationStatus {
+    #[default]
+    Idle,
+    Capturing {
+        /// Poses captured so far, indexed like [`AccelPose::ALL`]
+        captured: [bool; 6],
+        /// Pose the glasses currently rest in
+        current: Option<AccelPose>,
+        /// Capture progress of the current pose, 0 to 1
+        progress: f32,
+    },
+    Done(AccelCalibration),
+    Failed,
+}
+
+/// Pose readings collected during a six-position calibration
+#[derive(Debug, Clone, Default)]
+pub struct AccelCapture {
+    readings: [Option<Vec3>; 6],
+    /// Pose being averaged, with the sum and count of its still samples
+    current: Option<(AccelPose, Vec3, u32)>,
+}
+
+impl AccelCapture {
+    /// Feed a raw accelerometer sample and the bias corrected gyro rate
+    /// Returns the pose whose capture completed with this sample
+    pub fn push(&mut self, accel: Vec3, gyro: Vec3) -> Option<AccelPose> {
+        let pose = AccelPose::detect(accel)
+            .filter(|pose| self.readings[pose.index()].is_none())
+            .filter(|_| gyro.length() < MOTION_THRESHOLD);
+        let Some(pose) = pose else {
+            self.current = None;
+            return None;
+        };
+
+        let (sum, count) = match self.current {
+            Some((current, sum, count)) if current == pose => (sum + accel, count + 1),
+            _ => (accel, 1),
+        };
+        if count < SAMPLES_PER_POSE {
+            self.current = Some((pose, sum, count));
+            return None;
+        }
+        self.readings[pose.index()] = Some(sum / count as f32);
+        self.current = None;
+        Some(pose)
+    }
+
+    /// True once every pose was captured
+    pub fn is_complete(&self) -> bool {
+        self.readings.iter().all(Option::is_some)
+    }
+
+    pub fn status(&self) -> AccelCalibrationStatus {
+        AccelCalibrationStatus::Capturing {
+            captured: self.readings.map(|reading| reading.is_some()),
+            current: self.current.map(|(pose, _, _)| pose),
+            progress: self
+                .current
+                .map_or(0.0, |(_, _, count)| count as f32 / SAMPLES_PER_POSE as f32),
+        }
+    }
+
+    /// Solve the calibration from the captured poses
+    pub fn solve(&self, cross_axis: bool) -> Result<AccelCalibration> {
+        let mut readings = [Vec3::ZERO; 6];
+        for (pose, reading) in AccelPose::ALL.into_iter().zip(self.readings) {
+            readings[pose.index()] =
+                reading.ok_or_else(|| anyhow::anyhow!("{} pose missing", pose.label()))?;
+        }
+        AccelCalibration::solve(&readings, cross_axis)
+    }
+}
+
+/// Validate and persist a finished accelerometer calibration, the tracking
+/// thread picks it up from the persisted state
+pub fn store_accel_calibration(
+    status: Res<AccelCalibrationStatus>,
+    mut persistent_state: ResMut<PersistentAppState>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    match *status {
+        AccelCalibrationStatus::Done(calibration) => {
+            let mut data = persistent_state.calibration_data.clone();
+            calibration.store(&mut data);
+            if let Err(e) = data.validate() {
+                error!("❌ Rejected accelerometer calibration: {}", e);
+                return;
+            }
+            info!(
+                "📐 Accelerometer calibrated: offset {:?} m/s², scale {:?}, quality {:.2}",
+                calibration.offset,
+                calibration.scale(),
+                calibration.quality()
+            );
+            persistent_state.calibration_data = data;
+        }
+        AccelCalibrationStatus::Failed => warn!("📐 Accelerometer calibration failed"),
+        _ => {}
+    }
+}
@@ -0,0 +1,281 @@
+//! Streaming gyro bias calibration
+//!
+//! While the glasses rest still the raw gyro rate is the bias. Samples are
+//! folded into running statistics as they arrive instead of being buffered,
+//! and samples taken while moving, or shortly after, are rejected so a bump
+//! does not skew the bias.
+//!
+//! Results are kept per pair of glasses, keyed by serial number, and the
+//! record of the connected glasses is applied whenever they connect.
+
+use anyhow::Result;
+use bevy::prelude::*;
+
+use super::filter::STANDARD_GRAVITY;
+use crate::driver::connection::GlassesConnectionEvent;
+use crate::state::schema::calibration::CalibrationState as RecordState;
+use crate::state::schema::core::{PersistentAppState, StateValidation};
+use crate::tracking::{send_queued, Command};
+use crate::CommandChannel;
+
+/// Still samples averaged into the bias, about 5 s at the IMU rate
+pub const TARGET_SAMPLES: usize = 5000;
+
+/// Fewest still samples a calibration is accepted with
+pub const MIN_SAMPLES: usize = 1000;
+
+/// Capture time after which the calibration ends with what it has, in seconds
+pub const CAPTURE_TIMEOUT_S: f32 = 30.0;
+
+/// Gyro rate above which the glasses count as moving, about 3°/s
+const MOTION_THRESHOLD: f32 = 0.05;
+
+/// Accelerometer magnitude deviation from gravity tolerated while still, in m/s²
+const STILL_ACCEL_TOLERANCE: f32 = 0.5;
+
+/// Time samples stay rejected after motion, in seconds
+const SETTLE_TIME_S: f32 = 0.25;
+
+/// Gyro noise at which the quality drops to zero, in rad/s
+const MAX_NOISE: f32 = 0.02;
+
+/// Running mean and variance of a vector stream (Welford's algorithm)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunningStats {
+    count: usize,
+    mean: Vec3,
+    /// Sum of squared deviations from the mean
+    m2: Vec3,
+}
+
+impl RunningStats {
+    pub fn push(&mut self, sample: Vec3) {
+        self.count += 1;
+        let delta = sample - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (sample - self.mean);
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    #[inline]
+    pub fn mean(&self) -> Vec3 {
+        self.mean
+    }
+
+    /// Sample variance per axis
+    pub fn variance(&self) -> Vec3 {
+        if self.count < 2 {
+            Vec3::ZERO
+        } else {
+            self.m2 / (self.count - 1) as f32
+        }
+    }
+}
+
+/// Progress of a gyro calibration, reported by the tracking thread
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CalibrationProgress {
+    /// Still samples accepted so far
+    pub samples: usize,
+    /// Samples rejected as motion
+    pub rejected: usize,
+    /// Gyro variance per axis of the accepted samples, in rad²/s²
+    pub variance: Vec3,
+    /// The glasses currently rest still
+    pub still: bool,
+    /// Capture time so far, in seconds
+    pub elapsed_s: f32,
+}
+
+impl CalibrationProgress {
+    /// Share of the target samples captured, 0 to 1
+    pub fn fraction(&self) -> f32 {
+        (self.samples as f32 / TARGET_SAMPLES as f32).min(1.0)
+    }
+
+    /// Quality from 0 to 1, from the gyro noise while still
+    pub fn quality(&self) -> f32 {
+        let noise = self.variance.max_element().sqrt();
+        (1.0 - noise / MAX_NOISE).clamp(0.0, 1.0)
+    }
+}
+
+/// Result of a finished gyro calibration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GyroCalibration {
+    /// Gyro bias in the IMU frame, in rad/s
+    pub bias: Vec3,
+    /// Still samples the bias was averaged from
+    pub samples: usize,
+    /// Quality from 0 to 1
+    pub quality: f32,
+    /// IMU temperature during the capture, measured or estimated from the uptime
+    pub temperature_celsius: Option<f32>,
+}
+
+/// Latest gyro calibration finished by the tracking thread
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatestGyroCalibration(pub Option<GyroCalibration>);
+
+/// Gyro bias capture with motion rejection
+#[derive(Debug, Clone, Default)]
+pub struct BiasCapture {
+    gyro: RunningStats,
+    rejected: usize,
+    /// Time left until samples are accepted again after motion
+    settle_s: f32,
+    still: bool,
+    elapsed_s: f32,
+}
+
+impl BiasCapture {
+    /// Feed a raw gyro sample with the accelerometer sample taken with it,
+    /// `dt` seconds after the previous one
+    /// Returns whether the sample was accepted as still
+    pub fn push(&mut self, gyro: Vec3, accel: Vec3, dt: f32) -> bool {
+        self.elapsed_s += dt;
+        let moving = gyro.length() > MOTION_THRESHOLD
+            || (accel.length() - STANDARD_GRAVITY).abs() > STILL_ACCEL_TOLERANCE;
+        self.settle_s = if moving {
+            SETTLE_TIME_S
+        } else {
+            (self.settle_s - dt).max(0.0)
+        };
+        self.still = !moving && self.settle_s <= 0.0;
+        if self.still {
+            self.gyro.push(gyro);
+        } else {
+            self.rejected += 1;
+        }
+        self.still
+    }
+
+    /// True once enough still samples were captured or the capture timed out
+    pub fn is_finished(&self) -> bool {
+        self.gyro.count() >= TARGET_SAMPLES || self.elapsed_s >= CAPTURE_TIMEOUT_S
+    }
+
+    pub fn progress(&self) -> CalibrationProgress {
+        CalibrationProgress {
+            samples: self.gyro.count(),
+            rejected: self.rejected,
+            variance: self.gyro.variance(),
+            still: self.still,
+            elapsed_s: self.elapsed_s,
+        }
+    }
+
+    /// Gyro bias from the still samples
+    pub fn finish(&self) -> Result<GyroCalibration> {
+        if self.gyro.count() < MIN_SAMPLES {
+            anyhow::bail!(
+                "Only {} still samples in {:.0} s, keep the glasses still",
+                self.gyro.count(),
+                self.elapsed_s
+            );
+        }
+        Ok(GyroCalibration {
+            bias: self.gyro.mean(),
+            samples: self.gyro.count(),
+            quality: self.progress().quality(),
+            temperature_celsius: None,
+        })
+    }
+}
+
+/// Seconds since the Unix epoch
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Validate and persist a finished gyro calibration in the record of the glasses in use
+pub fn store_gyro_calibration(
+    latest: Res<LatestGyroCalibration>,
+    mut persistent_state: ResMut<PersistentAppState>,
+) {
+    if !latest.is_changed() {
+        return;
+    }
+    let Some(calibration) = latest.0 else {
+        return;
+    };
+
+    let mut data = persistent_state.calibration_data.clone();
+    data.state = RecordState::Calibrated;
+    data.gyro_bias = calibration.bias.to_array();
+    data.calibrated_at = unix_time();
+    data.sample_count = calibration.samples as u32;
+    data.quality_score = calibration.quality;
+    if let Some(temperature) = calibration.temperature_celsius {
+        data.temperature_celsius = temperature;
+    }
+    if let Err(e) = data.validate() {
+        error!("❌ Rejected gyro calibration: {}", e);
+        return;
+    }
+    persistent_state.calibration_data = data;
+}
+
+/// Apply the stored calibration of the glasses whenever another pair connects
+/// Reconnecting the same glasses keeps the biases of this session
+pub fn apply_calibration_record(
+    mut connection_events: EventReader<GlassesConnectionEvent>,
+    mut persistent_state: ResMut<PersistentAppState>,
+    command_channel: Option<Res<CommandChannel>>,
+    mut applied: Local<Option<String>>,
+    mut queued: Local<Vec<Command>>,
+) {
+    let Some(command_channel) = command_channel else {
+        return;
+    };
+    for event in connection_events.read() {
+        let GlassesConnectionEvent::Connected { serial, .. } = event else {
+            continue;
+        };
+        if applied.as_deref() == Some(serial.as_str()) {
+            continue;
+        }
+        *applied = Some(serial.clone());
+        // Commands not sent yet were meant for the glasses connected before
+        queued.clear();
+        if persistent_state.activate_calibration(serial) {
+            info!("📐 Switched to the calibration of glasses {}", serial);
+        }
+
+        let record = &persistent_state.calibration_data;
+        let now = unix_time();
+        if record.is_calibrated() {
+            info!(
+                "🔁 Applying calibration from {:.1} days ago",
+                record.age_seconds(now) as f32 / 86_400.0
+            );
+            queued.push(Command::RestoreCalibration {
+                gyro_bias: record.gyro_bias,
+            });
+        } else {
+            // Otherwise the bias of the glasses connected before stays in use
+            queued.push(Command::ResetCalibration);
+        }
+        if record.needs_recalibration(now) {
+            warn!(
+                "📐 Calibration of glasses {} is missing or stale, please recalibrate",
+                serial
+            );
+        }
+
+        queued.push(Command::RestoreDriftEstimate {
+            gyro_bias: record.gyro_bias,
+            yaw_drift_rate: record.yaw_drift_rate,
+            gyro_bias_points: record.gyro_bias_points.clone(),
+        });
+    }
+    // A full command channel is retried on the next frame
+    send_queued(&command_channel.0, &mut queued);
+}
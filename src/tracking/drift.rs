@@ -0,0 +1,268 @@
+//! Online gyro bias estimation and yaw drift correction
+//!
+//! Gravity keeps pitch and roll in check, but nothing observes yaw without a
+//! magnetometer, so any gyro bias left after calibration turns into a slow
+//! yaw drift. Whenever the head is held still the raw gyro rate is the bias,
+//! so it is refined continuously. While still the yaw is held as well, and
+//! whatever yaw rate the filter still shows there is learned as the residual
+//! drift rate, which is then removed while the head moves.
+//! With bias recorded at several temperatures the estimate also follows the
+//! fitted temperature curve, see [`super::thermal`].
+
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+use super::filter::STANDARD_GRAVITY;
+use super::thermal;
+use crate::state::schema::calibration::GyroBiasPoint;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::tracking::DriftCompensationSettings;
+use crate::tracking::Command;
+use crate::CommandChannel;
+
+/// Time constant of the gyro fluctuation estimate, in seconds
+const MOTION_TIME_CONSTANT_S: f32 = 0.2;
+
+/// Accelerometer magnitude deviation from gravity tolerated while still, in m/s²
+const STILL_ACCEL_TOLERANCE: f32 = 0.5;
+
+/// Estimate change worth saving, in rad/s
+const PERSIST_TOLERANCE: f32 = 0.02 * PI / 180.0;
+
+/// Drift estimate reported by the tracking thread
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct DriftEstimate {
+    /// Gyro bias in the IMU frame, in rad/s
+    pub gyro_bias: Vec3,
+    /// Residual yaw drift, in rad/s
+    pub yaw_drift_rate: f32,
+    /// The head is held still
+    pub stationary: bool,
+    /// IMU temperature, measured or estimated from the uptime, in °C
+    pub temperature_celsius: f32,
+    /// Gyro bias recorded at the current temperature, if any
+    pub bias_point: Option<GyroBiasPoint>,
+}
+
+/// Wrap an angle into [-π, π)
+/// Angles already in range are returned as is, shifting them by π costs precision
+#[inline]
+fn wrap_angle(angle: f32) -> f32 {
+    if (-PI..PI).contains(&angle) {
+        angle
+    } else {
+        (angle + PI).rem_euclid(TAU) - PI
+    }
+}
+
+/// Stationarity detection, gyro bias refinement and yaw drift correction
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DriftCompensator {
+    bias: Vec3,
+    /// Residual yaw drift, in rad/s
+    yaw_drift_rate: f32,
+    /// Low-pass filtered raw gyro rate
+    gyro_mean: Vec3,
+    /// Low-pass filtered deviation of the raw gyro rate from its mean
+    motion: f32,
+    /// How long the stillness criteria have held, in seconds
+    still_for: f32,
+    stationary: bool,
+    /// Yaw correction applied to the filter output
+    yaw_offset: f32,
+    /// Filter yaw at the previous update while still
+    still_yaw: Option<f32>,
+}
+
+impl DriftCompensator {
+    /// Start from a calibrated gyro bias
+    pub fn new(gyro_bias: Vec3) -> Self {
+        Self {
+            bias: gyro_bias,
+            gyro_mean: gyro_bias,
+            ..Default::default()
+        }
+    }
+
+    /// Current gyro bias estimate, in rad/s
+    #[inline]
+    pub fn bias(&self) -> Vec3 {
+        self.bias
+    }
+
+    /// Replace the bias estimate, e.g. after a calibration
+    pub fn set_bias(&mut self, gyro_bias: Vec3) {
+        self.bias = gyro_bias;
+        self.gyro_mean = gyro_bias;
+        self.still_for = 0.0;
+        self.stationary = false;
+    }
+
+    /// Move the bias estimate by `delta`, e.g. along its temperature curve
+    /// Unlike [`Self::set_bias`] stillness detection carries on
+    #[inline]
+    pub fn shift_bias(&mut self, delta: Vec3) {
+        self.bias += delta;
+    }
+
+    /// Residual yaw drift, in rad/s
+    #[inline]
+    pub fn yaw_drift_rate(&self) -> f32 {
+        self.yaw_drift_rate
+    }
+
+    /// Restore a yaw drift rate learned in an earlier session
+    pub fn set_yaw_drift_rate(&mut self, rate: f32) {
+        self.yaw_drift_rate = rate;
+    }
+
+    /// True while the head is held still
+    #[inline]
+    pub fn is_stationary(&self) -> bool {
+        self.stationary
+    }
+
+    /// Snapshot reported to the app
+    pub fn estimate(&self) -> DriftEstimate {
+        DriftEstimate {
+            gyro_bias: self.bias,
+            yaw_drift_rate: self.yaw_drift_rate,
+            stationary: self.stationary,
+            ..Default::default()
+        }
+    }
+
+    /// Bias corrected rate of a raw gyro sample, `dt` seconds after the previous one
+    /// The bias is refined while the head is still
+    pub fn correct_gyro(
+        &mut self,
+        gyro: Vec3,
+        accel: Vec3,
+        dt: f32,
+        settings: &DriftCompensationSettings,
+    ) -> Vec3 {
+        if !settings.enabled {
+            self.still_for = 0.0;
+            self.stationary = false;
+            return gyro - self.bias;
+        }
+
+        let k = (dt / MOTION_TIME_CONSTANT_S).min(1.0);
+        self.gyro_mean += (gyro - self.gyro_mean) * k;
+        self.motion += ((gyro - self.gyro_mean).length() - self.motion) * k;
+
+        // Slow rotations above the drift threshold are motion, not bias; the
+        // unfiltered rate catches the start of a turn before the means follow
+        let drift_threshold = settings.drift_threshold_dps.to_radians();
+        let still = self.motion < settings.stationary_threshold_dps.to_radians()
+            && (self.gyro_mean - self.bias).length() < drift_threshold
+            && (gyro - self.bias).length() < 2.0 * drift_threshold
+            && (accel.length() - STANDARD_GRAVITY).abs() < STILL_ACCEL_TOLERANCE;
+        self.still_for = if still { self.still_for + dt } else { 0.0 };
+        self.stationary = still && self.still_for >= settings.stationary_time_s;
+
+        if self.stationary && settings.learn_gyro_bias {
+            self.bias += (gyro - self.bias) * (settings.correction_rate * dt).min(1.0);
+        }
+        gyro - self.bias
+    }
+
+    /// Remove yaw drift from the filter orientation, `dt` seconds after the previous call
+    pub fn correct_yaw(
+        &mut self,
+        orientation: Quat,
+        dt: f32,
+        settings: &DriftCompensationSettings,
+    ) -> Quat {
+        if !settings.enabled || !settings.yaw_correction {
+            self.still_yaw = None;
+            return orientation;
+        }
+
+        let yaw = orientation.to_euler(EulerRot::YXZ).0;
+        if self.stationary {
+            if let Some(previous) = self.still_yaw {
+                let change = wrap_angle(yaw - previous);
+                if dt > 0.0 {
+                    let k = (settings.correction_rate * dt).min(1.0);
+                    let limit = settings.drift_threshold_dps.to_radians();
+                    self.yaw_drift_rate = (self.yaw_drift_rate
+                        + (change / dt - self.yaw_drift_rate) * k)
+                        .clamp(-limit, limit);
+                }
+                self.yaw_offset = wrap_angle(self.yaw_offset - change);
+            }
+            self.still_yaw = Some(yaw);
+        } else {
+            self.still_yaw = None;
+            self.yaw_offset = wrap_angle(self.yaw_offset - self.yaw_drift_rate * dt);
+        }
+        Quat::from_rotation_y(self.yaw_offset) * orientation
+    }
+
+    /// Drop the accumulated yaw correction, e.g. to recenter
+    pub fn reset_yaw(&mut self) {
+        self.yaw_offset = 0.0;
+        self.still_yaw = None;
+    }
+}
+
+/// Hand the estimate saved in an earlier session to the tracking thread once
+pub fn restore_drift_estimate(
+    persistent_state: Res<PersistentAppState>,
+    command_channel: Option<Res<CommandChannel>>,
+    mut restored: Local<bool>,
+) {
+    if *restored {
+        return;
+    }
+    let Some(command_channel) = command_channel else {
+        return;
+    };
+    let calibration = &persistent_state.calibration_data;
+    let command = Command::RestoreDriftEstimate {
+        gyro_bias: calibration.gyro_bias,
+        yaw_drift_rate: calibration.yaw_drift_rate,
+        gyro_bias_points: calibration.gyro_bias_points.clone(),
+    };
+    if command_channel.0.try_send(command).is_ok() {
+        *restored = true;
+        if calibration.yaw_drift_rate != 0.0 {
+            info!(
+                "🧭 Restored yaw drift estimate: {:.2}°/min",
+                calibration.yaw_drift_rate.to_degrees() * 60.0
+            );
+        }
+    }
+}
+
+/// Save the drift estimate and the bias at the current temperature once they
+/// moved noticeably from the saved ones
+pub fn persist_drift_estimate(
+    estimate: Res<DriftEstimate>,
+    mut persistent_state: ResMut<PersistentAppState>,
+) {
+    if !estimate.is_changed() || estimate.is_added() {
+        return;
+    }
+    if let Some(point) = estimate.bias_point {
+        let points = &persistent_state.calibration_data.gyro_bias_points;
+        if thermal::point_changed(points, &point, PERSIST_TOLERANCE) {
+            thermal::upsert_point(
+                &mut persistent_state.calibration_data.gyro_bias_points,
+                point,
+            );
+        }
+    }
+
+    let calibration = &persistent_state.calibration_data;
+    let saved_bias = Vec3::from_array(calibration.gyro_bias);
+    if saved_bias.distance(estimate.gyro_bias) < PERSIST_TOLERANCE
+        && (calibration.yaw_drift_rate - estimate.yaw_drift_rate).abs() < PERSIST_TOLERANCE
+    {
+        return;
+    }
+    let calibration = &mut persistent_state.calibration_data;
+    calibration.gyro_bias = estimate.gyro_bias.to_array();
+    calibration.yaw_drift_rate = estimate.yaw_drift_rate;
+}
@@ -0,0 +1,362 @@
+//! Orientation filters
+//!
+//! Every filter turns gyroscope, accelerometer and (optionally) magnetometer
+//! samples into a head orientation behind the [`OrientationFilter`] trait, so
+//! the tracking thread can switch between them at runtime. Samples are in the
+//! IMU's right/up/back frame as delivered by ar-drivers: angular velocity in
+//! rad/s and acceleration in m/s², reading +1 g along up when at rest.
+//!
+//! Fusion AHRS and Madgwick are defined for a z-up frame, so their samples are
+//! rotated into forward/left/up and their orientation back.
+
+use bevy::prelude::*;
+use imu_fusion::{Fusion, FusionAhrsSettings, FusionConvention, FusionVector};
+
+use crate::state::schema::tracking::{
+    ComplementaryFilterSettings, FilterKind, FusionFilterSettings, MadgwickFilterSettings,
+    MahonyFilterSettings, OrientationFilterSettings,
+};
+
+/// Standard gravity in m/s²
+pub const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// Nominal IMU sample rate, used by Fusion for its recovery timing
+const SAMPLE_RATE_HZ: u32 = 1000;
+
+/// Sensor fusion turning IMU samples into an orientation
+pub trait OrientationFilter: Send {
+    fn kind(&self) -> FilterKind;
+
+    /// Feed one sample, `dt` seconds after the previous one
+    /// `mag` is `None` until the magnetometer reported
+    fn update(&mut self, gyro: Vec3, accel: Vec3, mag: Option<Vec3>, dt: f32);
+
+    /// Current orientation, rotating the head frame into the world frame
+    fn orientation(&self) -> Quat;
+
+    /// Forget the orientation, e.g. to recenter
+    fn reset(&mut self);
+
+    /// Apply new parameters of the same filter kind
+    fn configure(&mut self, settings: &OrientationFilterSettings);
+
+    /// True if yaw is corrected from magnetometer samples
+    fn uses_magnetometer(&self) -> bool {
+        false
+    }
+}
+
+/// Build the filter selected in the settings
+pub fn build_filter(settings: &OrientationFilterSettings) -> Box<dyn OrientationFilter> {
+    match settings.kind {
+        FilterKind::Fusion => Box::new(FusionFilter::new(&settings.fusion)),
+        FilterKind::Complementary => Box::new(ComplementaryFilter::new(&settings.complementary)),
+        FilterKind::Madgwick => Box::new(MadgwickFilter::new(&settings.madgwick)),
+        FilterKind::Mahony => Box::new(MahonyFilter::new(&settings.mahony)),
+    }
+}
+
+/// Replace or reconfigure `filter` to match the settings
+/// Switching filters starts the new one from the gravity direction
+pub fn apply_filter_settings(
+    filter: &mut Box<dyn OrientationFilter>,
+    settings: &OrientationFilterSettings,
+) {
+    if filter.kind() == settings.kind {
+        filter.configure(settings);
+    } else {
+        *filter = build_filter(settings);
+    }
+}
+
+/// Orientation with the measured up direction and zero yaw
+fn tilt_from_gravity(accel: Vec3) -> Option<Quat> {
+    let up = accel.try_normalize()?;
+    Some(Quat::from_rotation_arc(up, Vec3::Y))
+}
+
+/// Right/up/back to forward/left/up
+#[inline]
+fn to_flu(v: Vec3) -> Vec3 {
+    Vec3::new(-v.z, -v.x, v.y)
+}
+
+/// Forward/left/up to right/up/back
+#[inline]
+fn from_flu(v: Vec3) -> Vec3 {
+    Vec3::new(-v.y, v.z, -v.x)
+}
+
+#[inline]
+fn quat_to_flu(q: Quat) -> Quat {
+    let axis = to_flu(Vec3::new(q.x, q.y, q.z));
+    Quat::from_xyzw(axis.x, axis.y, axis.z, q.w)
+}
+
+#[inline]
+fn quat_from_flu(q: Quat) -> Quat {
+    let axis = from_flu(Vec3::new(q.x, q.y, q.z));
+    Quat::from_xyzw(axis.x, axis.y, axis.z, q.w)
+}
+
+#[inline]
+fn fusion_vector(v: Vec3) -> FusionVector {
+    FusionVector {
+        x: v.x,
+        y: v.y,
+        z: v.z,
+    }
+}
+
+/// Fusion AHRS, expecting deg/s and g
+pub struct FusionFilter {
+    fusion: Fusion,
+    settings: FusionFilterSettings,
+}
+
+impl FusionFilter {
+    pub fn new(settings: &FusionFilterSettings) -> Self {
+        Self {
+            fusion: Fusion::new(SAMPLE_RATE_HZ, Self::ahrs_settings(settings)),
+            settings: settings.clone(),
+        }
+    }
+
+    fn ahrs_settings(settings: &FusionFilterSettings) -> FusionAhrsSettings {
+        let mut ahrs = FusionAhrsSettings::new();
+        ahrs.convention = FusionConvention::NWU;
+        ahrs.gain = settings.gain;
+        ahrs.acc_rejection = settings.acc_rejection;
+        ahrs.mag_rejection = settings.mag_rejection;
+        ahrs.recovery_trigger_period = (settings.recovery_trigger_s * SAMPLE_RATE_HZ as f32) as _;
+        ahrs
+    }
+}
+
+impl OrientationFilter for FusionFilter {
+    fn kind(&self) -> FilterKind {
+        FilterKind::Fusion
+    }
+
+    fn update(&mut self, gyro: Vec3, accel: Vec3, mag: Option<Vec3>, dt: f32) {
+        let gyro = fusion_vector(to_flu(gyro) * 180.0 / std::f32::consts::PI);
+        let accel = fusion_vector(to_flu(accel) / STANDARD_GRAVITY);
+        match mag.filter(|_| self.settings.use_magnetometer) {
+            Some(mag) => {
+                self.fusion
+                    .update_by_duration_seconds(gyro, accel, fusion_vector(to_flu(mag)), dt)
+            }
+            None => self
+                .fusion
+                .update_no_mag_by_duration_seconds(gyro, accel, dt),
+        }
+    }
+
+    fn orientation(&self) -> Quat {
+        let q = self.fusion.quaternion();
+        quat_from_flu(Quat::from_xyzw(q.x, q.y, q.z, q.w))
+    }
+
+    fn reset(&mut self) {
+        self.fusion.ahrs.reset();
+    }
+
+    fn configure(&mut self, settings: &OrientationFilterSettings) {
+        // The AHRS only takes settings on construction
+        if settings.fusion != self.settings {
+            *self = Self::new(&settings.fusion);
+        }
+    }
+
+    fn uses_magnetometer(&self) -> bool {
+        self.settings.use_magnetometer
+    }
+}
+
+/// Gyro integration pulled towards the measured gravity direction
+pub struct ComplementaryFilter {
+    quat: Option<Quat>,
+    settings: ComplementaryFilterSettings,
+}
+
+impl ComplementaryFilter {
+    pub fn new(settings: &ComplementaryFilterSettings) -> Self {
+        Self {
+            quat: None,
+            settings: settings.clone(),
+        }
+    }
+}
+
+impl OrientationFilter for ComplementaryFilter {
+    fn kind(&self) -> FilterKind {
+        FilterKind::Complementary
+    }
+
+    fn update(&mut self, gyro: Vec3, accel: Vec3, _mag: Option<Vec3>, dt: f32) {
+        let Some(quat) = self.quat.or_else(|| tilt_from_gravity(accel)) else {
+            return;
+        };
+        let mut quat = (quat * Quat::from_scaled_axis(gyro * dt)).normalize();
+
+        // Only trust the accelerometer while it mostly measures gravity
+        let g = accel.length() / STANDARD_GRAVITY;
+        if g > 0.0 && (g - 1.0).abs() <= self.settings.accel_tolerance {
+            let measured_up = accel.normalize();
+            let estimated_up = quat.inverse() * Vec3::Y;
+            let correction = Quat::from_rotation_arc(measured_up, estimated_up);
+            let k = dt / (self.settings.time_constant_s + dt);
+            quat = (quat * Quat::IDENTITY.slerp(correction, k)).normalize();
+        }
+        self.quat = Some(quat);
+    }
+
+    fn orientation(&self) -> Quat {
+        self.quat.unwrap_or(Quat::IDENTITY)
+    }
+
+    fn reset(&mut self) {
+        self.quat = None;
+    }
+
+    fn configure(&mut self, settings: &OrientationFilterSettings) {
+        self.settings = settings.complementary.clone();
+    }
+}
+
+/// Madgwick gradient descent filter (gyroscope and accelerometer)
+pub struct MadgwickFilter {
+    /// Orientation in the forward/left/up frame
+    quat: Option<Quat>,
+    settings: MadgwickFilterSettings,
+}
+
+impl MadgwickFilter {
+    pub fn new(settings: &MadgwickFilterSettings) -> Self {
+        Self {
+            quat: None,
+            settings: settings.clone(),
+        }
+    }
+}
+
+impl OrientationFilter for MadgwickFilter {
+    fn kind(&self) -> FilterKind {
+        FilterKind::Madgwick
+    }
+
+    fn update(&mut self, gyro: Vec3, accel: Vec3, _mag: Option<Vec3>, dt: f32) {
+        let Some(q) = self
+            .quat
+            .or_else(|| tilt_from_gravity(accel).map(quat_to_flu))
+        else {
+            return;
+        };
+        let g = to_flu(gyro);
+        let (q0, q1, q2, q3) = (q.w, q.x, q.y, q.z);
+
+        // Rate of change from the gyroscope, as (w, x, y, z)
+        let mut q_dot = 0.5
+            * Vec4::new(
+                -q1 * g.x - q2 * g.y - q3 * g.z,
+                q0 * g.x + q2 * g.z - q3 * g.y,
+                q0 * g.y - q1 * g.z + q3 * g.x,
+                q0 * g.z + q1 * g.y - q2 * g.x,
+            );
+
+        if let Some(a) = to_flu(accel).try_normalize() {
+            // Gradient of the error between estimated and measured gravity
+            let s = Vec4::new(
+                4.0 * q0 * q2 * q2 + 2.0 * q2 * a.x + 4.0 * q0 * q1 * q1 - 2.0 * q1 * a.y,
+                4.0 * q1 * q3 * q3 - 2.0 * q3 * a.x + 4.0 * q0 * q0 * q1
+                    - 2.0 * q0 * a.y
+                    - 4.0 * q1
+                    + 8.0 * q1 * q1 * q1
+                    + 8.0 * q1 * q2 * q2
+                    + 4.0 * q1 * a.z,
+                4.0 * q0 * q0 * q2 + 2.0 * q0 * a.x + 4.0 * q2 * q3 * q3
+                    - 2.0 * q3 * a.y
+                    - 4.0 * q2
+                    + 8.0 * q2 * q1 * q1
+                    + 8.0 * q2 * q2 * q2
+                    + 4.0 * q2 * a.z,
+                4.0 * q1 * q1 * q3 - 2.0 * q1 * a.x + 4.0 * q2 * q2 * q3 - 2.0 * q2 * a.y,
+            );
+            if let Some(step) = s.try_normalize() {
+                q_dot -= self.settings.beta * step;
+            }
+        }
+
+        let q = Vec4::new(q0, q1, q2, q3) + q_dot * dt;
+        self.quat = Some(Quat::from_xyzw(q.y, q.z, q.w, q.x).normalize());
+    }
+
+    fn orientation(&self) -> Quat {
+        self.quat.map_or(Quat::IDENTITY, quat_from_flu)
+    }
+
+    fn reset(&mut self) {
+        self.quat = None;
+    }
+
+    fn configure(&mut self, settings: &OrientationFilterSettings) {
+        self.settings = settings.madgwick.clone();
+    }
+}
+
+/// Mahony complementary filter with integral gyro bias correction
+pub struct MahonyFilter {
+    quat: Option<Quat>,
+    /// Integral of the gravity error, the estimated gyro bias correction
+    integral: Vec3,
+    settings: MahonyFilterSettings,
+}
+
+impl MahonyFilter {
+    pub fn new(settings: &MahonyFilterSettings) -> Self {
+        Self {
+            quat: None,
+            integral: Vec3::ZERO,
+            settings: settings.clone(),
+        }
+    }
+}
+
+impl OrientationFilter for MahonyFilter {
+    fn kind(&self) -> FilterKind {
+        FilterKind::Mahony
+    }
+
+    fn update(&mut self, gyro: Vec3, accel: Vec3, _mag: Option<Vec3>, dt: f32) {
+        let Some(quat) = self.quat.or_else(|| tilt_from_gravity(accel)) else {
+            return;
+        };
+        let mut omega = gyro;
+
+        if let Some(measured_up) = accel.try_normalize() {
+            let estimated_up = quat.inverse() * Vec3::Y;
+            let error = measured_up.cross(estimated_up);
+            if self.settings.ki > 0.0 {
+                self.integral += self.settings.ki * error * dt;
+            } else {
+                self.integral = Vec3::ZERO;
+            }
+            omega += self.settings.kp * error + self.integral;
+        }
+
+        self.quat = Some((quat * Quat::from_scaled_axis(omega * dt)).normalize());
+    }
+
+    fn orientation(&self) -> Quat {
+        self.quat.unwrap_or(Quat::IDENTITY)
+    }
+
+    fn reset(&mut self) {
+        self.quat = None;
+        self.integral = Vec3::ZERO;
+    }
+
+    fn configure(&mut self, settings: &OrientationFilterSettings) {
+        self.settings = settings.mahony.clone();
+    }
+}
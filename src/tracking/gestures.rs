@@ -0,0 +1,269 @@
+//! Head gesture recognition
+//!
+//! Gestures are read from the head-frame gyro: a nod is a pitch swing down
+//! and back, a shake alternates yaw swings and a tilt rolls out and back.
+//! A swing is a rotation faster than the sensitivity threshold that clearly
+//! dominates the other axes; swings in alternating directions form one
+//! gesture until the head rests for a moment. A tap on the frame shows up as
+//! a short spike of the accelerometer against its low-passed value, two of
+//! them in quick succession are a double tap.
+//!
+//! Recognised gestures are sent to the main thread, where they trigger the
+//! actions bound to them.
+
+use bevy::prelude::*;
+
+use crate::input::buttons::InputActionEvent;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::input::{GestureInputSettings, HeadGesture};
+
+/// Pause after the last swing that ends a gesture, in seconds
+const SWING_GAP_S: f32 = 0.35;
+
+/// Factor a swing axis must exceed the other axes by
+const SWING_DOMINANCE: f32 = 2.0;
+
+/// Share of its threshold a rate or spike has to drop below to end a swing or tap
+const RELEASE: f32 = 0.5;
+
+/// Time constant of the accelerometer baseline taps are measured against, in seconds
+const TAP_BASELINE_S: f32 = 0.1;
+
+/// Shortest gap between two taps, shorter spikes are ringing of the first, in seconds
+const TAP_MIN_GAP_S: f32 = 0.08;
+
+/// Longest gap between the taps of a double tap, in seconds
+const DOUBLE_TAP_WINDOW_S: f32 = 0.5;
+
+/// Rotation rate a swing has to exceed, in rad/s
+/// Sensitivity 0 needs about 170°/s, sensitivity 1 about 57°/s
+pub fn swing_threshold(sensitivity: f32) -> f32 {
+    3.0 - 2.0 * sensitivity.clamp(0.0, 1.0)
+}
+
+/// Acceleration spike a tap has to exceed, in m/s²
+pub fn tap_threshold(sensitivity: f32) -> f32 {
+    30.0 - 20.0 * sensitivity.clamp(0.0, 1.0)
+}
+
+/// A recognised head gesture, forwarded from the tracking thread
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadGestureEvent(pub HeadGesture);
+
+/// Alternating swings about one axis
+#[derive(Debug, Clone, Copy, Default)]
+struct SwingSequence {
+    /// Swings so far, 0 while idle
+    count: u32,
+    /// Direction of the first swing
+    first_positive: bool,
+    /// Direction of the running or last swing
+    positive: bool,
+    /// The rate is still above the release threshold
+    swinging: bool,
+    started_s: f64,
+    /// Last time the rate was above the swing threshold
+    last_s: f64,
+}
+
+impl SwingSequence {
+    /// Feed the rate about this axis, returning the finished sequence once the head rests
+    /// as its swing count, first direction and duration
+    fn update(
+        &mut self,
+        rate: f32,
+        dominant: bool,
+        now_s: f64,
+        threshold: f32,
+    ) -> Option<(u32, bool, f32)> {
+        if rate.abs() > threshold && dominant {
+            let positive = rate > 0.0;
+            if self.count == 0 {
+                *self = Self {
+                    count: 1,
+                    first_positive: positive,
+                    positive,
+                    swinging: true,
+                    started_s: now_s,
+                    last_s: now_s,
+                };
+            } else if !self.swinging || positive != self.positive {
+                if positive != self.positive {
+                    self.count += 1;
+                }
+                self.positive = positive;
+                self.swinging = true;
+            }
+            self.last_s = now_s;
+        } else if rate.abs() < threshold * RELEASE {
+            self.swinging = false;
+        }
+
+        if self.count > 0 && !self.swinging && (now_s - self.last_s) as f32 > SWING_GAP_S {
+            let finished = (
+                self.count,
+                self.first_positive,
+                (self.last_s - self.started_s) as f32,
+            );
+            *self = Self::default();
+            return Some(finished);
+        }
+        None
+    }
+}
+
+/// Taps on the frame
+#[derive(Debug, Clone, Copy, Default)]
+struct TapDetector {
+    /// Low-passed acceleration
+    baseline: Option<Vec3>,
+    /// The spike of the last tap is still above the release threshold
+    in_spike: bool,
+    last_tap_s: Option<f64>,
+}
+
+impl TapDetector {
+    /// Feed an accelerometer sample, returning true when it completes a double tap
+    fn update(&mut self, accel: Vec3, dt: f32, now_s: f64, threshold: f32) -> bool {
+        let baseline = self.baseline.get_or_insert(accel);
+        let spike = accel.distance(*baseline);
+        *baseline += (accel - *baseline) * (dt / (TAP_BASELINE_S + dt));
+
+        if spike < threshold * RELEASE {
+            self.in_spike = false;
+        }
+        if spike <= threshold || self.in_spike {
+            return false;
+        }
+        self.in_spike = true;
+
+        match self.last_tap_s {
+            Some(last) if ((now_s - last) as f32) < TAP_MIN_GAP_S => false,
+            Some(last) if (now_s - last) as f32 <= DOUBLE_TAP_WINDOW_S => {
+                self.last_tap_s = None;
+                true
+            }
+            _ => {
+                self.last_tap_s = Some(now_s);
+                false
+            }
+        }
+    }
+}
+
+/// Recognises head gestures from the IMU stream
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GestureRecognizer {
+    /// Seconds of samples seen, in double precision so that hours of use
+    /// still resolve single samples
+    time_s: f64,
+    /// No gesture is recognised before this time
+    cooldown_until_s: f64,
+    pitch: SwingSequence,
+    yaw: SwingSequence,
+    roll: SwingSequence,
+    taps: TapDetector,
+}
+
+impl GestureRecognizer {
+    /// Feed a bias-corrected gyro sample and the accelerometer sample taken with it,
+    /// `dt` seconds after the previous one
+    pub fn update(
+        &mut self,
+        gyro: Vec3,
+        accel: Vec3,
+        dt: f32,
+        settings: &GestureInputSettings,
+    ) -> Option<HeadGesture> {
+        self.time_s += f64::from(dt);
+        let now = self.time_s;
+        let threshold = swing_threshold(settings.sensitivity);
+        let tapped = self
+            .taps
+            .update(accel, dt, now, tap_threshold(settings.sensitivity));
+
+        let rates = gyro.abs();
+        let pitch = self.pitch.update(
+            gyro.x,
+            rates.x > SWING_DOMINANCE * rates.y.max(rates.z),
+            now,
+            threshold,
+        );
+        let yaw = self.yaw.update(
+            gyro.y,
+            rates.y > SWING_DOMINANCE * rates.x.max(rates.z),
+            now,
+            threshold,
+        );
+        let roll = self.roll.update(
+            gyro.z,
+            rates.z > SWING_DOMINANCE * rates.x.max(rates.y),
+            now,
+            threshold,
+        );
+
+        // Motion during the cooldown does not count towards the next gesture
+        if now < self.cooldown_until_s {
+            self.forget_partial();
+            return None;
+        }
+        let min_s = settings.min_duration_ms as f32 / 1000.0;
+        let max_s = settings.max_duration_ms as f32 / 1000.0;
+        let timed = |duration_s: f32| (min_s..=max_s).contains(&duration_s);
+
+        let gesture = if tapped {
+            Some(HeadGesture::DoubleTap)
+        } else if yaw.is_some_and(|(swings, _, duration_s)| swings >= 3 && timed(duration_s)) {
+            Some(HeadGesture::Shake)
+        } else if pitch.is_some_and(|(swings, _, duration_s)| swings >= 2 && timed(duration_s)) {
+            Some(HeadGesture::Nod)
+        } else if let Some((_, first_positive, _)) =
+            roll.filter(|&(swings, _, duration_s)| swings >= 2 && timed(duration_s))
+        {
+            // Rolling about the backward axis, positive turns the top of the head left
+            Some(if first_positive {
+                HeadGesture::TiltLeft
+            } else {
+                HeadGesture::TiltRight
+            })
+        } else {
+            None
+        };
+
+        if gesture.is_some() {
+            self.cooldown_until_s = now + f64::from(settings.cooldown_ms) / 1000.0;
+            self.forget_partial();
+        }
+        gesture
+    }
+
+    fn forget_partial(&mut self) {
+        self.pitch = SwingSequence::default();
+        self.yaw = SwingSequence::default();
+        self.roll = SwingSequence::default();
+        self.taps.last_tap_s = None;
+    }
+
+    /// Forget partial gestures
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Trigger the actions bound to recognised head gestures
+pub fn trigger_gesture_actions(
+    mut gesture_events: EventReader<HeadGestureEvent>,
+    persistent_state: Res<PersistentAppState>,
+    mut action_events: EventWriter<InputActionEvent>,
+) {
+    let settings = &persistent_state.input_config.gesture_input;
+    for HeadGestureEvent(gesture) in gesture_events.read() {
+        match settings.action_for(*gesture) {
+            Some(action) => {
+                info!("🙆 Head gesture {}: {}", gesture.label(), action.label());
+                action_events.write(InputActionEvent(action));
+            }
+            None => debug!("Unbound head gesture {}", gesture.label()),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! IMU health monitoring
+//!
+//! Every sample is checked on the tracking thread: device timestamps for
+//! gaps and regressions, raw readings for saturation and stuck values, and
+//! the filtered orientation against the accelerometer and magnetometer for
+//! the disagreement the orientation filter rejects. Once a second the window
+//! is summarised as [`ImuTelemetry`] and rated as a [`TrackingQuality`];
+//! changes of the rating are raised as [`TrackingQualityEvent`]s. Rates
+//! and ranges are those of the connected model's [`ImuProfile`].
+
+use bevy::prelude::*;
+
+use super::filter::STANDARD_GRAVITY;
+use super::Command;
+use crate::driver::{DeviceInfo, ImuProfile};
+use crate::CommandChannel;
+
+/// Share of the full scale range a reading counts as saturated from
+const SATURATION_MARGIN: f32 = 0.98;
+
+/// Identical consecutive raw samples after which the sensor counts as stuck
+const STUCK_SAMPLES: u32 = 100;
+
+/// A sample interval this many nominal periods long means samples were dropped
+const GAP_FACTOR: f32 = 1.5;
+
+/// Longer intervals are a paused stream rather than dropped samples, in seconds
+const MAX_GAP_S: f32 = 1.0;
+
+/// Angle the measured gravity or heading may disagree with the orientation
+/// before the filter rejects it, matching the Fusion AHRS default
+const REJECTION_DEG: f32 = 10.0;
+
+/// Field strength deviation from its recent level counted as a disturbance
+const MAG_DISTURBANCE: f32 = 0.2;
+
+/// Time constant of the magnetic field reference, in seconds
+const MAG_REFERENCE_S: f32 = 10.0;
+
+/// Share of a window a condition has to last to be flagged
+const FLAG_SHARE: f32 = 0.5;
+
+/// Overall tracking quality, rated from the telemetry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrackingQuality {
+    #[default]
+    Good,
+    /// Tracking works but drifts or jitters more than usual
+    Degraded,
+    /// The IMU stream is unreliable
+    Poor,
+}
+
+impl TrackingQuality {
+    /// Human readable name for settings displays
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrackingQuality::Good => "Good",
+            TrackingQuality::Degraded => "Degraded",
+            TrackingQuality::Poor => "Poor",
+        }
+    }
+}
+
+/// IMU stream health over the last window, reported by the tracking thread
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImuTelemetry {
+    /// Samples per second of device time
+    pub sample_rate_hz: f32,
+    /// Rate the connected model streams at, in Hz
+    pub nominal_rate_hz: f32,
+    /// Standard deviation of the sample interval, in ms
+    pub dt_jitter_ms: f32,
+    /// Longest sample interval, in ms
+    pub max_dt_ms: f32,
+    /// Share of the expected samples that were missing
+    pub packet_loss: f32,
+    /// Samples missing since tracking started
+    pub dropped_packets: u64,
+    /// Samples whose timestamp did not advance since tracking started
+    pub timestamp_regressions: u64,
+    /// A gyro or accelerometer reading reached the full scale range
+    pub saturated: bool,
+    /// The raw readings stopped changing
+    pub stuck: bool,
+    /// The magnetic field strength left its recent level
+    pub mag_disturbed: bool,
+    /// Gravity disagreed with the orientation, e.g. under linear acceleration
+    pub accel_rejected: bool,
+    /// The magnetic heading disagreed with the fused yaw
+    pub mag_rejected: bool,
+}
+
+impl ImuTelemetry {
+    /// Rate the window
+    pub fn quality(&self) -> TrackingQuality {
+        if self.saturated
+            || self.stuck
+            || self.sample_rate_hz <= 0.0
+            || self.sample_rate_hz < 0.5 * self.nominal_rate_hz
+        {
+            TrackingQuality::Poor
+        } else if self.packet_loss > 0.02
+            || self.dt_jitter_ms > 2.0
+            || self.sample_rate_hz < 0.9 * self.nominal_rate_hz
+            || self.accel_rejected
+            || self.mag_disturbed
+            || self.mag_rejected
+        {
+            TrackingQuality::Degraded
+        } else {
+            TrackingQuality::Good
+        }
+    }
+}
+
+/// Raised when the tracking quality changes
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct TrackingQualityEvent {
+    pub quality: TrackingQuality,
+    /// Telemetry of the window that changed the rating
+    pub telemetry: ImuTelemetry,
+}
+
+/// Counters of the current reporting window
+#[derive(Debug, Clone, Copy, Default)]
+struct Window {
+    intervals: u32,
+    dt_sum: f32,
+    dt_sq_sum: f32,
+    max_dt: f32,
+    dropped: u32,
+    saturated: bool,
+    stuck: bool,
+    fused: u32,
+    accel_rejected: u32,
+    mag_samples: u32,
+    mag_disturbed: u32,
+    mag_fused: u32,
+    mag_rejected: u32,
+}
+
+/// Checks the IMU stream sample by sample
+#[derive(Debug, Clone, Default)]
+pub struct ImuHealthMonitor {
+    imu: ImuProfile,
+    window: Window,
+    last_timestamp: Option<u64>,
+    last_raw: Option<(Vec3, Vec3)>,
+    unchanged: u32,
+    dropped_total: u64,
+    regressions_total: u64,
+    /// Recent magnetic field strength
+    mag_strength: Option<f32>,
+    /// Recent horizontal direction of the field in the world frame
+    mag_heading: Option<Vec3>,
+}
+
+impl ImuHealthMonitor {
+    /// Rate the stream against another IMU, e.g. when other glasses connect
+    pub fn set_imu(&mut self, imu: ImuProfile) {
+        self.imu = imu;
+    }
+
+    /// Check a raw gyro and accelerometer sample with its device timestamp in µs
+    pub fn push_sample(&mut self, timestamp: u64, gyro: Vec3, accel: Vec3) {
+        match self.last_timestamp {
+            Some(last) if timestamp <= last => self.regressions_total += 1,
+            Some(last) => {
+                let dt = (timestamp - last) as f32 / 1_000_000.0;
+                if dt <= MAX_GAP_S {
+                    let window = &mut self.window;
+                    window.intervals += 1;
+                    window.dt_sum += dt;
+                    window.dt_sq_sum += dt * dt;
+                    window.max_dt = window.max_dt.max(dt);
+                    let periods = dt * self.imu.sample_rate_hz;
+                    if periods > GAP_FACTOR {
+                        let missing = periods.round() as u32 - 1;
+                        window.dropped += missing;
+                        self.dropped_total += missing as u64;
+                    }
+                }
+            }
+            None => {}
+        }
+        self.last_timestamp = Some(timestamp);
+
+        let gyro_full_scale = self.imu.gyro_range_dps.to_radians();
+        let accel_full_scale = self.imu.accel_range_g * STANDARD_GRAVITY;
+        if gyro.abs().max_element() >= SATURATION_MARGIN * gyro_full_scale
+            || accel.abs().max_element() >= SATURATION_MARGIN * accel_full_scale
+        {
+            self.window.saturated = true;
+        }
+
+        if self.last_raw == Some((gyro, accel)) {
+            self.unchanged += 1;
+            if self.unchanged >= STUCK_SAMPLES {
+                self.window.stuck = true;
+            }
+        } else {
+            self.unchanged = 0;
+        }
+        self.last_raw = Some((gyro, accel));
+    }
+
+    /// Check the filtered orientation against the calibrated accelerometer and
+    /// magnetometer samples it was updated with, `dt` seconds after the previous one
+    /// `mag_fused` tells whether the filter corrects yaw from the magnetometer
+    pub fn push_fusion(
+        &mut self,
+        orientation: Quat,
+        accel: Vec3,
+        mag: Option<Vec3>,
+        mag_fused: bool,
+        dt: f32,
+    ) {
+        let window = &mut self.window;
+        let rejection = REJECTION_DEG.to_radians();
+        if let Some(measured_up) = accel.try_normalize() {
+            window.fused += 1;
+            let expected_up = orientation.inverse() * Vec3::Y;
+            if measured_up.angle_between(expected_up) > rejection {
+                window.accel_rejected += 1;
+            }
+        }
+
+        let Some(field) = mag.map(|mag| orientation * mag) else {
+            return;
+        };
+        let follow = dt / (MAG_REFERENCE_S + dt);
+
+        let strength = field.length();
+        if strength <= f32::EPSILON {
+            return;
+        }
+        window.mag_samples += 1;
+        let reference = self.mag_strength.get_or_insert(strength);
+        if (strength - *reference).abs() > MAG_DISTURBANCE * *reference {
+            window.mag_disturbed += 1;
+        }
+        *reference += (strength - *reference) * follow;
+
+        if let Some(heading) = Vec3::new(field.x, 0.0, field.z).try_normalize() {
+            let reference = self.mag_heading.get_or_insert(heading);
+            if mag_fused {
+                window.mag_fused += 1;
+                if heading.angle_between(*reference) > rejection {
+                    window.mag_rejected += 1;
+                }
+            }
+            *reference = reference.lerp(heading, follow).normalize_or(heading);
+        }
+    }
+
+    /// Forget the last timestamp, e.g. when the device clock restarts after a reconnect
+    pub fn restart_clock(&mut self) {
+        self.last_timestamp = None;
+    }
+
+    /// Summarise the window since the previous report and start a new one
+    pub fn report(&mut self) -> ImuTelemetry {
+        let window = std::mem::take(&mut self.window);
+        let share = |count: u32, of: u32| of > 0 && count as f32 > FLAG_SHARE * of as f32;
+
+        let (sample_rate_hz, dt_jitter_ms) = if window.intervals > 0 && window.dt_sum > 0.0 {
+            let n = window.intervals as f32;
+            let mean = window.dt_sum / n;
+            let variance = (window.dt_sq_sum / n - mean * mean).max(0.0);
+            (n / window.dt_sum, variance.sqrt() * 1000.0)
+        } else {
+            (0.0, 0.0)
+        };
+        let expected = window.intervals + window.dropped;
+
+        ImuTelemetry {
+            sample_rate_hz,
+            nominal_rate_hz: self.imu.sample_rate_hz,
+            dt_jitter_ms,
+            max_dt_ms: window.max_dt * 1000.0,
+            packet_loss: if expected > 0 {
+                window.dropped as f32 / expected as f32
+            } else {
+                0.0
+            },
+            dropped_packets: self.dropped_total,
+            timestamp_regressions: self.regressions_total,
+            saturated: window.saturated,
+            stuck: window.stuck,
+            mag_disturbed: share(window.mag_disturbed, window.mag_samples),
+            accel_rejected: share(window.accel_rejected, window.fused),
+            mag_rejected: share(window.mag_rejected, window.mag_fused),
+        }
+    }
+}
+
+/// Tell the tracking thread which IMU the connected glasses have
+pub fn sync_imu_profile(
+    device_info: Option<Res<DeviceInfo>>,
+    command_channel: Option<Res<CommandChannel>>,
+    mut sent: Local<Option<ImuProfile>>,
+) {
+    let (Some(device_info), Some(command_channel)) = (device_info, command_channel) else {
+        return;
+    };
+    let imu = device_info.profile.imu;
+    if *sent == Some(imu) {
+        return;
+    }
+    if command_channel
+        .0
+        .try_send(Command::SetImuProfile(imu))
+        .is_ok()
+    {
+        *sent = Some(imu);
+    }
+}
+
+/// Log tracking quality changes
+pub fn log_tracking_quality(mut quality_events: EventReader<TrackingQualityEvent>) {
+    for event in quality_events.read() {
+        let telemetry = &event.telemetry;
+        match event.quality {
+            TrackingQuality::Good => info!("✅ Tracking quality recovered"),
+            quality => warn!(
+                "⚠️ Tracking quality {}: {:.0} Hz, jitter {:.2} ms, {:.1}% lost{}{}{}{}{}",
+                quality.label().to_lowercase(),
+                telemetry.sample_rate_hz,
+                telemetry.dt_jitter_ms,
+                telemetry.packet_loss * 100.0,
+                if telemetry.saturated {
+                    ", saturated"
+                } else {
+                    ""
+                },
+                if telemetry.stuck { ", stuck" } else { "" },
+                if telemetry.accel_rejected {
+                    ", accelerating"
+                } else {
+                    ""
+                },
+                if telemetry.mag_disturbed {
+                    ", magnetic disturbance"
+                } else {
+                    ""
+                },
+                if telemetry.mag_rejected {
+                    ", heading rejected"
+                } else {
+                    ""
+                },
+            ),
+        }
+    }
+}
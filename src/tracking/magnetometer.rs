@@ -0,0 +1,380 @@
+//! Magnetometer hard and soft-iron calibration
+//!
+//! Ferrous parts and currents in the glasses add a constant offset to the
+//! measured field (hard iron) and stretch the sphere the field traces when the
+//! head turns into an ellipsoid (soft iron). A guided figure-eight capture
+//! collects samples in as many directions as possible, an ellipsoid is fitted
+//! to them and the calibration maps it back onto a sphere of the local field
+//! strength.
+
+use anyhow::Result;
+use bevy::math::{DMat3, DVec3};
+use bevy::prelude::*;
+
+use crate::state::schema::calibration::CalibrationData;
+use crate::state::schema::core::PersistentAppState;
+
+/// Direction bins around the azimuth
+const AZIMUTH_BINS: usize = 8;
+
+/// Direction bins from pole to pole, equal area bands
+const ELEVATION_BINS: usize = 4;
+
+/// Bounding box center shift, relative to the box diagonal, after which the
+/// captured samples are sorted into direction bins again
+const REBIN_TOLERANCE: f32 = 0.05;
+
+/// Direction coverage needed to finish the capture
+pub const TARGET_COVERAGE: f32 = 0.9;
+
+/// Direction coverage below which no fit is attempted
+pub const MIN_COVERAGE: f32 = 0.5;
+
+/// Fewest samples an ellipsoid is fitted to
+pub const MIN_SAMPLES: usize = 100;
+
+/// Samples kept for the fit, the capture ends when full
+pub const MAX_SAMPLES: usize = 4000;
+
+/// Longest capture, in seconds
+pub const CAPTURE_TIMEOUT_S: f32 = 60.0;
+
+/// Relative fit error at which the quality drops to zero
+const MAX_FIT_ERROR: f32 = 0.1;
+
+/// Hard and soft-iron correction of magnetometer samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MagCalibration {
+    /// Offset removed from raw samples, in µT
+    pub hard_iron: Vec3,
+    /// Maps the offset-free ellipsoid onto a sphere
+    pub soft_iron: Mat3,
+    /// Radius of the corrected sphere, in µT
+    pub field_strength: f32,
+    /// RMS deviation of corrected samples from the sphere, relative to its radius
+    pub fit_error: f32,
+    /// Fraction of directions the fitted samples covered
+    pub coverage: f32,
+}
+
+impl Default for MagCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl MagCalibration {
+    /// Leaves samples unchanged
+    pub const IDENTITY: MagCalibration = MagCalibration {
+        hard_iron: Vec3::ZERO,
+        soft_iron: Mat3::IDENTITY,
+        field_strength: 0.0,
+        fit_error: 0.0,
+        coverage: 0.0,
+    };
+
+    /// Correct a raw sample
+    #[inline]
+    pub fn apply(&self, raw: Vec3) -> Vec3 {
+        self.soft_iron * (raw - self.hard_iron)
+    }
+
+    /// Fit quality from 0 to 1, combining fit error and direction coverage
+    pub fn quality(&self) -> f32 {
+        let fit = (1.0 - self.fit_error / MAX_FIT_ERROR).clamp(0.0, 1.0);
+        fit * self.coverage.clamp(0.0, 1.0)
+    }
+
+    /// Calibration stored in the persistent state
+    pub fn from_calibration_data(data: &CalibrationData) -> Self {
+        Self {
+            hard_iron: Vec3::from_array(data.mag_bias),
+            soft_iron: Mat3::from_cols_array_2d(&data.mag_soft_iron),
+            field_strength: data.mag_field_strength,
+            fit_error: 0.0,
+            coverage: 0.0,
+        }
+    }
+
+    /// Store into the persistent state
+    pub fn store(&self, data: &mut CalibrationData) {
+        data.mag_bias = self.hard_iron.to_array();
+        data.mag_soft_iron = self.soft_iron.to_cols_array_2d();
+        data.mag_field_strength = self.field_strength;
+        data.mag_fit_quality = self.quality();
+    }
+}
+
+/// Progress of a magnetometer calibration, reported by the tracking thread
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub enum MagCalibrationStatus {
+    #[default]
+    Idle,
+    Capturing {
+        samples: usize,
+        coverage: f32,
+        elapsed_s: f32,
+    },
+    Done(MagCalibration),
+    Failed,
+}
+
+/// Direction bin of a sample seen from `center`, if it points anywhere
+fn direction_bin(sample: Vec3, center: Vec3) -> Option<usize> {
+    let direction = (sample - center).try_normalize()?;
+    let azimuth = direction.z.atan2(direction.x) / std::f32::consts::TAU + 0.5;
+    // Equal area bands are equal steps in the sine of the elevation
+    let elevation = (direction.y + 1.0) * 0.5;
+    let a = ((azimuth * AZIMUTH_BINS as f32) as usize).min(AZIMUTH_BINS - 1);
+    let e = ((elevation * ELEVATION_BINS as f32) as usize).min(ELEVATION_BINS - 1);
+    Some(e * AZIMUTH_BINS + a)
+}
+
+/// Samples collected during the figure-eight capture
+#[derive(Debug, Clone, Default)]
+pub struct MagCapture {
+    samples: Vec<Vec3>,
+    min: Vec3,
+    max: Vec3,
+    /// Directions seen from `binned_center`, updated as samples arrive
+    bins: [bool; AZIMUTH_BINS * ELEVATION_BINS],
+    binned_center: Vec3,
+}
+
+impl MagCapture {
+    pub fn push(&mut self, raw: Vec3) {
+        if self.samples.len() >= MAX_SAMPLES {
+            return;
+        }
+        if self.samples.is_empty() {
+            self.min = raw;
+            self.max = raw;
+        } else {
+            self.min = self.min.min(raw);
+            self.max = self.max.max(raw);
+        }
+        self.samples.push(raw);
+
+        // Sort everything again only once the center moved noticeably,
+        // which stops happening after the first turns
+        let center = (self.min + self.max) * 0.5;
+        if center.distance(self.binned_center) > REBIN_TOLERANCE * (self.max - self.min).length() {
+            self.binned_center = center;
+            self.bins = Default::default();
+            for sample in &self.samples {
+                if let Some(bin) = direction_bin(*sample, center) {
+                    self.bins[bin] = true;
+                }
+            }
+        } else if let Some(bin) = direction_bin(raw, self.binned_center) {
+            self.bins[bin] = true;
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Fraction of field directions seen so far, relative to the bounding box center
+    pub fn coverage(&self) -> f32 {
+        self.bins.iter().filter(|&&hit| hit).count() as f32 / self.bins.len() as f32
+    }
+
+    /// True once enough directions or samples were collected
+    pub fn is_complete(&self) -> bool {
+        self.samples.len() >= MAX_SAMPLES
+            || (self.samples.len() >= MIN_SAMPLES && self.coverage() >= TARGET_COVERAGE)
+    }
+
+    /// Fit a calibration to the captured samples
+    pub fn fit(&self) -> Result<MagCalibration> {
+        let coverage = self.coverage();
+        if self.samples.len() < MIN_SAMPLES {
+            anyhow::bail!("Too few magnetometer samples: {}", self.samples.len());
+        }
+        if coverage < MIN_COVERAGE {
+            anyhow::bail!("Magnetometer samples cover too few directions: {coverage:.2}");
+        }
+        let mut calibration = fit_ellipsoid(&self.samples)?;
+        calibration.coverage = coverage;
+        Ok(calibration)
+    }
+}
+
+/// Least squares ellipsoid fit, mapping the ellipsoid onto a sphere of the same volume
+pub fn fit_ellipsoid(samples: &[Vec3]) -> Result<MagCalibration> {
+    if samples.len() < 9 {
+        anyhow::bail!("An ellipsoid fit needs at least 9 samples");
+    }
+
+    // Fit in coordinates normalized around the bounding box for conditioning
+    let (min, max) = samples
+        .iter()
+        .fold((samples[0], samples[0]), |(min, max), s| {
+            (min.min(*s), max.max(*s))
+        });
+    let offset = ((min + max) * 0.5).as_dvec3();
+    let scale = ((max - min) * 0.5).max_element() as f64;
+    if scale <= f64::EPSILON {
+        anyhow::bail!("Magnetometer samples do not vary");
+    }
+
+    // a x² + b y² + c z² + 2d xy + 2e xz + 2f yz + 2g x + 2h y + 2i z = 1
+    let mut normal = [[0.0f64; 9]; 9];
+    let mut rhs = [0.0f64; 9];
+    for sample in samples {
+        let p = (sample.as_dvec3() - offset) / scale;
+        let row = [
+            p.x * p.x,
+            p.y * p.y,
+            p.z * p.z,
+            2.0 * p.x * p.y,
+            2.0 * p.x * p.z,
+            2.0 * p.y * p.z,
+            2.0 * p.x,
+            2.0 * p.y,
+            2.0 * p.z,
+        ];
+        for i in 0..9 {
+            for j in 0..9 {
+                normal[i][j] += row[i] * row[j];
+            }
+            rhs[i] += row[i];
+        }
+    }
+    let v = solve(normal, rhs).ok_or_else(|| anyhow::anyhow!("Ellipsoid fit is degenerate"))?;
+
+    let quadric = DMat3::from_cols(
+        DVec3::new(v[0], v[3], v[4]),
+        DVec3::new(v[3], v[1], v[5]),
+        DVec3::new(v[4], v[5], v[2]),
+    );
+    let linear = DVec3::new(v[6], v[7], v[8]);
+    if quadric.determinant().abs() <= f64::EPSILON {
+        anyhow::bail!("Ellipsoid fit is degenerate");
+    }
+    let center = -(quadric.inverse() * linear);
+    let k = 1.0 + center.dot(quadric * center);
+    // (p - center)ᵀ M (p - center) = 1 in sample units
+    let shape = quadric / (k * scale * scale);
+
+    let (eigenvalues, eigenvectors) = symmetric_eigen(shape);
+    if eigenvalues.min_element() <= 0.0 {
+        anyhow::bail!("Magnetometer samples do not form an ellipsoid");
+    }
+    let radius = (eigenvalues.x * eigenvalues.y * eigenvalues.z).powf(-1.0 / 6.0);
+    let sqrt_shape = eigenvectors
+        * DMat3::from_diagonal(DVec3::new(
+            eigenvalues.x.sqrt(),
+            eigenvalues.y.sqrt(),
+            eigenvalues.z.sqrt(),
+        ))
+        * eigenvectors.transpose();
+
+    let hard_iron = (offset + center * scale).as_vec3();
+    let soft_iron = (sqrt_shape * radius).as_mat3();
+    let field_strength = radius as f32;
+
+    let squared_error = samples
+        .iter()
+        .map(|sample| {
+            let deviation = (soft_iron * (*sample - hard_iron)).length() - field_strength;
+            deviation * deviation
+        })
+        .sum::<f32>();
+    let fit_error = (squared_error / samples.len() as f32).sqrt() / field_strength;
+
+    Ok(MagCalibration {
+        hard_iron,
+        soft_iron,
+        field_strength,
+        fit_error,
+        coverage: 0.0,
+    })
+}
+
+/// Gaussian elimination with partial pivoting
+fn solve<const N: usize>(mut a: [[f64; N]; N], mut b: [f64; N]) -> Option<[f64; N]> {
+    for col in 0..N {
+        let pivot = (col..N).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col];
+        for row in col + 1..N {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot_value) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; N];
+    for row in (0..N).rev() {
+        let sum: f64 = (row + 1..N).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix, by Jacobi rotations
+fn symmetric_eigen(matrix: DMat3) -> (DVec3, DMat3) {
+    let mut a = matrix.to_cols_array_2d();
+    let mut v = DMat3::IDENTITY.to_cols_array_2d();
+    for _ in 0..50 {
+        // Largest off-diagonal element
+        let (p, q) = [(0, 1), (0, 2), (1, 2)]
+            .into_iter()
+            .max_by(|&(i, j), &(k, l)| a[i][j].abs().total_cmp(&a[k][l].abs()))
+            .unwrap_or((0, 1));
+        if a[p][q].abs() < 1e-15 {
+            break;
+        }
+        let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+        let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+        let c = 1.0 / (t * t + 1.0).sqrt();
+        let s = t * c;
+        // A ← Jᵀ A J, V ← V J
+        for row in a.iter_mut().chain(v.iter_mut()) {
+            let (rp, rq) = (row[p], row[q]);
+            row[p] = c * rp - s * rq;
+            row[q] = s * rp + c * rq;
+        }
+        let (row_p, row_q) = (a[p], a[q]);
+        a[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+        a[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+    }
+    let eigenvectors = DMat3::from_cols_array_2d(&v).transpose();
+    (DVec3::new(a[0][0], a[1][1], a[2][2]), eigenvectors)
+}
+
+/// Persist a finished magnetometer calibration
+pub fn store_mag_calibration(
+    status: Res<MagCalibrationStatus>,
+    mut persistent_state: ResMut<PersistentAppState>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    match *status {
+        MagCalibrationStatus::Done(calibration) => {
+            info!(
+                "🧲 Magnetometer calibrated: offset {:?} µT, field {:.1} µT, quality {:.2}",
+                calibration.hard_iron,
+                calibration.field_strength,
+                calibration.quality()
+            );
+            calibration.store(&mut persistent_state.calibration_data);
+        }
+        MagCalibrationStatus::Failed => warn!("🧲 Magnetometer calibration failed"),
+        _ => {}
+    }
+}
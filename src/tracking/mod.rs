@@ -0,0 +1,678 @@
+pub mod accelerometer;
+pub mod calibration;
+pub mod drift;
+pub mod filter;
+pub mod gestures;
+pub mod health;
+pub mod magnetometer;
+pub mod pipeline;
+pub mod recenter;
+pub mod smoothing;
+pub mod thermal;
+
+use crate::driver::connection::GlassesConnectionEvent;
+use crate::driver::recording;
+use crate::driver::{
+    hardware_source, persisted_calibration_applies, DeviceCommand, GlassesSource, ImuEvent,
+    ImuProfile,
+};
+use crate::state::schema::calibration::GyroBiasPoint;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::input::{GestureInputSettings, HeadGesture};
+use crate::state::schema::tracking::{
+    DriftCompensationSettings, OrientationFilterSettings, RecenterSettings, SmoothingSettings,
+};
+use crate::CommandChannel;
+use anyhow::Result;
+use bevy::prelude::*;
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use instant::Instant;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Default, Resource)]
+pub struct Orientation {
+    pub quat: Quat,
+}
+
+#[derive(Copy, Clone, Resource)]
+pub enum CalibrationState {
+    Idle,
+    Calibrating(calibration::CalibrationProgress),
+    Calibrated {
+        gyro_bias: [f32; 3],
+        accel_bias: [f32; 3],
+        mag_bias: [f32; 3],
+    },
+}
+
+impl Default for CalibrationState {
+    fn default() -> Self {
+        CalibrationState::Idle
+    }
+}
+#[derive(Clone)]
+pub enum Command {
+    /// Turn the view back to the current head yaw
+    Recenter,
+    /// Retune recentering and auto-recenter
+    SetRecenter(RecenterSettings),
+    SetRollLock(bool),
+    StartCalibration,
+    /// Abort a running calibration and keep the previous biases
+    CancelCalibration,
+    SetBrightness(u8),
+    /// Record the raw glasses event stream to the given file
+    StartRecording(PathBuf),
+    StopRecording,
+    /// Apply a stored gyro bias and restart fusion, e.g. for glasses with a calibration record
+    RestoreCalibration {
+        gyro_bias: [f32; 3],
+    },
+    /// Restart fusion keeping the biases in use, e.g. after the same glasses reconnect
+    Restart,
+    /// Switch or retune the orientation filter
+    SetOrientationFilter(OrientationFilterSettings),
+    /// Retune the anti-jitter smoothing
+    SetSmoothing(SmoothingSettings),
+    /// Start the figure-eight capture for a magnetometer calibration
+    StartMagCalibration,
+    /// Apply a hard and soft-iron magnetometer calibration
+    SetMagCalibration(magnetometer::MagCalibration),
+    /// Start the six-position accelerometer capture, optionally solving cross-axis terms
+    StartAccelCalibration {
+        cross_axis: bool,
+    },
+    /// Apply an accelerometer offset, scale and cross-axis calibration
+    SetAccelCalibration(accelerometer::AccelCalibration),
+    /// Retune gyro bias refinement and yaw drift correction
+    SetDriftCompensation(DriftCompensationSettings),
+    /// Enable or retune head gesture recognition
+    SetGestures(GestureInputSettings),
+    /// Resume from the drift estimate and temperature model of an earlier session
+    RestoreDriftEstimate {
+        gyro_bias: [f32; 3],
+        yaw_drift_rate: f32,
+        gyro_bias_points: Vec<GyroBiasPoint>,
+    },
+    /// Rate the IMU stream against the connected model's sample rate and ranges
+    SetImuProfile(ImuProfile),
+    /// Drop the gyro calibration in use, e.g. when glasses without a
+    /// calibration record replace a calibrated pair
+    ResetCalibration,
+}
+
+/// Poses kept while the app does not read them, about a quarter second at 1 kHz
+pub const POSE_QUEUE_CAPACITY: usize = 256;
+
+/// Head pose measured by the tracking thread
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PoseSample {
+    pub quat: Quat,
+    /// Angular velocity in the head frame, in rad/s
+    pub angular_velocity: Vec3,
+    /// When the broker received the latest IMU sample fused into the pose
+    pub timestamp: Instant,
+}
+
+/// Latest poses from the tracking thread
+/// Publishing never blocks: once the app falls behind the oldest pose is
+/// dropped, so tracking never waits on the render loop
+#[derive(Resource, Clone)]
+pub struct PoseQueue {
+    tx: Sender<PoseSample>,
+    rx: Receiver<PoseSample>,
+}
+
+impl Default for PoseQueue {
+    fn default() -> Self {
+        let (tx, rx) = bounded(POSE_QUEUE_CAPACITY);
+        Self { tx, rx }
+    }
+}
+
+impl PoseQueue {
+    /// Queue a pose, dropping the oldest one if the queue is full
+    pub fn publish(&self, pose: PoseSample) {
+        let mut pose = pose;
+        loop {
+            match self.tx.try_send(pose) {
+                Err(TrySendError::Full(rejected)) => {
+                    pose = rejected;
+                    let _ = self.rx.try_recv();
+                }
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+            }
+        }
+    }
+
+    /// Poses queued since the last call, oldest first
+    pub fn drain(&self) -> impl Iterator<Item = PoseSample> + '_ {
+        self.rx.try_iter()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Data {
+    CalState(CalibrationState),
+    /// Result of a finished gyro calibration, to be persisted
+    GyroCalibration(calibration::GyroCalibration),
+    /// Periodic gyro bias and yaw drift estimate
+    Drift(drift::DriftEstimate),
+    /// Magnetometer calibration progress and result
+    MagCalibration(magnetometer::MagCalibrationStatus),
+    /// Accelerometer calibration progress and result
+    AccelCalibration(accelerometer::AccelCalibrationStatus),
+    /// A recognised head gesture
+    Gesture(HeadGesture),
+    /// IMU stream health of the last second
+    Telemetry(health::ImuTelemetry),
+    /// The tracking quality changed
+    TrackingQuality(health::TrackingQuality),
+}
+
+/// Registers forwarding of the persisted tracking settings and calibrations to the tracking thread
+pub struct TrackingSettingsPlugin;
+
+impl Plugin for TrackingSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GlassesConnectionEvent>()
+            .add_event::<gestures::HeadGestureEvent>()
+            .add_event::<crate::input::buttons::InputActionEvent>()
+            .add_event::<health::TrackingQualityEvent>()
+            .init_resource::<calibration::LatestGyroCalibration>()
+            .init_resource::<drift::DriftEstimate>()
+            .init_resource::<magnetometer::MagCalibrationStatus>()
+            .init_resource::<accelerometer::AccelCalibrationStatus>()
+            .init_resource::<health::ImuTelemetry>()
+            .add_systems(
+                Update,
+                (
+                    calibration::store_gyro_calibration.run_if(hardware_source),
+                    // Another pair's record wins over the session's biases
+                    calibration::apply_calibration_record
+                        .after(crate::driver::connection::restore_state_on_reconnect)
+                        .run_if(hardware_source),
+                    sync_to_tracking(
+                        |state| state.tracking.filter.clone(),
+                        Command::SetOrientationFilter,
+                    ),
+                    sync_to_tracking(
+                        |state| state.tracking.smoothing.clone(),
+                        Command::SetSmoothing,
+                    ),
+                    sync_to_tracking(
+                        |state| state.tracking.recenter.clone(),
+                        Command::SetRecenter,
+                    ),
+                    sync_to_tracking(
+                        |state| state.tracking.drift.clone(),
+                        Command::SetDriftCompensation,
+                    ),
+                    drift::restore_drift_estimate.run_if(persisted_calibration_applies),
+                    drift::persist_drift_estimate.run_if(hardware_source),
+                    sync_to_tracking(
+                        |state| {
+                            magnetometer::MagCalibration::from_calibration_data(
+                                &state.calibration_data,
+                            )
+                        },
+                        Command::SetMagCalibration,
+                    )
+                    .run_if(persisted_calibration_applies),
+                    magnetometer::store_mag_calibration.run_if(hardware_source),
+                    sync_to_tracking(
+                        |state| {
+                            accelerometer::AccelCalibration::from_calibration_data(
+                                &state.calibration_data,
+                            )
+                        },
+                        Command::SetAccelCalibration,
+                    )
+                    .run_if(persisted_calibration_applies),
+                    accelerometer::store_accel_calibration.run_if(hardware_source),
+                    sync_to_tracking(
+                        |state| state.input_config.gesture_input.clone(),
+                        Command::SetGestures,
+                    ),
+                    gestures::trigger_gesture_actions,
+                    health::sync_imu_profile,
+                    health::log_tracking_quality,
+                ),
+            );
+    }
+}
+
+/// System sending the part of the persisted state picked by `select` to the
+/// tracking thread whenever it changes
+/// A full command channel is retried on the next frame
+pub fn sync_to_tracking<T, S, C>(
+    select: S,
+    make_command: C,
+) -> impl FnMut(Res<PersistentAppState>, Option<Res<CommandChannel>>, Local<Option<T>>)
+where
+    T: Clone + PartialEq + Send + Sync + 'static,
+    S: Fn(&PersistentAppState) -> T + Send + Sync + 'static,
+    C: Fn(T) -> Command + Send + Sync + 'static,
+{
+    move |persistent_state: Res<PersistentAppState>,
+          command_channel: Option<Res<CommandChannel>>,
+          mut sent: Local<Option<T>>| {
+        let value = select(&persistent_state);
+        if sent.as_ref() == Some(&value) {
+            return;
+        }
+        let Some(command_channel) = command_channel else {
+            return;
+        };
+        if command_channel
+            .0
+            .try_send(make_command(value.clone()))
+            .is_ok()
+        {
+            *sent = Some(value);
+        }
+    }
+}
+
+/// Send commands queued for the tracking thread in order
+/// Those a full command channel doesn't take stay queued for the next frame
+pub fn send_queued(sender: &Sender<Command>, queued: &mut Vec<Command>) {
+    let mut commands = std::mem::take(queued).into_iter();
+    for command in commands.by_ref() {
+        match sender.try_send(command) {
+            Ok(()) => {}
+            Err(TrySendError::Full(command)) => {
+                queued.push(command);
+                break;
+            }
+            Err(TrySendError::Disconnected(_)) => return,
+        }
+    }
+    queued.extend(commands);
+}
+
+/// Spawn the IMU tracking thread consuming samples from the device broker
+pub fn spawn_imu_tracking(
+    source: GlassesSource,
+    imu_rx: Receiver<ImuEvent>,
+    rx_command: Receiver<Command>,
+    tx_data: Sender<Data>,
+    poses: PoseQueue,
+    device_tx: Sender<DeviceCommand>,
+) -> std::io::Result<JoinHandle<()>> {
+    std::thread::Builder::new()
+        .name("imu-tracking".into())
+        .spawn(move || {
+            if let Err(e) =
+                run_imu_tracking(&source, &imu_rx, &rx_command, &tx_data, &poses, &device_tx)
+            {
+                error!("IMU tracking thread failed: {}", e);
+            }
+        })
+}
+
+/// Blocking IMU tracking loop: orientation filtering, smoothing and calibration
+pub fn run_imu_tracking(
+    source: &GlassesSource,
+    imu_rx: &Receiver<ImuEvent>,
+    rx_command: &Receiver<Command>,
+    tx_data: &Sender<Data>,
+    poses: &PoseQueue,
+    device_tx: &Sender<DeviceCommand>,
+) -> Result<()> {
+    let mut pipeline = pipeline::TrackingPipeline::new(&OrientationFilterSettings::default());
+    let mut accel_capture: Option<(accelerometer::AccelCapture, bool)> = None;
+    let mut last_accel_report = Instant::now();
+    let mut mag_capture: Option<(magnetometer::MagCapture, Instant)> = None;
+    let mut last_mag_report = Instant::now();
+    let mut gesture_settings = GestureInputSettings::default();
+    let mut gestures = gestures::GestureRecognizer::default();
+    let mut last_drift_report = Instant::now();
+    let mut health = health::ImuHealthMonitor::default();
+    let mut tracking_quality = health::TrackingQuality::default();
+    let mut last_health_report = Instant::now();
+    let mut cal_state = CalibrationState::default();
+    // Running capture with the state to return to if it is cancelled
+    let mut bias_capture: Option<(calibration::BiasCapture, CalibrationState)> = None;
+    let mut last_cal_report = Instant::now();
+
+    // Replays run with the biases that were in use when they were recorded,
+    // the app sends them no calibrations from its persisted state
+    if let GlassesSource::Replay { path, .. } = source {
+        match recording::read_header(path) {
+            Ok(header) => {
+                let calibration = &header.calibration;
+                cal_state = CalibrationState::Calibrated {
+                    gyro_bias: calibration.gyro_bias,
+                    accel_bias: calibration.accel_bias,
+                    mag_bias: calibration.mag_bias,
+                };
+                pipeline.apply_recorded_calibration(calibration);
+                let _ = tx_data.send(Data::CalState(cal_state));
+            }
+            Err(e) => error!("❌ Failed to read recording header: {}", e),
+        }
+    }
+
+    // Allow recording from launch for bug reports
+    if let Ok(path) = std::env::var("XREAL_RECORD") {
+        let _ = device_tx.try_send(DeviceCommand::StartRecording {
+            path: PathBuf::from(path),
+            calibration: pipeline.recorded_calibration(),
+        });
+    }
+
+    loop {
+        // Handle every command queued by the main thread
+        while let Ok(cmd) = rx_command.try_recv() {
+            match cmd {
+                Command::Recenter => pipeline.recenter.request(),
+                Command::SetRecenter(settings) => pipeline.recenter_settings = settings,
+                Command::SetRollLock(b) => pipeline.roll_lock = b,
+                Command::StartCalibration => {
+                    info!("📐 Gyro calibration started, keep the glasses still");
+                    let previous = bias_capture
+                        .take()
+                        .map_or(cal_state, |(_, previous)| previous);
+                    let capture = calibration::BiasCapture::default();
+                    cal_state = CalibrationState::Calibrating(capture.progress());
+                    bias_capture = Some((capture, previous));
+                    last_cal_report = Instant::now();
+                    if tx_data.send(Data::CalState(cal_state)).is_err() {
+                        return Err(anyhow::anyhow!("Failed to send cal state"));
+                    }
+                }
+                Command::CancelCalibration => {
+                    if let Some((_, previous)) = bias_capture.take() {
+                        info!("📐 Gyro calibration cancelled");
+                        cal_state = previous;
+                        if tx_data.send(Data::CalState(cal_state)).is_err() {
+                            return Err(anyhow::anyhow!("Failed to send cal state"));
+                        }
+                    }
+                }
+                Command::SetBrightness(_) => {
+                    // Brightness control not available in ar-drivers API
+                }
+                Command::StartRecording(path) => {
+                    let _ = device_tx.try_send(DeviceCommand::StartRecording {
+                        path,
+                        calibration: pipeline.recorded_calibration(),
+                    });
+                }
+                Command::StopRecording => {
+                    let _ = device_tx.try_send(DeviceCommand::StopRecording);
+                }
+                Command::RestoreCalibration { gyro_bias } => {
+                    cal_state = CalibrationState::Calibrated {
+                        gyro_bias,
+                        accel_bias: pipeline.accel_calibration.offset.to_array(),
+                        mag_bias: pipeline.mag_calibration.hard_iron.to_array(),
+                    };
+                    pipeline.drift.set_bias(Vec3::from_array(gyro_bias));
+                    pipeline.restart();
+                    health.restart_clock();
+                    if tx_data.send(Data::CalState(cal_state)).is_err() {
+                        return Err(anyhow::anyhow!("Failed to send cal state"));
+                    }
+                }
+                Command::Restart => {
+                    pipeline.restart();
+                    health.restart_clock();
+                }
+                Command::SetOrientationFilter(settings) => {
+                    info!("🧭 Orientation filter: {}", settings.kind.label());
+                    pipeline.set_filter(&settings);
+                }
+                Command::SetSmoothing(settings) => pipeline.smoothing = settings,
+                Command::StartMagCalibration => {
+                    info!("🧲 Magnetometer calibration started");
+                    mag_capture = Some((magnetometer::MagCapture::default(), Instant::now()));
+                }
+                Command::SetMagCalibration(calibration) => pipeline.mag_calibration = calibration,
+                Command::StartAccelCalibration { cross_axis } => {
+                    info!("📐 Accelerometer calibration started");
+                    accel_capture = Some((accelerometer::AccelCapture::default(), cross_axis));
+                    last_accel_report = Instant::now();
+                    let status = accelerometer::AccelCapture::default().status();
+                    if tx_data.send(Data::AccelCalibration(status)).is_err() {
+                        return Err(anyhow::anyhow!("Failed to send accelerometer calibration"));
+                    }
+                }
+                Command::SetAccelCalibration(calibration) => {
+                    pipeline.accel_calibration = calibration
+                }
+                Command::SetDriftCompensation(settings) => pipeline.drift_settings = settings,
+                Command::SetGestures(settings) => {
+                    if !settings.enabled {
+                        gestures.reset();
+                    }
+                    gesture_settings = settings;
+                }
+                Command::SetImuProfile(imu) => health.set_imu(imu),
+                Command::ResetCalibration => {
+                    // A capture still running was measuring the previous glasses
+                    bias_capture = None;
+                    cal_state = CalibrationState::Idle;
+                    pipeline.drift.set_bias(Vec3::ZERO);
+                    pipeline.restart();
+                    health.restart_clock();
+                    if tx_data.send(Data::CalState(cal_state)).is_err() {
+                        return Err(anyhow::anyhow!("Failed to send cal state"));
+                    }
+                }
+                Command::RestoreDriftEstimate {
+                    gyro_bias,
+                    yaw_drift_rate,
+                    gyro_bias_points,
+                } => {
+                    // A calibration of this session beats the saved bias
+                    if !matches!(cal_state, CalibrationState::Calibrated { .. }) {
+                        pipeline.drift.set_bias(Vec3::from_array(gyro_bias));
+                    }
+                    pipeline.drift.set_yaw_drift_rate(yaw_drift_rate);
+                    pipeline.set_temperature_model(thermal::TemperatureBiasModel::from_points(
+                        &gyro_bias_points,
+                    ));
+                }
+            }
+        }
+
+        // Block briefly for the next sample, then drain whatever else is queued
+        let mut events = Vec::with_capacity(16);
+        match imu_rx.recv_timeout(Duration::from_millis(10)) {
+            Ok(event) => events.push(event),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                info!("🛑 Device broker stopped, ending IMU tracking");
+                return Ok(());
+            }
+        }
+        events.extend(imu_rx.try_iter().take(15));
+
+        let mut received = None;
+        for event in events {
+            match event {
+                ImuEvent::AccGyro {
+                    accelerometer,
+                    gyroscope,
+                    timestamp,
+                    received: at,
+                } => {
+                    received = Some(at);
+                    health.push_sample(timestamp, gyroscope, accelerometer);
+                    let sample = pipeline.push_imu(accelerometer, gyroscope, timestamp);
+                    if let Some((capture, _)) = &mut bias_capture {
+                        capture.push(gyroscope, sample.accel, sample.dt);
+                    }
+
+                    if let Some((capture, _)) = &mut accel_capture {
+                        if let Some(pose) = capture.push(accelerometer, sample.gyro) {
+                            info!("📐 Captured accelerometer pose: {}", pose.label());
+                        }
+                    } else if gesture_settings.enabled {
+                        // Turning the glasses through the calibration poses is no gesture
+                        if let Some(gesture) =
+                            gestures.update(sample.gyro, sample.accel, sample.dt, &gesture_settings)
+                        {
+                            if tx_data.send(Data::Gesture(gesture)).is_err() {
+                                return Err(anyhow::anyhow!("Failed to send head gesture"));
+                            }
+                        }
+                    }
+
+                    let filter = pipeline.filter();
+                    health.push_fusion(
+                        filter.orientation(),
+                        sample.accel,
+                        pipeline.magnetometer(),
+                        filter.uses_magnetometer(),
+                        sample.dt,
+                    );
+                }
+                ImuEvent::Magnetometer { magnetometer, .. } => {
+                    if let Some((capture, _)) = &mut mag_capture {
+                        capture.push(magnetometer);
+                    }
+                    pipeline.push_mag(magnetometer);
+                }
+            }
+        }
+
+        // A pose only follows accelerometer samples, which set `received`
+        if let (Some(quat), Some(received)) = (pipeline.pose(), received) {
+            poses.publish(PoseSample {
+                quat,
+                angular_velocity: pipeline.angular_velocity(),
+                timestamp: received,
+            });
+        }
+
+        if let Some((capture, started)) = &mag_capture {
+            let elapsed_s = started.elapsed().as_secs_f32();
+            if capture.is_complete() || elapsed_s > magnetometer::CAPTURE_TIMEOUT_S {
+                let status = match capture.fit() {
+                    Ok(calibration) => {
+                        pipeline.mag_calibration = calibration;
+                        magnetometer::MagCalibrationStatus::Done(calibration)
+                    }
+                    Err(e) => {
+                        error!("❌ Magnetometer calibration failed: {}", e);
+                        magnetometer::MagCalibrationStatus::Failed
+                    }
+                };
+                mag_capture = None;
+                if tx_data.send(Data::MagCalibration(status)).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send magnetometer calibration"));
+                }
+            } else if last_mag_report.elapsed() >= Duration::from_millis(100) {
+                last_mag_report = Instant::now();
+                let status = magnetometer::MagCalibrationStatus::Capturing {
+                    samples: capture.len(),
+                    coverage: capture.coverage(),
+                    elapsed_s,
+                };
+                if tx_data.send(Data::MagCalibration(status)).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send magnetometer calibration"));
+                }
+            }
+        }
+
+        if let Some((capture, cross_axis)) = &accel_capture {
+            if capture.is_complete() {
+                let status = match capture.solve(*cross_axis) {
+                    // Applied through the persisted state once it passed validation
+                    Ok(calibration) => accelerometer::AccelCalibrationStatus::Done(calibration),
+                    Err(e) => {
+                        error!("❌ Accelerometer calibration failed: {}", e);
+                        accelerometer::AccelCalibrationStatus::Failed
+                    }
+                };
+                accel_capture = None;
+                if tx_data.send(Data::AccelCalibration(status)).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send accelerometer calibration"));
+                }
+            } else if last_accel_report.elapsed() >= Duration::from_millis(100) {
+                last_accel_report = Instant::now();
+                if tx_data
+                    .send(Data::AccelCalibration(capture.status()))
+                    .is_err()
+                {
+                    return Err(anyhow::anyhow!("Failed to send accelerometer calibration"));
+                }
+            }
+        }
+
+        if last_drift_report.elapsed() >= Duration::from_secs(1) {
+            last_drift_report = Instant::now();
+            if tx_data
+                .send(Data::Drift(pipeline.drift_estimate()))
+                .is_err()
+            {
+                return Err(anyhow::anyhow!("Failed to send drift estimate"));
+            }
+        }
+
+        if last_health_report.elapsed() >= Duration::from_secs(1) {
+            last_health_report = Instant::now();
+            let telemetry = health.report();
+            if tx_data.send(Data::Telemetry(telemetry)).is_err() {
+                return Err(anyhow::anyhow!("Failed to send IMU telemetry"));
+            }
+            let quality = telemetry.quality();
+            // Without samples the glasses are disconnected, which is reported elsewhere
+            if telemetry.sample_rate_hz > 0.0 && quality != tracking_quality {
+                tracking_quality = quality;
+                if tx_data.send(Data::TrackingQuality(quality)).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send tracking quality"));
+                }
+            }
+        }
+
+        // Handle calibration state updates
+        if let Some((capture, previous)) = &bias_capture {
+            if capture.is_finished() {
+                cal_state = match capture.finish() {
+                    Ok(result) => {
+                        let result = calibration::GyroCalibration {
+                            temperature_celsius: Some(pipeline.temperature()),
+                            ..result
+                        };
+                        info!(
+                            "📐 Gyro calibrated from {} samples at {:.1} °C, quality {:.2}",
+                            result.samples,
+                            pipeline.temperature(),
+                            result.quality
+                        );
+                        pipeline.apply_gyro_calibration(result.bias, result.samples as u32);
+                        if tx_data.send(Data::GyroCalibration(result)).is_err() {
+                            return Err(anyhow::anyhow!("Failed to send gyro calibration"));
+                        }
+                        // Accelerometer offset and scale come from the six-position calibration
+                        CalibrationState::Calibrated {
+                            gyro_bias: result.bias.to_array(),
+                            accel_bias: pipeline.accel_calibration.offset.to_array(),
+                            mag_bias: pipeline.mag_calibration.hard_iron.to_array(),
+                        }
+                    }
+                    Err(e) => {
+                        warn!("📐 Gyro calibration failed: {}", e);
+                        *previous
+                    }
+                };
+                bias_capture = None;
+                if tx_data.send(Data::CalState(cal_state)).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send cal state"));
+                }
+            } else if last_cal_report.elapsed() >= Duration::from_millis(100) {
+                last_cal_report = Instant::now();
+                cal_state = CalibrationState::Calibrating(capture.progress());
+                if tx_data.send(Data::CalState(cal_state)).is_err() {
+                    return Err(anyhow::anyhow!("Failed to send cal state"));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Steppable tracking pipeline
+//!
+//! The sensor fusion core of the tracking thread without its channels and
+//! clocks: sensor calibrations, gyro bias refinement and its temperature
+//! model, the orientation filter,
+//! yaw drift correction, recentering, roll lock and smoothing. Samples are
+//! pushed in device order and the head pose is taken after each batch, so
+//! the same code runs on the tracking thread and synchronously in tests.
+
+use bevy::prelude::*;
+
+use super::accelerometer::AccelCalibration;
+use super::calibration::RunningStats;
+use super::drift::{DriftCompensator, DriftEstimate};
+use super::filter::{self, OrientationFilter};
+use super::magnetometer::MagCalibration;
+use super::recenter::Recenter;
+use super::smoothing::OrientationSmoother;
+use super::thermal::{self, TemperatureBiasModel};
+use crate::driver::recording::RecordedCalibration;
+use crate::state::schema::tracking::{
+    DriftCompensationSettings, OrientationFilterSettings, RecenterSettings, SmoothingSettings,
+};
+
+/// Interval assumed for the first sample and after a timestamp restart, in seconds
+const FALLBACK_DT_S: f32 = 0.001;
+
+/// Still time gathered before it is recorded in the temperature model, in seconds
+const STILL_RECORD_S: f32 = 1.0;
+
+/// Accelerometer and gyroscope sample after calibration, as fed to the filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FusedSample {
+    /// Seconds since the previous sample
+    pub dt: f32,
+    /// Bias corrected angular velocity, in rad/s
+    pub gyro: Vec3,
+    /// Calibrated acceleration, in m/s²
+    pub accel: Vec3,
+}
+
+/// Sensor fusion from raw IMU samples to the head orientation
+pub struct TrackingPipeline {
+    filter: Box<dyn OrientationFilter>,
+    pub accel_calibration: AccelCalibration,
+    pub mag_calibration: MagCalibration,
+    pub drift: DriftCompensator,
+    pub drift_settings: DriftCompensationSettings,
+    pub temperature_model: TemperatureBiasModel,
+    /// Raw gyro samples taken while still, not yet recorded in the temperature model
+    still_gyro: RunningStats,
+    /// Point temperature the still samples belong to, in °C
+    still_temperature: f32,
+    /// Time covered by the still samples, in seconds
+    still_s: f32,
+    pub recenter: Recenter,
+    pub recenter_settings: RecenterSettings,
+    smoother: OrientationSmoother,
+    pub smoothing: SmoothingSettings,
+    pub roll_lock: bool,
+    last_ts: u64,
+    /// IMU temperature of the latest sample, in °C
+    temperature: f32,
+    /// Curve bias the gyro bias estimate last followed
+    model_bias: Option<Vec3>,
+    /// Keep the bias in use where the curve is first followed instead of
+    /// taking the curve's, e.g. for a bias recorded mid-session
+    keep_bias: bool,
+    last_gyro: Vec3,
+    /// Calibrated field, `None` until the magnetometer reported
+    last_mag: Option<Vec3>,
+    /// Time covered by the samples since the last pose
+    batch_dt: f32,
+    has_samples: bool,
+}
+
+impl Default for TrackingPipeline {
+    fn default() -> Self {
+        Self::new(&OrientationFilterSettings::default())
+    }
+}
+
+impl TrackingPipeline {
+    pub fn new(filter_settings: &OrientationFilterSettings) -> Self {
+        Self {
+            filter: filter::build_filter(filter_settings),
+            accel_calibration: AccelCalibration::IDENTITY,
+            mag_calibration: MagCalibration::IDENTITY,
+            drift: DriftCompensator::default(),
+            drift_settings: DriftCompensationSettings::default(),
+            temperature_model: TemperatureBiasModel::default(),
+            still_gyro: RunningStats::default(),
+            still_temperature: thermal::AMBIENT_CELSIUS,
+            still_s: 0.0,
+            recenter: Recenter::default(),
+            recenter_settings: RecenterSettings::default(),
+            smoother: OrientationSmoother::default(),
+            smoothing: SmoothingSettings::default(),
+            roll_lock: false,
+            last_ts: 0,
+            temperature: thermal::AMBIENT_CELSIUS,
+            model_bias: None,
+            keep_bias: false,
+            last_gyro: Vec3::ZERO,
+            last_mag: None,
+            batch_dt: 0.0,
+            has_samples: false,
+        }
+    }
+
+    /// The orientation filter
+    #[inline]
+    pub fn filter(&self) -> &dyn OrientationFilter {
+        self.filter.as_ref()
+    }
+
+    /// Switch or retune the orientation filter
+    pub fn set_filter(&mut self, settings: &OrientationFilterSettings) {
+        filter::apply_filter_settings(&mut self.filter, settings);
+    }
+
+    /// Latest calibrated magnetometer sample
+    #[inline]
+    pub fn magnetometer(&self) -> Option<Vec3> {
+        self.last_mag
+    }
+
+    /// IMU temperature of the latest sample, estimated from the uptime, in °C
+    #[inline]
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// Replace the temperature model, e.g. with points saved in an earlier session
+    pub fn set_temperature_model(&mut self, model: TemperatureBiasModel) {
+        self.temperature_model = model;
+        self.model_bias = None;
+    }
+
+    /// Apply a gyro bias calibrated at the current temperature and record it in the model
+    pub fn apply_gyro_calibration(&mut self, bias: Vec3, samples: u32) {
+        self.drift.set_bias(bias);
+        self.temperature_model
+            .record(self.temperature, bias, samples);
+        // The calibrated bias holds here, the curve only moves it as the temperature changes
+        self.model_bias = self.temperature_model.bias_at(self.temperature);
+    }
+
+    /// Calibration in use, as stored with a recording
+    pub fn recorded_calibration(&self) -> RecordedCalibration {
+        RecordedCalibration {
+            gyro_bias: self.drift.bias().to_array(),
+            accel_bias: self.accel_calibration.offset.to_array(),
+            accel_correction: self.accel_calibration.correction.to_cols_array_2d(),
+            mag_bias: self.mag_calibration.hard_iron.to_array(),
+            mag_soft_iron: self.mag_calibration.soft_iron.to_cols_array_2d(),
+            gyro_bias_points: self.temperature_model.points().to_vec(),
+        }
+    }
+
+    /// Run with the calibration a recording was made with
+    pub fn apply_recorded_calibration(&mut self, calibration: &RecordedCalibration) {
+        self.accel_calibration.offset = Vec3::from_array(calibration.accel_bias);
+        self.accel_calibration.correction = Mat3::from_cols_array_2d(&calibration.accel_correction);
+        self.mag_calibration.hard_iron = Vec3::from_array(calibration.mag_bias);
+        self.mag_calibration.soft_iron = Mat3::from_cols_array_2d(&calibration.mag_soft_iron);
+        self.set_temperature_model(TemperatureBiasModel::from_points(
+            &calibration.gyro_bias_points,
+        ));
+        // The recorded bias holds at the first sample, the curve moves it from there
+        self.drift.set_bias(Vec3::from_array(calibration.gyro_bias));
+        self.keep_bias = true;
+    }
+
+    /// Gyro bias and yaw drift estimate with the bias at the current temperature
+    pub fn drift_estimate(&self) -> DriftEstimate {
+        DriftEstimate {
+            temperature_celsius: self.temperature,
+            bias_point: self.temperature_model.point_at(self.temperature),
+            ..self.drift.estimate()
+        }
+    }
+
+    /// Bias corrected angular velocity of the latest sample, in rad/s
+    #[inline]
+    pub fn angular_velocity(&self) -> Vec3 {
+        self.last_gyro
+    }
+
+    /// Feed a raw accelerometer and gyroscope sample with its device timestamp in µs
+    pub fn push_imu(
+        &mut self,
+        accelerometer: Vec3,
+        gyroscope: Vec3,
+        timestamp: u64,
+    ) -> FusedSample {
+        let accel = self.accel_calibration.apply(accelerometer);
+        // Device timestamps restart when the glasses reconnect
+        let dt = if self.last_ts > 0 && timestamp > self.last_ts {
+            (timestamp - self.last_ts) as f32 / 1_000_000.0
+        } else {
+            FALLBACK_DT_S
+        };
+        self.last_ts = timestamp;
+        self.batch_dt += dt;
+
+        // Device timestamps count from when the glasses were switched on
+        self.temperature = thermal::estimate_temperature((timestamp as f64 / 1e6) as f32);
+        self.follow_temperature_model();
+
+        let gyro = self
+            .drift
+            .correct_gyro(gyroscope, accel, dt, &self.drift_settings);
+        self.last_gyro = gyro;
+        if self.drift.is_stationary() && self.drift_settings.learn_gyro_bias {
+            self.gather_still_sample(gyroscope, dt);
+        }
+
+        self.filter.update(gyro, accel, self.last_mag, dt);
+        self.has_samples = true;
+        FusedSample { dt, gyro, accel }
+    }
+
+    /// Gather a raw gyro sample taken while still, recording the bias in the
+    /// temperature model after a second of still time or when the temperature
+    /// moves on to another point
+    fn gather_still_sample(&mut self, gyroscope: Vec3, dt: f32) {
+        let temperature = thermal::point_temperature(self.temperature);
+        if temperature != self.still_temperature {
+            self.record_still_samples();
+            self.still_temperature = temperature;
+        }
+        self.still_gyro.push(gyroscope);
+        self.still_s += dt;
+        if self.still_s >= STILL_RECORD_S {
+            self.record_still_samples();
+        }
+    }
+
+    fn record_still_samples(&mut self) {
+        let still = std::mem::take(&mut self.still_gyro);
+        self.still_s = 0.0;
+        if still.count() == 0 {
+            return;
+        }
+        self.temperature_model
+            .record(self.still_temperature, still.mean(), still.count() as u32);
+        // The refined bias already holds here, the curve only moves it as the temperature changes
+        if self.model_bias.is_some() {
+            self.model_bias = self.temperature_model.bias_at(self.temperature);
+        }
+    }
+
+    /// Move the gyro bias along the temperature curve
+    /// The first bias from the curve replaces the estimate unless it is kept,
+    /// later ones shift it
+    /// so refinement while still keeps correcting what the curve misses
+    fn follow_temperature_model(&mut self) {
+        let settings = &self.drift_settings;
+        let model_bias = (settings.enabled && settings.temperature_model)
+            .then(|| self.temperature_model.bias_at(self.temperature))
+            .flatten();
+        match (model_bias, self.model_bias) {
+            (Some(bias), Some(previous)) => self.drift.shift_bias(bias - previous),
+            (Some(_), None) if std::mem::take(&mut self.keep_bias) => {}
+            (Some(bias), None) => self.drift.shift_bias(bias - self.drift.bias()),
+            _ => {}
+        }
+        self.model_bias = model_bias;
+    }
+
+    /// Feed a raw magnetometer sample, used from the next accelerometer sample on
+    pub fn push_mag(&mut self, magnetometer: Vec3) {
+        self.last_mag = Some(self.mag_calibration.apply(magnetometer));
+    }
+
+    /// Head orientation after the samples pushed since the last call, with
+    /// yaw drift corrected, recentered, roll locked and smoothed
+    /// `None` if no accelerometer sample arrived since
+    pub fn pose(&mut self) -> Option<Quat> {
+        if !std::mem::take(&mut self.has_samples) {
+            return None;
+        }
+        let dt = std::mem::take(&mut self.batch_dt);
+
+        let mut q = self.filter.orientation();
+        // Yaw is observable when the filter follows the magnetometer
+        if !(self.filter.uses_magnetometer() && self.last_mag.is_some()) {
+            q = self.drift.correct_yaw(q, dt, &self.drift_settings);
+        }
+        q = self.recenter.apply(q, dt, &self.recenter_settings);
+        if self.roll_lock {
+            let euler = q.to_euler(EulerRot::YXZ);
+            q = Quat::from_euler(EulerRot::YXZ, euler.0, euler.1, 0.0);
+        }
+        Some(self.smoother.update(q, dt, &self.smoothing))
+    }
+
+    /// Restart fusion from gravity, e.g. after a reconnect
+    /// Calibrations, the gyro bias and settings are kept; with a temperature
+    /// curve the bias is taken from it again
+    pub fn restart(&mut self) {
+        self.model_bias = None;
+        self.keep_bias = false;
+        // Still samples of the glasses connected before are not recorded
+        self.still_gyro = RunningStats::default();
+        self.still_s = 0.0;
+        self.drift.reset_yaw();
+        self.filter.reset();
+        self.smoother.reset();
+        self.recenter.reset();
+        self.last_ts = 0;
+    }
+}
@@ -0,0 +1,113 @@
+//! Smooth yaw-only recentering
+//!
+//! Recentering leaves the orientation filter alone: a reference offset is
+//! applied on top of its output, cancelling the yaw (and optionally the pitch)
+//! the head had when recentering. Roll and the tilt measured from gravity are
+//! kept, and the offset turns to its new value over a short transition
+//! instead of snapping. Optionally the view recenters by itself once the head
+//! has looked away from the center for a while.
+
+use bevy::prelude::*;
+
+use crate::state::schema::tracking::RecenterSettings;
+use crate::state::schema::window::Easing;
+
+/// Offset bringing `head` back to the center: its yaw, and optionally pitch, undone
+fn reference(head: Quat, include_pitch: bool) -> Quat {
+    let (yaw, pitch, _) = head.to_euler(EulerRot::YXZ);
+    let pitch = if include_pitch { pitch } else { 0.0 };
+    Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0).inverse()
+}
+
+/// Transition of the offset to a new center
+#[derive(Debug, Clone, Copy)]
+struct Transition {
+    from: Quat,
+    to: Quat,
+    elapsed_s: f32,
+}
+
+/// Reference offset applied to the filtered orientation
+#[derive(Debug, Clone, Copy)]
+pub struct Recenter {
+    offset: Quat,
+    transition: Option<Transition>,
+    /// Recenter on the next update
+    requested: bool,
+    /// How long the head has been looking away from the center, in seconds
+    away_s: f32,
+}
+
+impl Default for Recenter {
+    fn default() -> Self {
+        Self {
+            offset: Quat::IDENTITY,
+            transition: None,
+            requested: false,
+            away_s: 0.0,
+        }
+    }
+}
+
+impl Recenter {
+    /// Recenter on the head orientation of the next update
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Recentered orientation for the filtered `head`, `dt` seconds after the previous update
+    pub fn apply(&mut self, head: Quat, dt: f32, settings: &RecenterSettings) -> Quat {
+        if settings.auto_recenter && !self.requested && !self.is_transitioning() {
+            let away = reference(self.offset * head, settings.include_pitch)
+                .angle_between(Quat::IDENTITY)
+                > settings.auto_angle_deg.to_radians();
+            self.away_s = if away { self.away_s + dt } else { 0.0 };
+            if self.away_s >= settings.auto_delay_s {
+                info!(
+                    "🎯 Looked away for {:.0} s, recentering",
+                    settings.auto_delay_s
+                );
+                self.requested = true;
+            }
+        }
+
+        if std::mem::take(&mut self.requested) {
+            self.away_s = 0.0;
+            self.transition = Some(Transition {
+                from: self.offset,
+                to: reference(head, settings.include_pitch),
+                elapsed_s: 0.0,
+            });
+        }
+
+        if let Some(transition) = &mut self.transition {
+            transition.elapsed_s += dt;
+            let progress = if settings.transition_s > 0.0 {
+                transition.elapsed_s / settings.transition_s
+            } else {
+                1.0
+            };
+            if progress >= 1.0 {
+                self.offset = transition.to;
+                self.transition = None;
+            } else {
+                self.offset = transition
+                    .from
+                    .slerp(transition.to, Easing::EaseInOut.apply(progress));
+            }
+        }
+
+        (self.offset * head).normalize()
+    }
+
+    /// True while turning to a new center
+    #[inline]
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Drop the offset, e.g. when the filter restarts
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,59 @@
+//! Velocity-adaptive orientation smoothing
+//!
+//! A One-Euro filter applied in quaternion space: the smoothed orientation
+//! follows the filtered one through a slerp whose cutoff frequency grows with
+//! the (low-pass filtered) angular speed. Slow reading motions are smoothed
+//! heavily, while fast head turns pass through with little lag.
+
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::state::schema::tracking::SmoothingSettings;
+
+/// Low-pass factor for a cutoff frequency over `dt` seconds
+#[inline]
+fn smoothing_factor(cutoff_hz: f32, dt: f32) -> f32 {
+    let tau = 1.0 / (TAU * cutoff_hz);
+    1.0 / (1.0 + tau / dt)
+}
+
+/// One-Euro filter over orientations
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrientationSmoother {
+    /// Last smoothed orientation
+    quat: Option<Quat>,
+    /// Filtered angular speed, in rad/s
+    speed: f32,
+}
+
+impl OrientationSmoother {
+    /// Smooth `quat`, measured `dt` seconds after the previous call
+    pub fn update(&mut self, quat: Quat, dt: f32, settings: &SmoothingSettings) -> Quat {
+        let Some(previous) = self.quat.filter(|_| settings.enabled && dt > 0.0) else {
+            self.quat = Some(quat);
+            self.speed = 0.0;
+            return quat;
+        };
+
+        let raw_speed = previous.angle_between(quat) / dt;
+        self.speed += (raw_speed - self.speed) * smoothing_factor(settings.d_cutoff_hz, dt);
+
+        let cutoff = settings.min_cutoff_hz + settings.beta * self.speed;
+        let smoothed = previous
+            .slerp(quat, smoothing_factor(cutoff, dt))
+            .normalize();
+        self.quat = Some(smoothed);
+        smoothed
+    }
+
+    /// Filtered angular speed, in rad/s
+    #[inline]
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Start over from the next orientation
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,246 @@
+//! Temperature-aware gyro bias
+//!
+//! The gyro bias of the glasses shifts while they warm up during the first
+//! minutes of wear. Bias measured while the head is still, and by gyro
+//! calibrations, is recorded per degree of IMU temperature, and a curve fitted
+//! through those points gives the bias at the current temperature. ar-drivers
+//! does not pass on the IMU temperature the glasses report, so it is estimated
+//! from their uptime with an exponential warm-up curve.
+
+use bevy::prelude::*;
+
+use crate::state::schema::calibration::GyroBiasPoint;
+
+/// IMU temperature of glasses that were just switched on, in °C
+pub const AMBIENT_CELSIUS: f32 = 25.0;
+
+/// Warm-up of the IMU while worn, in °C above ambient
+const WARM_UP_RISE_CELSIUS: f32 = 13.0;
+
+/// Time constant of the warm-up, in seconds; warm after about 20 minutes
+const WARM_UP_TIME_CONSTANT_S: f32 = 300.0;
+
+/// Temperature range sharing one bias point, in °C
+pub const POINT_SPACING_CELSIUS: f32 = 1.0;
+
+/// Most bias points kept per pair of glasses
+pub const MAX_POINTS: usize = 48;
+
+/// Sample count a point is capped at, so it keeps following bias changes
+const MAX_POINT_SAMPLES: u32 = 20_000;
+
+/// Still samples a point needs before it is fitted
+const MIN_POINT_SAMPLES: u32 = 1000;
+
+/// Temperature span the points need for a linear fit, in °C
+const MIN_SPAN_CELSIUS: f32 = 2.0;
+
+/// Temperature span the points need for a quadratic fit, in °C
+const QUADRATIC_SPAN_CELSIUS: f32 = 6.0;
+
+/// IMU temperature estimated from the time since the glasses were switched on
+pub fn estimate_temperature(uptime_s: f32) -> f32 {
+    AMBIENT_CELSIUS
+        + WARM_UP_RISE_CELSIUS * (1.0 - (-uptime_s.max(0.0) / WARM_UP_TIME_CONSTANT_S).exp())
+}
+
+/// Temperature of the point a measurement at `temperature` is recorded in
+#[inline]
+pub fn point_temperature(temperature: f32) -> f32 {
+    (temperature / POINT_SPACING_CELSIUS).round() * POINT_SPACING_CELSIUS
+}
+
+/// Index of the point recorded at the same temperature
+fn find_point(points: &[GyroBiasPoint], temperature: f32) -> Option<usize> {
+    points.iter().position(|point| {
+        (point.temperature_celsius - temperature).abs() < POINT_SPACING_CELSIUS / 2.0
+    })
+}
+
+/// True if `point` is new or moved more than `tolerance` rad/s from its saved version
+pub fn point_changed(points: &[GyroBiasPoint], point: &GyroBiasPoint, tolerance: f32) -> bool {
+    match find_point(points, point.temperature_celsius) {
+        Some(index) => {
+            Vec3::from_array(points[index].bias).distance(Vec3::from_array(point.bias)) >= tolerance
+        }
+        None => true,
+    }
+}
+
+/// Replace the saved point of the same temperature, or add it
+pub fn upsert_point(points: &mut Vec<GyroBiasPoint>, point: GyroBiasPoint) {
+    match find_point(points, point.temperature_celsius) {
+        Some(index) => points[index] = point,
+        None => {
+            if points.len() >= MAX_POINTS {
+                evict_point(points);
+            }
+            points.push(point);
+            points.sort_by(|a, b| a.temperature_celsius.total_cmp(&b.temperature_celsius));
+        }
+    }
+}
+
+/// Drop the point backed by the fewest samples
+fn evict_point(points: &mut Vec<GyroBiasPoint>) {
+    if let Some(index) = points
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, point)| point.samples)
+        .map(|(index, _)| index)
+    {
+        points.remove(index);
+    }
+}
+
+/// Polynomial of the bias over the temperature, per axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BiasCurve {
+    /// Temperature the polynomial is centred on, in °C
+    center: f32,
+    /// Constant, linear and quadratic terms
+    coefficients: [Vec3; 3],
+    /// Temperature range covered by points; the curve is held flat outside
+    min_celsius: f32,
+    max_celsius: f32,
+}
+
+impl BiasCurve {
+    /// Weighted least squares fit through the points with enough samples
+    fn fit(points: &[GyroBiasPoint]) -> Option<Self> {
+        let fitted: Vec<&GyroBiasPoint> = points
+            .iter()
+            .filter(|point| point.samples >= MIN_POINT_SAMPLES)
+            .collect();
+        let min_celsius = fitted
+            .iter()
+            .map(|point| point.temperature_celsius)
+            .fold(f32::INFINITY, f32::min);
+        let max_celsius = fitted
+            .iter()
+            .map(|point| point.temperature_celsius)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let span = max_celsius - min_celsius;
+        if fitted.len() < 2 || span < MIN_SPAN_CELSIUS {
+            return None;
+        }
+        let quadratic = fitted.len() >= 3 && span >= QUADRATIC_SPAN_CELSIUS;
+
+        let total: f32 = fitted.iter().map(|point| point.samples as f32).sum();
+        let center = fitted
+            .iter()
+            .map(|point| point.temperature_celsius * point.samples as f32)
+            .sum::<f32>()
+            / total;
+
+        // Normal equations in powers of the temperature offset from the centre
+        let mut normal = Mat3::ZERO;
+        let mut rhs = [Vec3::ZERO; 3];
+        for point in &fitted {
+            let weight = point.samples as f32 / total;
+            let x = point.temperature_celsius - center;
+            let powers = Vec3::new(1.0, x, if quadratic { x * x } else { 0.0 });
+            normal +=
+                Mat3::from_cols(powers * powers.x, powers * powers.y, powers * powers.z) * weight;
+            let bias = Vec3::from_array(point.bias);
+            for (term, power) in rhs.iter_mut().zip(powers.to_array()) {
+                *term += bias * power * weight;
+            }
+        }
+        if !quadratic {
+            // Pin the unused quadratic term to zero
+            normal.z_axis = Vec3::Z;
+        }
+        if normal.determinant().abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = normal.inverse();
+
+        // The same system is solved for each axis of the bias
+        let mut coefficients = [Vec3::ZERO; 3];
+        for axis in 0..3 {
+            let b = Vec3::new(rhs[0][axis], rhs[1][axis], rhs[2][axis]);
+            let solution = inverse * b;
+            for (coefficient, value) in coefficients.iter_mut().zip(solution.to_array()) {
+                coefficient[axis] = value;
+            }
+        }
+        Some(Self {
+            center,
+            coefficients,
+            min_celsius,
+            max_celsius,
+        })
+    }
+
+    fn evaluate(&self, temperature: f32) -> Vec3 {
+        let x = temperature.clamp(self.min_celsius, self.max_celsius) - self.center;
+        self.coefficients[0] + self.coefficients[1] * x + self.coefficients[2] * x * x
+    }
+}
+
+/// Gyro bias recorded per temperature with the curve fitted through it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemperatureBiasModel {
+    points: Vec<GyroBiasPoint>,
+    curve: Option<BiasCurve>,
+}
+
+impl TemperatureBiasModel {
+    /// Resume from points saved in an earlier session
+    pub fn from_points(points: &[GyroBiasPoint]) -> Self {
+        let mut model = Self::default();
+        for point in points
+            .iter()
+            .filter(|point| point.temperature_celsius.is_finite())
+        {
+            upsert_point(&mut model.points, *point);
+        }
+        model.curve = BiasCurve::fit(&model.points);
+        model
+    }
+
+    /// Recorded points, ordered by temperature
+    #[inline]
+    pub fn points(&self) -> &[GyroBiasPoint] {
+        &self.points
+    }
+
+    /// Point recorded at `temperature`, if any
+    pub fn point_at(&self, temperature: f32) -> Option<GyroBiasPoint> {
+        find_point(&self.points, point_temperature(temperature)).map(|index| self.points[index])
+    }
+
+    /// Fold a bias measured at `temperature` from `samples` still samples into its point
+    pub fn record(&mut self, temperature: f32, bias: Vec3, samples: u32) {
+        if samples == 0 || !temperature.is_finite() {
+            return;
+        }
+        let temperature = point_temperature(temperature);
+        let point = match self.point_at(temperature) {
+            Some(previous) => {
+                let total = previous.samples.saturating_add(samples);
+                let mean = Vec3::from_array(previous.bias)
+                    + (bias - Vec3::from_array(previous.bias)) * (samples as f32 / total as f32);
+                GyroBiasPoint {
+                    temperature_celsius: temperature,
+                    bias: mean.to_array(),
+                    samples: total.min(MAX_POINT_SAMPLES),
+                }
+            }
+            None => GyroBiasPoint {
+                temperature_celsius: temperature,
+                bias: bias.to_array(),
+                samples: samples.min(MAX_POINT_SAMPLES),
+            },
+        };
+        upsert_point(&mut self.points, point);
+        self.curve = BiasCurve::fit(&self.points);
+    }
+
+    /// Gyro bias at `temperature` from the fitted curve, in rad/s
+    /// `None` until the points span enough temperatures
+    pub fn bias_at(&self, temperature: f32) -> Option<Vec3> {
+        self.curve.map(|curve| curve.evaluate(temperature))
+    }
+}
@@ -0,0 +1,97 @@
+//! Startup check failure report
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::setup::{CacheValidityState, DeviceAccessState, LibusbCheckState, LibusbInstallStatus};
+use crate::AppState;
+
+/// Camera the failure report is drawn on, the scene camera only exists while running
+#[derive(Component)]
+pub struct ChecksFailedCamera;
+
+pub fn spawn_checks_failed_camera(mut commands: Commands) {
+    commands.spawn((Camera2d, ChecksFailedCamera));
+}
+
+pub fn despawn_checks_failed_camera(
+    mut commands: Commands,
+    cameras: Query<Entity, With<ChecksFailedCamera>>,
+) {
+    for entity in cameras.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Lists the failed checks with the steps that fix them
+pub fn checks_failed_ui(
+    mut contexts: EguiContexts,
+    libusb_check: Res<LibusbCheckState>,
+    libusb_install: Res<LibusbInstallStatus>,
+    device_access: Res<DeviceAccessState>,
+    cache_check: Res<CacheValidityState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Startup Checks Failed")
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            if libusb_check.0 == Some(false) && libusb_install.0 != Some(true) {
+                ui.colored_label(egui::Color32::RED, "❌ libusb is not installed");
+            }
+            if cache_check.0 == Some(false) {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    "❌ Dependency cache could not be updated",
+                );
+            }
+
+            if let Some(report) = device_access.0.as_ref().filter(|report| !report.is_ok()) {
+                ui.colored_label(egui::Color32::RED, "❌ No access to the glasses");
+                egui::Grid::new("checks_failed_nodes")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for node in report.inaccessible() {
+                            ui.monospace(node.path.display().to_string());
+                            ui.label(format!("{:04x}:{:04x}", node.vendor_id, node.product_id));
+                            ui.label(node.access.label());
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                match report.install_command() {
+                    Some(command) => {
+                        ui.label("Install the generated udev rules, then replug the glasses:");
+                        ui.horizontal(|ui| {
+                            ui.monospace(&command);
+                            if ui.button("📋").on_hover_text("Copy command").clicked() {
+                                ui.ctx().copy_text(command.clone());
+                            }
+                        });
+                    }
+                    None => {
+                        ui.label("Add these udev rules to /etc/udev/rules.d:");
+                    }
+                }
+                ui.collapsing("udev rules", |ui| {
+                    ui.monospace(report.udev_rules());
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("🔄 Retry checks").clicked() {
+                    next_state.set(AppState::Startup);
+                }
+                if ui.button("Continue anyway").clicked() {
+                    next_state.set(AppState::Running);
+                }
+            });
+        });
+}
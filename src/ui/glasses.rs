@@ -0,0 +1,351 @@
+//! Settings panel sections for the glasses hardware
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+use crate::driver::{
+    DeviceInfo, DeviceProfile, DisplayOptics, GlassesPresence, OpticsSource, ReplayStepControl,
+    XRealDevice,
+};
+use crate::input::buttons::GlassesButtonState;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::input::{ButtonPress, GlassesButtonSettings, HeadGesture, InputAction};
+use crate::xreal_stereo::StereoSettings;
+
+/// Glasses resources used by the settings panel
+#[derive(SystemParam)]
+pub struct GlassesUiParams<'w> {
+    pub device: Option<Res<'w, XRealDevice>>,
+    pub persistent_state: ResMut<'w, PersistentAppState>,
+    pub buttons: Res<'w, GlassesButtonState>,
+    pub presence: Option<Res<'w, GlassesPresence>>,
+    pub device_info: Option<Res<'w, DeviceInfo>>,
+    pub stereo_settings: Option<ResMut<'w, StereoSettings>>,
+    pub optics: Option<Res<'w, DisplayOptics>>,
+    pub replay: Option<Res<'w, ReplayStepControl>>,
+}
+
+impl GlassesUiParams<'_> {
+    /// Profile of the connected glasses, or the generic one
+    pub fn profile(&self) -> &'static DeviceProfile {
+        self.device_info
+            .as_deref()
+            .map_or(&DeviceProfile::GENERIC, |info| info.profile)
+    }
+}
+
+/// Model, serial and capabilities of the connected glasses
+pub fn device_info_ui(ui: &mut egui::Ui, glasses: &GlassesUiParams) {
+    let Some(info) = glasses.device_info.as_deref() else {
+        ui.label("No glasses connected yet");
+        return;
+    };
+    let profile = info.profile;
+    let (width, height) = profile.display.resolution;
+
+    egui::Grid::new("glasses_device_info")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Model");
+            if profile.is_generic() {
+                ui.label("Unknown (generic profile)");
+            } else {
+                ui.label(format!("{} {}", profile.vendor, profile.name));
+            }
+            ui.end_row();
+
+            ui.label("Serial");
+            ui.monospace(&info.serial);
+            ui.end_row();
+
+            ui.label("Resolution");
+            ui.label(format!("{}x{} per eye", width, height));
+            ui.end_row();
+
+            ui.label("Field of view");
+            ui.label(format!("{:.0}°", profile.display.half_fov_deg * 2.0));
+            ui.end_row();
+
+            ui.label("Refresh rates");
+            let rates: Vec<String> = profile
+                .refresh_rates
+                .iter()
+                .map(|hz| hz.to_string())
+                .collect();
+            ui.label(format!(
+                "{} Hz (now {} Hz)",
+                rates.join(", "),
+                info.refresh_rate()
+            ));
+            ui.end_row();
+
+            ui.label("Display modes");
+            let modes: Vec<String> = profile
+                .display_modes
+                .iter()
+                .map(|mode| format!("{:?}", mode))
+                .collect();
+            ui.label(modes.join(", "));
+            ui.end_row();
+
+            ui.label("Current mode");
+            ui.label(
+                info.display_mode
+                    .map_or("Unknown".to_string(), |mode| format!("{:?}", mode)),
+            );
+            ui.end_row();
+
+            ui.label("Buttons");
+            let buttons: Vec<&str> = profile.buttons.iter().map(|button| button.label).collect();
+            ui.label(buttons.join(", "));
+            ui.end_row();
+
+            ui.label("IMU axes");
+            ui.label(profile.imu_axes.label());
+            ui.end_row();
+
+            ui.label("Optics");
+            ui.label(match info.optics_source {
+                OpticsSource::Calibration => "Factory calibration",
+                OpticsSource::Profile => "Model profile",
+            });
+            ui.end_row();
+        });
+}
+
+/// Eye separation, convergence and render resolution of the stereo cameras
+/// `screen_distance` is offered as convergence distance so the screen has zero parallax
+pub fn stereo_ui(ui: &mut egui::Ui, glasses: &mut GlassesUiParams, screen_distance: f32) {
+    let Some(current) = glasses.stereo_settings.as_deref().copied() else {
+        return;
+    };
+    let mut edited = current;
+
+    let mut ipd_mm = edited.eye_separation * 1000.0;
+    if ui
+        .add(egui::Slider::new(&mut ipd_mm, 50.0..=80.0).text("Eye separation (mm)"))
+        .changed()
+    {
+        edited.eye_separation = ipd_mm / 1000.0;
+    }
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::Slider::new(&mut edited.convergence_distance, 0.3..=20.0)
+                .logarithmic(true)
+                .text("Convergence (m)"),
+        )
+        .on_hover_text("Content at this distance has no parallax between the eyes");
+        if ui.button("Match screen").clicked() {
+            edited.convergence_distance = screen_distance;
+        }
+    });
+    ui.add(egui::Slider::new(&mut edited.render_scale, 0.5..=2.0).text("Render scale"));
+
+    let resolution = glasses
+        .optics
+        .as_deref()
+        .copied()
+        .unwrap_or_default()
+        .left
+        .resolution;
+    let (width, height) = edited.render_resolution(resolution);
+    ui.label(format!("Render target: {}x{} per eye", width, height));
+
+    if edited != current {
+        if let Some(settings) = glasses.stereo_settings.as_deref_mut() {
+            *settings = edited;
+        }
+    }
+}
+
+/// Buttons releasing events of a stepped replay
+pub fn replay_step_ui(ui: &mut egui::Ui, replay: &ReplayStepControl) {
+    ui.horizontal(|ui| {
+        for (label, count) in [("Step", 1), ("+100", 100), ("+1 s", 1000)] {
+            if ui.button(label).clicked() {
+                replay.0.step(count);
+            }
+        }
+    });
+}
+
+/// Settings for pausing while the glasses are taken off
+pub fn auto_pause_ui(ui: &mut egui::Ui, glasses: &mut GlassesUiParams) {
+    let mut edited = glasses.persistent_state.user_preferences.auto_pause.clone();
+    let mut changed = false;
+
+    changed |= ui
+        .checkbox(&mut edited.enabled, "Pause when glasses are taken off")
+        .changed();
+    ui.add_enabled_ui(edited.enabled, |ui| {
+        changed |= ui
+            .add(egui::Slider::new(&mut edited.pause_delay_ms, 0..=10_000).text("Delay (ms)"))
+            .changed();
+        changed |= ui
+            .checkbox(&mut edited.pause_capture, "Pause screen capture")
+            .changed();
+        changed |= ui
+            .checkbox(&mut edited.disable_stereo, "Disable stereo cameras")
+            .changed();
+        changed |= ui
+            .checkbox(
+                &mut edited.suspend_input,
+                "Suspend mouse and keyboard control",
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.paused_fps, 0..=60)
+                    .text("Paused FPS (0 = unlimited)"),
+            )
+            .changed();
+        changed |= ui
+            .checkbox(&mut edited.recenter_on_wake, "Recenter when put back on")
+            .changed();
+    });
+
+    if let Some(presence) = glasses.presence.as_deref() {
+        let status = if presence.is_paused() {
+            "Paused (glasses off)"
+        } else if presence.is_worn() {
+            "Glasses on"
+        } else {
+            "Glasses off"
+        };
+        ui.label(status);
+    }
+
+    if changed {
+        glasses.persistent_state.user_preferences.auto_pause = edited;
+    }
+}
+
+/// Editor for the glasses button bindings
+/// Edits a copy so the persisted state only changes when the user changes something
+pub fn button_bindings_ui(ui: &mut egui::Ui, glasses: &mut GlassesUiParams) {
+    let current = &glasses.persistent_state.input_config.glasses_buttons;
+    let mut edited = current.clone();
+    let mut changed = false;
+
+    changed |= ui
+        .checkbox(&mut edited.enabled, "Enable glasses buttons")
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut edited.long_press_ms, 200..=3000).text("Long press (ms)"))
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut edited.double_press_ms, 100..=1000).text("Double press (ms)"))
+        .changed();
+
+    let profile = glasses.profile();
+    let mut buttons = edited.bound_buttons();
+    buttons.extend(profile.buttons.iter().map(|button| button.id));
+    buttons.extend(glasses.buttons.seen_buttons.iter().copied());
+    buttons.sort_unstable();
+    buttons.dedup();
+
+    egui::Grid::new("glasses_button_bindings")
+        .num_columns(ButtonPress::ALL.len() + 1)
+        .show(ui, |ui| {
+            ui.label("");
+            for press in ButtonPress::ALL {
+                ui.label(press.label());
+            }
+            ui.end_row();
+
+            for button in buttons {
+                match profile.button_label(button) {
+                    Some(label) => ui.label(label),
+                    None => ui.label(format!("Button {}", button)),
+                };
+                for press in ButtonPress::ALL {
+                    changed |= binding_combo(ui, &mut edited, button, press);
+                }
+                ui.end_row();
+            }
+        });
+
+    if let Some(last) = glasses.buttons.last_press {
+        let button = profile
+            .button_label(last.button)
+            .map_or_else(|| format!("Button {}", last.button), str::to_string);
+        ui.label(format!("Last press: {} ({})", button, last.press.label()));
+    }
+
+    if changed {
+        glasses.persistent_state.input_config.glasses_buttons = edited;
+    }
+}
+
+fn binding_combo(
+    ui: &mut egui::Ui,
+    settings: &mut GlassesButtonSettings,
+    button: u8,
+    press: ButtonPress,
+) -> bool {
+    let mut action = settings.action_for(button, press);
+    let before = action;
+    egui::ComboBox::from_id_salt(("glasses_button", button, press.label()))
+        .selected_text(action.map_or("None", |action| action.label()))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut action, None, "None");
+            for option in InputAction::ALL {
+                ui.selectable_value(&mut action, Some(option), option.label());
+            }
+        });
+
+    if action != before {
+        settings.set_action(button, press, action);
+        true
+    } else {
+        false
+    }
+}
+
+/// Head gesture recognition and the actions bound to each gesture
+pub fn head_gestures_ui(ui: &mut egui::Ui, glasses: &mut GlassesUiParams) {
+    let mut edited = glasses.persistent_state.input_config.gesture_input.clone();
+    let mut changed = ui
+        .checkbox(&mut edited.enabled, "Enable head gestures")
+        .on_hover_text("Nod, shake, tilt or double-tap the frame to trigger actions")
+        .changed();
+
+    ui.add_enabled_ui(edited.enabled, |ui| {
+        changed |= ui
+            .add(egui::Slider::new(&mut edited.sensitivity, 0.0..=1.0).text("Sensitivity"))
+            .on_hover_text("Higher recognises gentler gestures, and more false ones")
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut edited.cooldown_ms, 100..=5000).text("Cooldown (ms)"))
+            .on_hover_text("Pause after a gesture before the next one is recognised")
+            .changed();
+
+        egui::Grid::new("head_gesture_bindings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for gesture in HeadGesture::ALL {
+                    ui.label(gesture.label());
+                    let mut action = edited.action_for(gesture);
+                    let before = action;
+                    egui::ComboBox::from_id_salt(("head_gesture", gesture.label()))
+                        .selected_text(action.map_or("None", |action| action.label()))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut action, None, "None");
+                            for option in InputAction::ALL {
+                                ui.selectable_value(&mut action, Some(option), option.label());
+                            }
+                        });
+                    if action != before {
+                        edited.set_action(gesture, action);
+                        changed = true;
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+
+    if changed {
+        glasses.persistent_state.input_config.gesture_input = edited;
+    }
+}
@@ -1,11 +1,20 @@
+pub mod checks;
+pub mod glasses;
+pub mod prediction;
+pub mod screen;
 pub mod state;
+pub mod tracking;
 
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 use tracing::{error, info};
 
+use self::glasses::GlassesUiParams;
 use self::state::{AppTab, DisplayPreset, SettingsPanelState, SystemStatus, TopMenuState};
+use self::tracking::TrackingUiParams;
 use crate::{
+    driver::ConnectionState,
+    prediction::PredictionStats,
     tracking::{CalibrationState, Command},
     BrightnessState, CommandChannel, DisplayModeState, RollLockState, ScreenCaptures,
     ScreenDistance,
@@ -71,7 +80,7 @@ pub fn settings_ui(
     mut guard: ResMut<UiRenderGuard>,
     mut settings_panel: ResMut<SettingsPanelState>,
     mut top_menu: ResMut<TopMenuState>,
-    _cal_state: ResMut<CalibrationState>,
+    cal_state: Res<CalibrationState>,
     mut display_mode: ResMut<DisplayModeState>,
     mut roll_lock: ResMut<RollLockState>,
     mut brightness: ResMut<BrightnessState>,
@@ -79,6 +88,9 @@ pub fn settings_ui(
     system_status: ResMut<SystemStatus>,
     mut screen_captures: ResMut<ScreenCaptures>,
     command_sender: Res<CommandChannel>,
+    mut glasses: GlassesUiParams,
+    mut prediction_stats: Option<ResMut<PredictionStats>>,
+    tracking_state: TrackingUiParams,
 ) {
     if guard.rendered_this_frame {
         return;
@@ -125,6 +137,7 @@ pub fn settings_ui(
                             // Display Mode Section
                             ui.group(|ui| {
                                 ui.label("Display Mode");
+                                let previous_preset = settings_panel.display_preset;
                                 ui.horizontal(|ui| {
                                     ui.selectable_value(
                                         &mut settings_panel.display_preset,
@@ -142,6 +155,12 @@ pub fn settings_ui(
                                         "Gaming",
                                     );
                                 });
+                                if settings_panel.display_preset != previous_preset {
+                                    tracking::apply_display_preset(
+                                        &mut glasses.persistent_state,
+                                        settings_panel.display_preset,
+                                    );
+                                }
                                 if ui
                                     .checkbox(&mut settings_panel.sbs_enabled, "Enable SBS 3D")
                                     .changed()
@@ -173,6 +192,8 @@ pub fn settings_ui(
                                         error!("Failed to send Recenter command: {}", e);
                                     }
                                 }
+                                tracking::recenter_ui(ui, &mut glasses.persistent_state);
+                                tracking::neck_model_ui(ui, &mut glasses.persistent_state);
 
                                 // Exercise the other Command variants
                                 if ui.button("🔓 Toggle Roll Lock").clicked() {
@@ -190,11 +211,32 @@ pub fn settings_ui(
                                     }
                                 }
 
-                                if ui.button("📐 Start Calibration").clicked() {
-                                    if let Err(e) =
-                                        command_sender.0.try_send(Command::StartCalibration)
-                                    {
-                                        error!("Failed to send calibration command: {}", e);
+                                tracking::gyro_calibration_ui(
+                                    ui,
+                                    &cal_state,
+                                    &glasses.persistent_state.calibration_data,
+                                    &command_sender,
+                                );
+
+                                let record_label = if settings_panel.imu_recording {
+                                    "⏹ Stop IMU Recording"
+                                } else {
+                                    "📼 Record IMU Session"
+                                };
+                                if ui.button(record_label).clicked() {
+                                    let command = if settings_panel.imu_recording {
+                                        Command::StopRecording
+                                    } else {
+                                        Command::StartRecording(
+                                            crate::driver::recording::default_recording_path(),
+                                        )
+                                    };
+                                    match command_sender.0.try_send(command) {
+                                        Ok(()) => {
+                                            settings_panel.imu_recording =
+                                                !settings_panel.imu_recording
+                                        }
+                                        Err(e) => error!("Failed to send recording command: {}", e),
                                     }
                                 }
 
@@ -215,6 +257,9 @@ pub fn settings_ui(
                                     }
                                 });
 
+                                ui.separator();
+                                tracking::smoothing_ui(ui, &mut glasses.persistent_state);
+
                                 // Exercise CyrupTheme constants
                                 ui.colored_label(
                                     CyrupTheme::SUCCESS,
@@ -222,6 +267,87 @@ pub fn settings_ui(
                                 );
                             });
 
+                            // Orientation Filter
+                            ui.group(|ui| {
+                                ui.label("Orientation Filter");
+                                tracking::orientation_filter_ui(ui, &mut glasses.persistent_state);
+                            });
+
+                            // Drift Compensation
+                            ui.group(|ui| {
+                                ui.label("Drift Compensation");
+                                tracking::drift_compensation_ui(
+                                    ui,
+                                    &mut glasses.persistent_state,
+                                    &tracking_state.drift_estimate,
+                                );
+                            });
+
+                            // Magnetometer
+                            ui.group(|ui| {
+                                ui.label("Magnetometer");
+                                tracking::mag_calibration_ui(
+                                    ui,
+                                    &tracking_state.mag_calibration,
+                                    &glasses.persistent_state.calibration_data,
+                                    &command_sender,
+                                );
+                            });
+
+                            // Accelerometer
+                            ui.group(|ui| {
+                                ui.label("Accelerometer");
+                                tracking::accel_calibration_ui(
+                                    ui,
+                                    &tracking_state.accel_calibration,
+                                    &glasses.persistent_state.calibration_data,
+                                    &mut settings_panel.accel_cross_axis,
+                                    &command_sender,
+                                );
+                            });
+
+                            // IMU Diagnostics
+                            ui.group(|ui| {
+                                ui.label("IMU Diagnostics");
+                                tracking::tracking_diagnostics_ui(ui, &tracking_state.telemetry);
+                            });
+
+                            // Pose Prediction
+                            ui.group(|ui| {
+                                ui.label("Pose Prediction");
+                                prediction::pose_prediction_ui(
+                                    ui,
+                                    &mut glasses.persistent_state,
+                                    prediction_stats.as_deref_mut(),
+                                );
+                            });
+
+                            // Glasses Button Bindings
+                            ui.group(|ui| {
+                                ui.label("Glasses Buttons");
+                                glasses::button_bindings_ui(ui, &mut glasses);
+                            });
+
+                            // Head Gestures
+                            ui.group(|ui| {
+                                ui.label("Head Gestures");
+                                glasses::head_gestures_ui(ui, &mut glasses);
+                            });
+
+                            // Auto Pause
+                            ui.group(|ui| {
+                                ui.label("Auto Pause");
+                                glasses::auto_pause_ui(ui, &mut glasses);
+                            });
+
+                            // Stepped replay
+                            if let Some(replay) = glasses.replay.as_deref() {
+                                ui.group(|ui| {
+                                    ui.label("Replay");
+                                    glasses::replay_step_ui(ui, replay);
+                                });
+                            }
+
                             // Brightness Control
                             ui.group(|ui| {
                                 ui.label("Brightness");
@@ -245,6 +371,22 @@ pub fn settings_ui(
                                 );
                             });
 
+                            // Stereo projection
+                            ui.group(|ui| {
+                                ui.label("Stereo");
+                                glasses::stereo_ui(ui, &mut glasses, screen_distance.0);
+                            });
+
+                            // Anchoring
+                            ui.group(|ui| {
+                                ui.label("Anchoring");
+                                screen::screen_anchoring_ui(
+                                    ui,
+                                    &mut glasses.persistent_state,
+                                    screen_captures.num_streams,
+                                );
+                            });
+
                             // Screen Capture
                             ui.group(|ui| {
                                 ui.label("Screen Capture");
@@ -258,6 +400,11 @@ pub fn settings_ui(
                             ui.label("XREAL Bevy Driver");
                             ui.label("Version 0.1.0");
                             ui.label("Powered by CYRUP.ai");
+
+                            ui.group(|ui| {
+                                ui.label("Glasses");
+                                glasses::device_info_ui(ui, &glasses);
+                            });
                         }
                     }
 
@@ -266,6 +413,33 @@ pub fn settings_ui(
                     // System Status Section
                     ui.group(|ui| {
                         ui.label("System Status");
+                        match glasses.device.as_deref() {
+                            Some(device) => {
+                                let state = device.connection_state();
+                                let color = if state == ConnectionState::Connected {
+                                    CyrupTheme::SUCCESS
+                                } else {
+                                    CyrupTheme::WARNING
+                                };
+                                ui.colored_label(color, format!("Glasses: {}", state.label()));
+                                if let Some(identity) = device.identity() {
+                                    if state == ConnectionState::Connected {
+                                        ui.label(format!(
+                                            "{} ({})",
+                                            identity.name, identity.serial
+                                        ));
+                                    }
+                                }
+                                if state != ConnectionState::Connected {
+                                    if let Some(error) = device.last_error() {
+                                        ui.label(error);
+                                    }
+                                }
+                            }
+                            None => {
+                                ui.colored_label(CyrupTheme::WARNING, "Glasses: Not available");
+                            }
+                        }
                         ui.horizontal(|ui| {
                             ui.label(format!("FPS: {:.2}", system_status.fps));
                             ui.label(format!("Jitter: {:.4} ms", system_status.jitter));
@@ -0,0 +1,106 @@
+//! Pose prediction tuning and error overlay
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::prediction::{PredictedOrientation, PredictionStats};
+use crate::state::schema::core::PersistentAppState;
+
+/// Settings for motion-to-photon latency compensation
+pub fn pose_prediction_ui(
+    ui: &mut egui::Ui,
+    persistent_state: &mut PersistentAppState,
+    stats: Option<&mut PredictionStats>,
+) {
+    let mut edited = persistent_state.tracking.prediction.clone();
+    let mut changed = false;
+
+    changed |= ui
+        .checkbox(&mut edited.enabled, "Predict head pose for display time")
+        .changed();
+    ui.add_enabled_ui(edited.enabled, |ui| {
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.render_latency_ms, 0.0..=100.0)
+                    .text("Render latency (ms)"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.max_horizon_ms, 0.0..=200.0).text("Max horizon (ms)"),
+            )
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut edited.damping, 0.0..=50.0).text("Damping (1/s)"))
+            .changed();
+    });
+    changed |= ui
+        .checkbox(&mut edited.show_overlay, "Show prediction error overlay")
+        .changed();
+
+    if let Some(stats) = stats {
+        if ui.button("Reset error statistics").clicked() {
+            stats.reset();
+        }
+    }
+
+    if changed {
+        persistent_state.tracking.prediction = edited;
+    }
+}
+
+/// Debug overlay comparing predicted poses with the poses measured later
+pub fn prediction_overlay_ui(
+    mut contexts: EguiContexts,
+    persistent_state: Res<PersistentAppState>,
+    predicted: Res<PredictedOrientation>,
+    stats: Res<PredictionStats>,
+) {
+    let settings = &persistent_state.tracking.prediction;
+    if !settings.show_overlay {
+        return;
+    }
+    let Ok(ctx) = contexts.ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Pose Prediction")
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0))
+        .resizable(false)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            if !settings.enabled {
+                ui.label("Prediction disabled");
+            }
+            egui::Grid::new("pose_prediction_overlay")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Horizon");
+                    ui.label(format!(
+                        "{:.1} ms",
+                        predicted.horizon.as_secs_f32() * 1000.0
+                    ));
+                    ui.end_row();
+
+                    ui.label("Head speed");
+                    ui.label(format!("{:.0} °/s", stats.angular_speed_dps));
+                    ui.end_row();
+
+                    ui.label("Error (predicted)");
+                    ui.label(format!("{:.2}°", stats.mean_error_deg));
+                    ui.end_row();
+
+                    ui.label("Error (unpredicted)");
+                    ui.label(format!("{:.2}°", stats.mean_unpredicted_error_deg));
+                    ui.end_row();
+
+                    ui.label("Max error");
+                    ui.label(format!("{:.2}°", stats.max_error_deg));
+                    ui.end_row();
+
+                    ui.label("Measured");
+                    ui.label(stats.measured.to_string());
+                    ui.end_row();
+                });
+        });
+}
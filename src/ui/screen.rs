@@ -0,0 +1,56 @@
+//! Virtual screen settings
+
+use bevy_egui::egui;
+
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::window::{AnchorMode, Easing};
+
+/// Anchor mode of each virtual screen with the lazy-follow tuning
+pub fn screen_anchoring_ui(
+    ui: &mut egui::Ui,
+    persistent_state: &mut PersistentAppState,
+    screen_count: usize,
+) {
+    let mut edited = persistent_state.window_layout.anchoring.clone();
+
+    for index in 0..screen_count.max(1) {
+        let mut mode = edited.mode(index);
+        egui::ComboBox::from_label(format!("Screen {}", index + 1))
+            .selected_text(mode.label())
+            .show_ui(ui, |ui| {
+                for option in AnchorMode::ALL {
+                    ui.selectable_value(&mut mode, option, option.label());
+                }
+            });
+        if mode != edited.mode(index) {
+            edited.set_mode(index, mode);
+        }
+    }
+
+    let lazy_follow_used =
+        (0..screen_count.max(1)).any(|index| edited.mode(index) == AnchorMode::LazyFollow);
+    ui.add_enabled_ui(lazy_follow_used, |ui| {
+        let follow = &mut edited.lazy_follow;
+        ui.add(
+            egui::Slider::new(&mut follow.threshold_deg, 5.0..=90.0)
+                .text("Follow after")
+                .suffix("°"),
+        );
+        ui.add(
+            egui::Slider::new(&mut follow.duration_s, 0.05..=5.0)
+                .text("Glide time")
+                .suffix(" s"),
+        );
+        egui::ComboBox::from_label("Glide easing")
+            .selected_text(follow.easing.label())
+            .show_ui(ui, |ui| {
+                for easing in Easing::ALL {
+                    ui.selectable_value(&mut follow.easing, easing, easing.label());
+                }
+            });
+    });
+
+    if edited != persistent_state.window_layout.anchoring {
+        persistent_state.window_layout.anchoring = edited;
+    }
+}
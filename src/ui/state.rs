@@ -13,6 +13,9 @@ pub struct SettingsPanelState {
     pub head_locked: bool,
     pub brightness: u8,
     pub is_open: bool,
+    pub imu_recording: bool,
+    /// Solve cross-axis terms in the next accelerometer calibration
+    pub accel_cross_axis: bool,
 }
 
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
@@ -0,0 +1,575 @@
+//! Head tracking settings
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+use crate::state::schema::calibration::CalibrationData;
+use crate::state::schema::core::PersistentAppState;
+use crate::state::schema::tracking::{FilterKind, SmoothingPreset, SmoothingSettings};
+use crate::tracking::accelerometer::{AccelCalibrationStatus, AccelPose};
+use crate::tracking::calibration;
+use crate::tracking::drift::DriftEstimate;
+use crate::tracking::health::{ImuTelemetry, TrackingQuality};
+use crate::tracking::magnetometer::{self, MagCalibrationStatus};
+use crate::tracking::{CalibrationState, Command};
+use crate::ui::state::DisplayPreset;
+use crate::CommandChannel;
+
+/// Live tracking state shown in the settings panel
+#[derive(SystemParam)]
+pub struct TrackingUiParams<'w> {
+    pub drift_estimate: Res<'w, DriftEstimate>,
+    pub mag_calibration: Res<'w, MagCalibrationStatus>,
+    pub accel_calibration: Res<'w, AccelCalibrationStatus>,
+    pub telemetry: Res<'w, ImuTelemetry>,
+}
+
+/// Orientation filter selection and tuning
+/// Parameter changes apply live; switching filters restarts tracking from gravity
+pub fn orientation_filter_ui(ui: &mut egui::Ui, persistent_state: &mut PersistentAppState) {
+    let mut edited = persistent_state.tracking.filter.clone();
+    let mut changed = false;
+
+    egui::ComboBox::from_label("Filter")
+        .selected_text(edited.kind.label())
+        .show_ui(ui, |ui| {
+            for kind in FilterKind::ALL {
+                changed |= ui
+                    .selectable_value(&mut edited.kind, kind, kind.label())
+                    .changed();
+            }
+        });
+
+    match edited.kind {
+        FilterKind::Fusion => {
+            let fusion = &mut edited.fusion;
+            changed |= ui
+                .add(egui::Slider::new(&mut fusion.gain, 0.0..=2.0).text("Gain"))
+                .on_hover_text("Lower drifts more, higher reacts more to acceleration")
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut fusion.acc_rejection, 0.0..=90.0)
+                        .text("Acceleration rejection (°)"),
+                )
+                .changed();
+            changed |= ui
+                .checkbox(&mut fusion.use_magnetometer, "Use magnetometer")
+                .changed();
+            ui.add_enabled_ui(fusion.use_magnetometer, |ui| {
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut fusion.mag_rejection, 0.0..=90.0)
+                            .text("Magnetic rejection (°)"),
+                    )
+                    .changed();
+            });
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut fusion.recovery_trigger_s, 0.0..=30.0)
+                        .text("Recovery trigger (s)"),
+                )
+                .changed();
+        }
+        FilterKind::Complementary => {
+            let complementary = &mut edited.complementary;
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut complementary.time_constant_s, 0.05..=10.0)
+                        .logarithmic(true)
+                        .text("Time constant (s)"),
+                )
+                .on_hover_text("Longer trusts the gyro more: smoother but drifts longer")
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut complementary.accel_tolerance, 0.0..=0.5)
+                        .text("Acceleration tolerance (g)"),
+                )
+                .changed();
+        }
+        FilterKind::Madgwick => {
+            changed |= ui
+                .add(egui::Slider::new(&mut edited.madgwick.beta, 0.0..=1.0).text("Beta"))
+                .on_hover_text("Higher corrects drift faster but adds jitter")
+                .changed();
+        }
+        FilterKind::Mahony => {
+            let mahony = &mut edited.mahony;
+            changed |= ui
+                .add(egui::Slider::new(&mut mahony.kp, 0.0..=10.0).text("Kp"))
+                .on_hover_text("Proportional gain of the gravity correction")
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut mahony.ki, 0.0..=1.0).text("Ki"))
+                .on_hover_text("Integral gain, learns the gyro bias")
+                .changed();
+        }
+    }
+
+    if ui.button("Reset filter parameters").clicked() {
+        let kind = edited.kind;
+        edited = Default::default();
+        edited.kind = kind;
+        changed = true;
+    }
+
+    if changed {
+        persistent_state.tracking.filter = edited;
+    }
+}
+
+/// Anti-jitter smoothing, tuned live
+/// Moving a slider switches to the custom preset
+pub fn smoothing_ui(ui: &mut egui::Ui, persistent_state: &mut PersistentAppState) {
+    let mut edited = persistent_state.tracking.smoothing.clone();
+    let mut changed = ui
+        .checkbox(&mut edited.enabled, "Smoothing")
+        .on_hover_text("Smooths jitter when still, follows fast head turns")
+        .changed();
+
+    ui.add_enabled_ui(edited.enabled, |ui| {
+        egui::ComboBox::from_label("Smoothing preset")
+            .selected_text(edited.preset.label())
+            .show_ui(ui, |ui| {
+                for preset in SmoothingPreset::ALL {
+                    if ui
+                        .selectable_value(&mut edited.preset, preset, preset.label())
+                        .changed()
+                    {
+                        // Custom starts from the current values
+                        if preset != SmoothingPreset::Custom {
+                            edited = SmoothingSettings {
+                                enabled: edited.enabled,
+                                ..SmoothingSettings::from_preset(preset)
+                            };
+                        }
+                        changed = true;
+                    }
+                }
+            });
+
+        let mut tuned = false;
+        tuned |= ui
+            .add(
+                egui::Slider::new(&mut edited.min_cutoff_hz, 0.05..=10.0)
+                    .logarithmic(true)
+                    .text("Min cutoff (Hz)"),
+            )
+            .on_hover_text("Lower removes more jitter when holding still")
+            .changed();
+        tuned |= ui
+            .add(egui::Slider::new(&mut edited.beta, 0.0..=50.0).text("Speed coefficient"))
+            .on_hover_text("Higher reduces lag during fast head turns")
+            .changed();
+        tuned |= ui
+            .add(
+                egui::Slider::new(&mut edited.d_cutoff_hz, 0.1..=10.0)
+                    .logarithmic(true)
+                    .text("Speed cutoff (Hz)"),
+            )
+            .changed();
+        if tuned {
+            edited.preset = SmoothingPreset::Custom;
+            changed = true;
+        }
+    });
+
+    if changed {
+        persistent_state.tracking.smoothing = edited;
+    }
+}
+
+/// Recenter transition and auto-recenter
+pub fn recenter_ui(ui: &mut egui::Ui, persistent_state: &mut PersistentAppState) {
+    let mut edited = persistent_state.tracking.recenter.clone();
+    let mut changed = ui
+        .checkbox(&mut edited.include_pitch, "Recenter pitch too")
+        .on_hover_text("Also levels the view to where you look up or down")
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut edited.transition_s, 0.0..=2.0).text("Recenter transition (s)"))
+        .changed();
+    changed |= ui
+        .checkbox(&mut edited.auto_recenter, "Auto-recenter")
+        .on_hover_text("Recenter after looking away from the screens for a while")
+        .changed();
+    ui.add_enabled_ui(edited.auto_recenter, |ui| {
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.auto_angle_deg, 10.0..=120.0)
+                    .text("Looking away beyond (°)"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.auto_delay_s, 1.0..=120.0)
+                    .logarithmic(true)
+                    .text("Recenter after (s)"),
+            )
+            .changed();
+    });
+
+    if changed {
+        persistent_state.tracking.recenter = edited;
+    }
+}
+
+/// Neck model moving the eyes as the head turns
+pub fn neck_model_ui(ui: &mut egui::Ui, persistent_state: &mut PersistentAppState) {
+    let mut edited = persistent_state.tracking.neck_model.clone();
+    let mut changed = ui
+        .checkbox(&mut edited.enabled, "Neck model")
+        .on_hover_text("Turns the view about the neck so near panels show parallax")
+        .changed();
+    ui.add_enabled_ui(edited.enabled, |ui| {
+        changed |= ui
+            .add(egui::Slider::new(&mut edited.neck_length_m, 0.0..=0.3).text("Neck length (m)"))
+            .on_hover_text("Height of the eyes above the pivot")
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut edited.eye_offset_m, 0.0..=0.2).text("Eye offset (m)"))
+            .on_hover_text("Distance of the eyes in front of the pivot")
+            .changed();
+    });
+
+    if changed {
+        persistent_state.tracking.neck_model = edited;
+    }
+}
+
+/// Follow a display preset with the matching smoothing preset
+/// Custom smoothing is kept as it is
+pub fn apply_display_preset(persistent_state: &mut PersistentAppState, preset: DisplayPreset) {
+    let smoothing = &mut persistent_state.tracking.smoothing;
+    if smoothing.preset == SmoothingPreset::Custom {
+        return;
+    }
+    let preset = match preset {
+        DisplayPreset::Standard => SmoothingPreset::Standard,
+        DisplayPreset::Cinema => SmoothingPreset::Cinema,
+        DisplayPreset::Gaming => SmoothingPreset::Gaming,
+    };
+    *smoothing = SmoothingSettings {
+        enabled: smoothing.enabled,
+        ..SmoothingSettings::from_preset(preset)
+    };
+}
+
+/// Gyro bias refinement and yaw drift correction, with the live estimate
+pub fn drift_compensation_ui(
+    ui: &mut egui::Ui,
+    persistent_state: &mut PersistentAppState,
+    estimate: &DriftEstimate,
+) {
+    let mut edited = persistent_state.tracking.drift.clone();
+    let mut changed = ui
+        .checkbox(&mut edited.enabled, "Drift compensation")
+        .on_hover_text("Refines the gyro bias whenever the head is held still")
+        .changed();
+
+    ui.add_enabled_ui(edited.enabled, |ui| {
+        changed |= ui
+            .checkbox(&mut edited.learn_gyro_bias, "Refine gyro bias")
+            .changed();
+        changed |= ui
+            .checkbox(&mut edited.yaw_correction, "Correct yaw drift")
+            .on_hover_text("Not applied while the filter uses the magnetometer")
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.correction_rate, 0.01..=2.0)
+                    .logarithmic(true)
+                    .text("Correction rate (1/s)"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.stationary_threshold_dps, 0.1..=5.0)
+                    .text("Stillness threshold (°/s)"),
+            )
+            .on_hover_text("Gyro fluctuation below which the head counts as still")
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.stationary_time_s, 0.1..=5.0)
+                    .text("Stillness time (s)"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut edited.drift_threshold_dps, 0.5..=10.0)
+                    .text("Drift threshold (°/s)"),
+            )
+            .on_hover_text("Slower rotations while still are taken as gyro bias")
+            .changed();
+        changed |= ui
+            .checkbox(
+                &mut edited.temperature_model,
+                "Follow bias temperature curve",
+            )
+            .on_hover_text("Moves the gyro bias with the IMU temperature while the glasses warm up")
+            .changed();
+    });
+
+    let bias = estimate.gyro_bias * 180.0 / std::f32::consts::PI;
+    ui.label(format!(
+        "Gyro bias: {:+.3} {:+.3} {:+.3} °/s",
+        bias.x, bias.y, bias.z
+    ));
+    ui.label(format!(
+        "Yaw drift: {:+.2} °/min",
+        estimate.yaw_drift_rate.to_degrees() * 60.0
+    ));
+    let points = persistent_state.calibration_data.gyro_bias_points.len();
+    ui.label(format!(
+        "IMU temperature: {:.1} °C, bias recorded at {} temperature{}",
+        estimate.temperature_celsius,
+        points,
+        if points == 1 { "" } else { "s" }
+    ));
+    ui.label(if estimate.stationary {
+        "🧘 Head still, learning"
+    } else {
+        "Head moving"
+    });
+
+    if changed {
+        persistent_state.tracking.drift = edited;
+    }
+}
+
+/// IMU stream health of the last second
+pub fn tracking_diagnostics_ui(ui: &mut egui::Ui, telemetry: &ImuTelemetry) {
+    let quality = telemetry.quality();
+    let color = match quality {
+        TrackingQuality::Good => egui::Color32::GREEN,
+        TrackingQuality::Degraded => egui::Color32::YELLOW,
+        TrackingQuality::Poor => egui::Color32::RED,
+    };
+    ui.colored_label(color, format!("Tracking quality: {}", quality.label()));
+
+    ui.label(format!("Sample rate: {:.0} Hz", telemetry.sample_rate_hz));
+    ui.label(format!(
+        "Interval jitter: {:.3} ms (max {:.1} ms)",
+        telemetry.dt_jitter_ms, telemetry.max_dt_ms
+    ));
+    ui.label(format!(
+        "Packet loss: {:.1}% ({} dropped, {} out of order)",
+        telemetry.packet_loss * 100.0,
+        telemetry.dropped_packets,
+        telemetry.timestamp_regressions
+    ));
+
+    let warnings = [
+        (telemetry.saturated, "⚠ Sensor saturated"),
+        (telemetry.stuck, "⚠ Sensor readings stuck"),
+        (
+            telemetry.accel_rejected,
+            "⚠ Accelerating, gravity rejected by the filter",
+        ),
+        (telemetry.mag_disturbed, "⚠ Magnetic disturbance nearby"),
+        (
+            telemetry.mag_rejected,
+            "⚠ Magnetic heading rejected by the filter",
+        ),
+    ];
+    for (_, warning) in warnings.iter().filter(|(active, _)| *active) {
+        ui.colored_label(egui::Color32::YELLOW, *warning);
+    }
+}
+
+/// Gyro bias calibration with live progress while it runs
+/// Prompts for a calibration when the glasses have none or it is outdated
+pub fn gyro_calibration_ui(
+    ui: &mut egui::Ui,
+    cal_state: &CalibrationState,
+    record: &CalibrationData,
+    command_sender: &CommandChannel,
+) {
+    let CalibrationState::Calibrating(progress) = cal_state else {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let age_days = record.age_seconds(now) / 86_400;
+        if !record.is_calibrated() {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "⚠ These glasses are not calibrated yet, please calibrate",
+            );
+        } else if record.needs_recalibration(now) {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("⚠ Calibration is {} days old, please recalibrate", age_days),
+            );
+        } else {
+            ui.label(format!(
+                "Calibrated {} days ago from {} samples, quality {:.0}%",
+                age_days,
+                record.sample_count,
+                record.quality_score * 100.0
+            ));
+        }
+        if ui.button("📐 Start Calibration").clicked() {
+            if let Err(e) = command_sender.0.try_send(Command::StartCalibration) {
+                error!("Failed to send calibration command: {}", e);
+            }
+        }
+        return;
+    };
+
+    ui.label("Rest the glasses on a flat surface and keep them still");
+    ui.add(egui::ProgressBar::new(progress.fraction()).text(format!(
+        "{} of {} samples",
+        progress.samples,
+        calibration::TARGET_SAMPLES
+    )));
+    if progress.still {
+        ui.label("🧘 Still");
+    } else {
+        ui.colored_label(egui::Color32::YELLOW, "⚠ Moving, samples rejected");
+    }
+    let noise = progress.variance.max_element().sqrt().to_degrees();
+    ui.label(format!(
+        "Noise {:.3}°/s, quality {:.0}%, {} rejected, {:.0} of {:.0} s",
+        noise,
+        progress.quality() * 100.0,
+        progress.rejected,
+        progress.elapsed_s,
+        calibration::CAPTURE_TIMEOUT_S
+    ));
+    if ui.button("✖ Cancel Calibration").clicked() {
+        if let Err(e) = command_sender.0.try_send(Command::CancelCalibration) {
+            error!("Failed to send calibration command: {}", e);
+        }
+    }
+}
+
+/// Guided magnetometer calibration with the stored result
+pub fn mag_calibration_ui(
+    ui: &mut egui::Ui,
+    status: &MagCalibrationStatus,
+    calibration: &CalibrationData,
+    command_sender: &CommandChannel,
+) {
+    if let MagCalibrationStatus::Capturing {
+        samples,
+        coverage,
+        elapsed_s,
+    } = *status
+    {
+        ui.label(
+            "Slowly trace a figure eight with your head while turning to face every direction",
+        );
+        ui.add(
+            egui::ProgressBar::new(coverage / magnetometer::TARGET_COVERAGE)
+                .text(format!("{:.0}% of directions", coverage * 100.0)),
+        );
+        ui.label(format!(
+            "{} samples, {:.0} of {:.0} s",
+            samples,
+            elapsed_s,
+            magnetometer::CAPTURE_TIMEOUT_S
+        ));
+        return;
+    }
+
+    if *status == MagCalibrationStatus::Failed {
+        ui.colored_label(
+            egui::Color32::RED,
+            "❌ Calibration failed, turn through more directions",
+        );
+    }
+    if calibration.mag_fit_quality > 0.0 {
+        let offset = calibration.mag_bias;
+        ui.label(format!(
+            "Fit quality {:.0}%, field {:.1} µT",
+            calibration.mag_fit_quality * 100.0,
+            calibration.mag_field_strength
+        ));
+        ui.label(format!(
+            "Hard-iron offset: {:+.1} {:+.1} {:+.1} µT",
+            offset[0], offset[1], offset[2]
+        ));
+    } else {
+        ui.label("Not calibrated");
+    }
+
+    if ui.button("🧲 Calibrate Magnetometer").clicked() {
+        if let Err(e) = command_sender.0.try_send(Command::StartMagCalibration) {
+            error!("Failed to send magnetometer calibration command: {}", e);
+        }
+    }
+}
+
+/// Guided six-position accelerometer calibration with the stored result
+pub fn accel_calibration_ui(
+    ui: &mut egui::Ui,
+    status: &AccelCalibrationStatus,
+    calibration: &CalibrationData,
+    cross_axis: &mut bool,
+    command_sender: &CommandChannel,
+) {
+    if let AccelCalibrationStatus::Capturing {
+        captured,
+        current,
+        progress,
+    } = *status
+    {
+        ui.label("Rest the glasses still on a flat surface in each pose, in any order");
+        let next = AccelPose::ALL
+            .into_iter()
+            .find(|pose| !captured[pose.index()]);
+        if let Some(pose) = current.or(next) {
+            ui.strong(pose.instruction());
+        }
+        for pose in AccelPose::ALL {
+            let mark = if captured[pose.index()] { "✅" } else { "⬜" };
+            ui.label(format!("{} {}", mark, pose.label()));
+        }
+        let text = match current {
+            Some(pose) => format!("Hold still: {}", pose.label()),
+            None => "Waiting for a still pose".to_string(),
+        };
+        ui.add(egui::ProgressBar::new(progress).text(text));
+        return;
+    }
+
+    if *status == AccelCalibrationStatus::Failed {
+        ui.colored_label(
+            egui::Color32::RED,
+            "❌ Calibration failed, make sure each pose rests level",
+        );
+    }
+    if calibration.accel_fit_quality > 0.0 {
+        let offset = calibration.accel_bias;
+        let correction = calibration.accel_correction;
+        ui.label(format!(
+            "Fit quality {:.0}%",
+            calibration.accel_fit_quality * 100.0
+        ));
+        ui.label(format!(
+            "Offset: {:+.3} {:+.3} {:+.3} m/s²",
+            offset[0], offset[1], offset[2]
+        ));
+        ui.label(format!(
+            "Scale correction: {:.3} {:.3} {:.3}",
+            correction[0][0], correction[1][1], correction[2][2]
+        ));
+    } else {
+        ui.label("Not calibrated");
+    }
+
+    ui.checkbox(cross_axis, "Solve cross-axis terms")
+        .on_hover_text("Also correct axis misalignment, needs each pose to rest precisely level");
+    if ui.button("📐 Calibrate Accelerometer").clicked() {
+        let command = Command::StartAccelCalibration {
+            cross_axis: *cross_axis,
+        };
+        if let Err(e) = command_sender.0.try_send(command) {
+            error!("Failed to send accelerometer calibration command: {}", e);
+        }
+    }
+}
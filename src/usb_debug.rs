@@ -3,6 +3,8 @@
 use anyhow::Result;
 use hidapi::HidApi;
 
+use crate::setup::hidraw::{self, DeviceAccessReport, DeviceRoots, KNOWN_DEVICES};
+
 /// Debug USB devices to help diagnose AR glasses connection issues
 pub fn debug_usb_devices() -> Result<()> {
     println!("🔍 USB Device Debug Report");
@@ -38,12 +40,11 @@ pub fn debug_usb_devices() -> Result<()> {
         println!();
     }
 
-    // Look for known AR glasses vendor IDs
-    println!("🥽 Known AR Glasses Vendors:");
-    println!("  0x0486 - Nreal/XREAL");
-    println!("  0x04d8 - Rokid");
-    println!("  0x0c45 - Mad Gaze");
-    println!("  0x2833 - Grawoow (verified vendor ID)");
+    // Look for known AR glasses vendor and product IDs
+    println!("🥽 Known AR Glasses Devices:");
+    for (vendor_id, product_id, name) in KNOWN_DEVICES {
+        println!("  {:04x}:{:04x} - {}", vendor_id, product_id, name);
+    }
     println!();
 
     println!("🎯 Potential AR Glasses Detected:");
@@ -53,19 +54,11 @@ pub fn debug_usb_devices() -> Result<()> {
         let vendor_id = device_info.vendor_id();
         let product_id = device_info.product_id();
 
-        let (is_ar_device, vendor_name) = match vendor_id {
-            0x0486 => (true, "Nreal/XREAL"),
-            0x04d8 => (true, "Rokid"),
-            0x0c45 => (true, "Mad Gaze"),
-            0x1234 => (true, "Grawoow"),
-            _ => (false, "Unknown"),
-        };
-
-        if is_ar_device {
+        if let Some(device_name) = hidraw::device_name(vendor_id, product_id) {
             found_ar_devices = true;
             println!(
                 "  ✅ {} Device: VID={:04x}, PID={:04x}",
-                vendor_name, vendor_id, product_id
+                device_name, vendor_id, product_id
             );
 
             if let Some(product) = device_info.product_string() {
@@ -127,6 +120,52 @@ pub fn check_libusb_status() -> Result<()> {
     Ok(())
 }
 
+/// Report hidraw permissions of connected glasses (Linux only)
+pub fn check_hidraw_access() -> Result<()> {
+    println!("🔒 hidraw Access Check");
+    println!("=====================");
+
+    let report = DeviceAccessReport::scan(&DeviceRoots::default());
+    if !report.glasses_detected() {
+        println!("  ❌ No glasses found in sysfs");
+        return Ok(());
+    }
+
+    for device in &report.usb_devices {
+        println!(
+            "  USB: VID={:04x}, PID={:04x} {}",
+            device.vendor_id,
+            device.product_id,
+            device.product.as_deref().unwrap_or("")
+        );
+    }
+    for node in &report.hidraw_nodes {
+        let marker = if node.access.is_usable() {
+            "✅"
+        } else {
+            "❌"
+        };
+        println!(
+            "  {} {}: {}",
+            marker,
+            node.path.display(),
+            node.access.label()
+        );
+    }
+
+    if !report.is_ok() {
+        println!();
+        println!(
+            "💡 Save these rules as /etc/udev/rules.d/{}:",
+            hidraw::UDEV_RULES_FILE
+        );
+        print!("{}", report.udev_rules());
+        println!("   then run: sudo udevadm control --reload-rules && sudo udevadm trigger");
+    }
+
+    Ok(())
+}
+
 /// Main debug function that runs all diagnostic checks
 pub fn run_full_debug() -> Result<()> {
     println!("🚀 AR Glasses USB Debug Tool");
@@ -136,14 +175,24 @@ pub fn run_full_debug() -> Result<()> {
     check_libusb_status()?;
     println!();
 
+    if cfg!(target_os = "linux") {
+        check_hidraw_access()?;
+        println!();
+    }
+
     debug_usb_devices()?;
 
     println!("🏁 Debug complete!");
     println!("If no AR glasses were found, try:");
     println!("1. Reconnecting the USB-C cable");
     println!("2. Checking if glasses are powered on");
-    println!("3. Running with sudo for USB permissions");
-    println!("4. Installing libusb: brew install libusb");
+    if cfg!(target_os = "linux") {
+        println!("3. Installing the udev rules printed above for hidraw permissions");
+        println!("4. Installing libusb with your package manager (e.g. libusb-1.0-0)");
+    } else {
+        println!("3. Running with sudo for USB permissions");
+        println!("4. Installing libusb: brew install libusb");
+    }
 
     Ok(())
 }
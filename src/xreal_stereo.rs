@@ -1,7 +1,14 @@
-use crate::driver::XRealDevice;
-use crate::tracking::Orientation;
+use crate::driver::connection::monitor_glasses_connection;
+use crate::driver::optics::{EyeOptics, DEFAULT_IPD};
+use crate::driver::{DisplayOptics, GlassesConnectionEvent, XRealDevice, XRealDisplayMode};
+use crate::neck;
+use crate::prediction::{predict_display_pose, PredictedOrientation};
+use crate::state::schema::core::PersistentAppState;
+use crate::DisplayModeState;
+use ar_drivers::Side;
+use bevy::math::Vec3A;
 use bevy::prelude::*;
-use bevy::render::camera::{ImageRenderTarget, RenderTarget};
+use bevy::render::camera::{CameraProjection, ImageRenderTarget, RenderTarget, SubCameraView};
 use bevy::render::render_resource::{
     Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 };
@@ -12,9 +19,19 @@ pub struct XRealStereoRenderingPlugin;
 
 impl Plugin for XRealStereoRenderingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_stereo_cameras)
-            .add_systems(Update, update_stereo_camera_transforms)
-            .add_systems(Update, validate_xreal_connection);
+        app.init_resource::<StereoSettings>()
+            .add_systems(
+                Update,
+                update_stereo_camera_transforms.after(predict_display_pose),
+            )
+            .add_systems(Update, apply_stereo_settings.after(rebuild_stereo_cameras))
+            .add_systems(
+                Update,
+                (rebuild_stereo_cameras, validate_xreal_connection)
+                    .chain()
+                    .after(monitor_glasses_connection),
+            )
+            .add_systems(Update, apply_display_mode_changes);
     }
 }
 
@@ -25,6 +42,17 @@ pub enum StereoEye {
     Right,
 }
 
+impl StereoEye {
+    /// Matching display side of the glasses
+    #[inline]
+    pub fn side(&self) -> Side {
+        match self {
+            StereoEye::Left => Side::Left,
+            StereoEye::Right => Side::Right,
+        }
+    }
+}
+
 /// Stereo render targets for left and right eye views
 #[derive(Resource)]
 pub struct StereoRenderTargets {
@@ -33,11 +61,14 @@ pub struct StereoRenderTargets {
     pub is_active: bool,
 }
 
-/// Eye separation distance for stereo rendering (in world units)
-#[derive(Resource)]
+/// Eye separation, zero-parallax distance and render resolution of the stereo cameras
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
 pub struct StereoSettings {
+    /// Interpupillary distance, in meters
     pub eye_separation: f32,
+    /// Distance of the plane both eyes see at the same place, in meters
     pub convergence_distance: f32,
+    /// Render target size relative to the display resolution
     pub render_scale: f32,
 }
 
@@ -45,169 +76,391 @@ impl Default for StereoSettings {
     #[inline]
     fn default() -> Self {
         Self {
-            eye_separation: 0.064,     // 64mm typical IPD
-            convergence_distance: 5.0, // 5 meters
-            render_scale: 1.0,         // Native resolution
+            eye_separation: DEFAULT_IPD, // 64mm typical IPD
+            convergence_distance: 5.0,   // 5 meters
+            render_scale: 1.0,           // Native resolution
         }
     }
 }
 
-/// Setup stereo cameras for XREAL glasses rendering
-/// Creates separate cameras for left and right eye views
-fn setup_stereo_cameras(
+impl StereoSettings {
+    /// Render target size for a display of `resolution`
+    pub fn render_resolution(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let scale = |pixels: u32| ((pixels as f32 * self.render_scale).round() as u32).max(1);
+        (scale(width), scale(height))
+    }
+}
+
+/// Asymmetric perspective projection of one eye
+///
+/// Both eyes look at the same rectangle on the convergence plane, centred
+/// between them and as large as the display FOV covers at that distance.
+/// Each frustum is shifted towards the nose by half the eye separation at
+/// that plane, so content on it has zero parallax while the eye cameras stay
+/// parallel. The result uses Bevy's reverse-Z infinite far plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OffAxisProjection {
+    /// Tangents of the frustum edges, positive right and up
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+    pub near: f32,
+    /// Far distance for culling and shadow cascades
+    pub far: f32,
+}
+
+impl OffAxisProjection {
+    /// Centred frustum with the given full FOVs, in radians
+    pub fn symmetric(fov_x: f32, fov_y: f32) -> Self {
+        let half_x = (fov_x * 0.5).tan();
+        let half_y = (fov_y * 0.5).tan();
+        let perspective = PerspectiveProjection::default();
+        Self {
+            left: -half_x,
+            right: half_x,
+            bottom: -half_y,
+            top: half_y,
+            near: perspective.near,
+            far: perspective.far,
+        }
+    }
+
+    /// Frustum of the eye `eye_offset` meters right of the eye centre that
+    /// converges with the other eye at `convergence_distance`
+    pub fn for_eye(optics: &EyeOptics, eye_offset: f32, convergence_distance: f32) -> Self {
+        let mut projection = Self::symmetric(optics.fov_x, optics.fov_y);
+        let shift = eye_offset / convergence_distance.max(f32::EPSILON);
+        projection.left -= shift;
+        projection.right -= shift;
+        projection
+    }
+
+    /// Frustum with edges at the given tangents
+    fn clip_from_view(&self, left: f32, right: f32, bottom: f32, top: f32) -> Mat4 {
+        let width = right - left;
+        let height = top - bottom;
+        Mat4::from_cols(
+            Vec4::new(2.0 / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / height, 0.0, 0.0),
+            Vec4::new((right + left) / width, (top + bottom) / height, 0.0, -1.0),
+            Vec4::new(0.0, 0.0, self.near, 0.0),
+        )
+    }
+}
+
+impl CameraProjection for OffAxisProjection {
+    fn get_clip_from_view(&self) -> Mat4 {
+        self.clip_from_view(self.left, self.right, self.bottom, self.top)
+    }
+
+    fn get_clip_from_view_for_sub(&self, sub_view: &SubCameraView) -> Mat4 {
+        let full = sub_view.full_size.as_vec2().max(Vec2::ONE);
+        let start = sub_view.offset / full;
+        let end = (sub_view.offset + sub_view.size.as_vec2()) / full;
+        let width = self.right - self.left;
+        let height = self.top - self.bottom;
+        // Sub view offsets count from the top left corner
+        self.clip_from_view(
+            self.left + width * start.x,
+            self.left + width * end.x,
+            self.top - height * end.y,
+            self.top - height * start.y,
+        )
+    }
+
+    /// The frustum follows the display optics rather than the target size
+    fn update(&mut self, _width: f32, _height: f32) {}
+
+    fn far(&self) -> f32 {
+        self.far
+    }
+
+    fn get_frustum_corners(&self, z_near: f32, z_far: f32) -> [Vec3A; 8] {
+        let corner = |x: f32, y: f32, z: f32| Vec3A::new(x * z.abs(), y * z.abs(), z);
+        // Same order as the built-in projections: bottom right, top right,
+        // top left, bottom left, near plane first
+        [
+            corner(self.right, self.bottom, z_near),
+            corner(self.right, self.top, z_near),
+            corner(self.left, self.top, z_near),
+            corner(self.left, self.bottom, z_near),
+            corner(self.right, self.bottom, z_far),
+            corner(self.right, self.top, z_far),
+            corner(self.left, self.top, z_far),
+            corner(self.left, self.bottom, z_far),
+        ]
+    }
+}
+
+/// Offset of an eye from the centre between both eyes, in meters, positive right
+fn eye_offset(optics: &DisplayOptics, side: Side, ipd: f32) -> f32 {
+    let center = (optics.eye_pose(Side::Left, ipd).translation.x
+        + optics.eye_pose(Side::Right, ipd).translation.x)
+        * 0.5;
+    optics.eye_pose(side, ipd).translation.x - center
+}
+
+/// Off-axis projection of one eye for the stereo settings
+pub fn eye_projection(
+    optics: &DisplayOptics,
+    side: Side,
+    settings: &StereoSettings,
+) -> OffAxisProjection {
+    OffAxisProjection::for_eye(
+        optics.eye(side),
+        eye_offset(optics, side, settings.eye_separation),
+        settings.convergence_distance,
+    )
+}
+
+/// Build the stereo cameras whenever glasses connect, the first connection
+/// included, and tear them down when the glasses go away
+fn rebuild_stereo_cameras(
     mut commands: Commands,
     mut images: ResMut<Assets<Image>>,
+    mut connection_events: EventReader<GlassesConnectionEvent>,
     xreal_device: Option<Res<XRealDevice>>,
+    stereo_settings: Option<Res<StereoSettings>>,
+    stereo_cameras: Query<Entity, With<StereoEye>>,
 ) {
-    if let Some(device) = xreal_device {
-        info!("🎯 Setting up stereo cameras for XREAL glasses...");
+    let Some(last_event) = connection_events.read().last() else {
+        return;
+    };
 
-        let (width, height) = device.get_display_resolution();
-        let stereo_width = width / 2; // Split screen for stereo
+    match last_event {
+        GlassesConnectionEvent::Connected { .. } => {
+            for entity in stereo_cameras.iter() {
+                commands.entity(entity).despawn();
+            }
+            if let Some(device) = xreal_device {
+                spawn_stereo_cameras(
+                    &mut commands,
+                    &mut images,
+                    &device,
+                    stereo_settings.as_deref(),
+                );
+            }
+        }
+        GlassesConnectionEvent::Lost { .. } | GlassesConnectionEvent::Disconnected { .. } => {
+            if !stereo_cameras.is_empty() {
+                info!("🧹 Removing stereo cameras until the glasses reconnect");
+            }
+            for entity in stereo_cameras.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+        GlassesConnectionEvent::Probing { .. } => {}
+    }
+}
 
-        // Create render targets for stereo rendering
-        let size = Extent3d {
-            width: stereo_width,
-            height,
-            depth_or_array_layers: 1,
-        };
+/// Create render targets and left/right eye cameras for the connected glasses
+/// Projections and eye poses follow the optics reported for the device
+fn spawn_stereo_cameras(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    device: &XRealDevice,
+    stereo_settings: Option<&StereoSettings>,
+) {
+    info!("🎯 Setting up stereo cameras for XREAL glasses...");
 
-        // Left eye render target
-        let left_image = images.add(Image {
-            texture_descriptor: TextureDescriptor {
-                label: Some("xreal_left_eye_render_target"),
-                size,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::Bgra8UnormSrgb,
-                mip_level_count: 1,
-                sample_count: 1,
-                usage: TextureUsages::TEXTURE_BINDING
-                    | TextureUsages::COPY_DST
-                    | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-            ..default()
-        });
-
-        // Right eye render target
-        let right_image = images.add(Image {
-            texture_descriptor: TextureDescriptor {
-                label: Some("xreal_right_eye_render_target"),
-                size,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::Bgra8UnormSrgb,
-                mip_level_count: 1,
-                sample_count: 1,
-                usage: TextureUsages::TEXTURE_BINDING
-                    | TextureUsages::COPY_DST
-                    | TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            },
-            ..default()
-        });
+    let optics = device.optics();
+    let settings = stereo_settings.copied().unwrap_or_default();
+    let ipd = settings.eye_separation;
 
-        // Create stereo render targets resource
-        commands.insert_resource(StereoRenderTargets {
-            left_image: left_image.clone(),
-            right_image: right_image.clone(),
-            is_active: device.is_stereo_enabled(),
-        });
+    let left_image = images.add(eye_render_target(
+        "xreal_left_eye_render_target",
+        settings.render_resolution(optics.left.resolution),
+    ));
+    let right_image = images.add(eye_render_target(
+        "xreal_right_eye_render_target",
+        settings.render_resolution(optics.right.resolution),
+    ));
 
-        // Create stereo settings resource
-        commands.insert_resource(StereoSettings::default());
+    // Create stereo render targets resource
+    commands.insert_resource(StereoRenderTargets {
+        left_image: left_image.clone(),
+        right_image: right_image.clone(),
+        is_active: device.is_stereo_enabled(),
+    });
+    commands.insert_resource(optics);
 
-        // Setup left eye camera
+    for (eye, image, order, name) in [
+        (StereoEye::Left, left_image, 0, "XReal Left Eye Camera"),
+        (StereoEye::Right, right_image, 1, "XReal Right Eye Camera"),
+    ] {
         commands.spawn((
-            Name::new("XReal Left Eye Camera"),
+            Name::new(name),
             Camera3d::default(),
             Camera {
-                order: 0,
+                order,
                 target: RenderTarget::Image(ImageRenderTarget {
-                    handle: left_image,
+                    handle: image,
                     scale_factor: bevy::math::FloatOrd(1.0),
                 }),
                 ..default()
             },
-            Transform::from_xyz(-0.032, 0.0, 0.0), // Half IPD offset
+            Projection::custom(eye_projection(&optics, eye.side(), &settings)),
+            optics.eye_pose(eye.side(), ipd),
             GlobalTransform::default(),
             Visibility::default(),
-            StereoEye::Left,
+            eye,
         ));
+    }
 
-        // Setup right eye camera
-        commands.spawn((
-            Name::new("XReal Right Eye Camera"),
-            Camera3d::default(),
-            Camera {
-                order: 1,
-                target: RenderTarget::Image(ImageRenderTarget {
-                    handle: right_image,
-                    scale_factor: bevy::math::FloatOrd(1.0),
-                }),
-                ..default()
-            },
-            Transform::from_xyz(0.032, 0.0, 0.0), // Half IPD offset
-            GlobalTransform::default(),
-            Visibility::default(),
-            StereoEye::Right,
-        ));
+    let (width, height) = settings.render_resolution(optics.left.resolution);
+    info!(
+        "✅ Stereo cameras configured for {}x{} per eye, {:.1}° horizontal FOV, converging at {:.2} m ({:?})",
+        width,
+        height,
+        optics.left.fov_x.to_degrees(),
+        settings.convergence_distance,
+        optics.source
+    );
+}
 
-        info!(
-            "✅ Stereo cameras configured for {}x{} resolution",
-            width, height
-        );
-    } else {
-        info!("🖥️  No XREAL device detected - skipping stereo camera setup");
+/// Re-project the eye cameras and resize their render targets whenever the
+/// stereo settings or the display optics change
+fn apply_stereo_settings(
+    stereo_settings: Res<StereoSettings>,
+    optics: Option<Res<DisplayOptics>>,
+    stereo_targets: Option<Res<StereoRenderTargets>>,
+    mut images: ResMut<Assets<Image>>,
+    mut stereo_cameras: Query<(&mut Projection, &StereoEye)>,
+) {
+    let Some(optics) = optics else {
+        return;
+    };
+    if !(stereo_settings.is_changed() || optics.is_changed()) {
+        return;
+    }
+
+    for (mut projection, eye) in stereo_cameras.iter_mut() {
+        *projection = Projection::custom(eye_projection(&optics, eye.side(), &stereo_settings));
+    }
+
+    let Some(targets) = stereo_targets else {
+        return;
+    };
+    for (handle, label, eye_optics) in [
+        (
+            &targets.left_image,
+            "xreal_left_eye_render_target",
+            &optics.left,
+        ),
+        (
+            &targets.right_image,
+            "xreal_right_eye_render_target",
+            &optics.right,
+        ),
+    ] {
+        let resolution = stereo_settings.render_resolution(eye_optics.resolution);
+        let Some(size) = images
+            .get(handle)
+            .map(|image| image.texture_descriptor.size)
+        else {
+            continue;
+        };
+        if (size.width, size.height) == resolution {
+            continue;
+        }
+        if let Some(image) = images.get_mut(handle) {
+            *image = eye_render_target(label, resolution);
+        }
+    }
+}
+
+/// Render target image for one eye
+fn eye_render_target(label: &'static str, (width, height): (u32, u32)) -> Image {
+    Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
     }
 }
 
 /// Update stereo camera transforms based on head tracking
-/// Zero-allocation transform updates with blazing-fast performance
+/// Eye poses are rotated from the IMU frame by the head orientation
+/// predicted for display time and moved with the eyes by the neck model
 fn update_stereo_camera_transforms(
-    orientation: Res<Orientation>,
+    orientation: Res<PredictedOrientation>,
     stereo_settings: Option<Res<StereoSettings>>,
+    optics: Option<Res<DisplayOptics>>,
+    persistent_state: Res<PersistentAppState>,
     mut stereo_cameras: Query<(&mut Transform, &StereoEye)>,
 ) {
-    if orientation.is_changed() {
-        let base_rotation = orientation.quat;
-        let eye_offset = if let Some(settings) = stereo_settings {
-            settings.eye_separation * 0.5
-        } else {
-            0.032 // Default 64mm IPD
-        };
-
-        for (mut transform, eye) in stereo_cameras.iter_mut() {
-            // Apply head tracking rotation
-            transform.rotation = base_rotation;
+    let settings_changed = stereo_settings
+        .as_ref()
+        .is_some_and(|settings| settings.is_changed());
+    let optics_changed = optics.as_ref().is_some_and(|optics| optics.is_changed());
+    if !(orientation.is_changed()
+        || settings_changed
+        || optics_changed
+        || persistent_state.is_changed())
+    {
+        return;
+    }
 
-            // Apply stereo eye offset
-            let eye_translation = match eye {
-                StereoEye::Left => Vec3::new(-eye_offset, 0.0, 0.0),
-                StereoEye::Right => Vec3::new(eye_offset, 0.0, 0.0),
-            };
+    let base_rotation = orientation.quat;
+    let eye_center = neck::eye_position(base_rotation, &persistent_state.tracking.neck_model);
+    let ipd = stereo_settings.map_or(DEFAULT_IPD, |settings| settings.eye_separation);
+    let optics = optics.map(|optics| *optics).unwrap_or_default();
 
-            // Rotate eye offset by head orientation
-            let rotated_offset = base_rotation * eye_translation;
-            transform.translation = rotated_offset;
-        }
+    for (mut transform, eye) in stereo_cameras.iter_mut() {
+        let eye_pose = optics.eye_pose(eye.side(), ipd);
+        transform.rotation = base_rotation * eye_pose.rotation;
+        transform.translation = eye_center + base_rotation * eye_pose.translation;
     }
 }
 
 /// Validate XREAL connection and update stereo rendering state
 /// Ensures stereo rendering remains synchronized with device state
 fn validate_xreal_connection(
-    xreal_device: Option<ResMut<XRealDevice>>,
+    xreal_device: Option<Res<XRealDevice>>,
     stereo_targets: Option<ResMut<StereoRenderTargets>>,
 ) {
-    if let (Some(mut device), Some(mut targets)) = (xreal_device, stereo_targets) {
-        // Validate connection without blocking
-        if let Ok(connected) = device.validate_connection() {
-            if !connected {
-                warn!("⚠️  XREAL glasses connection lost");
-                targets.is_active = false;
-            } else {
-                targets.is_active = device.is_stereo_enabled();
-            }
+    let Some(device) = xreal_device else {
+        return;
+    };
+
+    // Status comes from the device broker, so this never touches the IMU stream
+    if let Some(mut targets) = stereo_targets {
+        targets.is_active = device.is_connected() && device.is_stereo_enabled();
+    }
+}
+
+/// Forward pending 2D/3D display mode requests to the glasses
+fn apply_display_mode_changes(
+    mut display_mode: ResMut<DisplayModeState>,
+    xreal_device: Option<ResMut<XRealDevice>>,
+) {
+    let Some(enabled) = display_mode.pending_change.take() else {
+        return;
+    };
+    display_mode.is_3d_enabled = enabled;
+
+    if let Some(mut device) = xreal_device {
+        let mode = if enabled {
+            XRealDisplayMode::Stereo
+        } else {
+            XRealDisplayMode::Mirror
+        };
+        if let Err(e) = device.set_display_mode(mode) {
+            error!("❌ Failed to request display mode change: {}", e);
         }
     }
 }
@@ -0,0 +1,338 @@
+//! Tests for the device broker thread
+
+use ar_drivers::{ARGlasses, DisplayMode, GlassesEvent, Side};
+use bevy::math::Vec3;
+use nalgebra::Isometry3;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use xreal_virtual_desktop::driver::simulated::{
+    MotionScript, ScriptedEvent, SimulatedEvent, SimulatedGlasses, SimulationConfig,
+};
+use xreal_virtual_desktop::driver::*;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn fast_reconnect() -> BrokerConfig {
+    BrokerConfig {
+        reconnect: true,
+        backoff: ReconnectBackoff {
+            initial: Duration::from_millis(5),
+            max: Duration::from_millis(20),
+            multiplier: 2.0,
+        },
+    }
+}
+
+fn spawn_simulated(config: SimulationConfig) -> DeviceBroker {
+    DeviceBroker::spawn_with(
+        move || Ok(Box::new(SimulatedGlasses::new(config.clone())) as Box<dyn ARGlasses>),
+        BrokerConfig::default(),
+    )
+    .expect("broker thread should spawn")
+}
+
+/// Skip probing updates until the next status transition
+fn next_transition(broker: &DeviceBroker) -> DeviceStatus {
+    loop {
+        match broker.status.recv_timeout(TIMEOUT).unwrap() {
+            DeviceStatus::Probing { .. } => continue,
+            status => return status,
+        }
+    }
+}
+
+fn wait_connected(broker: &DeviceBroker) -> DeviceIdentity {
+    match next_transition(broker) {
+        DeviceStatus::Connected(identity) => identity,
+        other => panic!("expected Connected, got {:?}", other),
+    }
+}
+
+/// Simulated glasses that fail after a fixed number of reads, like an unplug
+struct FlakyGlasses {
+    inner: SimulatedGlasses,
+    reads_left: u32,
+}
+
+impl ARGlasses for FlakyGlasses {
+    fn serial(&mut self) -> Result<String, ar_drivers::Error> {
+        self.inner.serial()
+    }
+
+    fn read_event(&mut self) -> Result<GlassesEvent, ar_drivers::Error> {
+        if self.reads_left == 0 {
+            return Err(ar_drivers::Error::Other("Device unplugged"));
+        }
+        self.reads_left -= 1;
+        self.inner.read_event()
+    }
+
+    fn get_display_mode(&mut self) -> Result<DisplayMode, ar_drivers::Error> {
+        self.inner.get_display_mode()
+    }
+
+    fn set_display_mode(&mut self, display_mode: DisplayMode) -> Result<(), ar_drivers::Error> {
+        self.inner.set_display_mode(display_mode)
+    }
+
+    fn display_fov(&self) -> f32 {
+        self.inner.display_fov()
+    }
+
+    fn imu_to_display_matrix(&self, side: Side, ipd: f32) -> Isometry3<f64> {
+        self.inner.imu_to_display_matrix(side, ipd)
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn display_delay(&self) -> u64 {
+        self.inner.display_delay()
+    }
+}
+
+#[test]
+fn test_reports_identity_on_connect() {
+    let broker = spawn_simulated(SimulationConfig::default());
+    let identity = wait_connected(&broker);
+
+    assert_eq!(identity.name, simulated::SIMULATED_DEVICE_NAME);
+    assert!(identity.serial.starts_with("SIM-"));
+    assert_eq!(identity.display_mode, Some(DisplayMode::SameOnBoth));
+
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_fans_out_events_by_type() {
+    let config = SimulationConfig {
+        motion: MotionScript::constant_rotation(Vec3::Y, 1.0),
+        events: vec![
+            ScriptedEvent {
+                at_secs: 0.01,
+                event: SimulatedEvent::KeyPress(1),
+            },
+            ScriptedEvent {
+                at_secs: 0.02,
+                event: SimulatedEvent::ProximityNear,
+            },
+        ],
+        ..SimulationConfig::default()
+    };
+    let broker = spawn_simulated(config);
+    wait_connected(&broker);
+
+    let key = broker.keys.recv_timeout(TIMEOUT).unwrap();
+    assert_eq!(key.key, 1);
+    assert_eq!(
+        broker.proximity.recv_timeout(TIMEOUT).unwrap(),
+        ProximityEvent::Near
+    );
+
+    let mut saw_gyro = false;
+    let mut saw_mag = false;
+    while !(saw_gyro && saw_mag) {
+        match broker.imu.recv_timeout(TIMEOUT).unwrap() {
+            ImuEvent::AccGyro { gyroscope, .. } => {
+                assert!((gyroscope.y - 1.0).abs() < 0.05);
+                saw_gyro = true;
+            }
+            ImuEvent::Magnetometer { .. } => saw_mag = true,
+        }
+    }
+
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_applies_display_commands() {
+    let broker = spawn_simulated(SimulationConfig::default());
+    wait_connected(&broker);
+
+    broker
+        .commands
+        .send(DeviceCommand::SetDisplayMode(DisplayMode::Stereo))
+        .unwrap();
+    assert_eq!(
+        broker.status.recv_timeout(TIMEOUT).unwrap(),
+        DeviceStatus::DisplayModeChanged(DisplayMode::Stereo)
+    );
+
+    drop(broker.commands);
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_reports_open_failure() {
+    let config = BrokerConfig {
+        reconnect: false,
+        ..BrokerConfig::default()
+    };
+    let broker = DeviceBroker::spawn_with(|| Err(anyhow::anyhow!("no glasses")), config).unwrap();
+
+    assert_eq!(
+        broker.status.recv_timeout(TIMEOUT).unwrap(),
+        DeviceStatus::Probing { attempt: 1 }
+    );
+    match broker.status.recv_timeout(TIMEOUT).unwrap() {
+        DeviceStatus::Disconnected { reason, retry_in } => {
+            assert!(reason.contains("no glasses"));
+            assert_eq!(retry_in, None);
+        }
+        other => panic!("expected Disconnected, got {:?}", other),
+    }
+    broker.thread.join().unwrap();
+    assert!(broker.imu.recv_timeout(TIMEOUT).is_err());
+}
+
+#[test]
+fn test_backoff_grows_and_caps() {
+    let backoff = ReconnectBackoff::default();
+    assert_eq!(backoff.delay(1), backoff.initial);
+    assert_eq!(backoff.delay(2), backoff.initial * 2);
+    assert_eq!(backoff.delay(3), backoff.initial * 4);
+    assert_eq!(backoff.delay(100), backoff.max);
+}
+
+#[test]
+fn test_retries_until_glasses_appear() {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let counter = attempts.clone();
+    let broker = DeviceBroker::spawn_with(
+        move || {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(anyhow::anyhow!("no glasses"))
+            } else {
+                Ok(Box::new(SimulatedGlasses::new(SimulationConfig::default()))
+                    as Box<dyn ARGlasses>)
+            }
+        },
+        fast_reconnect(),
+    )
+    .unwrap();
+
+    let backoff = fast_reconnect().backoff;
+    for expected in [backoff.delay(1), backoff.delay(2)] {
+        match next_transition(&broker) {
+            DeviceStatus::Disconnected { retry_in, .. } => assert_eq!(retry_in, Some(expected)),
+            other => panic!("expected Disconnected, got {:?}", other),
+        }
+    }
+    wait_connected(&broker);
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_reconnects_after_lost_device() {
+    let broker = DeviceBroker::spawn_with(
+        || {
+            Ok(Box::new(FlakyGlasses {
+                inner: SimulatedGlasses::new(SimulationConfig::default()),
+                reads_left: 20,
+            }) as Box<dyn ARGlasses>)
+        },
+        fast_reconnect(),
+    )
+    .unwrap();
+
+    let first = wait_connected(&broker);
+    match next_transition(&broker) {
+        DeviceStatus::Lost { reason } => assert!(reason.contains("unplugged")),
+        other => panic!("expected Lost, got {:?}", other),
+    }
+    let second = wait_connected(&broker);
+    assert_eq!(first.name, second.name);
+
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_display_mode_fails_while_disconnected() {
+    let config = BrokerConfig {
+        reconnect: true,
+        backoff: ReconnectBackoff {
+            initial: Duration::from_secs(5),
+            ..ReconnectBackoff::default()
+        },
+    };
+    let broker = DeviceBroker::spawn_with(|| Err(anyhow::anyhow!("no glasses")), config).unwrap();
+    next_transition(&broker);
+
+    broker
+        .commands
+        .send(DeviceCommand::SetDisplayMode(DisplayMode::Stereo))
+        .unwrap();
+    assert!(matches!(
+        broker.status.recv_timeout(TIMEOUT).unwrap(),
+        DeviceStatus::DisplayModeFailed(_)
+    ));
+
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_full_imu_channel_drops_the_oldest_samples() {
+    let broker = spawn_simulated(SimulationConfig {
+        mag_rate_hz: 0,
+        realtime: false,
+        ..Default::default()
+    });
+    wait_connected(&broker);
+
+    // Unpaced glasses fill the channel at once and keep producing
+    let deadline = std::time::Instant::now() + TIMEOUT;
+    while broker.imu.len() < broker::IMU_CHANNEL_CAPACITY {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "IMU channel never filled"
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    std::thread::sleep(Duration::from_millis(50));
+
+    // At 1 kHz the first sample of a full channel would be stamped 0 µs
+    match broker.imu.recv_timeout(TIMEOUT).unwrap() {
+        ImuEvent::AccGyro { timestamp, .. } => assert!(timestamp > 0),
+        other => panic!("expected an IMU sample, got {:?}", other),
+    }
+
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+}
+
+#[test]
+fn test_records_through_broker() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("session.xrimu");
+
+    let broker = spawn_simulated(SimulationConfig::default());
+    wait_connected(&broker);
+    broker
+        .commands
+        .send(DeviceCommand::StartRecording {
+            path: path.clone(),
+            calibration: recording::RecordedCalibration {
+                gyro_bias: [0.5; 3],
+                ..Default::default()
+            },
+        })
+        .unwrap();
+    for _ in 0..50 {
+        broker.imu.recv_timeout(TIMEOUT).unwrap();
+    }
+    broker.commands.send(DeviceCommand::Shutdown).unwrap();
+    broker.thread.join().unwrap();
+
+    let mut reader = recording::SessionReader::open(&path).unwrap();
+    assert_eq!(reader.header().calibration.gyro_bias, [0.5; 3]);
+    assert!(reader.next_event().unwrap().is_some());
+}
@@ -0,0 +1,26 @@
+//! Driver integration tests
+//!
+//! Tests for glasses sources, simulated devices, session recordings,
+//! event streams and stereo eye projections that run without physical
+//! hardware attached.
+
+pub mod broker_test;
+pub mod optics_test;
+pub mod profile_test;
+pub mod proximity_test;
+pub mod recording_test;
+pub mod simulator_test;
+pub mod stereo_test;
+
+use xreal_virtual_desktop::driver::simulated::SimulationConfig;
+
+/// Noise-free, unbiased simulation that runs as fast as it is polled
+pub fn ideal_simulation() -> SimulationConfig {
+    SimulationConfig {
+        gyro_noise_std: 0.0,
+        accel_noise_std: 0.0,
+        mag_noise_std: 0.0,
+        realtime: false,
+        ..Default::default()
+    }
+}
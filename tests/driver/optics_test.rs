@@ -0,0 +1,99 @@
+//! Tests for per-eye display optics
+
+use ar_drivers::{DisplayMatrices, Side};
+use nalgebra::{Matrix3, Translation3, UnitQuaternion};
+use xreal_virtual_desktop::driver::optics::{
+    DisplayOptics, DisplayProfile, OpticsSource, DEFAULT_IPD,
+};
+use xreal_virtual_desktop::driver::{SimulatedGlasses, SimulationConfig};
+
+const EPSILON: f32 = 1e-4;
+
+fn display(x: f64, yaw: f64) -> DisplayMatrices {
+    DisplayMatrices {
+        intrinsic_matrix: Matrix3::new(1000.0, 0.0, 960.0, 0.0, 1000.0, 540.0, 0.0, 0.0, 1.0),
+        resolution: (1920, 1080),
+        isometry: Translation3::new(x, 0.0, 0.0) * UnitQuaternion::from_euler_angles(0.0, yaw, 0.0),
+    }
+}
+
+#[test]
+fn test_calibration_drives_fov_and_eye_poses() {
+    let optics = DisplayOptics::from_calibration(&display(-0.03, -0.01), &display(0.03, 0.01))
+        .expect("valid calibration");
+
+    assert_eq!(optics.source, OpticsSource::Calibration);
+    assert!((optics.ipd - 0.06).abs() < EPSILON);
+    assert_eq!(optics.left.resolution, (1920, 1080));
+    assert!((optics.left.fov_x - 2.0 * 0.96f32.atan()).abs() < EPSILON);
+    assert!((optics.left.fov_y - 2.0 * 0.54f32.atan()).abs() < EPSILON);
+    assert!((optics.left.pose.translation.x + 0.03).abs() < EPSILON);
+
+    // The display rotation from the IMU frame is kept
+    let (yaw, _, _) = optics
+        .right
+        .pose
+        .rotation
+        .to_euler(bevy::math::EulerRot::YXZ);
+    assert!((yaw - 0.01).abs() < EPSILON);
+}
+
+#[test]
+fn test_invalid_calibration_is_rejected() {
+    let mut broken = display(0.03, 0.0);
+    broken.intrinsic_matrix = Matrix3::zeros();
+    assert!(DisplayOptics::from_calibration(&display(-0.03, 0.0), &broken).is_none());
+}
+
+#[test]
+fn test_unsupported_calibration_falls_back_to_profile() {
+    let config = SimulationConfig {
+        display_fov: 20f32.to_radians(),
+        ..SimulationConfig::default()
+    };
+    let glasses = SimulatedGlasses::new(config);
+    let optics = DisplayOptics::from_glasses(&glasses);
+
+    assert_eq!(optics.source, OpticsSource::Profile);
+    assert_eq!(optics.left.resolution, DisplayProfile::GENERIC.resolution);
+    assert!((optics.left.fov_x - 40f32.to_radians()).abs() < EPSILON);
+    assert!(optics.left.fov_y < optics.left.fov_x);
+    assert!((optics.ipd - DEFAULT_IPD).abs() < EPSILON);
+    assert!((optics.right.pose.translation.x - DEFAULT_IPD * 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn test_invalid_fov_uses_model_profile() {
+    let config = SimulationConfig {
+        display_fov: 0.0,
+        ..SimulationConfig::default()
+    };
+    let optics = DisplayOptics::from_glasses(&SimulatedGlasses::new(config));
+    let expected = DisplayProfile::GENERIC.half_fov() * 2.0;
+    assert!((optics.left.fov_x - expected).abs() < EPSILON);
+}
+
+#[test]
+fn test_profile_lookup_by_model_name() {
+    assert_eq!(DisplayProfile::for_model("Rokid Max").half_fov_deg, 23.0);
+    assert_eq!(
+        DisplayProfile::for_model("XREAL Air 2 Pro").half_fov_deg,
+        24.0
+    );
+    assert_eq!(
+        DisplayProfile::for_model("Unknown Glasses"),
+        &DisplayProfile::GENERIC
+    );
+}
+
+#[test]
+fn test_eye_pose_follows_configured_ipd() {
+    let optics = DisplayOptics::from_calibration(&display(-0.03, 0.0), &display(0.03, 0.0))
+        .expect("valid calibration");
+
+    let left = optics.eye_pose(Side::Left, 0.07);
+    let right = optics.eye_pose(Side::Right, 0.07);
+    assert!((left.translation.x + 0.035).abs() < EPSILON);
+    assert!((right.translation.x - 0.035).abs() < EPSILON);
+    assert!((optics.eye_pose(Side::Right, optics.ipd).translation.x - 0.03).abs() < EPSILON);
+}
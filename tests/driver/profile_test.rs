@@ -0,0 +1,99 @@
+//! Tests for the per-model device profile registry
+
+use ar_drivers::DisplayMode;
+use bevy::math::Vec3;
+use xreal_virtual_desktop::driver::profile::{Axis, ImuAxes};
+use xreal_virtual_desktop::driver::{
+    DeviceIdentity, DeviceInfo, DeviceProfile, DisplayOptics, DisplayProfile, OpticsSource,
+};
+
+const MODELS: &[&str] = &[
+    "XREAL Air",
+    "XREAL Air 2",
+    "XREAL Air 2 Pro",
+    "Nreal Light",
+    "Rokid Air",
+    "Rokid Max",
+    "Mad Gaze Glow",
+    "Grawoow G530",
+];
+
+fn identity(name: &'static str, display_mode: Option<DisplayMode>) -> DeviceIdentity {
+    DeviceIdentity {
+        name,
+        serial: "SN-0042".to_string(),
+        display_fov: 0.4,
+        display_delay_us: 0,
+        display_mode,
+        optics: DisplayOptics::default(),
+    }
+}
+
+#[test]
+fn test_every_supported_model_has_a_profile() {
+    for name in MODELS {
+        let profile = DeviceProfile::for_model(name);
+        assert_eq!(profile.name, *name);
+        assert!(!profile.is_generic());
+        assert!(!profile.refresh_rates.is_empty());
+        assert!(profile.refresh_rates.windows(2).all(|w| w[0] < w[1]));
+        assert!(profile.supports_mode(DisplayMode::SameOnBoth));
+        assert!(!profile.buttons.is_empty());
+        assert_eq!(DisplayProfile::for_model(name), &profile.display);
+    }
+}
+
+#[test]
+fn test_unknown_model_uses_generic_profile() {
+    let profile = DeviceProfile::for_model("Prototype Glasses");
+    assert!(profile.is_generic());
+    assert_eq!(profile.display, DisplayProfile::GENERIC);
+    assert_eq!(profile.max_refresh_rate(), 60);
+}
+
+#[test]
+fn test_refresh_rate_follows_display_mode() {
+    let profile = DeviceProfile::for_model("XREAL Air 2 Pro");
+    assert_eq!(profile.refresh_rate(DisplayMode::SameOnBoth), 60);
+    assert_eq!(profile.refresh_rate(DisplayMode::Stereo), 60);
+    assert_eq!(profile.refresh_rate(DisplayMode::HighRefreshRate), 120);
+
+    let light = DeviceProfile::for_model("Nreal Light");
+    assert_eq!(light.refresh_rate(DisplayMode::HighRefreshRate), 60);
+    assert!(!light.supports_mode(DisplayMode::HighRefreshRate));
+}
+
+#[test]
+fn test_device_info_from_identity() {
+    let info =
+        DeviceInfo::from_identity(&identity("XREAL Air", Some(DisplayMode::HighRefreshRate)));
+    assert_eq!(info.profile.name, "XREAL Air");
+    assert_eq!(info.serial, "SN-0042");
+    assert_eq!(info.optics_source, OpticsSource::Profile);
+    assert_eq!(info.refresh_rate(), 72);
+
+    let unknown_mode = DeviceInfo::from_identity(&identity("Rokid Max", None));
+    assert_eq!(unknown_mode.refresh_rate(), 60);
+}
+
+#[test]
+fn test_button_labels() {
+    let profile = DeviceProfile::for_model("XREAL Air 2");
+    assert_eq!(profile.button_label(1), Some("Brightness up"));
+    assert_eq!(profile.button_label(9), None);
+}
+
+#[test]
+fn test_imu_axes_conversion() {
+    let v = Vec3::new(1.0, 2.0, 3.0);
+    assert_eq!(ImuAxes::RUB.to_bevy(v), v);
+    assert_eq!(ImuAxes::RUB.label(), "RUB");
+
+    let flipped = ImuAxes {
+        x: Axis::Right,
+        y: Axis::Down,
+        z: Axis::Forward,
+    };
+    assert_eq!(flipped.to_bevy(v), Vec3::new(1.0, -2.0, -3.0));
+    assert_eq!(flipped.label(), "RDF");
+}
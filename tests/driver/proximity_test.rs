@@ -0,0 +1,74 @@
+//! Tests for proximity driven auto-pause
+
+use std::time::{Duration, Instant};
+use xreal_virtual_desktop::driver::proximity::{AutoPauseController, AutoPauseEvent};
+
+const DELAY: Duration = Duration::from_millis(1000);
+
+#[test]
+fn test_pauses_after_delay() {
+    let mut controller = AutoPauseController::default();
+    let start = Instant::now();
+
+    controller.glasses_removed(start);
+    assert_eq!(controller.poll(start + DELAY / 2, DELAY, true), None);
+    assert!(!controller.is_paused());
+
+    assert_eq!(
+        controller.poll(start + DELAY, DELAY, true),
+        Some(AutoPauseEvent::Paused)
+    );
+    assert!(controller.is_paused());
+    assert_eq!(controller.poll(start + DELAY * 2, DELAY, true), None);
+}
+
+#[test]
+fn test_putting_on_before_delay_cancels_pause() {
+    let mut controller = AutoPauseController::default();
+    let start = Instant::now();
+
+    controller.glasses_removed(start);
+    assert_eq!(controller.glasses_worn(), None);
+    assert_eq!(controller.poll(start + DELAY * 2, DELAY, true), None);
+    assert!(!controller.is_paused());
+}
+
+#[test]
+fn test_wakes_when_put_back_on() {
+    let mut controller = AutoPauseController::default();
+    let start = Instant::now();
+
+    controller.glasses_removed(start);
+    controller.poll(start + DELAY, DELAY, true);
+    assert_eq!(controller.glasses_worn(), Some(AutoPauseEvent::Resumed));
+    assert!(!controller.is_paused());
+}
+
+#[test]
+fn test_disabled_never_pauses_and_resumes() {
+    let mut controller = AutoPauseController::default();
+    let start = Instant::now();
+
+    controller.glasses_removed(start);
+    assert_eq!(controller.poll(start + DELAY, DELAY, false), None);
+    assert!(!controller.is_paused());
+
+    controller.glasses_removed(start);
+    controller.poll(start + DELAY, DELAY, true);
+    assert_eq!(
+        controller.poll(start + DELAY, DELAY, false),
+        Some(AutoPauseEvent::Resumed)
+    );
+}
+
+#[test]
+fn test_zero_delay_pauses_immediately() {
+    let mut controller = AutoPauseController::default();
+    let start = Instant::now();
+
+    controller.glasses_removed(start);
+    assert_eq!(
+        controller.poll(start, Duration::ZERO, true),
+        Some(AutoPauseEvent::Paused)
+    );
+}
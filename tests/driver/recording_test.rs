@@ -0,0 +1,215 @@
+//! Tests for IMU session recording and replay
+
+use ar_drivers::{ARGlasses, GlassesEvent};
+use bevy::math::Vec3;
+use std::io::Cursor;
+use xreal_virtual_desktop::driver::recording::*;
+use xreal_virtual_desktop::driver::simulated::{
+    MotionScript, ScriptedEvent, SimulatedEvent, SimulatedGlasses, SimulationConfig,
+};
+use xreal_virtual_desktop::driver::GlassesSource;
+use xreal_virtual_desktop::state::schema::calibration::GyroBiasPoint;
+
+fn simulated() -> SimulatedGlasses {
+    SimulatedGlasses::new(SimulationConfig {
+        motion: MotionScript::oscillation(Vec3::Y, 0.5, 1.0),
+        events: vec![ScriptedEvent {
+            at_secs: 0.05,
+            event: SimulatedEvent::KeyPress(2),
+        }],
+        realtime: false,
+        ..Default::default()
+    })
+}
+
+fn calibration() -> RecordedCalibration {
+    RecordedCalibration {
+        gyro_bias: [0.01, 0.02, 0.03],
+        accel_bias: [0.1, 0.2, 0.3],
+        accel_correction: [[1.01, 0.0, 0.0], [0.002, 0.99, 0.0], [0.0, 0.0, 1.0]],
+        mag_bias: [1.0, 2.0, 3.0],
+        mag_soft_iron: [[0.9, 0.05, 0.0], [0.05, 1.1, 0.0], [0.0, 0.0, 1.0]],
+        gyro_bias_points: vec![
+            GyroBiasPoint {
+                temperature_celsius: 28.0,
+                bias: [0.01, 0.02, 0.03],
+                samples: 5000,
+            },
+            GyroBiasPoint {
+                temperature_celsius: 34.0,
+                bias: [0.012, 0.025, 0.03],
+                samples: 1200,
+            },
+        ],
+    }
+}
+
+fn record_session(glasses: &mut SimulatedGlasses, count: usize) -> (Vec<u8>, Vec<GlassesEvent>) {
+    let header = RecordingHeader::for_glasses(glasses).with_calibration(calibration());
+    let mut recorder = SessionRecorder::new(Vec::new(), &header).unwrap();
+    let mut events = Vec::with_capacity(count);
+    for _ in 0..count {
+        let event = glasses.read_event().unwrap();
+        recorder.record(&event).unwrap();
+        events.push(event);
+    }
+    assert_eq!(recorder.event_count(), count as u64);
+    (recorder.finish().unwrap(), events)
+}
+
+#[test]
+fn test_header_round_trip() {
+    let mut glasses = simulated();
+    let (bytes, _) = record_session(&mut glasses, 10);
+
+    let reader = SessionReader::new(Cursor::new(bytes)).unwrap();
+    let header = reader.header();
+    assert_eq!(header.device_name, glasses.name());
+    assert_eq!(header.serial, glasses.serial().unwrap());
+    assert_eq!(header.calibration, calibration());
+    assert_eq!(header.display_fov, glasses.display_fov());
+}
+
+#[test]
+fn test_reads_version_1_recordings() {
+    let mut bytes = RECORDING_MAGIC.to_vec();
+    bytes.extend(1u16.to_le_bytes());
+    for text in ["XREAL Air", "ABC123"] {
+        bytes.extend((text.len() as u16).to_le_bytes());
+        bytes.extend(text.as_bytes());
+    }
+    bytes.extend(1_700_000_000_000u64.to_le_bytes());
+    bytes.extend(0.4f32.to_le_bytes());
+    bytes.extend(15_000u64.to_le_bytes());
+    for value in [0.01f32, 0.02, 0.03, 0.1, 0.2, 0.3, 1.0, 2.0, 3.0] {
+        bytes.extend(value.to_le_bytes());
+    }
+    // One VSync record
+    bytes.push(7);
+    bytes.extend(1000u64.to_le_bytes());
+
+    let mut reader = SessionReader::new(Cursor::new(bytes)).unwrap();
+    let header = reader.header();
+    assert_eq!(header.serial, "ABC123");
+    assert_eq!(
+        header.calibration,
+        RecordedCalibration {
+            gyro_bias: [0.01, 0.02, 0.03],
+            accel_bias: [0.1, 0.2, 0.3],
+            mag_bias: [1.0, 2.0, 3.0],
+            ..RecordedCalibration::default()
+        }
+    );
+    let recorded = reader.next_event().unwrap().expect("event missing");
+    assert_eq!(recorded.timestamp_us, 1000);
+    assert!(matches!(recorded.event, GlassesEvent::VSync));
+}
+
+#[test]
+fn test_event_stream_round_trip() {
+    let mut glasses = simulated();
+    let (bytes, events) = record_session(&mut glasses, 200);
+
+    let mut reader = SessionReader::new(Cursor::new(bytes)).unwrap();
+    for expected in &events {
+        let recorded = reader.next_event().unwrap().expect("event missing");
+        assert_eq!(format!("{:?}", recorded.event), format!("{:?}", expected));
+    }
+    assert!(reader.next_event().unwrap().is_none());
+}
+
+#[test]
+fn test_rejects_foreign_and_future_files() {
+    assert!(SessionReader::new(Cursor::new(b"NOTARECORDING".to_vec())).is_err());
+
+    let mut glasses = simulated();
+    let (mut bytes, _) = record_session(&mut glasses, 1);
+    let future = (RECORDING_VERSION + 1).to_le_bytes();
+    bytes[8..10].copy_from_slice(&future);
+    assert!(SessionReader::new(Cursor::new(bytes)).is_err());
+}
+
+#[test]
+fn test_replay_yields_recorded_events() {
+    let mut glasses = simulated();
+    let (bytes, events) = record_session(&mut glasses, 100);
+
+    let mut replay = ReplayGlasses::from_reader(
+        Box::new(Cursor::new(bytes)),
+        ReplaySpeed::Accelerated(f32::INFINITY),
+    )
+    .unwrap();
+    assert_eq!(replay.name(), glasses.name());
+
+    for expected in &events {
+        let event = replay.read_event().unwrap();
+        assert_eq!(format!("{:?}", event), format!("{:?}", expected));
+    }
+    assert!(replay.read_event().is_err());
+}
+
+#[test]
+fn test_stepped_replay_waits_for_steps() {
+    let mut glasses = simulated();
+    let (bytes, _) = record_session(&mut glasses, 5);
+
+    let stepper = ReplayStepper::default();
+    let mut replay = ReplayGlasses::from_reader(Box::new(Cursor::new(bytes)), ReplaySpeed::Stepped)
+        .unwrap()
+        .with_stepper(stepper.clone());
+
+    let handle = std::thread::spawn(move || {
+        (0..5)
+            .map(|_| replay.read_event().is_ok())
+            .collect::<Vec<_>>()
+    });
+    stepper.step(3);
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(!handle.is_finished());
+    stepper.step(2);
+
+    assert_eq!(handle.join().unwrap(), vec![true; 5]);
+}
+
+#[test]
+fn test_replay_advances_through_handle_created_beforehand() {
+    let mut glasses = simulated();
+    let (bytes, _) = record_session(&mut glasses, 2);
+
+    let stepper = ReplayStepper::default();
+    let mut replay = ReplayGlasses::from_reader(Box::new(Cursor::new(bytes)), ReplaySpeed::Stepped)
+        .unwrap()
+        .with_stepper(stepper.clone());
+
+    stepper.step(2);
+    assert!(replay.read_event().is_ok());
+    assert!(replay.read_event().is_ok());
+}
+
+#[test]
+fn test_only_stepped_replays_expose_a_stepper() {
+    let replay = |speed| GlassesSource::Replay {
+        path: "session.xrimu".into(),
+        speed,
+        stepper: ReplayStepper::default(),
+    };
+    assert!(replay(ReplaySpeed::Stepped).replay_stepper().is_some());
+    assert!(replay(ReplaySpeed::RealTime).replay_stepper().is_none());
+    assert!(GlassesSource::Hardware.replay_stepper().is_none());
+}
+
+#[test]
+fn test_replay_speed_parsing() {
+    assert_eq!(ReplaySpeed::parse("realtime"), Some(ReplaySpeed::RealTime));
+    assert_eq!(ReplaySpeed::parse("step"), Some(ReplaySpeed::Stepped));
+    assert_eq!(
+        ReplaySpeed::parse("4x"),
+        Some(ReplaySpeed::Accelerated(4.0))
+    );
+    assert_eq!(
+        ReplaySpeed::parse("max"),
+        Some(ReplaySpeed::Accelerated(f32::INFINITY))
+    );
+    assert_eq!(ReplaySpeed::parse("-2"), None);
+    assert_eq!(ReplaySpeed::parse("bogus"), None);
+}
@@ -0,0 +1,226 @@
+//! Tests for the simulated glasses device
+
+use super::ideal_simulation;
+use ar_drivers::{ARGlasses, GlassesEvent, Side};
+use bevy::math::{Quat, Vec3};
+use xreal_virtual_desktop::driver::simulated::*;
+
+fn read_imu(glasses: &mut SimulatedGlasses) -> (Vec3, Vec3, u64) {
+    loop {
+        if let GlassesEvent::AccGyro {
+            accelerometer,
+            gyroscope,
+            timestamp,
+        } = glasses.read_event().expect("simulator never fails")
+        {
+            return (
+                Vec3::new(accelerometer.x, accelerometer.y, accelerometer.z),
+                Vec3::new(gyroscope.x, gyroscope.y, gyroscope.z),
+                timestamp,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_stationary_reads_gravity_up() {
+    let mut glasses = SimulatedGlasses::new(ideal_simulation());
+
+    for _ in 0..100 {
+        let (accel, gyro, _) = read_imu(&mut glasses);
+        assert!((accel - Vec3::new(0.0, GRAVITY, 0.0)).length() < 1e-4);
+        assert!(gyro.length() < 1e-6);
+    }
+}
+
+#[test]
+fn test_imu_timestamps_follow_sample_rate() {
+    let config = SimulationConfig {
+        sample_rate_hz: 500,
+        mag_rate_hz: 0,
+        ..ideal_simulation()
+    };
+    let mut glasses = SimulatedGlasses::new(config);
+
+    let (_, _, first) = read_imu(&mut glasses);
+    let (_, _, second) = read_imu(&mut glasses);
+    assert_eq!(first, 0);
+    assert_eq!(second - first, 2000);
+}
+
+#[test]
+fn test_constant_rotation_integrates_to_expected_angle() {
+    let rate = 90f32.to_radians();
+    let config = SimulationConfig {
+        motion: MotionScript::constant_rotation(Vec3::Y, rate),
+        mag_rate_hz: 0,
+        ..ideal_simulation()
+    };
+    let mut glasses = SimulatedGlasses::new(config);
+
+    // One second at 1 kHz
+    for _ in 0..=1000 {
+        let (_, gyro, _) = read_imu(&mut glasses);
+        assert!((gyro - Vec3::new(0.0, rate, 0.0)).length() < 1e-5);
+    }
+
+    let expected = Quat::from_rotation_y(rate);
+    assert!(glasses.true_orientation().angle_between(expected) < 1e-3);
+}
+
+#[test]
+fn test_script_without_duration_holds_the_pose() {
+    let rotate = SegmentMotion::Rotate {
+        axis: Vec3::Y,
+        rate: 1.0,
+    };
+    for duration_secs in [0.0, f32::NAN, -1.0] {
+        let mut player = MotionPlayer::new(MotionScript {
+            segments: vec![MotionSegment {
+                motion: rotate,
+                duration_secs,
+            }],
+            looping: true,
+        });
+        assert_eq!(player.sample(1.0), (Quat::IDENTITY, Vec3::ZERO));
+    }
+
+    // Zero-length segments are skipped
+    let mut player = MotionPlayer::new(MotionScript {
+        segments: vec![
+            MotionSegment {
+                motion: SegmentMotion::Hold,
+                duration_secs: 0.0,
+            },
+            MotionSegment {
+                motion: rotate,
+                duration_secs: 1.0,
+            },
+        ],
+        looping: true,
+    });
+    let (orientation, rate) = player.sample(2.5);
+    assert!(orientation.angle_between(Quat::from_rotation_y(2.5)) < 1e-4);
+    assert_eq!(rate, Vec3::Y);
+}
+
+#[test]
+fn test_bias_is_added_to_samples() {
+    let config = SimulationConfig {
+        gyro_bias: Vec3::new(0.01, -0.02, 0.03),
+        accel_bias: Vec3::new(0.1, 0.0, -0.1),
+        ..ideal_simulation()
+    };
+    let mut glasses = SimulatedGlasses::new(config);
+
+    let (accel, gyro, _) = read_imu(&mut glasses);
+    assert!((gyro - Vec3::new(0.01, -0.02, 0.03)).length() < 1e-6);
+    assert!((accel - Vec3::new(0.1, GRAVITY, -0.1)).length() < 1e-4);
+}
+
+#[test]
+fn test_noise_has_configured_spread() {
+    let config = SimulationConfig {
+        gyro_noise_std: 0.05,
+        mag_rate_hz: 0,
+        ..ideal_simulation()
+    };
+    let mut glasses = SimulatedGlasses::new(config);
+
+    let samples = 4000;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for _ in 0..samples {
+        let (_, gyro, _) = read_imu(&mut glasses);
+        sum += gyro.x;
+        sum_sq += gyro.x * gyro.x;
+    }
+    let mean = sum / samples as f32;
+    let std = (sum_sq / samples as f32 - mean * mean).sqrt();
+
+    assert!(mean.abs() < 0.005);
+    assert!((std - 0.05).abs() < 0.005);
+}
+
+#[test]
+fn test_same_seed_is_deterministic() {
+    let config = SimulationConfig {
+        motion: MotionScript::look_around(),
+        ..SimulationConfig {
+            realtime: false,
+            ..Default::default()
+        }
+    };
+    let mut a = SimulatedGlasses::new(config.clone());
+    let mut b = SimulatedGlasses::new(config);
+
+    for _ in 0..500 {
+        assert_eq!(
+            format!("{:?}", a.read_event().unwrap()),
+            format!("{:?}", b.read_event().unwrap())
+        );
+    }
+}
+
+#[test]
+fn test_magnetometer_interleaved_at_its_rate() {
+    let config = SimulationConfig {
+        sample_rate_hz: 1000,
+        mag_rate_hz: 100,
+        ..ideal_simulation()
+    };
+    let mut glasses = SimulatedGlasses::new(config);
+
+    let mut imu = 0;
+    let mut mag = 0;
+    while imu < 1000 {
+        match glasses.read_event().unwrap() {
+            GlassesEvent::AccGyro { .. } => imu += 1,
+            GlassesEvent::Magnetometer { .. } => mag += 1,
+            _ => {}
+        }
+    }
+    assert!((99..=101).contains(&mag));
+}
+
+#[test]
+fn test_scripted_events_are_emitted_in_order() {
+    let config = SimulationConfig {
+        events: vec![
+            ScriptedEvent {
+                at_secs: 0.2,
+                event: SimulatedEvent::ProximityNear,
+            },
+            ScriptedEvent {
+                at_secs: 0.1,
+                event: SimulatedEvent::KeyPress(1),
+            },
+        ],
+        ..ideal_simulation()
+    };
+    let mut glasses = SimulatedGlasses::new(config);
+
+    let mut seen = Vec::new();
+    for _ in 0..500 {
+        match glasses.read_event().unwrap() {
+            GlassesEvent::KeyPress(key) => seen.push(format!("key{}", key)),
+            GlassesEvent::ProximityNear => seen.push("near".to_string()),
+            _ => {}
+        }
+    }
+    assert_eq!(seen, vec!["key1", "near"]);
+}
+
+#[test]
+fn test_device_metadata() {
+    let mut glasses = SimulatedGlasses::new(ideal_simulation());
+
+    assert_eq!(glasses.name(), SIMULATED_DEVICE_NAME);
+    assert!(glasses.serial().unwrap().starts_with("SIM-"));
+    assert!(glasses.display_fov() > 0.0);
+
+    let left = glasses.imu_to_display_matrix(Side::Left, 0.064);
+    let right = glasses.imu_to_display_matrix(Side::Right, 0.064);
+    assert!((left.translation.vector.x + 0.032).abs() < 1e-6);
+    assert!((right.translation.vector.x - 0.032).abs() < 1e-6);
+}
@@ -0,0 +1,113 @@
+//! Tests for the off-axis stereo eye projections
+
+use ar_drivers::Side;
+use bevy::math::{Vec3, Vec4};
+use bevy::render::camera::CameraProjection;
+use xreal_virtual_desktop::driver::optics::DisplayOptics;
+use xreal_virtual_desktop::xreal_stereo::{eye_projection, OffAxisProjection, StereoSettings};
+
+const EPSILON: f32 = 1e-4;
+
+/// Horizontal NDC of a head space point seen by one eye
+fn ndc_x(optics: &DisplayOptics, side: Side, settings: &StereoSettings, point: Vec3) -> f32 {
+    let eye = optics.eye_pose(side, settings.eye_separation).translation;
+    let clip = eye_projection(optics, side, settings).get_clip_from_view()
+        * Vec4::from((point - Vec3::X * eye.x, 1.0));
+    clip.x / clip.w
+}
+
+#[test]
+fn test_eyes_shift_towards_the_nose() {
+    let optics = DisplayOptics::default();
+    let settings = StereoSettings::default();
+    let left = eye_projection(&optics, Side::Left, &settings);
+    let right = eye_projection(&optics, Side::Right, &settings);
+
+    // Frustum centres point inwards by the same amount
+    let centre = |p: &OffAxisProjection| (p.left + p.right) * 0.5;
+    assert!(centre(&left) > 0.0);
+    assert!(centre(&right) < 0.0);
+    assert!((centre(&left) + centre(&right)).abs() < EPSILON);
+    let expected = settings.eye_separation * 0.5 / settings.convergence_distance;
+    assert!((centre(&left) - expected).abs() < EPSILON);
+
+    // The FOV itself is unchanged
+    let symmetric = OffAxisProjection::symmetric(optics.left.fov_x, optics.left.fov_y);
+    assert!(((left.right - left.left) - (symmetric.right - symmetric.left)).abs() < EPSILON);
+    assert_eq!(left.top, symmetric.top);
+    assert_eq!(left.bottom, symmetric.bottom);
+}
+
+#[test]
+fn test_convergence_plane_has_zero_parallax() {
+    let optics = DisplayOptics::default();
+    let settings = StereoSettings {
+        convergence_distance: 2.0,
+        ..Default::default()
+    };
+
+    for x in [-0.4, 0.0, 0.3] {
+        let point = Vec3::new(x, 0.1, -settings.convergence_distance);
+        let left = ndc_x(&optics, Side::Left, &settings, point);
+        let right = ndc_x(&optics, Side::Right, &settings, point);
+        assert!(
+            (left - right).abs() < EPSILON,
+            "at x = {x}: {left} vs {right}"
+        );
+    }
+
+    // Nearer content is crossed, farther content uncrossed
+    let near = Vec3::new(0.0, 0.0, -0.5);
+    assert!(
+        ndc_x(&optics, Side::Left, &settings, near) > ndc_x(&optics, Side::Right, &settings, near)
+    );
+    let far = Vec3::new(0.0, 0.0, -10.0);
+    assert!(
+        ndc_x(&optics, Side::Left, &settings, far) < ndc_x(&optics, Side::Right, &settings, far)
+    );
+}
+
+#[test]
+fn test_zero_separation_is_symmetric() {
+    let optics = DisplayOptics::default();
+    let settings = StereoSettings {
+        eye_separation: 0.0,
+        ..Default::default()
+    };
+    let projection = eye_projection(&optics, Side::Right, &settings);
+    assert!((projection.left + projection.right).abs() < EPSILON);
+}
+
+#[test]
+fn test_projection_uses_reverse_z() {
+    let projection = OffAxisProjection::symmetric(50_f32.to_radians(), 30_f32.to_radians());
+    let clip_from_view = projection.get_clip_from_view();
+
+    let at_near = clip_from_view * Vec4::new(0.0, 0.0, -projection.near, 1.0);
+    assert!((at_near.z / at_near.w - 1.0).abs() < EPSILON);
+    let far_away = clip_from_view * Vec4::new(0.0, 0.0, -1.0e6, 1.0);
+    assert!(far_away.z / far_away.w < EPSILON);
+
+    // Frustum edges land on the edges of the image
+    let edge = clip_from_view * Vec4::new(projection.right, projection.top, -1.0, 1.0);
+    assert!((edge.x / edge.w - 1.0).abs() < EPSILON);
+    assert!((edge.y / edge.w - 1.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_render_resolution_follows_scale() {
+    let native = StereoSettings::default();
+    assert_eq!(native.render_resolution((1920, 1080)), (1920, 1080));
+
+    let supersampled = StereoSettings {
+        render_scale: 1.5,
+        ..Default::default()
+    };
+    assert_eq!(supersampled.render_resolution((1920, 1080)), (2880, 1620));
+
+    let tiny = StereoSettings {
+        render_scale: 0.0,
+        ..Default::default()
+    };
+    assert_eq!(tiny.render_resolution((1920, 1080)), (1, 1));
+}
@@ -0,0 +1,157 @@
+//! Tests for glasses button press recognition and bindings
+
+use std::time::{Duration, Instant};
+use xreal_virtual_desktop::input::buttons::{ButtonPressDetector, GlassesButtonEvent};
+use xreal_virtual_desktop::state::schema::core::PersistentAppState;
+use xreal_virtual_desktop::state::schema::{
+    ButtonPress, GlassesButtonSettings, InputAction, StateValidation,
+};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn event(button: u8, press: ButtonPress) -> GlassesButtonEvent {
+    GlassesButtonEvent { button, press }
+}
+
+#[test]
+fn test_single_press_is_short_after_window() {
+    let settings = GlassesButtonSettings::default();
+    let mut detector = ButtonPressDetector::default();
+    let start = Instant::now();
+
+    assert!(detector.press(0, start, &settings).is_empty());
+    assert!(detector.poll(start + ms(100), &settings).is_empty());
+    assert_eq!(
+        detector.poll(start + ms(400), &settings),
+        vec![event(0, ButtonPress::Short)]
+    );
+    assert!(!detector.is_pending());
+}
+
+#[test]
+fn test_two_quick_presses_are_double() {
+    let settings = GlassesButtonSettings::default();
+    let mut detector = ButtonPressDetector::default();
+    let start = Instant::now();
+
+    detector.press(1, start, &settings);
+    detector.press(1, start + ms(150), &settings);
+    assert_eq!(
+        detector.poll(start + ms(500), &settings),
+        vec![event(1, ButtonPress::Double)]
+    );
+}
+
+#[test]
+fn test_slow_presses_are_two_shorts() {
+    let settings = GlassesButtonSettings::default();
+    let mut detector = ButtonPressDetector::default();
+    let start = Instant::now();
+
+    detector.press(0, start, &settings);
+    assert_eq!(
+        detector.press(0, start + ms(500), &settings),
+        vec![event(0, ButtonPress::Short)]
+    );
+    assert_eq!(
+        detector.poll(start + ms(900), &settings),
+        vec![event(0, ButtonPress::Short)]
+    );
+}
+
+#[test]
+fn test_repeat_burst_is_single_long_press() {
+    let settings = GlassesButtonSettings::default();
+    let mut detector = ButtonPressDetector::default();
+    let start = Instant::now();
+
+    let mut events = Vec::new();
+    for i in 0..10 {
+        events.extend(detector.press(0, start + ms(i * 100), &settings));
+    }
+    events.extend(detector.poll(start + ms(1500), &settings));
+
+    assert_eq!(events, vec![event(0, ButtonPress::Long)]);
+}
+
+#[test]
+fn test_buttons_are_tracked_independently() {
+    let settings = GlassesButtonSettings::default();
+    let mut detector = ButtonPressDetector::default();
+    let start = Instant::now();
+
+    detector.press(0, start, &settings);
+    detector.press(1, start + ms(50), &settings);
+    detector.press(0, start + ms(100), &settings);
+
+    let mut events = detector.poll(start + ms(600), &settings);
+    events.sort_by_key(|e| e.button);
+    assert_eq!(
+        events,
+        vec![event(0, ButtonPress::Double), event(1, ButtonPress::Short)]
+    );
+}
+
+#[test]
+fn test_rebinding_replaces_existing_action() {
+    let mut settings = GlassesButtonSettings::default();
+    assert_eq!(
+        settings.action_for(0, ButtonPress::Short),
+        Some(InputAction::Recenter)
+    );
+
+    settings.set_action(0, ButtonPress::Short, Some(InputAction::SwitchLayout));
+    assert_eq!(
+        settings.action_for(0, ButtonPress::Short),
+        Some(InputAction::SwitchLayout)
+    );
+    assert!(settings.validate().is_ok());
+
+    settings.set_action(0, ButtonPress::Short, None);
+    assert_eq!(settings.action_for(0, ButtonPress::Short), None);
+}
+
+#[test]
+fn test_duplicate_bindings_are_invalid() {
+    let mut settings = GlassesButtonSettings::default();
+    let duplicate = settings.bindings[0];
+    settings.bindings.push(duplicate);
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_bindings_survive_serialization() {
+    let mut state = PersistentAppState::default();
+    state.input_config.glasses_buttons.set_action(
+        3,
+        ButtonPress::Long,
+        Some(InputAction::CycleBrightness),
+    );
+
+    let json = serde_json::to_string(&state).unwrap();
+    let restored: PersistentAppState = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        restored
+            .input_config
+            .glasses_buttons
+            .action_for(3, ButtonPress::Long),
+        Some(InputAction::CycleBrightness)
+    );
+}
+
+#[test]
+fn test_missing_bindings_use_defaults() {
+    let mut value = serde_json::to_value(PersistentAppState::default()).unwrap();
+    value["input_config"]
+        .as_object_mut()
+        .unwrap()
+        .remove("glasses_buttons");
+
+    let restored: PersistentAppState = serde_json::from_value(value).unwrap();
+    assert_eq!(
+        restored.input_config.glasses_buttons.bindings,
+        GlassesButtonSettings::default().bindings
+    );
+}
@@ -0,0 +1,220 @@
+//! Tests for head gesture recognition and bindings
+
+use crate::tracking::at_rest;
+use bevy::math::Vec3;
+use std::f32::consts::TAU;
+use xreal_virtual_desktop::state::schema::{
+    GestureInputSettings, HeadGesture, InputAction, StateValidation,
+};
+use xreal_virtual_desktop::tracking::gestures::GestureRecognizer;
+
+const DT: f32 = 0.001;
+
+fn settings() -> GestureInputSettings {
+    GestureInputSettings {
+        enabled: true,
+        ..GestureInputSettings::default()
+    }
+}
+
+/// Gyro samples of `half_swings` alternating swings about `axis`, peaking at 3 rad/s
+fn swings(axis: Vec3, half_swings: usize, period_s: f32) -> Vec<Vec3> {
+    let steps = (period_s / 2.0 * half_swings as f32 / DT) as usize;
+    (0..steps)
+        .map(|step| axis * 3.0 * (TAU * step as f32 * DT / period_s).sin())
+        .collect()
+}
+
+fn still(seconds: f32) -> Vec<Vec3> {
+    vec![Vec3::ZERO; (seconds / DT) as usize]
+}
+
+/// Gestures recognised from gyro samples, with the glasses otherwise at rest
+fn recognise(
+    recognizer: &mut GestureRecognizer,
+    gyro: &[Vec3],
+    settings: &GestureInputSettings,
+) -> Vec<HeadGesture> {
+    gyro.iter()
+        .filter_map(|gyro| recognizer.update(*gyro, at_rest(), DT, settings))
+        .collect()
+}
+
+#[test]
+fn test_nod_shake_and_tilts() {
+    let settings = settings();
+    let cases = [
+        (swings(-Vec3::X, 2, 0.5), HeadGesture::Nod),
+        (swings(Vec3::Y, 4, 0.4), HeadGesture::Shake),
+        (swings(Vec3::Z, 2, 0.6), HeadGesture::TiltLeft),
+        (swings(-Vec3::Z, 2, 0.6), HeadGesture::TiltRight),
+    ];
+    for (motion, expected) in cases {
+        let mut recognizer = GestureRecognizer::default();
+        let samples = [motion, still(1.0)].concat();
+        assert_eq!(
+            recognise(&mut recognizer, &samples, &settings),
+            vec![expected]
+        );
+    }
+}
+
+#[test]
+fn test_ordinary_head_motion_is_no_gesture() {
+    let settings = settings();
+    let mut recognizer = GestureRecognizer::default();
+
+    // Glancing aside and back is two yaw swings, not a shake
+    let glance = [swings(Vec3::Y, 2, 0.6), still(1.0)].concat();
+    assert!(recognise(&mut recognizer, &glance, &settings).is_empty());
+
+    // Slow looking around stays below the swing threshold
+    let slow: Vec<Vec3> = swings(Vec3::new(0.3, 0.25, 0.0), 6, 2.0);
+    let slow = [slow, still(1.0)].concat();
+    assert!(recognise(&mut recognizer, &slow, &settings).is_empty());
+
+    // A turn mixing pitch and yaw does not dominate either axis
+    let diagonal = [swings(Vec3::new(1.0, 1.0, 0.0), 2, 0.5), still(1.0)].concat();
+    assert!(recognise(&mut recognizer, &diagonal, &settings).is_empty());
+}
+
+#[test]
+fn test_sensitivity_scales_the_threshold() {
+    let gentle: Vec<Vec3> = swings(-Vec3::X, 2, 0.5)
+        .into_iter()
+        .map(|gyro| gyro * 0.5)
+        .chain(still(1.0))
+        .collect();
+
+    let mut recognizer = GestureRecognizer::default();
+    assert!(recognise(&mut recognizer, &gentle, &settings()).is_empty());
+
+    let sensitive = GestureInputSettings {
+        sensitivity: 1.0,
+        ..settings()
+    };
+    let mut recognizer = GestureRecognizer::default();
+    assert_eq!(
+        recognise(&mut recognizer, &gentle, &sensitive),
+        vec![HeadGesture::Nod]
+    );
+}
+
+#[test]
+fn test_cooldown_suppresses_repeats() {
+    let settings = settings();
+    let nod = [swings(-Vec3::X, 2, 0.5), still(0.4)].concat();
+    let mut recognizer = GestureRecognizer::default();
+
+    let twice = [nod.clone(), nod.clone()].concat();
+    assert_eq!(
+        recognise(&mut recognizer, &twice, &settings),
+        vec![HeadGesture::Nod]
+    );
+
+    let later = [still(settings.cooldown_ms as f32 / 1000.0), nod].concat();
+    assert_eq!(
+        recognise(&mut recognizer, &later, &settings),
+        vec![HeadGesture::Nod]
+    );
+}
+
+#[test]
+fn test_gestures_after_a_day_of_wear() {
+    let settings = settings();
+    let mut recognizer = GestureRecognizer::default();
+    for _ in 0..86_400 {
+        assert_eq!(
+            recognizer.update(Vec3::ZERO, at_rest(), 1.0, &settings),
+            None
+        );
+    }
+
+    // Single samples still advance the clock, so the cooldown ends
+    let nod = [swings(-Vec3::X, 2, 0.5), still(0.4)].concat();
+    let twice = [
+        nod.clone(),
+        still(settings.cooldown_ms as f32 / 1000.0),
+        nod,
+    ]
+    .concat();
+    assert_eq!(
+        recognise(&mut recognizer, &twice, &settings),
+        vec![HeadGesture::Nod, HeadGesture::Nod]
+    );
+}
+
+/// Accelerometer samples with a 5 ms spike at each of the given times
+fn taps(at_s: &[f32], duration_s: f32) -> Vec<Vec3> {
+    (0..(duration_s / DT) as usize)
+        .map(|step| {
+            let t = step as f32 * DT;
+            if at_s.iter().any(|tap| (*tap..*tap + 0.005).contains(&t)) {
+                at_rest() + Vec3::Z * 25.0
+            } else {
+                at_rest()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_double_tap() {
+    let settings = settings();
+    let count = |accel: Vec<Vec3>| {
+        let mut recognizer = GestureRecognizer::default();
+        accel
+            .into_iter()
+            .filter_map(|accel| recognizer.update(Vec3::ZERO, accel, DT, &settings))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(count(taps(&[0.2, 0.45], 1.0)), vec![HeadGesture::DoubleTap]);
+    assert!(count(taps(&[0.2], 1.0)).is_empty());
+    // Taps too far apart are two single taps
+    assert!(count(taps(&[0.2, 1.0], 1.5)).is_empty());
+}
+
+#[test]
+fn test_gesture_bindings() {
+    let mut settings = GestureInputSettings::default();
+    assert!(!settings.enabled);
+    assert_eq!(
+        settings.action_for(HeadGesture::Nod),
+        Some(InputAction::ConfirmDwell)
+    );
+    assert_eq!(
+        settings.action_for(HeadGesture::Shake),
+        Some(InputAction::DismissDialog)
+    );
+    assert_eq!(
+        settings.action_for(HeadGesture::DoubleTap),
+        Some(InputAction::Recenter)
+    );
+    assert_eq!(settings.action_for(HeadGesture::TiltLeft), None);
+
+    settings.set_action(HeadGesture::TiltLeft, Some(InputAction::SwitchLayout));
+    settings.set_action(HeadGesture::Nod, None);
+    assert_eq!(
+        settings.action_for(HeadGesture::TiltLeft),
+        Some(InputAction::SwitchLayout)
+    );
+    assert_eq!(settings.action_for(HeadGesture::Nod), None);
+    assert!(settings.validate().is_ok());
+
+    settings.bindings.push(settings.bindings[0]);
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_settings_without_gesture_fields_load_defaults() {
+    let json = r#"{"enabled":true,"sensitivity":0.5,"min_duration_ms":200,"max_duration_ms":3000}"#;
+    let settings: GestureInputSettings = serde_json::from_str(json).expect("deserialize");
+    assert!(settings.enabled);
+    assert_eq!(
+        settings.cooldown_ms,
+        GestureInputSettings::default().cooldown_ms
+    );
+    assert_eq!(settings.bindings, GestureInputSettings::default().bindings);
+    assert!(settings.validate().is_ok());
+}
@@ -0,0 +1,7 @@
+//! Input handling integration tests
+//!
+//! Tests for glasses button press and head gesture recognition and their action
+//! bindings.
+
+pub mod buttons_test;
+pub mod gestures_test;
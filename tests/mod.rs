@@ -4,8 +4,12 @@
 //! with clean separation between unit tests (in src/) and integration tests (in tests/).
 
 // Test modules organized by category
+pub mod driver;
+pub mod input;
 pub mod plugins;
+pub mod setup;
 pub mod state;
+pub mod tracking;
 
 // Common test utilities
 pub mod test_utils;
@@ -0,0 +1,155 @@
+//! Tests for hidraw permission checks and udev rule generation
+
+use std::fs;
+use std::path::Path;
+use xreal_virtual_desktop::setup::hidraw::{
+    device_name, parse_hid_uevent, DeviceAccessReport, DeviceRoots, NodeAccess, KNOWN_DEVICES,
+    UDEV_RULES_FILE,
+};
+
+fn add_usb_device(sys: &Path, port: &str, vendor_id: &str, product_id: &str, product: &str) {
+    let dir = sys.join("bus/usb/devices").join(port);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("idVendor"), format!("{}\n", vendor_id)).unwrap();
+    fs::write(dir.join("idProduct"), format!("{}\n", product_id)).unwrap();
+    fs::write(dir.join("product"), format!("{}\n", product)).unwrap();
+}
+
+fn add_hidraw(sys: &Path, node: &str, hid_id: &str, name: &str) {
+    let dir = sys.join("class/hidraw").join(node).join("device");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("uevent"),
+        format!("DRIVER=hid-generic\nHID_ID={}\nHID_NAME={}\n", hid_id, name),
+    )
+    .unwrap();
+}
+
+fn fake_tree() -> (tempfile::TempDir, DeviceRoots) {
+    let root = tempfile::TempDir::new().unwrap();
+    let roots = DeviceRoots {
+        sys: root.path().join("sys"),
+        dev: root.path().join("dev"),
+    };
+    fs::create_dir_all(&roots.dev).unwrap();
+
+    add_usb_device(&roots.sys, "3-1", "3318", "0424", "XREAL Air");
+    add_usb_device(&roots.sys, "3-2", "046d", "c52b", "USB Receiver");
+    add_hidraw(&roots.sys, "hidraw2", "0003:00003318:00000424", "XREAL Air");
+    add_hidraw(
+        &roots.sys,
+        "hidraw5",
+        "0003:0000046D:0000C52B",
+        "Logitech Receiver",
+    );
+    (root, roots)
+}
+
+#[test]
+fn test_scan_finds_known_vendors_only() {
+    let (_root, roots) = fake_tree();
+    let report = DeviceAccessReport::scan(&roots);
+
+    assert_eq!(report.usb_devices.len(), 1);
+    assert_eq!(report.usb_devices[0].vendor_id, 0x3318);
+    assert_eq!(report.usb_devices[0].product.as_deref(), Some("XREAL Air"));
+    assert_eq!(report.hidraw_nodes.len(), 1);
+    assert_eq!(report.hidraw_nodes[0].path, roots.dev.join("hidraw2"));
+    assert_eq!(report.hidraw_nodes[0].product_id, 0x0424);
+    assert!(report.glasses_detected());
+}
+
+#[test]
+fn test_missing_node_is_reported_inaccessible() {
+    let (_root, roots) = fake_tree();
+    let report = DeviceAccessReport::scan(&roots);
+
+    assert_eq!(report.hidraw_nodes[0].access, NodeAccess::Missing);
+    assert_eq!(report.inaccessible().count(), 1);
+    assert!(!report.is_ok());
+}
+
+#[test]
+fn test_writable_node_is_usable() {
+    let (_root, roots) = fake_tree();
+    fs::write(roots.dev.join("hidraw2"), b"").unwrap();
+    let report = DeviceAccessReport::scan(&roots);
+
+    assert_eq!(report.hidraw_nodes[0].access, NodeAccess::ReadWrite);
+    assert!(report.is_ok());
+}
+
+#[test]
+fn test_empty_tree_passes() {
+    let root = tempfile::TempDir::new().unwrap();
+    let report = DeviceAccessReport::scan(&DeviceRoots {
+        sys: root.path().join("sys"),
+        dev: root.path().join("dev"),
+    });
+
+    assert!(!report.glasses_detected());
+    assert!(report.is_ok());
+}
+
+#[test]
+fn test_udev_rules_match_detected_glasses() {
+    let (_root, roots) = fake_tree();
+    let rules = DeviceAccessReport::scan(&roots).udev_rules();
+
+    assert!(rules.contains(
+        "SUBSYSTEM==\"hidraw\", ATTRS{idVendor}==\"3318\", ATTRS{idProduct}==\"0424\", MODE=\"0660\", TAG+=\"uaccess\""
+    ));
+    assert!(rules.contains("SUBSYSTEM==\"usb\", ATTRS{idVendor}==\"3318\""));
+    assert!(!rules.contains("046d"));
+}
+
+#[test]
+fn test_udev_rules_cover_all_models_without_glasses() {
+    let rules = DeviceAccessReport::default().udev_rules();
+    for (vendor_id, product_id, _) in KNOWN_DEVICES {
+        assert!(rules.contains(&format!(
+            "ATTRS{{idVendor}}==\"{:04x}\", ATTRS{{idProduct}}==\"{:04x}\", MODE",
+            vendor_id, product_id
+        )));
+    }
+    assert!(!rules.contains("04d8"));
+}
+
+#[test]
+fn test_unrelated_devices_of_shared_vendors_are_ignored() {
+    let (_root, roots) = fake_tree();
+    // OmniVision camera that is not part of any glasses
+    add_usb_device(&roots.sys, "3-3", "05a9", "4519", "Webcam");
+    add_hidraw(&roots.sys, "hidraw7", "0003:000005A9:00004519", "Webcam");
+    let report = DeviceAccessReport::scan(&roots);
+
+    assert_eq!(report.usb_devices.len(), 1);
+    assert_eq!(report.hidraw_nodes.len(), 1);
+    assert_eq!(device_name(0x04d2, 0x162f), Some("Rokid Air/Max"));
+    assert_eq!(device_name(0x05a9, 0x4519), None);
+}
+
+#[test]
+fn test_write_udev_rules_records_path() {
+    let (root, roots) = fake_tree();
+    let mut report = DeviceAccessReport::scan(&roots);
+    assert!(report.install_command().is_none());
+
+    let path = report.write_udev_rules(&root.path().join("cache")).unwrap();
+    assert_eq!(path.file_name().unwrap(), UDEV_RULES_FILE);
+    assert_eq!(fs::read_to_string(&path).unwrap(), report.udev_rules());
+    assert!(report
+        .install_command()
+        .unwrap()
+        .contains(&format!("/etc/udev/rules.d/{}", UDEV_RULES_FILE)));
+}
+
+#[test]
+fn test_parse_hid_uevent() {
+    let parsed = parse_hid_uevent("HID_ID=0003:00000486:0000573C\nHID_NAME=Nreal Light\n");
+    assert_eq!(
+        parsed,
+        Some((0x0486, 0x573c, Some("Nreal Light".to_string())))
+    );
+    assert_eq!(parse_hid_uevent("HID_NAME=No ids\n"), None);
+}
@@ -0,0 +1,5 @@
+//! Startup check integration tests
+//!
+//! Tests for device permission scans against a fake sysfs and `/dev` tree.
+
+pub mod hidraw_test;
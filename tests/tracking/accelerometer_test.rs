@@ -0,0 +1,157 @@
+//! Tests for the six-position accelerometer calibration
+
+use bevy::math::{Mat3, Vec3};
+use xreal_virtual_desktop::state::schema::{CalibrationData, StateValidation};
+use xreal_virtual_desktop::tracking::accelerometer::{
+    AccelCalibration, AccelCalibrationStatus, AccelCapture, AccelPose, SAMPLES_PER_POSE,
+};
+use xreal_virtual_desktop::tracking::filter::STANDARD_GRAVITY;
+
+fn offset() -> Vec3 {
+    Vec3::new(0.15, -0.2, 0.3)
+}
+
+/// Sensor response with per-axis scale and small cross-axis coupling
+fn sensitivity(cross_axis: bool) -> Mat3 {
+    let scale = Mat3::from_diagonal(Vec3::new(1.03, 0.97, 1.05));
+    if !cross_axis {
+        return scale;
+    }
+    scale
+        + Mat3::from_cols(
+            Vec3::new(0.0, 0.01, -0.02),
+            Vec3::new(0.015, 0.0, 0.01),
+            Vec3::new(-0.01, 0.02, 0.0),
+        )
+}
+
+/// Raw reading while resting in `pose`
+fn raw(pose: AccelPose, sensitivity: Mat3) -> Vec3 {
+    sensitivity * (pose.gravity_axis() * STANDARD_GRAVITY) + offset()
+}
+
+fn readings(sensitivity: Mat3) -> [Vec3; 6] {
+    AccelPose::ALL.map(|pose| raw(pose, sensitivity))
+}
+
+#[test]
+fn test_solve_recovers_offset_scale_and_cross_axis() {
+    let response = sensitivity(true);
+    let calibration = AccelCalibration::solve(&readings(response), true).expect("solve");
+
+    assert!(calibration.offset.distance(offset()) < 1e-4);
+    assert!(calibration.scale().distance(Vec3::new(1.03, 0.97, 1.05)) < 1e-4);
+    assert!(calibration.residual < 1e-5);
+    assert!(calibration.quality() > 0.99);
+
+    // Any reading maps back to the true acceleration
+    let truth = Vec3::new(3.0, -7.0, 5.0);
+    let corrected = calibration.apply(response * truth + offset());
+    assert!(corrected.distance(truth) < 1e-3, "{corrected:?}");
+}
+
+#[test]
+fn test_diagonal_only_solve_keeps_residual_of_cross_axis_terms() {
+    let diagonal = AccelCalibration::solve(&readings(sensitivity(false)), false).expect("solve");
+    assert!(diagonal.residual < 1e-5);
+    let correction = diagonal.correction.to_cols_array_2d();
+    assert_eq!(correction[0][1], 0.0);
+    assert_eq!(correction[1][0], 0.0);
+
+    // Coupling the diagonal model cannot express shows up in the residual
+    let coupled = AccelCalibration::solve(&readings(sensitivity(true)), false).expect("solve");
+    assert!(coupled.residual > 0.005);
+    assert!(coupled.offset.distance(offset()) < 1e-4);
+}
+
+#[test]
+fn test_pose_detection() {
+    for pose in AccelPose::ALL {
+        assert_eq!(AccelPose::detect(raw(pose, sensitivity(true))), Some(pose));
+    }
+    // Tilted 45° between two axes is neither pose
+    let tilted = (Vec3::Y + Vec3::X).normalize() * STANDARD_GRAVITY;
+    assert_eq!(AccelPose::detect(tilted), None);
+    assert_eq!(AccelPose::detect(Vec3::ZERO), None);
+}
+
+#[test]
+fn test_capture_rejects_motion_and_accepts_any_order() {
+    let response = sensitivity(true);
+    let mut capture = AccelCapture::default();
+
+    // Moving while resting in a pose restarts it
+    for _ in 0..SAMPLES_PER_POSE - 1 {
+        assert_eq!(
+            capture.push(raw(AccelPose::BackUp, response), Vec3::ZERO),
+            None
+        );
+    }
+    assert_eq!(
+        capture.push(raw(AccelPose::BackUp, response), Vec3::Y * 0.5),
+        None
+    );
+    assert_eq!(
+        capture.push(raw(AccelPose::BackUp, response), Vec3::ZERO),
+        None
+    );
+    assert!(matches!(
+        capture.status(),
+        AccelCalibrationStatus::Capturing { captured, current: Some(AccelPose::BackUp), .. }
+            if captured == [false; 6]
+    ));
+
+    let mut order = AccelPose::ALL;
+    order.reverse();
+    for pose in order {
+        let mut completed = None;
+        for _ in 0..SAMPLES_PER_POSE {
+            completed = completed.or(capture.push(raw(pose, response), Vec3::ZERO));
+        }
+        assert_eq!(completed, Some(pose));
+    }
+    assert!(capture.is_complete());
+
+    // Captured poses are not captured again
+    assert_eq!(
+        capture.push(raw(AccelPose::TopUp, response), Vec3::ZERO),
+        None
+    );
+
+    let calibration = capture.solve(true).expect("solve");
+    assert!(calibration.offset.distance(offset()) < 1e-3);
+}
+
+#[test]
+fn test_incomplete_capture_fails_to_solve() {
+    let mut capture = AccelCapture::default();
+    for _ in 0..SAMPLES_PER_POSE {
+        capture.push(raw(AccelPose::TopUp, Mat3::IDENTITY), Vec3::ZERO);
+    }
+    assert!(!capture.is_complete());
+    assert!(capture.solve(false).is_err());
+}
+
+#[test]
+fn test_calibration_roundtrips_and_validates() {
+    let calibration = AccelCalibration::solve(&readings(sensitivity(true)), true).expect("solve");
+    let mut data = CalibrationData::default();
+    calibration.store(&mut data);
+    assert!(data.validate().is_ok());
+
+    let restored = AccelCalibration::from_calibration_data(&data);
+    let sample = Vec3::new(1.0, 9.0, -2.0);
+    assert!(restored.apply(sample).distance(calibration.apply(sample)) < 1e-5);
+
+    let flipped_axis = CalibrationData {
+        accel_correction: [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]],
+        ..CalibrationData::default()
+    };
+    assert!(flipped_axis.validate().is_err());
+
+    let oversized_scale = CalibrationData {
+        accel_correction: [[3.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        ..CalibrationData::default()
+    };
+    assert!(oversized_scale.validate().is_err());
+}
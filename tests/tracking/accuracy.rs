@@ -0,0 +1,226 @@
+//! Tracking accuracy against synthetic head motion
+//!
+//! Simulated glasses turn a scripted ground-truth trajectory into noisy,
+//! biased IMU samples. Running them through a [`TrackingPipeline`] and
+//! comparing every pose with the truth gives orientation error, latency and
+//! yaw drift as numbers, so filter and smoothing changes can be judged
+//! without wearing the glasses.
+
+use ar_drivers::{ARGlasses, GlassesEvent};
+use bevy::math::{EulerRot, Quat, Vec3};
+use std::f32::consts::{PI, TAU};
+use xreal_virtual_desktop::driver::simulated::{MotionPlayer, SimulatedGlasses, SimulationConfig};
+use xreal_virtual_desktop::tracking::pipeline::TrackingPipeline;
+
+/// Truth rotation below which the run counts as motionless, in degrees
+const STILL_DEG: f32 = 0.5;
+
+/// How long and how to sample an accuracy run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccuracyRun {
+    /// Simulated time, in seconds
+    pub duration_s: f32,
+    /// Time the filter gets to converge before errors count, in seconds
+    pub warmup_s: f32,
+    /// Accelerometer samples between poses, like the batches of the tracking thread
+    pub batch_size: usize,
+    /// Longest latency searched for, in ms
+    pub max_latency_ms: f32,
+}
+
+impl Default for AccuracyRun {
+    fn default() -> Self {
+        Self {
+            duration_s: 20.0,
+            warmup_s: 2.0,
+            batch_size: 1,
+            max_latency_ms: 200.0,
+        }
+    }
+}
+
+/// Orientation error of a pipeline against the ground truth
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AccuracyReport {
+    /// Poses compared after the warmup
+    pub poses: usize,
+    /// Mean angle between estimated and true orientation, in degrees
+    pub mean_error_deg: f32,
+    /// Root mean square of the same angle, in degrees
+    pub rms_error_deg: f32,
+    pub max_error_deg: f32,
+    /// Root mean square angle between estimated and true up direction, in degrees
+    pub rms_tilt_error_deg: f32,
+    /// Delay that best aligns the estimate with the truth, in ms
+    /// Zero without motion
+    pub latency_ms: f32,
+    /// Growth of the yaw error, in degrees per minute
+    pub yaw_drift_deg_per_min: f32,
+}
+
+/// Pose compared against the truth
+#[derive(Debug, Clone, Copy)]
+struct Comparison {
+    time_s: f32,
+    estimate: Quat,
+    truth: Quat,
+}
+
+/// Wrap an angle into [-π, π)
+#[inline]
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+/// Heading error of the estimate, positive when it is turned left of the truth
+#[inline]
+fn yaw_error(estimate: Quat, truth: Quat) -> f32 {
+    wrap_angle((estimate * truth.inverse()).to_euler(EulerRot::YXZ).0)
+}
+
+/// Play the simulated motion through the pipeline and measure its accuracy
+/// The simulation runs as fast as possible regardless of its realtime setting
+pub fn measure(
+    pipeline: &mut TrackingPipeline,
+    config: SimulationConfig,
+    run: &AccuracyRun,
+) -> AccuracyReport {
+    let sample_rate_hz = config.sample_rate_hz.max(1) as f32;
+    // Plays the same script as the glasses to tell the true orientation
+    let mut truth = MotionPlayer::new(config.motion.clone());
+    let mut glasses = SimulatedGlasses::new(SimulationConfig {
+        realtime: false,
+        ..config
+    });
+
+    let samples = (run.duration_s * sample_rate_hz) as usize;
+    let batch_size = run.batch_size.max(1);
+    let mut comparisons = Vec::with_capacity(samples / batch_size + 1);
+    let mut sample = 0;
+    while sample < samples {
+        let Ok(event) = glasses.read_event() else {
+            break;
+        };
+        match event {
+            GlassesEvent::AccGyro {
+                accelerometer,
+                gyroscope,
+                timestamp,
+            } => {
+                pipeline.push_imu(
+                    Vec3::new(accelerometer.x, accelerometer.y, accelerometer.z),
+                    Vec3::new(gyroscope.x, gyroscope.y, gyroscope.z),
+                    timestamp,
+                );
+            }
+            GlassesEvent::Magnetometer { magnetometer, .. } => {
+                pipeline.push_mag(Vec3::new(magnetometer.x, magnetometer.y, magnetometer.z));
+                continue;
+            }
+            _ => continue,
+        }
+
+        let (true_orientation, _) = truth.sample(sample as f64 / sample_rate_hz as f64);
+        sample += 1;
+        if sample % batch_size == 0 {
+            if let Some(estimate) = pipeline.pose() {
+                comparisons.push(Comparison {
+                    time_s: sample as f32 / sample_rate_hz,
+                    estimate,
+                    truth: true_orientation,
+                });
+            }
+        }
+    }
+
+    let pose_interval_s = batch_size as f32 / sample_rate_hz;
+    let warmup = (run.warmup_s / pose_interval_s) as usize;
+    let max_lag = (run.max_latency_ms / 1000.0 / pose_interval_s) as usize;
+    report(&comparisons, warmup, max_lag, pose_interval_s)
+}
+
+/// Error statistics of the poses after the warmup
+fn report(
+    comparisons: &[Comparison],
+    warmup: usize,
+    max_lag: usize,
+    pose_interval_s: f32,
+) -> AccuracyReport {
+    let compared = comparisons.get(warmup..).unwrap_or_default();
+    if compared.is_empty() {
+        return AccuracyReport::default();
+    }
+    let n = compared.len() as f32;
+
+    let errors: Vec<f32> = compared
+        .iter()
+        .map(|c| c.estimate.angle_between(c.truth).to_degrees())
+        .collect();
+    let tilt_sq_sum: f32 = compared
+        .iter()
+        .map(|c| {
+            (c.estimate.inverse() * Vec3::Y)
+                .angle_between(c.truth.inverse() * Vec3::Y)
+                .to_degrees()
+                .powi(2)
+        })
+        .sum();
+
+    // The estimate compared with the truth some poses earlier; the best match is the latency
+    let lagged_error = |lag: usize| {
+        let pairs = comparisons.len().saturating_sub(warmup.max(lag));
+        if pairs == 0 {
+            return f32::INFINITY;
+        }
+        comparisons[warmup.max(lag)..]
+            .iter()
+            .zip(&comparisons[warmup.max(lag) - lag..])
+            .map(|(now, earlier)| now.estimate.angle_between(earlier.truth))
+            .sum::<f32>()
+            / pairs as f32
+    };
+    let first = compared[0].truth;
+    let moving = compared
+        .iter()
+        .any(|c| c.truth.angle_between(first).to_degrees() > STILL_DEG);
+    let mut best = (0, lagged_error(0));
+    if moving {
+        for lag in 1..=max_lag {
+            let error = lagged_error(lag);
+            if error < best.1 {
+                best = (lag, error);
+            }
+        }
+    }
+
+    // Least squares slope of the yaw error over time
+    let mean_t = compared.iter().map(|c| c.time_s).sum::<f32>() / n;
+    let yaw_errors: Vec<f32> = compared
+        .iter()
+        .map(|c| yaw_error(c.estimate, c.truth))
+        .collect();
+    let mean_yaw = yaw_errors.iter().sum::<f32>() / n;
+    let (covariance, variance) =
+        compared
+            .iter()
+            .zip(&yaw_errors)
+            .fold((0.0, 0.0), |(covariance, variance), (c, yaw)| {
+                let dt = c.time_s - mean_t;
+                (covariance + dt * (yaw - mean_yaw), variance + dt * dt)
+            });
+    let yaw_drift = if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    };
+
+    AccuracyReport {
+        poses: compared.len(),
+        mean_error_deg: errors.iter().sum::<f32>() / n,
+        rms_error_deg: (errors.iter().map(|e| e * e).sum::<f32>() / n).sqrt(),
+        max_error_deg: errors.iter().copied().fold(0.0, f32::max),
+        rms_tilt_error_deg: (tilt_sq_sum / n).sqrt(),
+        latency_ms: best.0 as f32 * pose_interval_s * 1000.0,
+        yaw_drift_deg_per_min: yaw_drift.to_degrees() * 60.0,
+    }
+}
@@ -0,0 +1,131 @@
+//! Tests for per-screen anchoring modes
+
+use bevy::math::{EulerRot, Quat, Vec3};
+use xreal_virtual_desktop::anchoring::{LazyFollow, ScreenAnchor};
+use xreal_virtual_desktop::state::schema::{
+    AnchorMode, Easing, LazyFollowSettings, ScreenAnchoring, StateValidation, WindowLayout,
+};
+
+const DT: f32 = 1.0 / 60.0;
+
+fn head(yaw_deg: f32, pitch_deg: f32, roll_deg: f32) -> Quat {
+    Quat::from_euler(
+        EulerRot::YXZ,
+        yaw_deg.to_radians(),
+        pitch_deg.to_radians(),
+        roll_deg.to_radians(),
+    )
+}
+
+fn yaw_deg(rotation: Quat) -> f32 {
+    rotation.to_euler(EulerRot::YXZ).0.to_degrees()
+}
+
+#[test]
+fn test_rigid_modes() {
+    let settings = LazyFollowSettings::default();
+    let mut anchor = ScreenAnchor::new(Vec3::new(0.0, 0.0, -2.0));
+    let looking = head(40.0, -20.0, 10.0);
+
+    assert_eq!(
+        anchor.rotation(AnchorMode::WorldLocked, looking, DT, &settings),
+        Quat::IDENTITY
+    );
+    assert_eq!(
+        anchor.rotation(AnchorMode::HeadLocked, looking, DT, &settings),
+        looking
+    );
+
+    let horizon = anchor.rotation(AnchorMode::HorizonLocked, looking, DT, &settings);
+    let (yaw, pitch, roll) = horizon.to_euler(EulerRot::YXZ);
+    assert!((yaw.to_degrees() - 40.0).abs() < 1e-3);
+    assert!(pitch.abs() < 1e-5 && roll.abs() < 1e-5);
+}
+
+#[test]
+fn test_lazy_follow_stays_within_threshold() {
+    let settings = LazyFollowSettings::default();
+    let mut follow = LazyFollow::default();
+    let start = follow.update(head(0.0, 0.0, 0.0), DT, &settings);
+
+    for step in 0..120 {
+        let yaw = (step as f32 * 0.1).sin() * (settings.threshold_deg - 5.0);
+        assert_eq!(follow.update(head(yaw, 0.0, 0.0), DT, &settings), start);
+        assert!(!follow.is_following());
+    }
+}
+
+#[test]
+fn test_lazy_follow_glides_back_with_easing() {
+    let settings = LazyFollowSettings {
+        easing: Easing::Linear,
+        ..LazyFollowSettings::default()
+    };
+    let mut follow = LazyFollow::default();
+    follow.update(head(0.0, 0.0, 0.0), DT, &settings);
+
+    // Turning past the threshold starts a glide towards the head, without its roll
+    let turned = head(60.0, 10.0, 15.0);
+    let first = follow.update(turned, DT, &settings);
+    assert!(follow.is_following());
+    let expected = 60.0 * DT / settings.duration_s;
+    assert!(
+        (yaw_deg(first) - expected).abs() < 0.1,
+        "{}",
+        yaw_deg(first)
+    );
+
+    let mut previous = yaw_deg(first);
+    let steps = (settings.duration_s / DT).ceil() as usize;
+    for _ in 0..steps {
+        let yaw = yaw_deg(follow.update(turned, DT, &settings));
+        assert!(yaw >= previous - 1e-4);
+        previous = yaw;
+    }
+    assert!(!follow.is_following());
+    let settled = follow.update(turned, DT, &settings);
+    let (yaw, pitch, roll) = settled.to_euler(EulerRot::YXZ);
+    assert!((yaw.to_degrees() - 60.0).abs() < 1e-3);
+    assert!((pitch.to_degrees() - 10.0).abs() < 1e-3);
+    assert!(roll.abs() < 1e-5);
+}
+
+#[test]
+fn test_easing_curves() {
+    for easing in Easing::ALL {
+        assert_eq!(easing.apply(0.0), 0.0);
+        assert_eq!(easing.apply(1.0), 1.0);
+        assert_eq!(easing.apply(2.0), 1.0);
+    }
+    assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    assert!(Easing::EaseInOut.apply(0.1) < 0.1);
+}
+
+#[test]
+fn test_modes_are_saved_per_screen() {
+    let mut anchoring = ScreenAnchoring::default();
+    assert_eq!(anchoring.mode(3), AnchorMode::WorldLocked);
+    anchoring.set_mode(2, AnchorMode::LazyFollow);
+    assert_eq!(
+        anchoring.modes,
+        vec![
+            AnchorMode::WorldLocked,
+            AnchorMode::WorldLocked,
+            AnchorMode::LazyFollow
+        ]
+    );
+
+    let mut layout = WindowLayout::default();
+    layout.anchoring = anchoring;
+    let json = serde_json::to_string(&layout).expect("serialize");
+    let restored: WindowLayout = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(restored.anchoring.mode(2), AnchorMode::LazyFollow);
+    assert!(restored.validate().is_ok());
+
+    let too_slow = LazyFollowSettings {
+        duration_s: 10.0,
+        ..LazyFollowSettings::default()
+    };
+    assert!(too_slow.validate().is_err());
+}
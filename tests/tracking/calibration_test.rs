@@ -0,0 +1,198 @@
+//! Tests for the streaming gyro bias calibration
+
+use super::at_rest;
+use bevy::math::Vec3;
+use xreal_virtual_desktop::state::schema::calibration::{
+    CalibrationData, CalibrationState, CALIBRATION_MAX_AGE_S,
+};
+use xreal_virtual_desktop::state::schema::core::PersistentAppState;
+use xreal_virtual_desktop::tracking::calibration::{
+    BiasCapture, RunningStats, CAPTURE_TIMEOUT_S, MIN_SAMPLES, TARGET_SAMPLES,
+};
+use xreal_virtual_desktop::tracking::{send_queued, Command};
+
+const DT: f32 = 0.001;
+
+/// Deterministic gyro noise of about ±0.1°/s per axis
+fn noise(step: usize) -> Vec3 {
+    let hash = |seed: f32| ((step as f32 * seed).sin() * 43_758.547).rem_euclid(1.0) - 0.5;
+    Vec3::new(hash(12.9898), hash(78.233), hash(37.719)) * 0.2_f32.to_radians()
+}
+
+fn bias() -> Vec3 {
+    Vec3::new(0.5, -0.3, 0.8) * std::f32::consts::PI / 180.0
+}
+
+#[test]
+fn test_running_stats_match_two_pass() {
+    let samples: Vec<Vec3> = (0..1000).map(|i| bias() + noise(i)).collect();
+    let mut stats = RunningStats::default();
+    for sample in &samples {
+        stats.push(*sample);
+    }
+
+    let mean = samples.iter().sum::<Vec3>() / samples.len() as f32;
+    let variance = samples
+        .iter()
+        .map(|sample| (*sample - mean) * (*sample - mean))
+        .sum::<Vec3>()
+        / (samples.len() - 1) as f32;
+    assert_eq!(stats.count(), 1000);
+    assert!(stats.mean().distance(mean) < 1e-7);
+    assert!(stats.variance().distance(variance) < 1e-9);
+}
+
+#[test]
+fn test_still_capture_finds_bias() {
+    let mut capture = BiasCapture::default();
+    let mut step = 0;
+    while !capture.is_finished() {
+        assert!(capture.push(bias() + noise(step), at_rest(), DT));
+        step += 1;
+    }
+    assert_eq!(step, TARGET_SAMPLES);
+
+    let progress = capture.progress();
+    assert_eq!(progress.rejected, 0);
+    assert!(progress.still);
+    assert_eq!(progress.fraction(), 1.0);
+    assert!(progress.quality() > 0.9, "{}", progress.quality());
+
+    let calibration = capture.finish().expect("bias");
+    assert_eq!(calibration.samples, TARGET_SAMPLES);
+    assert_eq!(calibration.quality, progress.quality());
+    let error = calibration.bias.distance(bias()).to_degrees();
+    assert!(error < 0.01, "bias error {error}°/s");
+}
+
+#[test]
+fn test_bump_is_rejected_with_settling_time() {
+    let mut capture = BiasCapture::default();
+    for step in 0..1000 {
+        capture.push(bias() + noise(step), at_rest(), DT);
+    }
+
+    // Knocking the table: a fast rotation and an acceleration spike
+    for step in 0..100 {
+        let rate = (step as f32 * 0.1).sin() * 30_f32.to_radians();
+        assert!(!capture.push(Vec3::X * rate, at_rest() * 1.3, DT));
+        assert!(!capture.progress().still);
+    }
+    // Settling samples after the bump are rejected too
+    assert!(!capture.push(bias(), at_rest(), DT));
+
+    let mut step = 1000;
+    while !capture.is_finished() {
+        capture.push(bias() + noise(step), at_rest(), DT);
+        step += 1;
+    }
+    assert!(capture.progress().rejected > 100);
+    let error = capture
+        .finish()
+        .expect("bias")
+        .bias
+        .distance(bias())
+        .to_degrees();
+    assert!(error < 0.01, "bias error {error}°/s");
+}
+
+#[test]
+fn test_moving_capture_times_out_and_fails() {
+    let mut capture = BiasCapture::default();
+    let mut step = 0;
+    while !capture.is_finished() {
+        // Brief pauses of 0.3 s every 3 s, mostly spent settling
+        let gyro = if step % 3000 < 2700 {
+            Vec3::Y * 0.5
+        } else {
+            bias() + noise(step)
+        };
+        capture.push(gyro, at_rest(), DT);
+        step += 1;
+    }
+    let progress = capture.progress();
+    assert!(progress.elapsed_s >= CAPTURE_TIMEOUT_S);
+    assert!(progress.samples > 0 && progress.samples < MIN_SAMPLES);
+    assert!(capture.finish().is_err());
+}
+
+#[test]
+fn test_noisy_gyro_lowers_quality() {
+    let mut quiet = BiasCapture::default();
+    let mut noisy = BiasCapture::default();
+    for step in 0..2000 {
+        quiet.push(bias() + noise(step), at_rest(), DT);
+        noisy.push(bias() + noise(step) * 10.0, at_rest(), DT);
+    }
+    assert!(noisy.progress().quality() < quiet.progress().quality());
+}
+
+fn calibrated(serial: &str, gyro_bias: [f32; 3]) -> CalibrationData {
+    CalibrationData {
+        state: CalibrationState::Calibrated,
+        gyro_bias,
+        calibrated_at: 1_000,
+        ..CalibrationData::for_device(serial)
+    }
+}
+
+#[test]
+fn test_records_are_kept_per_serial() {
+    let mut state = PersistentAppState {
+        calibration_data: calibrated("A", [0.1, 0.0, 0.0]),
+        ..PersistentAppState::default()
+    };
+
+    // Other glasses start without a calibration, the first keep theirs
+    assert!(state.activate_calibration("B"));
+    assert_eq!(state.calibration_data.device_serial, "B");
+    assert!(!state.calibration_data.is_calibrated());
+    state.calibration_data = calibrated("B", [0.0, 0.2, 0.0]);
+
+    assert!(state.activate_calibration("A"));
+    assert_eq!(state.calibration_data.gyro_bias, [0.1, 0.0, 0.0]);
+    assert!(!state.activate_calibration("A"));
+    assert!(state.activate_calibration("B"));
+    assert_eq!(state.calibration_data.gyro_bias, [0.0, 0.2, 0.0]);
+    assert_eq!(state.calibration_records.len(), 1);
+    assert!(state.validate().is_ok());
+}
+
+#[test]
+fn test_record_without_serial_is_adopted() {
+    let mut state = PersistentAppState {
+        calibration_data: calibrated("", [0.1, 0.0, 0.0]),
+        ..PersistentAppState::default()
+    };
+
+    assert!(!state.activate_calibration("A"));
+    assert_eq!(state.calibration_data.device_serial, "A");
+    assert_eq!(state.calibration_data.gyro_bias, [0.1, 0.0, 0.0]);
+
+    // Glasses without a serial leave the active record alone
+    assert!(!state.activate_calibration(""));
+    assert_eq!(state.calibration_data.device_serial, "A");
+    assert!(state.calibration_records.is_empty());
+}
+
+#[test]
+fn test_missing_or_old_calibration_needs_recalibration() {
+    let record = calibrated("A", [0.0; 3]);
+    assert!(!record.needs_recalibration(record.calibrated_at + 60));
+    assert!(record.needs_recalibration(record.calibrated_at + CALIBRATION_MAX_AGE_S + 1));
+    assert!(CalibrationData::for_device("A").needs_recalibration(record.calibrated_at));
+}
+
+#[test]
+fn test_queued_commands_wait_for_room_in_order() {
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    let mut queued = vec![Command::ResetCalibration, Command::Recenter];
+
+    send_queued(&tx, &mut queued);
+    assert!(matches!(queued[..], [Command::Recenter]));
+
+    assert!(matches!(rx.try_recv(), Ok(Command::ResetCalibration)));
+    send_queued(&tx, &mut queued);
+    assert!(queued.is_empty());
+    assert!(matches!(rx.try_recv(), Ok(Command::Recenter)));
+}
@@ -0,0 +1,161 @@
+//! Tests for gyro bias refinement and yaw drift correction
+
+use super::at_rest;
+use bevy::math::{EulerRot, Quat, Vec3};
+use xreal_virtual_desktop::state::schema::tracking::DriftCompensationSettings;
+use xreal_virtual_desktop::state::schema::{CalibrationData, StateValidation};
+use xreal_virtual_desktop::tracking::drift::DriftCompensator;
+
+const DT: f32 = 0.001;
+
+/// Deterministic gyro noise of about ±0.1°/s per axis
+fn noise(step: usize) -> Vec3 {
+    let hash = |seed: f32| ((step as f32 * seed).sin() * 43_758.547).rem_euclid(1.0) - 0.5;
+    Vec3::new(hash(12.9898), hash(78.233), hash(37.719)) * 0.2_f32.to_radians()
+}
+
+fn yaw(orientation: Quat) -> f32 {
+    orientation.to_euler(EulerRot::YXZ).0
+}
+
+#[test]
+fn test_bias_converges_while_still() {
+    let settings = DriftCompensationSettings::default();
+    let bias = Vec3::new(0.4, -0.8, 0.6) * std::f32::consts::PI / 180.0;
+    let mut drift = DriftCompensator::default();
+
+    for step in 0..30_000 {
+        drift.correct_gyro(bias + noise(step), at_rest(), DT, &settings);
+    }
+
+    assert!(drift.is_stationary());
+    let error = drift.bias().distance(bias).to_degrees();
+    assert!(error < 0.02, "bias error {error}°/s");
+}
+
+#[test]
+fn test_motion_is_not_learned() {
+    let settings = DriftCompensationSettings::default();
+    let mut drift = DriftCompensator::default();
+
+    // Head shaking at up to 60°/s
+    for step in 0..10_000 {
+        let rate = (step as f32 * DT * 6.0).sin() * 60_f32.to_radians();
+        drift.correct_gyro(Vec3::Y * rate + noise(step), at_rest(), DT, &settings);
+        assert!(!drift.is_stationary());
+    }
+    assert_eq!(drift.bias(), Vec3::ZERO);
+}
+
+#[test]
+fn test_slow_turn_above_drift_threshold_is_not_learned() {
+    let settings = DriftCompensationSettings::default();
+    let mut drift = DriftCompensator::default();
+
+    let rate = Vec3::Y * (settings.drift_threshold_dps * 2.0).to_radians();
+    for step in 0..10_000 {
+        drift.correct_gyro(rate + noise(step), at_rest(), DT, &settings);
+    }
+    assert!(!drift.is_stationary());
+    assert_eq!(drift.bias(), Vec3::ZERO);
+}
+
+#[test]
+fn test_yaw_drift_learned_while_still_and_removed_while_moving() {
+    let settings = DriftCompensationSettings {
+        learn_gyro_bias: false,
+        ..DriftCompensationSettings::default()
+    };
+    let mut drift = DriftCompensator::default();
+    // Filter yaw creeping at 0.5°/s with the head still
+    let drift_rate = 0.5_f32.to_radians();
+    let mut filter_yaw = 0.0;
+    let mut held_yaw = None;
+
+    for step in 0..20_000 {
+        filter_yaw += drift_rate * DT;
+        drift.correct_gyro(Vec3::Y * drift_rate + noise(step), at_rest(), DT, &settings);
+        let corrected = drift.correct_yaw(Quat::from_rotation_y(filter_yaw), DT, &settings);
+        if drift.is_stationary() {
+            let held = *held_yaw.get_or_insert(yaw(corrected));
+            assert!((yaw(corrected) - held).abs() < 1e-3);
+        }
+    }
+    let learned = drift.yaw_drift_rate();
+    assert!(
+        (learned - drift_rate).abs() < drift_rate * 0.1,
+        "learned {}°/s",
+        learned.to_degrees()
+    );
+
+    // A 90° turn keeps drifting in the filter, the correction removes it
+    let turn_rate = 45_f32.to_radians();
+    let start = yaw(drift.correct_yaw(Quat::from_rotation_y(filter_yaw), DT, &settings));
+    for step in 0..2_000 {
+        filter_yaw += (turn_rate + drift_rate) * DT;
+        drift.correct_gyro(Vec3::Y * turn_rate + noise(step), at_rest(), DT, &settings);
+        assert!(!drift.is_stationary());
+        drift.correct_yaw(Quat::from_rotation_y(filter_yaw), DT, &settings);
+    }
+    let end = yaw(drift.correct_yaw(Quat::from_rotation_y(filter_yaw), 0.0, &settings));
+    let turned = (end - start).to_degrees();
+    assert!((turned - 90.0).abs() < 0.2, "turned {turned}°");
+}
+
+#[test]
+fn test_disabled_only_removes_known_bias() {
+    let settings = DriftCompensationSettings {
+        enabled: false,
+        ..DriftCompensationSettings::default()
+    };
+    let bias = Vec3::new(0.01, 0.0, 0.0);
+    let mut drift = DriftCompensator::new(bias);
+
+    for step in 0..5_000 {
+        let corrected = drift.correct_gyro(Vec3::X * 0.02 + noise(step), at_rest(), DT, &settings);
+        assert!((corrected - (Vec3::X * 0.01 + noise(step))).length() < 1e-6);
+    }
+    assert!(!drift.is_stationary());
+    assert_eq!(drift.bias(), bias);
+
+    let orientation = Quat::from_rotation_y(0.3);
+    assert_eq!(drift.correct_yaw(orientation, DT, &settings), orientation);
+}
+
+#[test]
+fn test_reset_yaw_drops_correction() {
+    let settings = DriftCompensationSettings::default();
+    let mut drift = DriftCompensator::default();
+    drift.set_yaw_drift_rate(0.01);
+
+    // Moving, so the learned rate is removed
+    for _ in 0..1_000 {
+        drift.correct_gyro(Vec3::Y, at_rest(), DT, &settings);
+        drift.correct_yaw(Quat::IDENTITY, DT, &settings);
+    }
+    let corrected = drift.correct_yaw(Quat::IDENTITY, 0.0, &settings);
+    assert!((yaw(corrected) + 0.01).abs() < 1e-4);
+
+    drift.reset_yaw();
+    assert_eq!(
+        drift.correct_yaw(Quat::IDENTITY, 0.0, &settings),
+        Quat::IDENTITY
+    );
+}
+
+#[test]
+fn test_drift_settings_validation() {
+    assert!(DriftCompensationSettings::default().validate().is_ok());
+
+    let zero_threshold = DriftCompensationSettings {
+        stationary_threshold_dps: 0.0,
+        ..DriftCompensationSettings::default()
+    };
+    assert!(zero_threshold.validate().is_err());
+
+    let runaway_drift = CalibrationData {
+        yaw_drift_rate: 2.0,
+        ..CalibrationData::default()
+    };
+    assert!(runaway_drift.validate().is_err());
+}
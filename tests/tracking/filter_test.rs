@@ -0,0 +1,169 @@
+//! Tests for the orientation filter backends
+
+use bevy::math::{EulerRot, Quat, Vec3};
+use xreal_virtual_desktop::state::schema::tracking::{FilterKind, OrientationFilterSettings};
+use xreal_virtual_desktop::state::schema::StateValidation;
+use xreal_virtual_desktop::tracking::filter::{
+    apply_filter_settings, build_filter, OrientationFilter, STANDARD_GRAVITY,
+};
+
+const DT: f32 = 0.001;
+
+/// Filters implemented in this crate, Fusion AHRS is covered by its own tests
+const FILTERS: [FilterKind; 3] = [
+    FilterKind::Complementary,
+    FilterKind::Madgwick,
+    FilterKind::Mahony,
+];
+
+fn filter(kind: FilterKind) -> Box<dyn OrientationFilter> {
+    build_filter(&OrientationFilterSettings {
+        kind,
+        ..OrientationFilterSettings::default()
+    })
+}
+
+/// Accelerometer reading at rest for a head orientation
+fn gravity_in_head(orientation: Quat) -> Vec3 {
+    orientation.inverse() * Vec3::Y * STANDARD_GRAVITY
+}
+
+fn run(filter: &mut dyn OrientationFilter, gyro: Vec3, accel: Vec3, seconds: f32) {
+    for _ in 0..(seconds / DT) as usize {
+        filter.update(gyro, accel, None, DT);
+    }
+}
+
+/// Angle between the estimated and true up directions, in degrees
+fn tilt_error_deg(estimate: Quat, truth: Quat) -> f32 {
+    (estimate.inverse() * Vec3::Y)
+        .angle_between(truth.inverse() * Vec3::Y)
+        .to_degrees()
+}
+
+#[test]
+fn test_first_sample_starts_from_gravity() {
+    let truth = Quat::from_euler(EulerRot::YXZ, 0.0, 0.3, -0.2);
+    for kind in FILTERS {
+        let mut filter = filter(kind);
+        filter.update(Vec3::ZERO, gravity_in_head(truth), None, DT);
+        assert!(
+            tilt_error_deg(filter.orientation(), truth) < 0.5,
+            "{:?} did not start from gravity",
+            kind
+        );
+    }
+}
+
+#[test]
+fn test_gyro_yaw_is_integrated() {
+    for kind in FILTERS {
+        let mut filter = filter(kind);
+        run(
+            filter.as_mut(),
+            Vec3::new(0.0, 1.0, 0.0),
+            gravity_in_head(Quat::IDENTITY),
+            1.0,
+        );
+        let (yaw, pitch, roll) = filter.orientation().to_euler(EulerRot::YXZ);
+        assert!((yaw - 1.0).abs() < 0.02, "{:?} yaw {}", kind, yaw);
+        assert!(pitch.abs() < 0.01 && roll.abs() < 0.01, "{:?} tilted", kind);
+    }
+}
+
+#[test]
+fn test_tilt_converges_to_gravity() {
+    let truth = Quat::from_rotation_x(20f32.to_radians());
+    for kind in FILTERS {
+        let mut filter = filter(kind);
+        run(
+            filter.as_mut(),
+            Vec3::ZERO,
+            gravity_in_head(Quat::IDENTITY),
+            0.01,
+        );
+        run(filter.as_mut(), Vec3::ZERO, gravity_in_head(truth), 20.0);
+        let error = tilt_error_deg(filter.orientation(), truth);
+        assert!(error < 1.0, "{:?} tilt error {}°", kind, error);
+    }
+}
+
+#[test]
+fn test_mahony_integral_absorbs_gyro_bias() {
+    let bias = Vec3::new(0.02, 0.0, 0.0);
+    let mut settings = OrientationFilterSettings {
+        kind: FilterKind::Mahony,
+        ..OrientationFilterSettings::default()
+    };
+    settings.mahony.ki = 0.5;
+
+    let mut filter = build_filter(&settings);
+    run(filter.as_mut(), bias, gravity_in_head(Quat::IDENTITY), 60.0);
+    assert!(tilt_error_deg(filter.orientation(), Quat::IDENTITY) < 0.1);
+
+    settings.mahony.ki = 0.0;
+    let mut proportional_only = build_filter(&settings);
+    run(
+        proportional_only.as_mut(),
+        bias,
+        gravity_in_head(Quat::IDENTITY),
+        60.0,
+    );
+    assert!(tilt_error_deg(proportional_only.orientation(), Quat::IDENTITY) > 0.5);
+}
+
+#[test]
+fn test_reset_forgets_orientation() {
+    for kind in FILTERS {
+        let mut filter = filter(kind);
+        run(
+            filter.as_mut(),
+            Vec3::new(0.0, 1.0, 0.0),
+            gravity_in_head(Quat::IDENTITY),
+            0.5,
+        );
+        filter.reset();
+        filter.update(Vec3::ZERO, gravity_in_head(Quat::IDENTITY), None, DT);
+        assert!(filter.orientation().angle_between(Quat::IDENTITY) < 0.01);
+    }
+}
+
+#[test]
+fn test_apply_settings_switches_or_retunes() {
+    let mut settings = OrientationFilterSettings {
+        kind: FilterKind::Complementary,
+        ..OrientationFilterSettings::default()
+    };
+    let mut filter = build_filter(&settings);
+    run(
+        filter.as_mut(),
+        Vec3::new(0.0, 1.0, 0.0),
+        gravity_in_head(Quat::IDENTITY),
+        0.5,
+    );
+
+    // Retuning keeps the orientation
+    settings.complementary.time_constant_s = 2.0;
+    apply_filter_settings(&mut filter, &settings);
+    assert_eq!(filter.kind(), FilterKind::Complementary);
+    assert!(filter.orientation().angle_between(Quat::IDENTITY) > 0.4);
+
+    settings.kind = FilterKind::Mahony;
+    apply_filter_settings(&mut filter, &settings);
+    assert_eq!(filter.kind(), FilterKind::Mahony);
+    assert_eq!(filter.orientation(), Quat::IDENTITY);
+}
+
+#[test]
+fn test_filter_settings_validation() {
+    let mut settings = OrientationFilterSettings::default();
+    assert!(settings.validate().is_ok());
+    settings.madgwick.beta = -1.0;
+    assert!(settings.validate().is_err());
+
+    // Older state files without filter settings load with defaults
+    let parsed: xreal_virtual_desktop::state::schema::tracking::TrackingSettings =
+        serde_json::from_str(r#"{"prediction":{"enabled":true,"render_latency_ms":16.0,"max_horizon_ms":50.0,"damping":5.0,"show_overlay":false}}"#)
+            .unwrap();
+    assert_eq!(parsed.filter, OrientationFilterSettings::default());
+}
@@ -0,0 +1,194 @@
+//! Tests for IMU health telemetry
+
+use super::at_rest;
+use bevy::math::{Quat, Vec3};
+use xreal_virtual_desktop::driver::DeviceProfile;
+use xreal_virtual_desktop::tracking::health::{ImuHealthMonitor, ImuTelemetry, TrackingQuality};
+
+const PERIOD_US: u64 = 1000;
+
+/// Slightly noisy gyro sample, so a healthy stream never counts as stuck
+fn gyro(step: u64) -> Vec3 {
+    Vec3::splat((step % 7) as f32 * 1e-4)
+}
+
+/// Feed one second of samples at 1 kHz, skipping the steps `drop` returns true for
+fn second(monitor: &mut ImuHealthMonitor, start: u64, drop: impl Fn(u64) -> bool) -> u64 {
+    for step in start..start + 1000 {
+        if !drop(step) {
+            monitor.push_sample(step * PERIOD_US, gyro(step), at_rest());
+        }
+    }
+    start + 1000
+}
+
+#[test]
+fn test_healthy_stream_is_good() {
+    let mut monitor = ImuHealthMonitor::default();
+    second(&mut monitor, 1, |_| false);
+    let telemetry = monitor.report();
+
+    assert!((telemetry.sample_rate_hz - 1000.0).abs() < 1.0);
+    assert!(telemetry.dt_jitter_ms < 0.01);
+    assert!((telemetry.max_dt_ms - 1.0).abs() < 0.01);
+    assert_eq!(telemetry.dropped_packets, 0);
+    assert_eq!(telemetry.packet_loss, 0.0);
+    assert!(!telemetry.saturated && !telemetry.stuck);
+    assert_eq!(telemetry.quality(), TrackingQuality::Good);
+}
+
+#[test]
+fn test_dropped_packets_and_regressions() {
+    let mut monitor = ImuHealthMonitor::default();
+    // Every tenth sample lost
+    let next = second(&mut monitor, 1, |step| step % 10 == 5);
+    let telemetry = monitor.report();
+    assert_eq!(telemetry.dropped_packets, 100);
+    assert!((telemetry.packet_loss - 0.1).abs() < 0.005);
+    assert!(telemetry.sample_rate_hz < 950.0);
+    assert_eq!(telemetry.quality(), TrackingQuality::Degraded);
+
+    // A repeated timestamp is counted but does not distort the rate
+    monitor.push_sample((next - 1) * PERIOD_US, gyro(next), at_rest());
+    second(&mut monitor, next, |_| false);
+    let telemetry = monitor.report();
+    assert_eq!(telemetry.timestamp_regressions, 1);
+    assert_eq!(telemetry.dropped_packets, 100, "totals carry over");
+    assert_eq!(telemetry.packet_loss, 0.0, "the window starts over");
+    assert_eq!(telemetry.quality(), TrackingQuality::Good);
+
+    // A reconnect restarts the device clock without counting a regression
+    monitor.restart_clock();
+    second(&mut monitor, 1, |_| false);
+    assert_eq!(monitor.report().timestamp_regressions, 1);
+}
+
+#[test]
+fn test_saturated_and_stuck_sensors_are_poor() {
+    let mut monitor = ImuHealthMonitor::default();
+    for step in 1..500 {
+        let spin = if step == 250 {
+            Vec3::Y * 40.0
+        } else {
+            gyro(step)
+        };
+        monitor.push_sample(step * PERIOD_US, spin, at_rest());
+    }
+    let telemetry = monitor.report();
+    assert!(telemetry.saturated && !telemetry.stuck);
+    assert_eq!(telemetry.quality(), TrackingQuality::Poor);
+
+    for step in 500..1000 {
+        monitor.push_sample(step * PERIOD_US, Vec3::ZERO, at_rest());
+    }
+    let telemetry = monitor.report();
+    assert!(telemetry.stuck && !telemetry.saturated);
+    assert_eq!(telemetry.quality(), TrackingQuality::Poor);
+}
+
+#[test]
+fn test_stream_is_rated_against_the_model() {
+    let mut monitor = ImuHealthMonitor::default();
+    monitor.set_imu(DeviceProfile::for_model("Mad Gaze Glow").imu);
+
+    // A healthy 100 Hz stream loses nothing
+    for step in 1..=100 {
+        monitor.push_sample(step * 10 * PERIOD_US, gyro(step), at_rest());
+    }
+    let telemetry = monitor.report();
+    assert!((telemetry.sample_rate_hz - 100.0).abs() < 0.1);
+    assert_eq!(telemetry.packet_loss, 0.0);
+    assert_eq!(telemetry.quality(), TrackingQuality::Good);
+
+    // Its accelerometer only reaches 2 g
+    monitor.push_sample(1010 * PERIOD_US, gyro(101), at_rest() * 2.5);
+    let telemetry = monitor.report();
+    assert!(telemetry.saturated);
+    assert_eq!(telemetry.quality(), TrackingQuality::Poor);
+}
+
+#[test]
+fn test_fusion_rejection_flags() {
+    let dt = 0.001;
+    let field = Vec3::new(0.0, -0.4, -0.2);
+    let mut monitor = ImuHealthMonitor::default();
+
+    // Level and undisturbed
+    for _ in 0..500 {
+        monitor.push_fusion(Quat::IDENTITY, at_rest(), Some(field), true, dt);
+    }
+    let telemetry = monitor.report();
+    assert!(!telemetry.accel_rejected && !telemetry.mag_disturbed && !telemetry.mag_rejected);
+
+    // Linear acceleration tilts the measured gravity away from the orientation
+    for _ in 0..500 {
+        let accelerating = at_rest() + Vec3::NEG_Z * 4.0;
+        monitor.push_fusion(Quat::IDENTITY, accelerating, Some(field), true, dt);
+    }
+    let telemetry = monitor.report();
+    assert!(telemetry.accel_rejected);
+
+    // A magnet nearby strengthens and turns the field
+    let disturbed = Vec3::new(0.5, -0.4, 0.0);
+    for _ in 0..500 {
+        monitor.push_fusion(Quat::IDENTITY, at_rest(), Some(disturbed), true, dt);
+    }
+    let telemetry = monitor.report();
+    assert!(telemetry.mag_disturbed && telemetry.mag_rejected);
+    assert!(!telemetry.accel_rejected);
+}
+
+#[test]
+fn test_heading_is_only_rejected_while_fused() {
+    let dt = 0.001;
+    let mut monitor = ImuHealthMonitor::default();
+    monitor.push_fusion(
+        Quat::IDENTITY,
+        at_rest(),
+        Some(Vec3::new(0.0, -0.4, -0.2)),
+        false,
+        dt,
+    );
+    for _ in 0..500 {
+        let turned = Vec3::new(0.2, -0.4, 0.0);
+        monitor.push_fusion(Quat::IDENTITY, at_rest(), Some(turned), false, dt);
+    }
+    let telemetry = monitor.report();
+    assert!(!telemetry.mag_rejected && !telemetry.mag_disturbed);
+}
+
+#[test]
+fn test_quality_thresholds() {
+    let good = ImuTelemetry {
+        sample_rate_hz: 1000.0,
+        nominal_rate_hz: 1000.0,
+        ..ImuTelemetry::default()
+    };
+    assert_eq!(good.quality(), TrackingQuality::Good);
+    assert_eq!(
+        ImuTelemetry {
+            dt_jitter_ms: 3.0,
+            ..good
+        }
+        .quality(),
+        TrackingQuality::Degraded
+    );
+    assert_eq!(
+        ImuTelemetry {
+            sample_rate_hz: 300.0,
+            ..good
+        }
+        .quality(),
+        TrackingQuality::Poor
+    );
+    assert_eq!(
+        ImuTelemetry {
+            accel_rejected: true,
+            ..good
+        }
+        .quality(),
+        TrackingQuality::Degraded
+    );
+    // No samples at all
+    assert_eq!(ImuTelemetry::default().quality(), TrackingQuality::Poor);
+}
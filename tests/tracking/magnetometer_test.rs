@@ -0,0 +1,130 @@
+//! Tests for the magnetometer ellipsoid calibration
+
+use bevy::math::{Mat3, Vec3};
+use xreal_virtual_desktop::state::schema::{CalibrationData, StateValidation};
+use xreal_virtual_desktop::tracking::magnetometer::{
+    fit_ellipsoid, MagCalibration, MagCapture, MIN_SAMPLES, TARGET_COVERAGE,
+};
+
+const FIELD_UT: f32 = 50.0;
+
+/// Evenly spread directions on the unit sphere
+fn sphere(count: usize) -> Vec<Vec3> {
+    let golden = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+            let r = (1.0 - y * y).sqrt();
+            let theta = golden * i as f32;
+            Vec3::new(r * theta.cos(), y, r * theta.sin())
+        })
+        .collect()
+}
+
+fn soft_iron() -> Mat3 {
+    Mat3::from_cols(
+        Vec3::new(1.2, 0.1, 0.0),
+        Vec3::new(0.1, 0.9, 0.05),
+        Vec3::new(0.0, 0.05, 1.05),
+    )
+}
+
+fn hard_iron() -> Vec3 {
+    Vec3::new(20.0, -35.0, 10.0)
+}
+
+/// Raw readings of the field in `directions` through a distorting magnetometer
+fn distorted(directions: &[Vec3]) -> Vec<Vec3> {
+    directions
+        .iter()
+        .enumerate()
+        .map(|(i, direction)| {
+            let noise = ((i as f32 * 12.9898).sin() * 43_758.547).rem_euclid(1.0) - 0.5;
+            soft_iron() * (*direction * FIELD_UT) + hard_iron() + Vec3::splat(noise * 0.2)
+        })
+        .collect()
+}
+
+#[test]
+fn test_fit_recovers_hard_and_soft_iron() {
+    let directions = sphere(500);
+    let calibration = fit_ellipsoid(&distorted(&directions)).expect("fit");
+
+    assert!(calibration.hard_iron.distance(hard_iron()) < 0.5);
+    assert!(calibration.fit_error < 0.005, "{}", calibration.fit_error);
+
+    // Corrected readings are the true field up to a uniform scale
+    let undistorted = calibration.soft_iron * soft_iron();
+    let scale = undistorted.determinant().cbrt();
+    let residual = undistorted * (1.0 / scale) - Mat3::IDENTITY;
+    assert!(
+        residual.to_cols_array().iter().all(|v| v.abs() < 0.01),
+        "{residual:?}"
+    );
+
+    // The corrected sphere keeps the local field strength
+    let relative =
+        (calibration.field_strength - FIELD_UT * scale).abs() / calibration.field_strength;
+    assert!(relative < 0.01);
+    for direction in &directions {
+        let corrected = calibration.apply(soft_iron() * (*direction * FIELD_UT) + hard_iron());
+        assert!((corrected.length() - calibration.field_strength).abs() < 0.3);
+    }
+}
+
+#[test]
+fn test_capture_needs_directions_not_just_samples() {
+    // Turning only around the vertical axis sweeps one band of directions
+    let mut flat = MagCapture::default();
+    for i in 0..1000 {
+        let angle = i as f32 * 0.01;
+        flat.push(soft_iron() * Vec3::new(angle.cos(), 0.1, angle.sin()) * FIELD_UT + hard_iron());
+    }
+    assert!(flat.coverage() < TARGET_COVERAGE);
+    assert!(!flat.is_complete());
+    assert!(flat.fit().is_err());
+
+    let mut figure_eight = MagCapture::default();
+    for sample in distorted(&sphere(MIN_SAMPLES * 3)) {
+        figure_eight.push(sample);
+    }
+    assert!(figure_eight.coverage() >= TARGET_COVERAGE);
+    assert!(figure_eight.is_complete());
+    let calibration = figure_eight.fit().expect("fit");
+    assert!(calibration.quality() > 0.8, "{}", calibration.quality());
+}
+
+#[test]
+fn test_too_few_samples_are_rejected() {
+    let mut capture = MagCapture::default();
+    for sample in distorted(&sphere(MIN_SAMPLES / 2)) {
+        capture.push(sample);
+    }
+    assert!(capture.fit().is_err());
+    assert!(fit_ellipsoid(&[Vec3::ONE; 20]).is_err());
+}
+
+#[test]
+fn test_calibration_roundtrips_through_calibration_data() {
+    let calibration = fit_ellipsoid(&distorted(&sphere(300))).expect("fit");
+    let mut data = CalibrationData::default();
+    assert_eq!(
+        MagCalibration::from_calibration_data(&data).apply(Vec3::new(1.0, 2.0, 3.0)),
+        Vec3::new(1.0, 2.0, 3.0)
+    );
+
+    calibration.store(&mut data);
+    assert!(data.validate().is_ok());
+    let restored = MagCalibration::from_calibration_data(&data);
+    let raw = Vec3::new(30.0, -10.0, 45.0);
+    assert!(restored.apply(raw).distance(calibration.apply(raw)) < 1e-4);
+}
+
+#[test]
+fn test_singular_soft_iron_fails_validation() {
+    let data = CalibrationData {
+        mag_soft_iron: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]],
+        ..CalibrationData::default()
+    };
+    assert!(data.validate().is_err());
+}
@@ -0,0 +1,30 @@
+//! Head tracking integration tests
+//!
+//! Tests for orientation filters, gyro bias and its temperature model, drift
+//! compensation, magnetometer and accelerometer calibration, smoothing,
+//! recentering, pose history, prediction, screen anchoring, the neck model, IMU
+//! health telemetry, synthetic-motion accuracy of the fused pipeline and
+//! tracking maths that run without glasses or a render loop.
+
+pub mod accelerometer_test;
+pub mod accuracy;
+pub mod anchoring_test;
+pub mod calibration_test;
+pub mod drift_test;
+pub mod filter_test;
+pub mod health_test;
+pub mod magnetometer_test;
+pub mod neck_test;
+pub mod pipeline_test;
+pub mod prediction_test;
+pub mod recenter_test;
+pub mod smoothing_test;
+pub mod thermal_test;
+
+use bevy::math::Vec3;
+use xreal_virtual_desktop::tracking::filter::STANDARD_GRAVITY;
+
+/// Accelerometer reading of glasses resting level
+pub fn at_rest() -> Vec3 {
+    Vec3::Y * STANDARD_GRAVITY
+}
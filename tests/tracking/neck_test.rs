@@ -0,0 +1,65 @@
+//! Tests for the neck model
+
+use bevy::math::{EulerRot, Quat, Vec3};
+use xreal_virtual_desktop::neck::eye_position;
+use xreal_virtual_desktop::state::schema::{NeckModelSettings, StateValidation};
+
+fn enabled() -> NeckModelSettings {
+    NeckModelSettings {
+        enabled: true,
+        ..NeckModelSettings::default()
+    }
+}
+
+#[test]
+fn test_disabled_or_straight_ahead_keeps_the_eyes_still() {
+    let turned = Quat::from_rotation_y(1.0);
+    assert_eq!(
+        eye_position(turned, &NeckModelSettings::default()),
+        Vec3::ZERO
+    );
+    assert!(eye_position(Quat::IDENTITY, &enabled()).length() < 1e-6);
+}
+
+#[test]
+fn test_eyes_orbit_the_neck_pivot() {
+    let settings = enabled();
+    let pivot = -Vec3::new(0.0, settings.neck_length_m, -settings.eye_offset_m);
+    let reach = Vec3::new(0.0, settings.neck_length_m, settings.eye_offset_m).length();
+
+    for (yaw, pitch, roll) in [
+        (90.0_f32, 0.0_f32, 0.0_f32),
+        (0.0, -40.0, 0.0),
+        (30.0, 20.0, 10.0),
+    ] {
+        let head = Quat::from_euler(
+            EulerRot::YXZ,
+            yaw.to_radians(),
+            pitch.to_radians(),
+            roll.to_radians(),
+        );
+        let eyes = eye_position(head, &settings);
+        assert!((eyes.distance(pivot) - reach).abs() < 1e-5);
+    }
+
+    // Turning right carries the eyes to the right, looking down carries them forward and down
+    let right = eye_position(Quat::from_rotation_y(-90_f32.to_radians()), &settings);
+    assert!(right.x > 0.07 && right.z > 0.0, "{right:?}");
+    let down = eye_position(Quat::from_rotation_x(-45_f32.to_radians()), &settings);
+    assert!(down.y < 0.0 && down.z < 0.0, "{down:?}");
+}
+
+#[test]
+fn test_neck_model_validation() {
+    assert!(NeckModelSettings::default().validate().is_ok());
+    let giraffe = NeckModelSettings {
+        neck_length_m: 1.0,
+        ..enabled()
+    };
+    assert!(giraffe.validate().is_err());
+    let backwards = NeckModelSettings {
+        eye_offset_m: -0.1,
+        ..enabled()
+    };
+    assert!(backwards.validate().is_err());
+}
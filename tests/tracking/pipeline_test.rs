@@ -0,0 +1,133 @@
+//! Tests for the steppable tracking pipeline and the synthetic-motion accuracy harness
+
+use super::accuracy::{self, AccuracyRun};
+use crate::driver::ideal_simulation;
+use bevy::math::Vec3;
+use xreal_virtual_desktop::driver::simulated::{MotionScript, SimulationConfig};
+use xreal_virtual_desktop::state::schema::tracking::DriftCompensationSettings;
+use xreal_virtual_desktop::tracking::filter::STANDARD_GRAVITY;
+use xreal_virtual_desktop::tracking::pipeline::TrackingPipeline;
+
+fn without_magnetometer(motion: MotionScript) -> SimulationConfig {
+    SimulationConfig {
+        motion,
+        mag_rate_hz: 0,
+        ..ideal_simulation()
+    }
+}
+
+#[test]
+fn test_pose_only_after_new_samples() {
+    let mut pipeline = TrackingPipeline::default();
+    assert!(pipeline.pose().is_none());
+
+    let sample = pipeline.push_imu(Vec3::Y * STANDARD_GRAVITY, Vec3::ZERO, 1_000);
+    pipeline.push_imu(Vec3::Y * STANDARD_GRAVITY, Vec3::ZERO, 3_000);
+    assert!(pipeline.pose().is_some());
+    assert!(pipeline.pose().is_none());
+
+    pipeline.push_mag(Vec3::new(0.0, -44.0, -20.0));
+    assert!(pipeline.pose().is_none());
+    assert!(pipeline.magnetometer().is_some());
+    assert_eq!(sample.accel, Vec3::Y * STANDARD_GRAVITY);
+}
+
+#[test]
+fn test_sample_interval_from_timestamps() {
+    let mut pipeline = TrackingPipeline::default();
+    pipeline.push_imu(Vec3::Y * STANDARD_GRAVITY, Vec3::ZERO, 10_000);
+    let sample = pipeline.push_imu(Vec3::Y * STANDARD_GRAVITY, Vec3::ZERO, 12_000);
+    assert!((sample.dt - 0.002).abs() < 1e-6);
+
+    // A reconnect restarts the device clock
+    let sample = pipeline.push_imu(Vec3::Y * STANDARD_GRAVITY, Vec3::ZERO, 500);
+    assert!(sample.dt > 0.0 && sample.dt < 0.002);
+}
+
+#[test]
+fn test_stationary_has_small_error() {
+    let mut pipeline = TrackingPipeline::default();
+    let report = accuracy::measure(
+        &mut pipeline,
+        SimulationConfig {
+            realtime: false,
+            ..Default::default()
+        },
+        &AccuracyRun::default(),
+    );
+
+    assert!(report.poses > 0);
+    assert!(report.mean_error_deg <= report.rms_error_deg);
+    assert!(report.rms_error_deg < 1.0, "{report:?}");
+    assert!(report.rms_tilt_error_deg < 1.0, "{report:?}");
+    assert_eq!(report.latency_ms, 0.0);
+}
+
+#[test]
+fn test_oscillation_is_tracked() {
+    let mut pipeline = TrackingPipeline::default();
+    let motion = MotionScript::oscillation(Vec3::Y, 30f32.to_radians(), 0.25);
+    let report = accuracy::measure(
+        &mut pipeline,
+        without_magnetometer(motion),
+        &AccuracyRun::default(),
+    );
+
+    assert!(report.rms_error_deg < 3.0, "{report:?}");
+    assert!(report.max_error_deg >= report.rms_error_deg);
+    assert!(report.latency_ms <= AccuracyRun::default().max_latency_ms);
+}
+
+#[test]
+fn test_smoothing_shows_as_latency() {
+    let motion = || MotionScript::oscillation(Vec3::Y, 30f32.to_radians(), 0.5);
+    let run = AccuracyRun {
+        batch_size: 16,
+        ..Default::default()
+    };
+
+    let mut raw = TrackingPipeline::default();
+    raw.smoothing.enabled = false;
+    let raw_report = accuracy::measure(&mut raw, without_magnetometer(motion()), &run);
+
+    let mut smoothed = TrackingPipeline::default();
+    smoothed.smoothing.min_cutoff_hz = 2.0;
+    smoothed.smoothing.beta = 0.0;
+    let smoothed_report = accuracy::measure(&mut smoothed, without_magnetometer(motion()), &run);
+
+    assert!(
+        smoothed_report.latency_ms > raw_report.latency_ms,
+        "smoothed {smoothed_report:?} raw {raw_report:?}"
+    );
+}
+
+#[test]
+fn test_bias_drift_is_compensated() {
+    let config = || SimulationConfig {
+        gyro_bias: Vec3::Y * 0.5f32.to_radians(),
+        ..without_magnetometer(MotionScript::stationary())
+    };
+    let run = AccuracyRun {
+        duration_s: 60.0,
+        ..Default::default()
+    };
+
+    let mut uncompensated = TrackingPipeline::default();
+    uncompensated.drift_settings = DriftCompensationSettings {
+        enabled: false,
+        ..Default::default()
+    };
+    let drifting = accuracy::measure(&mut uncompensated, config(), &run);
+    // 0.5°/s of bias turns the view by 30° a minute
+    assert!(
+        (drifting.yaw_drift_deg_per_min.abs() - 30.0).abs() < 3.0,
+        "{drifting:?}"
+    );
+
+    let mut compensated = TrackingPipeline::default();
+    let report = accuracy::measure(&mut compensated, config(), &run);
+    assert!(
+        report.yaw_drift_deg_per_min.abs() < drifting.yaw_drift_deg_per_min.abs() * 0.25,
+        "compensated {report:?} uncompensated {drifting:?}"
+    );
+}
@@ -0,0 +1,140 @@
+//! Tests for motion-to-photon pose prediction
+
+use bevy::math::{EulerRot, Quat, Vec3};
+use instant::Instant;
+use std::time::Duration;
+use xreal_virtual_desktop::prediction::{
+    predict_orientation, prediction_horizon, PoseHistory, PredictionStats,
+};
+use xreal_virtual_desktop::state::schema::tracking::PredictionSettings;
+use xreal_virtual_desktop::tracking::{PoseQueue, PoseSample, POSE_QUEUE_CAPACITY};
+
+const EPSILON: f32 = 1e-4;
+
+/// Constant yaw rate of 90°/s, sampled every millisecond
+fn yawing_pose(start: Instant, ms: u64) -> PoseSample {
+    let rate = 90f32.to_radians();
+    let t = ms as f32 / 1000.0;
+    PoseSample {
+        quat: Quat::from_rotation_y(rate * t),
+        angular_velocity: Vec3::new(0.0, rate, 0.0),
+        timestamp: start + Duration::from_millis(ms),
+    }
+}
+
+fn yaw(quat: Quat) -> f32 {
+    quat.to_euler(EulerRot::YXZ).0
+}
+
+#[test]
+fn test_constant_velocity_prediction() {
+    let pose = yawing_pose(Instant::now(), 0);
+    let predicted = predict_orientation(&pose, 0.02, 0.0);
+    assert!((yaw(predicted) - 1.8f32.to_radians()).abs() < EPSILON);
+}
+
+#[test]
+fn test_damping_shortens_prediction() {
+    let pose = yawing_pose(Instant::now(), 0);
+    let undamped = yaw(predict_orientation(&pose, 0.05, 0.0));
+    let damped = yaw(predict_orientation(&pose, 0.05, 10.0));
+    assert!(damped > 0.0 && damped < undamped);
+
+    // Velocity decays to zero, so the rotation converges instead of growing
+    let far = yaw(predict_orientation(&pose, 10.0, 10.0));
+    assert!((far - 9f32.to_radians()).abs() < 1e-3);
+}
+
+#[test]
+fn test_horizon_includes_display_delay_and_is_capped() {
+    let settings = PredictionSettings {
+        render_latency_ms: 10.0,
+        max_horizon_ms: 50.0,
+        ..PredictionSettings::default()
+    };
+    assert_eq!(
+        prediction_horizon(&settings, 7000),
+        Duration::from_secs_f32(0.017)
+    );
+    assert_eq!(
+        prediction_horizon(&settings, 100_000),
+        Duration::from_secs_f32(0.05)
+    );
+}
+
+#[test]
+fn test_history_interpolates_between_samples() {
+    let start = Instant::now();
+    let mut history = PoseHistory::default();
+    history.push(yawing_pose(start, 0));
+    history.push(yawing_pose(start, 10));
+
+    let middle = history
+        .orientation_at(start + Duration::from_millis(5))
+        .expect("inside history");
+    assert!((yaw(middle) - 0.45f32.to_radians()).abs() < EPSILON);
+    assert!(history
+        .orientation_at(start + Duration::from_millis(11))
+        .is_none());
+}
+
+#[test]
+fn test_history_is_bounded_and_ordered() {
+    let start = Instant::now();
+    let mut history = PoseHistory::default();
+    for ms in 0..1000 {
+        history.push(yawing_pose(start, ms));
+    }
+    assert!(history.len() < 1000);
+    assert_eq!(
+        history.latest().map(|p| p.timestamp),
+        Some(start + Duration::from_millis(999))
+    );
+
+    // Late samples do not rewind the history
+    history.push(yawing_pose(start, 500));
+    assert_eq!(
+        history.latest().map(|p| p.timestamp),
+        Some(start + Duration::from_millis(999))
+    );
+}
+
+#[test]
+fn test_pose_queue_keeps_the_latest_poses() {
+    let start = Instant::now();
+    let queue = PoseQueue::default();
+    let count = POSE_QUEUE_CAPACITY as u64 + 10;
+    for ms in 0..count {
+        queue.publish(yawing_pose(start, ms));
+    }
+
+    let poses: Vec<PoseSample> = queue.drain().collect();
+    assert_eq!(poses.len(), POSE_QUEUE_CAPACITY);
+    assert_eq!(poses[0], yawing_pose(start, 10));
+    assert_eq!(poses.last(), Some(&yawing_pose(start, count - 1)));
+    assert_eq!(queue.drain().count(), 0);
+}
+
+#[test]
+fn test_prediction_error_is_measured_once_pose_arrives() {
+    let start = Instant::now();
+    let mut history = PoseHistory::default();
+    let mut stats = PredictionStats::default();
+    history.push(yawing_pose(start, 0));
+
+    let target = start + Duration::from_millis(20);
+    let latest = *history.latest().expect("pose");
+    let predicted = predict_orientation(&latest, 0.02, 0.0);
+    stats.record(target, predicted, latest.quat);
+
+    stats.resolve(&history);
+    assert_eq!(stats.measured, 0);
+
+    for ms in 1..=25 {
+        history.push(yawing_pose(start, ms));
+    }
+    stats.resolve(&history);
+    assert_eq!(stats.measured, 1);
+    assert!(stats.mean_error_deg < 0.01);
+    assert!((stats.mean_unpredicted_error_deg - 1.8).abs() < 0.01);
+}
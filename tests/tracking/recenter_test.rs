@@ -0,0 +1,136 @@
+//! Tests for smooth recentering
+
+use bevy::math::{EulerRot, Quat};
+use xreal_virtual_desktop::state::schema::{RecenterSettings, StateValidation};
+use xreal_virtual_desktop::tracking::recenter::Recenter;
+
+const DT: f32 = 0.01;
+
+fn head(yaw_deg: f32, pitch_deg: f32, roll_deg: f32) -> Quat {
+    Quat::from_euler(
+        EulerRot::YXZ,
+        yaw_deg.to_radians(),
+        pitch_deg.to_radians(),
+        roll_deg.to_radians(),
+    )
+}
+
+fn euler_deg(rotation: Quat) -> (f32, f32, f32) {
+    let (yaw, pitch, roll) = rotation.to_euler(EulerRot::YXZ);
+    (yaw.to_degrees(), pitch.to_degrees(), roll.to_degrees())
+}
+
+#[test]
+fn test_recenter_zeroes_yaw_only() {
+    let settings = RecenterSettings {
+        transition_s: 0.0,
+        ..RecenterSettings::default()
+    };
+    let mut recenter = Recenter::default();
+    let looking = head(70.0, -15.0, 8.0);
+    assert_eq!(recenter.apply(looking, DT, &settings), looking);
+
+    recenter.request();
+    let (yaw, pitch, roll) = euler_deg(recenter.apply(looking, DT, &settings));
+    assert!(yaw.abs() < 1e-3, "{yaw}");
+    assert!((pitch + 15.0).abs() < 1e-3, "{pitch}");
+    assert!((roll - 8.0).abs() < 1e-3, "{roll}");
+
+    // Turning afterwards is relative to the new center
+    let (yaw, _, _) = euler_deg(recenter.apply(head(100.0, -15.0, 8.0), DT, &settings));
+    assert!((yaw - 30.0).abs() < 1e-3, "{yaw}");
+}
+
+#[test]
+fn test_recenter_with_pitch_keeps_roll() {
+    let settings = RecenterSettings {
+        include_pitch: true,
+        transition_s: 0.0,
+        ..RecenterSettings::default()
+    };
+    let mut recenter = Recenter::default();
+    recenter.request();
+    let (yaw, pitch, roll) = euler_deg(recenter.apply(head(-40.0, 25.0, -12.0), DT, &settings));
+    assert!(yaw.abs() < 1e-3 && pitch.abs() < 1e-3, "{yaw} {pitch}");
+    assert!((roll + 12.0).abs() < 1e-3, "{roll}");
+}
+
+#[test]
+fn test_recenter_transition_is_smooth() {
+    let settings = RecenterSettings::default();
+    let mut recenter = Recenter::default();
+    let looking = head(60.0, 0.0, 0.0);
+    recenter.request();
+
+    let mut previous = 60.0;
+    let mut largest_step = 0.0_f32;
+    let mut steps = 0_usize;
+    loop {
+        let (yaw, _, _) = euler_deg(recenter.apply(looking, DT, &settings));
+        assert!(yaw <= previous + 1e-4);
+        largest_step = largest_step.max(previous - yaw);
+        previous = yaw;
+        steps += 1;
+        if !recenter.is_transitioning() {
+            break;
+        }
+    }
+    let expected = (settings.transition_s / DT).round() as usize;
+    assert!(steps.abs_diff(expected) <= 1, "{steps} steps");
+    assert!(!recenter.is_transitioning());
+    assert!(previous.abs() < 1e-2, "{previous}");
+    // Eased: the view never jumps by more than twice the linear step
+    assert!(largest_step < 2.0 * 60.0 * DT / settings.transition_s);
+}
+
+#[test]
+fn test_auto_recenter_after_looking_away() {
+    let settings = RecenterSettings {
+        auto_recenter: true,
+        auto_delay_s: 2.0,
+        transition_s: 0.0,
+        ..RecenterSettings::default()
+    };
+    let mut recenter = Recenter::default();
+
+    // Glancing aside for less than the delay keeps the center
+    let aside = head(settings.auto_angle_deg + 20.0, 0.0, 0.0);
+    for _ in 0..150 {
+        recenter.apply(aside, DT, &settings);
+    }
+    assert_eq!(
+        recenter.apply(Quat::IDENTITY, DT, &settings),
+        Quat::IDENTITY
+    );
+
+    // Looking away for longer recenters on the new direction
+    let mut view = Quat::IDENTITY;
+    for _ in 0..210 {
+        view = recenter.apply(aside, DT, &settings);
+    }
+    let (yaw, _, _) = euler_deg(view);
+    assert!(yaw.abs() < 1e-3, "{yaw}");
+
+    // Looking down is not looking away unless pitch is recentered too
+    let mut recenter = Recenter::default();
+    let down = head(0.0, -80.0, 0.0);
+    for _ in 0..300 {
+        let view = recenter.apply(down, DT, &settings);
+        assert!(view.angle_between(down) < 1e-5);
+    }
+}
+
+#[test]
+fn test_recenter_settings_validation() {
+    assert!(RecenterSettings::default().validate().is_ok());
+    let slow = RecenterSettings {
+        transition_s: 10.0,
+        ..RecenterSettings::default()
+    };
+    assert!(slow.validate().is_err());
+    let impatient = RecenterSettings {
+        auto_delay_s: 0.0,
+        ..RecenterSettings::default()
+    };
+    assert!(impatient.validate().is_err());
+}
@@ -0,0 +1,126 @@
+//! Tests for the velocity-adaptive orientation smoothing
+
+use bevy::math::Quat;
+use xreal_virtual_desktop::state::schema::tracking::{SmoothingPreset, SmoothingSettings};
+use xreal_virtual_desktop::state::schema::StateValidation;
+use xreal_virtual_desktop::tracking::smoothing::OrientationSmoother;
+
+/// Pose rate of the tracking thread
+const DT: f32 = 1.0 / 60.0;
+
+/// Deterministic jitter of about ±0.1° around a yaw angle
+fn jittered(step: usize, yaw: f32) -> Quat {
+    let noise = ((step as f32 * 12.9898).sin() * 43_758.547).fract() - 0.5;
+    Quat::from_rotation_y(yaw + noise * 0.2_f32.to_radians())
+        * Quat::from_rotation_x(noise * 0.2_f32.to_radians())
+}
+
+/// Mean angle between consecutive orientations, in degrees
+fn mean_step_deg(orientations: &[Quat]) -> f32 {
+    let total: f32 = orientations
+        .windows(2)
+        .map(|pair| pair[0].angle_between(pair[1]))
+        .sum();
+    (total / (orientations.len() - 1) as f32).to_degrees()
+}
+
+#[test]
+fn test_holding_still_removes_jitter() {
+    let settings = SmoothingSettings::default();
+    let mut smoother = OrientationSmoother::default();
+
+    let raw: Vec<Quat> = (0..600).map(|step| jittered(step, 0.0)).collect();
+    let smoothed: Vec<Quat> = raw
+        .iter()
+        .map(|&quat| smoother.update(quat, DT, &settings))
+        .collect();
+
+    let raw_jitter = mean_step_deg(&raw[300..]);
+    let smoothed_jitter = mean_step_deg(&smoothed[300..]);
+    assert!(
+        smoothed_jitter < raw_jitter * 0.5,
+        "jitter {smoothed_jitter}° vs raw {raw_jitter}°"
+    );
+}
+
+#[test]
+fn test_fast_turn_lags_less_than_fixed_slerp() {
+    let settings = SmoothingSettings::default();
+    let mut smoother = OrientationSmoother::default();
+    let mut fixed = Quat::IDENTITY;
+
+    // 200°/s head turn for half a second
+    let speed = 200_f32.to_radians();
+    let mut truth = Quat::IDENTITY;
+    let mut adaptive = Quat::IDENTITY;
+    smoother.update(truth, DT, &settings);
+    for step in 1..=30 {
+        truth = Quat::from_rotation_y(speed * DT * step as f32);
+        adaptive = smoother.update(truth, DT, &settings);
+        // The smoothing this filter replaced
+        fixed = fixed.slerp(truth, 0.05);
+    }
+
+    let adaptive_lag = adaptive.angle_between(truth).to_degrees();
+    let fixed_lag = fixed.angle_between(truth).to_degrees();
+    assert!(
+        adaptive_lag < fixed_lag * 0.25,
+        "lag {adaptive_lag}° vs fixed {fixed_lag}°"
+    );
+    assert!(smoother.speed() > speed * 0.5);
+}
+
+#[test]
+fn test_disabled_passes_through() {
+    let settings = SmoothingSettings {
+        enabled: false,
+        ..SmoothingSettings::default()
+    };
+    let mut smoother = OrientationSmoother::default();
+    for step in 0..10 {
+        let quat = jittered(step, step as f32 * 0.3);
+        assert_eq!(smoother.update(quat, DT, &settings), quat);
+    }
+}
+
+#[test]
+fn test_reset_starts_from_next_orientation() {
+    let settings = SmoothingSettings::default();
+    let mut smoother = OrientationSmoother::default();
+    smoother.update(Quat::IDENTITY, DT, &settings);
+
+    let turned = Quat::from_rotation_y(1.0);
+    smoother.reset();
+    assert_eq!(smoother.update(turned, DT, &settings), turned);
+    assert_eq!(smoother.speed(), 0.0);
+}
+
+#[test]
+fn test_presets_trade_smoothness_for_latency() {
+    let cinema = SmoothingSettings::from_preset(SmoothingPreset::Cinema);
+    let standard = SmoothingSettings::from_preset(SmoothingPreset::Standard);
+    let gaming = SmoothingSettings::from_preset(SmoothingPreset::Gaming);
+
+    assert_eq!(SmoothingSettings::default(), standard);
+    assert!(cinema.min_cutoff_hz < standard.min_cutoff_hz);
+    assert!(standard.min_cutoff_hz < gaming.min_cutoff_hz);
+    assert!(cinema.beta < standard.beta && standard.beta < gaming.beta);
+    for preset in SmoothingPreset::ALL {
+        assert!(SmoothingSettings::from_preset(preset).validate().is_ok());
+    }
+}
+
+#[test]
+fn test_validation_rejects_bad_cutoffs() {
+    let zero_cutoff = SmoothingSettings {
+        min_cutoff_hz: 0.0,
+        ..SmoothingSettings::default()
+    };
+    assert!(zero_cutoff.validate().is_err());
+
+    let negative_beta = SmoothingSettings {
+        beta: -1.0,
+        ..SmoothingSettings::default()
+    };
+    assert!(negative_beta.validate().is_err());
+}
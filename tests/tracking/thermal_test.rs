@@ -0,0 +1,248 @@
+//! Tests for the temperature-aware gyro bias model
+
+use bevy::math::Vec3;
+use xreal_virtual_desktop::state::schema::calibration::GyroBiasPoint;
+use xreal_virtual_desktop::state::schema::tracking::DriftCompensationSettings;
+use xreal_virtual_desktop::state::schema::{CalibrationData, StateValidation};
+use xreal_virtual_desktop::tracking::filter::STANDARD_GRAVITY;
+use xreal_virtual_desktop::tracking::pipeline::TrackingPipeline;
+use xreal_virtual_desktop::tracking::thermal::{self, TemperatureBiasModel, AMBIENT_CELSIUS};
+
+/// Bias rising by 0.02°/s per °C on the yaw axis from a constant offset
+fn bias_at(temperature: f32) -> Vec3 {
+    Vec3::new(0.004, -0.002, 0.001) + Vec3::Y * (temperature - 25.0) * 0.02_f32.to_radians()
+}
+
+fn model_from(
+    temperatures: impl IntoIterator<Item = f32>,
+    bias: impl Fn(f32) -> Vec3,
+) -> TemperatureBiasModel {
+    let mut model = TemperatureBiasModel::default();
+    for temperature in temperatures {
+        model.record(temperature, bias(temperature), 2000);
+    }
+    model
+}
+
+#[test]
+fn test_temperature_rises_with_uptime() {
+    assert_eq!(thermal::estimate_temperature(0.0), AMBIENT_CELSIUS);
+
+    let warm = thermal::estimate_temperature(20.0 * 60.0);
+    let later = thermal::estimate_temperature(60.0 * 60.0);
+    assert!(warm > thermal::estimate_temperature(60.0));
+    // Practically warm after 20 minutes
+    assert!(
+        later - warm < 0.05 * (later - AMBIENT_CELSIUS),
+        "{warm} vs {later}"
+    );
+}
+
+#[test]
+fn test_single_temperature_is_not_fitted() {
+    let model = model_from([30.0], bias_at);
+    assert!(model.bias_at(30.0).is_none());
+
+    // Too few samples to trust
+    let mut sparse = TemperatureBiasModel::default();
+    sparse.record(25.0, bias_at(25.0), 10);
+    sparse.record(35.0, bias_at(35.0), 10);
+    assert!(sparse.bias_at(30.0).is_none());
+}
+
+#[test]
+fn test_linear_bias_is_interpolated() {
+    let model = model_from([25.0, 28.0], bias_at);
+
+    let bias = model.bias_at(26.5).unwrap();
+    assert!(
+        bias.distance(bias_at(26.5)) < 1e-5,
+        "{bias} vs {}",
+        bias_at(26.5)
+    );
+}
+
+#[test]
+fn test_quadratic_bias_is_fitted() {
+    let curved = |temperature: f32| Vec3::Z * 1e-4 * (temperature - 30.0).powi(2);
+    let model = model_from((25..=37).map(|t| t as f32), curved);
+
+    for temperature in [26.0, 30.0, 33.5] {
+        let bias = model.bias_at(temperature).unwrap();
+        assert!(
+            bias.distance(curved(temperature)) < 5e-5,
+            "at {temperature} °C: {bias}"
+        );
+    }
+}
+
+#[test]
+fn test_bias_is_held_outside_recorded_range() {
+    let model = model_from([26.0, 30.0], bias_at);
+
+    assert!(model.bias_at(10.0).unwrap().distance(bias_at(26.0)) < 1e-5);
+    assert!(model.bias_at(50.0).unwrap().distance(bias_at(30.0)) < 1e-5);
+}
+
+#[test]
+fn test_nearby_temperatures_share_a_point() {
+    let mut model = TemperatureBiasModel::default();
+    model.record(29.8, Vec3::X * 0.01, 1000);
+    model.record(30.2, Vec3::X * 0.03, 1000);
+
+    let point = model.point_at(30.0).unwrap();
+    assert_eq!(point.temperature_celsius, 30.0);
+    assert_eq!(point.samples, 2000);
+    assert!((point.bias[0] - 0.02).abs() < 1e-6);
+}
+
+#[test]
+fn test_saved_points_restore_the_curve() {
+    let temperatures = [25.0, 29.0, 33.0];
+    let model = model_from(temperatures, bias_at);
+    let points: Vec<GyroBiasPoint> = temperatures
+        .iter()
+        .filter_map(|temperature| model.point_at(*temperature))
+        .collect();
+    let restored = TemperatureBiasModel::from_points(&points);
+
+    assert_eq!(restored, model);
+    assert_eq!(restored.bias_at(31.0), model.bias_at(31.0));
+}
+
+#[test]
+fn test_upsert_replaces_point_of_same_temperature() {
+    let point = |temperature_celsius: f32, bias: f32| GyroBiasPoint {
+        temperature_celsius,
+        bias: [bias, 0.0, 0.0],
+        samples: 1000,
+    };
+    let mut points = vec![point(25.0, 0.01), point(30.0, 0.02)];
+
+    assert!(!thermal::point_changed(&points, &point(30.0, 0.02), 1e-4));
+    assert!(thermal::point_changed(&points, &point(30.0, 0.03), 1e-4));
+    assert!(thermal::point_changed(&points, &point(27.0, 0.02), 1e-4));
+
+    thermal::upsert_point(&mut points, point(30.0, 0.03));
+    thermal::upsert_point(&mut points, point(27.0, 0.02));
+    let temperatures: Vec<f32> = points.iter().map(|p| p.temperature_celsius).collect();
+    assert_eq!(temperatures, [25.0, 27.0, 30.0]);
+    assert_eq!(points[2].bias[0], 0.03);
+}
+
+#[test]
+fn test_pipeline_follows_bias_while_warming_up() {
+    let mut pipeline = TrackingPipeline::default();
+    pipeline.set_temperature_model(model_from([25.0, 30.0, 35.0], bias_at));
+
+    // A steady turn, so nothing is learned while still
+    let rate = Vec3::Y * 30_f32.to_radians();
+    let accel = Vec3::Y * STANDARD_GRAVITY;
+    for uptime_s in [30_u64, 180, 360] {
+        for step in 0..100 {
+            let timestamp = uptime_s * 1_000_000 + step * 1000;
+            let temperature = thermal::estimate_temperature((timestamp as f64 / 1e6) as f32);
+            let sample = pipeline.push_imu(accel, rate + bias_at(temperature), timestamp);
+            assert!(sample.gyro.distance(rate) < 1e-4, "at {temperature} °C");
+            assert_eq!(pipeline.temperature(), temperature);
+        }
+    }
+}
+
+#[test]
+fn test_still_time_is_recorded_once_a_second() {
+    let mut pipeline = TrackingPipeline::default();
+    let accel = Vec3::Y * STANDARD_GRAVITY;
+    // Warm after an hour, so all samples share a point
+    let start = 3_600_000_000;
+    let temperature = thermal::estimate_temperature((start as f64 / 1e6) as f32);
+    let bias = Vec3::new(0.004, -0.002, 0.001);
+
+    let mut still = 0;
+    let mut timestamp = start;
+    while still < 900 {
+        timestamp += 1000;
+        pipeline.push_imu(accel, bias, timestamp);
+        if pipeline.drift.is_stationary() {
+            still += 1;
+        }
+    }
+    assert!(pipeline.temperature_model.point_at(temperature).is_none());
+
+    while still < 1100 {
+        timestamp += 1000;
+        pipeline.push_imu(accel, bias, timestamp);
+        still += 1;
+    }
+    let point = pipeline.temperature_model.point_at(temperature).unwrap();
+    assert!((999..=1001).contains(&point.samples), "{}", point.samples);
+    assert!(Vec3::from(point.bias).distance(bias) < 1e-6);
+}
+
+#[test]
+fn test_recorded_calibration_resumes_mid_session() {
+    let mut original = TrackingPipeline::default();
+    original.set_temperature_model(model_from([25.0, 30.0, 35.0], bias_at));
+    let refined = Vec3::new(0.01, 0.0, 0.0);
+    original.drift.set_bias(refined);
+    let calibration = original.recorded_calibration();
+
+    let mut replay = TrackingPipeline::default();
+    replay.apply_recorded_calibration(&calibration);
+    assert_eq!(replay.recorded_calibration(), calibration);
+
+    // The recorded bias holds at the first sample, the curve moves it from there
+    let rate = Vec3::Y * 30_f32.to_radians();
+    let accel = Vec3::Y * STANDARD_GRAVITY;
+    replay.push_imu(accel, rate, 120_000_000);
+    assert!(replay.drift.bias().distance(refined) < 1e-6);
+
+    let start = replay.temperature();
+    replay.push_imu(accel, rate, 240_000_000);
+    let moved = bias_at(replay.temperature()) - bias_at(start);
+    assert!(replay.drift.bias().distance(refined + moved) < 1e-5);
+}
+
+#[test]
+fn test_temperature_model_can_be_disabled() {
+    let mut pipeline = TrackingPipeline::default();
+    pipeline.drift_settings = DriftCompensationSettings {
+        temperature_model: false,
+        ..Default::default()
+    };
+    pipeline.set_temperature_model(model_from([25.0, 35.0], bias_at));
+
+    // Warm after ten minutes
+    pipeline.push_imu(Vec3::Y * STANDARD_GRAVITY, Vec3::ZERO, 600_000_000);
+    assert_eq!(pipeline.drift.bias(), Vec3::ZERO);
+}
+
+#[test]
+fn test_bias_points_are_validated() {
+    let valid = CalibrationData {
+        gyro_bias_points: vec![GyroBiasPoint {
+            temperature_celsius: 32.0,
+            bias: [0.001, 0.002, -0.001],
+            samples: 5000,
+        }],
+        ..CalibrationData::default()
+    };
+    assert!(valid.validate().is_ok());
+
+    let mut scorching = valid.clone();
+    scorching.gyro_bias_points[0].temperature_celsius = 120.0;
+    assert!(scorching.validate().is_err());
+
+    let mut broken = valid;
+    broken.gyro_bias_points[0].bias[1] = f32::NAN;
+    assert!(broken.validate().is_err());
+}
+
+#[test]
+fn test_settings_without_temperature_model_default_to_on() {
+    let mut json = serde_json::to_value(DriftCompensationSettings::default()).unwrap();
+    json.as_object_mut().unwrap().remove("temperature_model");
+
+    let settings: DriftCompensationSettings = serde_json::from_value(json).unwrap();
+    assert!(settings.temperature_model);
+}